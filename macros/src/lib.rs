@@ -0,0 +1,457 @@
+//! Procedural macros of [near-workspaces](https://docs.rs/near-workspaces). These are
+//! re-exported from there, which is where they should be used from.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use serde_json::{Map, Value};
+use syn::parse::{Parse, ParseStream};
+use syn::{Ident, LitStr, Token};
+
+/// Generate a typed client for a contract out of its [near-abi](https://github.com/near/abi)
+/// JSON file, such as the one `cargo near abi` writes out. The path of the file is relative
+/// to the `Cargo.toml` of the crate invoking the macro:
+/// ```ignore
+/// near_workspaces::generate_client!("res/status_message_abi.json");
+/// // or, to pick the name of the client:
+/// near_workspaces::generate_client!(StatusMessage, "res/status_message_abi.json");
+/// ```
+///
+/// The client wraps a `near_workspaces::Contract`, with a method for each of the functions
+/// of the contract taking its arguments as typed parameters. View functions are called
+/// right away and return their deserialized result, while change functions return the
+/// `CallTransaction` of `Contract::call`, so that deposit, gas and signer can still be
+/// specified before calling `transact`. The structs and enums the ABI defines are
+/// generated along with the client.
+///
+/// The generated code relies on `serde` and `serde_json` being dependencies of the crate
+/// invoking the macro. The macro is available through the `generate-client` feature of
+/// near-workspaces, which leaves out the dependencies of the `abi` feature.
+#[proc_macro]
+pub fn generate_client(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as ClientInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+struct ClientInput {
+    name: Option<Ident>,
+    path: LitStr,
+}
+
+impl Parse for ClientInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = if input.peek(Ident) {
+            let name = input.parse()?;
+            input.parse::<Token![,]>()?;
+            Some(name)
+        } else {
+            None
+        };
+        let path = input.parse()?;
+        Ok(Self { name, path })
+    }
+}
+
+fn expand(input: ClientInput) -> syn::Result<TokenStream> {
+    let ClientInput { name, path } = input;
+    let span = path.span();
+    let error = |msg: String| syn::Error::new(span, msg);
+
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR")
+        .map_err(|_| error("CARGO_MANIFEST_DIR is not set".into()))?;
+    let path = PathBuf::from(manifest_dir).join(path.value());
+    let abi = std::fs::read_to_string(&path)
+        .map_err(|e| error(format!("failed to read ABI at {}: {}", path.display(), e)))?;
+    let abi: Value = serde_json::from_str(&abi)
+        .map_err(|e| error(format!("failed to parse ABI at {}: {}", path.display(), e)))?;
+
+    let client = match name {
+        Some(name) => name,
+        None => {
+            let contract = abi["metadata"]["name"]
+                .as_str()
+                .ok_or_else(|| error("ABI has no contract name, pass one to the macro".into()))?;
+            format_ident!("{}Client", upper_camel_case(contract))
+        }
+    };
+    let definitions = abi["body"]["root_schema"]["definitions"]
+        .as_object()
+        .cloned()
+        .unwrap_or_default();
+    let functions = abi["body"]["functions"]
+        .as_array()
+        .ok_or_else(|| error("ABI has no functions".into()))?;
+
+    let mut types = TypeGen::new(definitions);
+    let mut methods = Vec::new();
+    for function in functions {
+        methods.extend(types.method(function).map_err(error)?);
+    }
+    let items = types.items;
+    let path = path.display().to_string();
+
+    Ok(quote! {
+        // Rebuild the client whenever the ABI changes.
+        const _: &[u8] = include_bytes!(#path);
+
+        #(#items)*
+
+        #[derive(Clone)]
+        pub struct #client {
+            contract: near_workspaces::Contract,
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        impl #client {
+            pub fn new(contract: near_workspaces::Contract) -> Self {
+                Self { contract }
+            }
+
+            pub fn contract(&self) -> &near_workspaces::Contract {
+                &self.contract
+            }
+
+            #(#methods)*
+        }
+    })
+}
+
+/// Rust type generated for a JSON schema.
+struct RustType {
+    tokens: TokenStream,
+    /// Whether the type is an `Option` already, since the schema accepts `null`.
+    optional: bool,
+}
+
+impl RustType {
+    fn plain(tokens: TokenStream) -> Self {
+        Self {
+            tokens,
+            optional: false,
+        }
+    }
+
+    fn option(inner: TokenStream) -> Self {
+        Self {
+            tokens: quote!(Option<#inner>),
+            optional: true,
+        }
+    }
+}
+
+/// Generates the Rust types for the JSON schemas of an ABI, along with the items for the
+/// definitions these refer to.
+struct TypeGen {
+    definitions: Map<String, Value>,
+    generated: BTreeSet<String>,
+    items: Vec<TokenStream>,
+}
+
+impl TypeGen {
+    fn new(definitions: Map<String, Value>) -> Self {
+        Self {
+            definitions,
+            generated: BTreeSet::new(),
+            items: Vec::new(),
+        }
+    }
+
+    /// Client method calling into `function`, if its arguments can be passed as JSON.
+    fn method(&mut self, function: &Value) -> Result<Option<TokenStream>, String> {
+        let name = function["name"]
+            .as_str()
+            .ok_or("ABI function has no name")?;
+        let method = ident(name);
+        let doc = function["doc"].as_str().map(|doc| quote!(#[doc = #doc]));
+
+        let params = &function["params"];
+        let args = match params["serialization_type"].as_str() {
+            None => Vec::new(),
+            Some("json") => params["args"].as_array().cloned().unwrap_or_default(),
+            Some(_) => return Ok(None),
+        };
+        let mut params = Vec::new();
+        let mut fields = Vec::new();
+        let mut field_names = Vec::new();
+        for arg in &args {
+            let arg_name = arg["name"].as_str().ok_or("ABI argument has no name")?;
+            let field = ident(arg_name);
+            let ty = self.rust_type(&arg["type_schema"], &format!("{name}_{arg_name}"))?;
+            params.push(quote!(#field: #ty));
+            fields.push(quote! {
+                #[serde(rename = #arg_name)]
+                #field: #ty
+            });
+            field_names.push(field);
+        }
+        let args = if fields.is_empty() {
+            quote!()
+        } else {
+            quote! {
+                .args_json({
+                    #[derive(::serde::Serialize)]
+                    struct Args { #(#fields),* }
+                    Args { #(#field_names),* }
+                })
+            }
+        };
+
+        if function["kind"] == "view" {
+            let (ret, parse) = match &function["result"] {
+                Value::Null => (quote!(()), quote!(.map(|_| ()))),
+                result if result["serialization_type"] == "json" => {
+                    let ty = self.rust_type(&result["type_schema"], &format!("{name}_result"))?;
+                    (ty, quote!(?.json()))
+                }
+                _ => return Ok(None),
+            };
+            Ok(Some(quote! {
+                #doc
+                pub async fn #method(&self, #(#params),*) -> near_workspaces::Result<#ret> {
+                    self.contract.view(#name) #args .await #parse
+                }
+            }))
+        } else {
+            Ok(Some(quote! {
+                #doc
+                pub fn #method(&self, #(#params),*) -> near_workspaces::operations::CallTransaction {
+                    self.contract.call(#name) #args
+                }
+            }))
+        }
+    }
+
+    /// Rust type of `schema`, with `context` naming the types that are defined inline and
+    /// need to be generated.
+    fn rust_type(&mut self, schema: &Value, context: &str) -> Result<TokenStream, String> {
+        self.schema_type(schema, context).map(|ty| ty.tokens)
+    }
+
+    /// Same as [`Self::rust_type`], but keeping track of whether the type is optional.
+    fn schema_type(&mut self, schema: &Value, context: &str) -> Result<RustType, String> {
+        let schema = match schema {
+            Value::Object(schema) => schema,
+            _ => return Ok(RustType::plain(quote!(::serde_json::Value))),
+        };
+
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let name = reference
+                .strip_prefix("#/definitions/")
+                .ok_or_else(|| format!("unsupported reference {reference}"))?;
+            return self.definition(name);
+        }
+
+        for key in &["anyOf", "oneOf"] {
+            if let Some(variants) = schema.get(*key).and_then(Value::as_array) {
+                let non_null = variants
+                    .iter()
+                    .filter(|v| v["type"] != "null")
+                    .collect::<Vec<_>>();
+                if non_null.len() == 1 && variants.len() == 2 {
+                    let ty = self.rust_type(non_null[0], context)?;
+                    return Ok(RustType::option(ty));
+                }
+                return Ok(RustType::plain(quote!(::serde_json::Value)));
+            }
+        }
+
+        if let Some(types) = schema.get("type").and_then(Value::as_array) {
+            let non_null = types.iter().filter(|t| *t != "null").collect::<Vec<_>>();
+            if non_null.len() == 1 && types.len() == 2 {
+                let mut inner = schema.clone();
+                inner.insert("type".into(), non_null[0].clone());
+                let ty = self.rust_type(&Value::Object(inner), context)?;
+                return Ok(RustType::option(ty));
+            }
+            return Ok(RustType::plain(quote!(::serde_json::Value)));
+        }
+
+        let ty = match schema.get("type").and_then(Value::as_str) {
+            Some("string") => match schema.get("enum").and_then(Value::as_array) {
+                Some(variants) => self.string_enum(&upper_camel_case(context), variants)?,
+                None => quote!(String),
+            },
+            Some("boolean") => quote!(bool),
+            Some("integer") => integer_type(schema.get("format").and_then(Value::as_str)),
+            Some("number") => match schema.get("format").and_then(Value::as_str) {
+                Some("float") => quote!(f32),
+                _ => quote!(f64),
+            },
+            Some("array") => match schema.get("items") {
+                Some(Value::Array(items)) => {
+                    let items = items
+                        .iter()
+                        .enumerate()
+                        .map(|(i, item)| self.rust_type(item, &format!("{context}_{i}")))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    quote!((#(#items,)*))
+                }
+                Some(items) => {
+                    let item = self.rust_type(items, &format!("{context}_item"))?;
+                    quote!(Vec<#item>)
+                }
+                None => quote!(Vec<::serde_json::Value>),
+            },
+            Some("object") => {
+                if schema.contains_key("properties") {
+                    self.object(&upper_camel_case(context), schema)?
+                } else {
+                    match schema.get("additionalProperties") {
+                        Some(value @ Value::Object(_)) => {
+                            let value = self.rust_type(value, &format!("{context}_value"))?;
+                            quote!(::std::collections::HashMap<String, #value>)
+                        }
+                        _ => quote!(::serde_json::Value),
+                    }
+                }
+            }
+            Some("null") => quote!(()),
+            _ => quote!(::serde_json::Value),
+        };
+        Ok(RustType::plain(ty))
+    }
+
+    /// Rust type of the definition of `name`, generating it if need be.
+    fn definition(&mut self, name: &str) -> Result<RustType, String> {
+        if name == "AccountId" {
+            return Ok(RustType::plain(quote!(near_workspaces::AccountId)));
+        }
+        let schema = self
+            .definitions
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("ABI has no definition of {name}"))?;
+
+        let is_struct = schema.get("properties").is_some();
+        let is_enum = schema["type"] == "string" && schema.get("enum").is_some();
+        if !is_struct && !is_enum {
+            // Definitions of plain types, such as `U128` which is a string, are inlined.
+            return self.schema_type(&schema, name);
+        }
+
+        let ty = ident(&upper_camel_case(name));
+        if self.generated.insert(name.to_string()) {
+            if is_struct {
+                self.object(name, schema.as_object().unwrap())?;
+            } else {
+                self.string_enum(name, schema["enum"].as_array().unwrap())?;
+            }
+        }
+        Ok(RustType::plain(quote!(#ty)))
+    }
+
+    fn object(&mut self, name: &str, schema: &Map<String, Value>) -> Result<TokenStream, String> {
+        let ty = ident(&upper_camel_case(name));
+        let required = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|r| r.iter().filter_map(Value::as_str).collect::<BTreeSet<_>>())
+            .unwrap_or_default();
+        let properties = schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .map(|p| p.iter().collect::<BTreeMap<_, _>>())
+            .unwrap_or_default();
+
+        let mut fields = Vec::new();
+        for (field_name, field_schema) in properties {
+            let field = ident(field_name);
+            let field_ty = self.schema_type(field_schema, &format!("{name}_{field_name}"))?;
+            let optional = field_ty.optional;
+            let mut field_ty = field_ty.tokens;
+            if !required.contains(field_name.as_str()) && !optional {
+                field_ty = quote!(Option<#field_ty>);
+            }
+            let doc = field_schema["description"]
+                .as_str()
+                .map(|doc| quote!(#[doc = #doc]));
+            fields.push(quote! {
+                #doc
+                #[serde(rename = #field_name)]
+                pub #field: #field_ty
+            });
+        }
+        let doc = schema
+            .get("description")
+            .and_then(Value::as_str)
+            .map(|doc| quote!(#[doc = #doc]));
+
+        self.items.push(quote! {
+            #doc
+            #[derive(Clone, Debug, PartialEq, ::serde::Serialize, ::serde::Deserialize)]
+            pub struct #ty {
+                #(#fields),*
+            }
+        });
+        Ok(quote!(#ty))
+    }
+
+    fn string_enum(&mut self, name: &str, variants: &[Value]) -> Result<TokenStream, String> {
+        let ty = ident(&upper_camel_case(name));
+        let variants = variants
+            .iter()
+            .map(|variant| {
+                let variant = variant
+                    .as_str()
+                    .ok_or_else(|| format!("variant of enum {name} is not a string"))?;
+                let ident = ident(&upper_camel_case(variant));
+                Ok(quote! {
+                    #[serde(rename = #variant)]
+                    #ident
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        self.items.push(quote! {
+            #[derive(Clone, Copy, Debug, PartialEq, Eq, ::serde::Serialize, ::serde::Deserialize)]
+            pub enum #ty {
+                #(#variants),*
+            }
+        });
+        Ok(quote!(#ty))
+    }
+}
+
+/// Rust integer type of the schemars `format` of an integer schema.
+fn integer_type(format: Option<&str>) -> TokenStream {
+    match format {
+        Some("uint8") => quote!(u8),
+        Some("uint16") => quote!(u16),
+        Some("uint32") => quote!(u32),
+        Some("uint64") | Some("uint") => quote!(u64),
+        Some("uint128") => quote!(u128),
+        Some("int8") => quote!(i8),
+        Some("int16") => quote!(i16),
+        Some("int32") => quote!(i32),
+        Some("int128") => quote!(i128),
+        _ => quote!(i64),
+    }
+}
+
+/// Identifier for `name`, falling back to a raw identifier for keywords.
+fn ident(name: &str) -> Ident {
+    let mut name = name.replace(|c: char| !c.is_ascii_alphanumeric() && c != '_', "_");
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    syn::parse_str::<Ident>(&name)
+        .unwrap_or_else(|_| Ident::new_raw(&name, proc_macro2::Span::call_site()))
+}
+
+fn upper_camel_case(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
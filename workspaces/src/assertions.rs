@@ -0,0 +1,265 @@
+//! Assertions for token amounts and gas, which print the values in a human readable form
+//! along with their difference on failure. Comparing raw yoctoNEAR amounts by eye such as
+//! `3860000000000000000000 != 3859999999999999999000` is error prone, so these print
+//! `0 NEAR + 3860000000000000000000 yoctoNEAR` along with a delta of `1000 yoctoNEAR`.
+//!
+//! Gas consumption can also be capped, either for a whole execution with
+//! [`assert_gas_under!`] or per function with [`GasBudgets`], so that tests start failing
+//! as soon as a function burns too much gas to be usable within composed calls.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::result::{ExecutionFinalResult, ExecutionOutcome, ExecutionResult};
+use crate::types::{Gas, NearToken};
+
+const YOCTO_PER_NEAR: u128 = 10u128.pow(24);
+const GAS_PER_TGAS: u64 = 10u64.pow(12);
+
+/// Asserts that two [`NearToken`] amounts are equal, printing both amounts in NEAR along
+/// with the yoctoNEAR remainder and their difference on failure.
+///
+/// [`NearToken`]: crate::types::NearToken
+#[macro_export]
+macro_rules! assert_near_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assertions::check_near_eq($left, $right, None)
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        $crate::assertions::check_near_eq($left, $right, Some(format_args!($($arg)+)))
+    };
+}
+
+/// Asserts that two [`Gas`] amounts are equal, printing both amounts in Tgas along with the
+/// gas remainder and their difference on failure.
+///
+/// [`Gas`]: crate::types::Gas
+#[macro_export]
+macro_rules! assert_gas_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assertions::check_gas_eq($left, $right, None)
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        $crate::assertions::check_gas_eq($left, $right, Some(format_args!($($arg)+)))
+    };
+}
+
+/// Asserts that the gas burnt by an execution is at most `limit`, printing the amount of
+/// gas burnt along with how far it went over on failure. The execution can be anything
+/// implementing [`GasUsage`], such as an [`ExecutionFinalResult`] or a plain [`Gas`] amount.
+///
+/// [`GasUsage`]: crate::assertions::GasUsage
+/// [`ExecutionFinalResult`]: crate::result::ExecutionFinalResult
+/// [`Gas`]: crate::types::Gas
+#[macro_export]
+macro_rules! assert_gas_under {
+    ($outcome:expr, $limit:expr $(,)?) => {
+        $crate::assertions::check_gas_under(&$outcome, $limit, None)
+    };
+    ($outcome:expr, $limit:expr, $($arg:tt)+) => {
+        $crate::assertions::check_gas_under(&$outcome, $limit, Some(format_args!($($arg)+)))
+    };
+}
+
+/// Types that burnt an amount of gas, which can be checked against a limit with
+/// [`assert_gas_under!`].
+pub trait GasUsage {
+    /// Total amount of gas burnt.
+    fn gas_burnt(&self) -> Gas;
+}
+
+impl GasUsage for Gas {
+    fn gas_burnt(&self) -> Gas {
+        *self
+    }
+}
+
+impl GasUsage for ExecutionFinalResult {
+    fn gas_burnt(&self) -> Gas {
+        self.total_gas_burnt
+    }
+}
+
+impl<T> GasUsage for ExecutionResult<T> {
+    fn gas_burnt(&self) -> Gas {
+        self.total_gas_burnt
+    }
+}
+
+impl GasUsage for ExecutionOutcome {
+    fn gas_burnt(&self) -> Gas {
+        self.gas_burnt
+    }
+}
+
+impl<T: GasUsage + ?Sized> GasUsage for &T {
+    fn gas_burnt(&self) -> Gas {
+        (**self).gas_burnt()
+    }
+}
+
+/// Table of gas budgets per function, for checking every function called during an
+/// execution against its own budget. Functions are matched by name regardless of the
+/// contract they were called on, and each one is charged the gas burnt by the receipt
+/// that executed it.
+///
+/// ```ignore
+/// let budgets = GasBudgets::new()
+///     .budget("ft_transfer", Gas::from_tgas(10))
+///     .budget("ft_on_transfer", Gas::from_tgas(25));
+///
+/// let outcome = alice.call(token.id(), "ft_transfer_call").args_json(args).transact().await?;
+/// budgets.assert_within(&outcome);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct GasBudgets {
+    budgets: BTreeMap<String, Gas>,
+}
+
+impl GasBudgets {
+    /// Create an empty table of gas budgets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the gas budget of the function called `method`.
+    pub fn budget(mut self, method: &str, limit: Gas) -> Self {
+        self.budgets.insert(method.into(), limit);
+        self
+    }
+
+    /// Asserts that every function with a budget that was called during `outcome` stayed
+    /// within its budget, listing all the functions that went over it on failure. This
+    /// relies on the details of the receipts, which are only fetched for transactions sent
+    /// with `with_receipts`.
+    #[track_caller]
+    pub fn assert_within(&self, outcome: &ExecutionFinalResult) {
+        let usage = match outcome.details.function_call_gas() {
+            Some(usage) => usage,
+            None => {
+                panic!(
+                    "cannot check gas budgets: the receipts of this execution were not fetched, \
+                     see `with_receipts`"
+                )
+            }
+        };
+
+        let exceeded = usage
+            .into_iter()
+            .filter_map(|(call, burnt)| {
+                let limit = *self.budgets.get(&call.method_name)?;
+                (burnt > limit).then(|| {
+                    format!(
+                        "\n  {}.{}: burnt {}, budget {} (over by {})",
+                        call.receiver_id,
+                        call.method_name,
+                        format_gas(burnt),
+                        format_gas(limit),
+                        format_gas(Gas::from_gas(burnt.as_gas() - limit.as_gas())),
+                    )
+                })
+            })
+            .collect::<String>();
+        if !exceeded.is_empty() {
+            panic!("gas budgets exceeded:{exceeded}");
+        }
+    }
+}
+
+/// Formats a token amount in whole NEAR along with the yoctoNEAR remainder, such as
+/// `3 NEAR + 859999999999999999000 yoctoNEAR`.
+pub fn format_near(amount: NearToken) -> String {
+    format_yocto(amount.as_yoctonear())
+}
+
+/// Formats a gas amount in whole Tgas along with the gas remainder, such as
+/// `12 Tgas + 345678 gas`.
+pub fn format_gas(gas: Gas) -> String {
+    let gas = gas.as_gas();
+    let (tgas, remainder) = (gas / GAS_PER_TGAS, gas % GAS_PER_TGAS);
+    if remainder == 0 {
+        format!("{tgas} Tgas")
+    } else {
+        format!("{tgas} Tgas + {remainder} gas")
+    }
+}
+
+fn format_yocto(yocto: u128) -> String {
+    let (near, remainder) = (yocto / YOCTO_PER_NEAR, yocto % YOCTO_PER_NEAR);
+    if remainder == 0 {
+        format!("{near} NEAR")
+    } else {
+        format!("{near} NEAR + {remainder} yoctoNEAR")
+    }
+}
+
+fn format_delta(left: u128, right: u128, format: impl Fn(u128) -> String) -> String {
+    if left >= right {
+        format!("+{}", format(left - right))
+    } else {
+        format!("-{}", format(right - left))
+    }
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn check_near_eq(left: NearToken, right: NearToken, msg: Option<fmt::Arguments<'_>>) {
+    if left != right {
+        let (left, right) = (left.as_yoctonear(), right.as_yoctonear());
+        failed(
+            &format_yocto(left),
+            &format_yocto(right),
+            &format_delta(left, right, format_yocto),
+            msg,
+        );
+    }
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn check_gas_eq(left: Gas, right: Gas, msg: Option<fmt::Arguments<'_>>) {
+    if left != right {
+        let gas = |gas: u128| format_gas(Gas::from_gas(gas as u64));
+        let (left, right) = (u128::from(left.as_gas()), u128::from(right.as_gas()));
+        failed(
+            &gas(left),
+            &gas(right),
+            &format_delta(left, right, gas),
+            msg,
+        );
+    }
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn check_gas_under<T: GasUsage + ?Sized>(
+    outcome: &T,
+    limit: Gas,
+    msg: Option<fmt::Arguments<'_>>,
+) {
+    let burnt = outcome.gas_burnt();
+    if burnt > limit {
+        let over = format_gas(Gas::from_gas(burnt.as_gas() - limit.as_gas()));
+        let (burnt, limit) = (format_gas(burnt), format_gas(limit));
+        match msg {
+            Some(msg) => panic!(
+                "assertion failed: `(burnt <= limit)`: {msg}\n burnt: {burnt}\n limit: {limit}\n  over: +{over}"
+            ),
+            None => panic!(
+                "assertion failed: `(burnt <= limit)`\n burnt: {burnt}\n limit: {limit}\n  over: +{over}"
+            ),
+        }
+    }
+}
+
+#[track_caller]
+fn failed(left: &str, right: &str, delta: &str, msg: Option<fmt::Arguments<'_>>) -> ! {
+    match msg {
+        Some(msg) => panic!(
+            "assertion failed: `(left == right)`: {msg}\n  left: {left}\n right: {right}\n delta: {delta} (left - right)"
+        ),
+        None => panic!(
+            "assertion failed: `(left == right)`\n  left: {left}\n right: {right}\n delta: {delta} (left - right)"
+        ),
+    }
+}
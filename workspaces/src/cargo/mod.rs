@@ -1,6 +1,9 @@
 use std::convert::TryInto;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
 use crate::error::ErrorKind;
+use crate::CryptoHash;
 
 /// Builds the cargo project located at `project_path` and returns the generated wasm file contents.
 ///
@@ -39,3 +42,162 @@ pub async fn compile_project(project_path: &str) -> crate::Result<Vec<u8>> {
         .await
         .map_err(|e| ErrorKind::Io.custom(e))
 }
+
+/// Options for how [`compile_project_with`] builds a contract.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct CompileOptions {
+    reproducible: bool,
+    embed_abi: bool,
+}
+
+impl CompileOptions {
+    /// Create the options of a plain release build, which is what [`compile_project`] does,
+    /// except for the ABI not getting embedded unless asked for with [`Self::embed_abi`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build the contract reproducibly by invoking `cargo near build reproducible-wasm`,
+    /// which builds inside of the docker image pinned in the `[package.metadata.near]`
+    /// section of the contract's `Cargo.toml`. This requires `cargo-near` to be installed
+    /// and docker to be running, but yields the exact same wasm as the one deployed from
+    /// a reproducible build elsewhere.
+    pub fn reproducible(mut self) -> Self {
+        self.reproducible = true;
+        self
+    }
+
+    /// Embed the ABI of the contract into the wasm. Only used for non-reproducible builds,
+    /// since reproducible builds take this from the contract's `Cargo.toml` instead. The
+    /// ABI is also handed back through [`CompiledContract::abi`].
+    pub fn embed_abi(mut self) -> Self {
+        self.embed_abi = true;
+        self
+    }
+}
+
+/// Contract compiled by [`compile_project_with`], along with the metadata of the build.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct CompiledContract {
+    /// Contents of the compiled wasm file.
+    pub wasm: Vec<u8>,
+    /// Path to the compiled wasm file.
+    pub path: PathBuf,
+    /// Hash of the wasm, which is what the `code_hash` of an account deployed with this
+    /// contract will be. Useful for asserting the tested wasm is the one on chain.
+    pub code_hash: CryptoHash,
+    /// Whether the wasm was built reproducibly.
+    pub reproducible: bool,
+    /// Path to the JSON file of the ABI `cargo near` generated next to the wasm, when built
+    /// with [`CompileOptions::embed_abi`].
+    pub abi_path: Option<PathBuf>,
+    /// The ABI of the contract read from [`Self::abi_path`], when built with
+    /// [`CompileOptions::embed_abi`].
+    pub abi: Option<serde_json::Value>,
+}
+
+/// Builds the cargo project located at `project_path` with the given `options`, returning
+/// the generated wasm file contents along with the metadata of the build.
+pub async fn compile_project_with(
+    project_path: &str,
+    options: CompileOptions,
+) -> crate::Result<CompiledContract> {
+    let project_path = std::fs::canonicalize(project_path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => ErrorKind::Io.message(format!(
+            "Incorrect file supplied to compile_project_with('{}')",
+            project_path
+        )),
+        _ => ErrorKind::Io.custom(e),
+    })?;
+    let manifest_path = project_path.join("Cargo.toml");
+
+    let path = if options.reproducible {
+        build_reproducible(&manifest_path)?
+    } else {
+        let cargo_near_build_command = cargo_near::BuildCommand {
+            release: true,
+            embed_abi: options.embed_abi,
+            doc: false,
+            color: cargo_near::ColorPreference::Always,
+            no_abi: !options.embed_abi,
+            out_dir: None,
+            manifest_path: Some(
+                manifest_path
+                    .clone()
+                    .try_into()
+                    .map_err(|e| ErrorKind::Io.custom(e))?,
+            ),
+        };
+        cargo_near::build::run(cargo_near_build_command)
+            .map_err(|e| ErrorKind::Io.custom(e))?
+            .path
+            .canonicalize()
+            .map_err(|e| ErrorKind::Io.custom(e))?
+    };
+
+    let wasm = tokio::fs::read(&path)
+        .await
+        .map_err(|e| ErrorKind::Io.custom(e))?;
+
+    let (abi_path, abi) = if options.embed_abi && !options.reproducible {
+        let abi_path = abi_path_of(&path)?;
+        let abi = tokio::fs::read(&abi_path).await.map_err(|e| {
+            ErrorKind::Io.full(format!("failed to read the ABI at {abi_path:?}"), e)
+        })?;
+        let abi = serde_json::from_slice(&abi).map_err(|e| ErrorKind::DataConversion.custom(e))?;
+        (Some(abi_path), Some(abi))
+    } else {
+        (None, None)
+    };
+
+    Ok(CompiledContract {
+        code_hash: CryptoHash::hash_bytes(&wasm),
+        wasm,
+        path,
+        reproducible: options.reproducible,
+        abi_path,
+        abi,
+    })
+}
+
+/// Path of the ABI `cargo near` writes next to the wasm at `wasm_path`, which is named
+/// after the wasm with an `_abi.json` suffix.
+fn abi_path_of(wasm_path: &Path) -> crate::Result<PathBuf> {
+    let stem = wasm_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| ErrorKind::Io.message(format!("invalid wasm path {wasm_path:?}")))?;
+    Ok(wasm_path.with_file_name(format!("{stem}_abi.json")))
+}
+
+/// Runs `cargo near build reproducible-wasm` for the project, returning the path to the
+/// wasm it produced under `target/near`.
+fn build_reproducible(manifest_path: &Path) -> crate::Result<PathBuf> {
+    let status = Command::new("cargo")
+        .args(["near", "build", "reproducible-wasm", "--manifest-path"])
+        .arg(manifest_path)
+        .status()
+        .map_err(|e| ErrorKind::Io.full("failed to run `cargo near`, is it installed?", e))?;
+    if !status.success() {
+        return Err(ErrorKind::Io.message(format!(
+            "`cargo near build reproducible-wasm` failed with status={status}"
+        )));
+    }
+
+    let metadata = cargo_metadata::MetadataCommand::new()
+        .manifest_path(manifest_path)
+        .no_deps()
+        .exec()
+        .map_err(|e| ErrorKind::Io.custom(e))?;
+    let package = metadata
+        .root_package()
+        .ok_or_else(|| ErrorKind::Io.message("no root package found for the contract"))?;
+
+    Ok(metadata
+        .target_directory
+        .join("near")
+        .join(format!("{}.wasm", package.name.replace('-', "_")))
+        .into_std_path_buf())
+}
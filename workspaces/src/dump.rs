@@ -0,0 +1,280 @@
+//! Import source reading accounts out of a state dump of a local mainnet or testnet node,
+//! rather than pulling them down from an archival RPC node. This makes importing
+//! contracts with large amounts of state fast, and works without network access.
+//!
+//! The data directory of a node cannot be read while the node is running, so the state
+//! first has to be dumped from it with the `neard` binary the node runs with:
+//! ```sh
+//! neard --home ~/.near view-state dump-state --stream
+//! ```
+//! This writes the state of the last block out to `~/.near/output`, which can then be
+//! loaded with [`StateDump::load`] and imported into sandbox with
+//! [`Worker::import_contract_from_dump`](crate::Worker::import_contract_from_dump):
+//! ```ignore
+//! let dump = StateDump::load_accounts("~/.near/output", [&contract_id]).await?;
+//! let contract = worker
+//!     .import_contract_from_dump(&contract_id, &dump)
+//!     .with_data()
+//!     .transact()
+//!     .await?;
+//! ```
+//!
+//! A whole dump can also be seeded into a sandbox on startup with `seed_from_dump` on the
+//! builder returned by [`sandbox`](crate::sandbox), which stands up a sandbox with realistic
+//! state without having to import accounts one by one.
+//! State parts downloaded through state sync are not a dump of their own, but can be turned
+//! into one by loading them into a node with `neard view-state state-parts load` first.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+
+use near_primitives::state_record::StateRecord;
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use tokio::sync::mpsc;
+
+use crate::error::{ErrorKind, SandboxErrorCode};
+use crate::result::Result;
+use crate::rpc::client::Client;
+use crate::types::AccountId;
+
+/// Name of the file `dump-state --stream` writes the state records to.
+const RECORDS_FILE: &str = "records.json";
+/// Name of the genesis file `dump-state` writes the state records into without `--stream`.
+const GENESIS_FILE: &str = "genesis.json";
+/// Number of records patched into sandbox per request when seeding it from a dump.
+const SEED_CHUNK_SIZE: usize = 500;
+
+/// Accounts loaded out of a state dump, ready to be imported into sandbox.
+#[derive(Default)]
+pub struct StateDump {
+    accounts: HashMap<AccountId, DumpedAccount>,
+}
+
+/// Everything about an account that can be imported from a state dump.
+#[derive(Default)]
+pub(crate) struct DumpedAccount {
+    pub(crate) account: Option<near_primitives::account::Account>,
+    pub(crate) code: Option<Vec<u8>>,
+    pub(crate) data: Vec<StateRecord>,
+}
+
+impl StateDump {
+    /// Load all of the accounts out of the state dump at `path`. This can either be the
+    /// output directory of `dump-state`, or the records or genesis file within it. Note
+    /// that a dump of mainnet or testnet does not fit into memory as a whole, in which
+    /// case [`StateDump::load_accounts`] should be used instead.
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::load_with(path.as_ref().to_path_buf(), None).await
+    }
+
+    /// Load only `accounts` out of the state dump at `path`, skipping over the records of
+    /// every other account. See [`StateDump::load`] for what `path` can be.
+    pub async fn load_accounts<'a>(
+        path: impl AsRef<Path>,
+        accounts: impl IntoIterator<Item = &'a AccountId>,
+    ) -> Result<Self> {
+        let accounts = accounts.into_iter().cloned().collect();
+        Self::load_with(path.as_ref().to_path_buf(), Some(accounts)).await
+    }
+
+    /// Ids of all the accounts loaded out of the dump.
+    pub fn accounts(&self) -> impl Iterator<Item = &AccountId> {
+        self.accounts.keys()
+    }
+
+    /// Whether the account `id` was loaded out of the dump.
+    pub fn contains(&self, id: &AccountId) -> bool {
+        self.accounts.contains_key(id)
+    }
+
+    pub(crate) fn account(&self, id: &AccountId) -> Result<&DumpedAccount> {
+        self.accounts.get(id).ok_or_else(|| {
+            ErrorKind::DataConversion.message(format!("account {id} is not in the state dump"))
+        })
+    }
+
+    async fn load_with(path: PathBuf, only: Option<HashSet<AccountId>>) -> Result<Self> {
+        tokio::task::spawn_blocking(move || read(&path, only.as_ref()))
+            .await
+            .map_err(|e| ErrorKind::Io.full("failed to load state dump", e))?
+    }
+
+    fn insert(&mut self, record: StateRecord) {
+        let id = match record_account_id(&record) {
+            Some(id) => id.clone(),
+            None => return,
+        };
+
+        let entry = self.accounts.entry(id).or_default();
+        match record {
+            StateRecord::Account { account, .. } => entry.account = Some(account),
+            StateRecord::Contract { code, .. } => entry.code = Some(code),
+            record => entry.data.push(record),
+        }
+    }
+}
+
+/// Account the record belongs to, for the records that get imported. Access keys are not
+/// imported, since the keys of dumped accounts are of no use without their secret keys.
+/// Receipts in flight are not imported either.
+fn record_account_id(record: &StateRecord) -> Option<&AccountId> {
+    match record {
+        StateRecord::Account { account_id, .. }
+        | StateRecord::Contract { account_id, .. }
+        | StateRecord::Data { account_id, .. } => Some(account_id),
+        _ => None,
+    }
+}
+
+fn read(path: &Path, only: Option<&HashSet<AccountId>>) -> Result<StateDump> {
+    let mut dump = StateDump::default();
+    read_records(path, |record| {
+        let wanted = match (only, record_account_id(&record)) {
+            (Some(only), Some(id)) => only.contains(id),
+            _ => true,
+        };
+        if wanted {
+            dump.insert(record);
+        }
+        ControlFlow::Continue(())
+    })?;
+
+    Ok(dump)
+}
+
+/// Read the records out of the state dump at `path` one by one, handing each of them to
+/// `on_record`, until it breaks out of reading the rest. See [`StateDump::load`] for what
+/// `path` can be.
+pub(crate) fn read_records(
+    path: &Path,
+    mut on_record: impl FnMut(StateRecord) -> ControlFlow<()>,
+) -> Result<()> {
+    let path = if path.is_dir() {
+        [RECORDS_FILE, GENESIS_FILE]
+            .iter()
+            .map(|file| path.join(file))
+            .find(|file| file.exists())
+            .ok_or_else(|| {
+                ErrorKind::Io.message(format!(
+                    "neither {RECORDS_FILE} nor {GENESIS_FILE} found in {path:?}"
+                ))
+            })?
+    } else {
+        path.to_path_buf()
+    };
+
+    let file = File::open(&path)
+        .map_err(|e| ErrorKind::Io.full(format!("failed to open state dump {path:?}"), e))?;
+    let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(file));
+    let mut stopped = false;
+    let result = Records {
+        on_record: &mut on_record,
+        stopped: &mut stopped,
+    }
+    .deserialize(&mut deserializer);
+    match result {
+        // Stopping leaves the rest of the dump unread, which surfaces as a deserialize error.
+        Err(_) if stopped => Ok(()),
+        result => result
+            .map_err(|e| ErrorKind::DataConversion.full(format!("invalid state dump {path:?}"), e)),
+    }
+}
+
+/// Seed the sandbox behind `client` with all of the accounts, access keys, contracts and
+/// contract data out of the state dump at `path`, returning the number of records seeded.
+/// The records are patched in as they are read, so that the dump never has to be held in
+/// memory as a whole.
+pub(crate) async fn seed(client: &Client, path: PathBuf) -> Result<usize> {
+    let (sender, mut receiver) = mpsc::channel::<Vec<StateRecord>>(4);
+    let reader = tokio::task::spawn_blocking(move || {
+        let mut chunk = Vec::with_capacity(SEED_CHUNK_SIZE);
+        read_records(&path, |record| {
+            if let StateRecord::Account { .. }
+            | StateRecord::Contract { .. }
+            | StateRecord::Data { .. }
+            | StateRecord::AccessKey { .. } = record
+            {
+                chunk.push(record);
+            }
+            if chunk.len() == SEED_CHUNK_SIZE {
+                // The receiving end only goes away once patching failed, which gets reported
+                // without waiting on the reader, so there is no point reading any further.
+                if sender.blocking_send(std::mem::take(&mut chunk)).is_err() {
+                    return ControlFlow::Break(());
+                }
+            }
+            ControlFlow::Continue(())
+        })?;
+        if !chunk.is_empty() {
+            // Nothing is left to read at this point, so a failed send has nothing to stop.
+            let _ = sender.blocking_send(chunk);
+        }
+        Ok::<_, crate::error::Error>(())
+    });
+
+    let mut seeded = 0;
+    while let Some(records) = receiver.recv().await {
+        seeded += records.len();
+        client.patch_state(records).await.map_err(|e| {
+            SandboxErrorCode::PatchStateFailure.full("failed to seed state dump", e)
+        })?;
+    }
+
+    reader
+        .await
+        .map_err(|e| ErrorKind::Io.full("failed to read state dump", e))??;
+    Ok(seeded)
+}
+
+/// Streams the records out of either a records file, which is a list of records, or out
+/// of a genesis file, which holds the list of records under its `records` field. The
+/// records are streamed one by one, as dumps are usually too big to be read in whole.
+struct Records<'a> {
+    on_record: &'a mut dyn FnMut(StateRecord) -> ControlFlow<()>,
+    /// Set once `on_record` breaks out of reading the rest of the records.
+    stopped: &'a mut bool,
+}
+
+impl<'de> DeserializeSeed<'de> for Records<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Records<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a list of state records or a genesis config with records")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(record) = seq.next_element::<StateRecord>()? {
+            if let ControlFlow::Break(()) = (self.on_record)(record) {
+                *self.stopped = true;
+                return Err(de::Error::custom("stopped reading state records"));
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let mut records = Some(self);
+        while let Some(key) = map.next_key::<String>()? {
+            match (key.as_str(), records.take()) {
+                ("records", Some(records)) => map.next_value_seed(records)?,
+                (_, records_left) => {
+                    records = records_left;
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,9 @@
 use std::fmt;
 use std::{borrow::Cow, sync::PoisonError};
 
+use near_jsonrpc_client::errors::{JsonRpcError, JsonRpcServerError};
+use near_jsonrpc_client::methods::query::RpcQueryError;
+
 use crate::result::ExecutionFailure;
 
 use super::{Error, ErrorKind, ErrorRepr, RpcErrorCode, SandboxErrorCode};
@@ -109,6 +112,15 @@ impl Error {
         }
     }
 
+    /// Returns the [`ExecutionFailure`] this error was created from, if any. This allows
+    /// failed transactions to be mapped into application specific error types.
+    pub fn execution_failure(&self) -> Option<&ExecutionFailure> {
+        match &self.repr {
+            ErrorRepr::Detailed { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+
     /// Consumes the `Error`, returning its inner error (if any).
     ///
     /// If this [`Error`] was constructed via a Custom or Full variant, then
@@ -121,6 +133,20 @@ impl Error {
             _ => Err(self),
         }
     }
+
+    /// Whether this is the error of querying an account that does not exist.
+    pub(crate) fn is_unknown_account(&self) -> bool {
+        let error = match &self.repr {
+            ErrorRepr::Custom { error, .. } | ErrorRepr::Full { error, .. } => error,
+            _ => return false,
+        };
+        matches!(
+            error.downcast_ref::<JsonRpcError<RpcQueryError>>(),
+            Some(JsonRpcError::ServerError(JsonRpcServerError::HandlerError(
+                RpcQueryError::UnknownAccount { .. }
+            )))
+        )
+    }
 }
 
 impl fmt::Display for Error {
@@ -82,6 +82,8 @@ pub enum SandboxErrorCode {
     PatchStateFailure,
     #[error("Sandbox failed to fast forward")]
     FastForwardFailure,
+    #[error("Sandbox failed to snapshot or restore its state")]
+    SnapshotFailure,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
@@ -0,0 +1,179 @@
+//! Exporter writing the accounts, access keys and contract state of a network into a
+//! SQLite database, so that state left behind by a test can be analyzed with SQL, such
+//! as joining the state of several contracts or aggregating balances.
+//!
+//! The database has the following schema, with balances stored as decimal strings in
+//! yoctoNEAR since they do not fit into a SQLite integer:
+//! ```sql
+//! CREATE TABLE accounts (
+//!     account_id TEXT PRIMARY KEY, balance TEXT, locked TEXT,
+//!     code_hash TEXT, storage_usage INTEGER
+//! );
+//! CREATE TABLE access_keys (
+//!     account_id TEXT, public_key TEXT, nonce INTEGER, permission TEXT,
+//!     allowance TEXT, receiver_id TEXT, method_names TEXT,
+//!     PRIMARY KEY (account_id, public_key)
+//! );
+//! CREATE TABLE state (
+//!     account_id TEXT, key BLOB, value BLOB,
+//!     PRIMARY KEY (account_id, key)
+//! );
+//! CREATE TABLE metadata (block_height INTEGER, block_hash TEXT);
+//! ```
+//! `permission` is either `FullAccess` or `FunctionCall`, and `method_names` is a JSON
+//! array. Keys and values of the state are raw bytes, which can be turned into text with
+//! `CAST(key AS TEXT)` for contracts using human readable keys.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+
+use crate::error::ErrorKind;
+use crate::network::NetworkClient;
+use crate::result::Result;
+use crate::types::{AccessKeyInfo, AccessKeyPermission, AccountDetails, AccountId, CryptoHash};
+use crate::Worker;
+
+const SCHEMA: &str = "
+CREATE TABLE accounts (
+    account_id TEXT PRIMARY KEY,
+    balance TEXT NOT NULL,
+    locked TEXT NOT NULL,
+    code_hash TEXT NOT NULL,
+    storage_usage INTEGER NOT NULL
+);
+CREATE TABLE access_keys (
+    account_id TEXT NOT NULL,
+    public_key TEXT NOT NULL,
+    nonce INTEGER NOT NULL,
+    permission TEXT NOT NULL,
+    allowance TEXT,
+    receiver_id TEXT,
+    method_names TEXT,
+    PRIMARY KEY (account_id, public_key)
+);
+CREATE TABLE state (
+    account_id TEXT NOT NULL,
+    key BLOB NOT NULL,
+    value BLOB NOT NULL,
+    PRIMARY KEY (account_id, key)
+);
+CREATE TABLE metadata (
+    block_height INTEGER NOT NULL,
+    block_hash TEXT NOT NULL
+);
+";
+
+/// Everything exported for a single account.
+struct AccountExport {
+    id: AccountId,
+    details: AccountDetails,
+    keys: Vec<AccessKeyInfo>,
+    state: HashMap<Vec<u8>, Vec<u8>>,
+}
+
+impl<T> Worker<T>
+where
+    T: NetworkClient + ?Sized,
+{
+    /// Export the details, access keys and contract state of `accounts` into a new SQLite
+    /// database at `path`. Everything is read from the same block, whose height and hash
+    /// are recorded in the `metadata` table. See the [`export`](crate::export) module for
+    /// the schema of the database.
+    pub async fn export_sqlite<'a>(
+        &self,
+        path: impl AsRef<Path>,
+        accounts: impl IntoIterator<Item = &'a AccountId>,
+    ) -> Result<()> {
+        let block = self.view_block().await?;
+        let block_hash = *block.hash();
+
+        let mut exports = Vec::new();
+        for id in accounts {
+            exports.push(AccountExport {
+                id: id.clone(),
+                details: self.view_account(id).block_hash(block_hash).await?,
+                keys: self.view_access_keys(id).block_hash(block_hash).await?,
+                state: self.view_state(id).block_hash(block_hash).await?,
+            });
+        }
+
+        let path = path.as_ref().to_path_buf();
+        let height = block.height();
+        tokio::task::spawn_blocking(move || write(&path, height, &block_hash, &exports))
+            .await
+            .map_err(|e| ErrorKind::Other.full("failed to export state to SQLite", e))?
+    }
+}
+
+fn write(path: &Path, height: u64, hash: &CryptoHash, exports: &[AccountExport]) -> Result<()> {
+    let sqlite_err =
+        |e: rusqlite::Error| ErrorKind::Io.full(format!("failed to write {path:?}"), e);
+
+    let mut conn = Connection::open(path).map_err(sqlite_err)?;
+    let tx = conn.transaction().map_err(sqlite_err)?;
+    tx.execute_batch(SCHEMA).map_err(sqlite_err)?;
+    tx.execute(
+        "INSERT INTO metadata (block_height, block_hash) VALUES (?1, ?2)",
+        params![height as i64, hash.to_string()],
+    )
+    .map_err(sqlite_err)?;
+
+    for export in exports {
+        let id = export.id.as_str();
+        tx.execute(
+            "INSERT INTO accounts (account_id, balance, locked, code_hash, storage_usage)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                id,
+                export.details.balance.as_yoctonear().to_string(),
+                export.details.locked.as_yoctonear().to_string(),
+                export.details.code_hash.to_string(),
+                export.details.storage_usage as i64,
+            ],
+        )
+        .map_err(sqlite_err)?;
+
+        for key in &export.keys {
+            let (permission, allowance, receiver_id, method_names) =
+                match &key.access_key.permission {
+                    AccessKeyPermission::FullAccess => ("FullAccess", None, None, None),
+                    AccessKeyPermission::FunctionCall(perm) => (
+                        "FunctionCall",
+                        perm.allowance
+                            .map(|allowance| allowance.as_yoctonear().to_string()),
+                        Some(perm.receiver_id.clone()),
+                        Some(serde_json::to_string(&perm.method_names).map_err(|e| {
+                            ErrorKind::DataConversion.full("failed to serialize method names", e)
+                        })?),
+                    ),
+                };
+            tx.execute(
+                "INSERT INTO access_keys
+                 (account_id, public_key, nonce, permission, allowance, receiver_id, method_names)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    id,
+                    key.public_key.to_string(),
+                    key.access_key.nonce as i64,
+                    permission,
+                    allowance,
+                    receiver_id,
+                    method_names,
+                ],
+            )
+            .map_err(sqlite_err)?;
+        }
+
+        for (key, value) in &export.state {
+            tx.execute(
+                "INSERT INTO state (account_id, key, value) VALUES (?1, ?2, ?3)",
+                params![id, key, value],
+            )
+            .map_err(sqlite_err)?;
+        }
+    }
+
+    tx.commit().map_err(sqlite_err)
+}
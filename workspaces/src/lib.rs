@@ -9,11 +9,17 @@
 #[cfg(feature = "unstable")]
 mod cargo;
 #[cfg(feature = "unstable")]
-pub use cargo::compile_project;
+pub use cargo::{compile_project, compile_project_with, CompileOptions, CompiledContract};
 
 mod worker;
 
+pub mod assertions;
+pub mod dump;
 pub mod error;
+#[cfg(feature = "sqlite")]
+pub mod export;
+pub mod load;
+pub mod mock;
 pub mod network;
 pub mod operations;
 pub mod prelude;
@@ -24,15 +30,19 @@ pub mod types;
 pub use network::pick_unused_port;
 pub use network::variants::{DevNetwork, Network};
 pub use result::Result;
-pub use types::account::{Account, AccountDetailsPatch, Contract};
+pub use types::account::{Account, AccountDetailsPatch, Contract, Subaccount};
 pub use types::block::Block;
 pub use types::chunk::Chunk;
 pub use types::{AccessKey, AccountId, BlockHeight, CryptoHash, InMemorySigner};
 pub use worker::{
-    betanet, mainnet, mainnet_archival, sandbox, sandbox_with_version, testnet, testnet_archival,
-    with_betanet, with_mainnet, with_mainnet_archival, with_sandbox, with_testnet,
-    with_testnet_archival, Worker,
+    betanet, mainnet, mainnet_archival, sandbox, sandbox_attach, sandbox_cluster,
+    sandbox_cluster_with_version, sandbox_docker, sandbox_fork, sandbox_from_home,
+    sandbox_with_version, shared_sandbox, testnet, testnet_archival, with_betanet, with_mainnet,
+    with_mainnet_archival, with_sandbox, with_testnet, with_testnet_archival, Worker,
 };
 
 #[cfg(feature = "unstable")]
 pub use worker::{custom, with_custom};
+
+#[cfg(feature = "generate-client")]
+pub use near_workspaces_macros::generate_client;
@@ -0,0 +1,277 @@
+//! Load testing harness for benchmarking contracts, or the sandbox itself. A [`LoadTest`]
+//! spins up a set of dev accounts, has each of them fire off a weighted mix of calls for a
+//! set duration, and reports the throughput and latencies achieved along with what went
+//! wrong with the calls that failed:
+//! ```ignore
+//! let report = LoadTest::new(&worker)
+//!     .accounts(20)
+//!     .duration(Duration::from_secs(30))
+//!     .call("increment", 3, move |account| account.call(contract.id(), "increment"))
+//!     .call("get_num", 1, move |account| account.call(contract.id(), "get_num"))
+//!     .run()
+//!     .await?;
+//! println!("{report}");
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+use crate::error::ErrorKind;
+use crate::operations::CallTransaction;
+use crate::result::Result;
+use crate::{Account, DevNetwork, Worker};
+
+/// Default number of dev accounts sending calls concurrently.
+const DEFAULT_ACCOUNTS: usize = 10;
+/// Default duration calls are sent for.
+const DEFAULT_DURATION: Duration = Duration::from_secs(10);
+
+type MakeCall = Arc<dyn Fn(&Account) -> CallTransaction + Send + Sync>;
+
+/// A call making up part of the mix of calls a [`LoadTest`] sends.
+struct Scenario {
+    name: String,
+    weight: u32,
+    make: MakeCall,
+}
+
+/// Builder for a load test run against a [`Worker`]. See the [module level](self) docs
+/// for an example.
+pub struct LoadTest<T: ?Sized> {
+    worker: Worker<T>,
+    accounts: usize,
+    duration: Duration,
+    scenarios: Vec<Scenario>,
+}
+
+impl<T> LoadTest<T>
+where
+    T: DevNetwork + 'static,
+{
+    /// Start setting up a load test against the network `worker` is connected to.
+    pub fn new(worker: &Worker<T>) -> Self {
+        Self {
+            worker: worker.clone(),
+            accounts: DEFAULT_ACCOUNTS,
+            duration: DEFAULT_DURATION,
+            scenarios: Vec::new(),
+        }
+    }
+
+    /// Number of dev accounts to create, each of which sends one call after the other.
+    /// This is the number of calls in flight at any point in time. Defaults to 10.
+    pub fn accounts(mut self, accounts: usize) -> Self {
+        self.accounts = accounts;
+        self
+    }
+
+    /// How long to keep sending calls for. Defaults to 10 seconds.
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Add a call to the mix, which gets picked `weight` times out of the sum of the weights
+    /// of all calls. `make` constructs the call out of the dev account sending it. The call
+    /// is reported on under `name`.
+    pub fn call<F>(mut self, name: &str, weight: u32, make: F) -> Self
+    where
+        F: Fn(&Account) -> CallTransaction + Send + Sync + 'static,
+    {
+        self.scenarios.push(Scenario {
+            name: name.into(),
+            weight,
+            make: Arc::new(make),
+        });
+        self
+    }
+
+    /// Create the dev accounts and send calls from all of them for the configured duration,
+    /// returning a report of how the calls went. The time spent creating the accounts does
+    /// not count towards the duration.
+    pub async fn run(self) -> Result<LoadReport> {
+        let total_weight: u32 = self.scenarios.iter().map(|scenario| scenario.weight).sum();
+        if total_weight == 0 {
+            return Err(ErrorKind::DataConversion
+                .message("load test needs at least one call with a weight above zero"));
+        }
+
+        let mut accounts = Vec::with_capacity(self.accounts);
+        for _ in 0..self.accounts {
+            accounts.push(self.worker.dev_create_account().await?);
+        }
+
+        let scenarios = Arc::new(self.scenarios);
+        let started = Instant::now();
+        let deadline = started + self.duration;
+        let tasks: Vec<_> = accounts
+            .into_iter()
+            .map(|account| {
+                let scenarios = scenarios.clone();
+                tokio::spawn(async move {
+                    let mut samples = Vec::new();
+                    while Instant::now() < deadline {
+                        let scenario = pick(&scenarios, total_weight);
+                        let call = (scenario.make)(&account);
+                        let sent = Instant::now();
+                        let outcome = call_outcome(call).await;
+                        samples.push(Sample {
+                            scenario: scenario.name.clone(),
+                            latency: sent.elapsed(),
+                            outcome,
+                        });
+                    }
+                    samples
+                })
+            })
+            .collect();
+
+        let mut report = LoadReport::default();
+        for task in tasks {
+            let samples = task
+                .await
+                .map_err(|e| ErrorKind::Other.full("load test task failed", e))?;
+            report.record(samples);
+        }
+        report.elapsed = started.elapsed();
+        report.latencies.sort();
+
+        Ok(report)
+    }
+}
+
+/// Pick one of the scenarios at random, according to their weights.
+fn pick(scenarios: &[Scenario], total_weight: u32) -> &Scenario {
+    let mut roll = rand::thread_rng().gen_range(0..total_weight);
+    for scenario in scenarios {
+        if roll < scenario.weight {
+            return scenario;
+        }
+        roll -= scenario.weight;
+    }
+    unreachable!("roll is below the sum of the weights")
+}
+
+/// Send `call`, returning what made it fail if it did. Calls that fail either get
+/// rejected by the network, or fail while being executed.
+async fn call_outcome(call: CallTransaction) -> std::result::Result<(), String> {
+    match call.transact().await {
+        Ok(outcome) => outcome
+            .into_result()
+            .map(|_| ())
+            .map_err(|failure| failure.error_type()),
+        Err(err) => Err(format!("{:?}", err.kind())),
+    }
+}
+
+struct Sample {
+    scenario: String,
+    latency: Duration,
+    outcome: std::result::Result<(), String>,
+}
+
+/// Report of a [`LoadTest`] run, with the throughput, latencies and failures of the calls
+/// sent. Printing it with `{}` gives a summary of all of these.
+#[derive(Clone, Debug, Default)]
+pub struct LoadReport {
+    elapsed: Duration,
+    succeeded: usize,
+    latencies: Vec<Duration>,
+    calls: BTreeMap<String, usize>,
+    failures: BTreeMap<String, usize>,
+}
+
+impl LoadReport {
+    fn record(&mut self, samples: Vec<Sample>) {
+        for sample in samples {
+            self.latencies.push(sample.latency);
+            *self.calls.entry(sample.scenario).or_default() += 1;
+            match sample.outcome {
+                Ok(()) => self.succeeded += 1,
+                Err(reason) => *self.failures.entry(reason).or_default() += 1,
+            }
+        }
+    }
+
+    /// Total number of calls sent.
+    pub fn total(&self) -> usize {
+        self.latencies.len()
+    }
+
+    /// Number of calls that executed successfully.
+    pub fn succeeded(&self) -> usize {
+        self.succeeded
+    }
+
+    /// Number of calls that either got rejected or failed while executing.
+    pub fn failed(&self) -> usize {
+        self.total() - self.succeeded
+    }
+
+    /// How long sending the calls took, up until the last of them completed.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Number of successful calls completed per second.
+    pub fn tps(&self) -> f64 {
+        self.succeeded as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// Latency of calls at the `percentile`, between `0.0` and `100.0`, out of all calls
+    /// sent whether they succeeded or not. Returns zero if no calls were sent.
+    pub fn latency(&self, percentile: f64) -> Duration {
+        if self.latencies.is_empty() {
+            return Duration::ZERO;
+        }
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * (self.latencies.len() - 1) as f64)
+            .round() as usize;
+        self.latencies[rank]
+    }
+
+    /// Number of calls sent for each of the calls in the mix, by name.
+    pub fn calls(&self) -> &BTreeMap<String, usize> {
+        &self.calls
+    }
+
+    /// Number of failed calls for each reason they failed for. Calls failing while
+    /// executing are keyed by the type of the execution error, such as
+    /// `ActionError::FunctionCallError::ExecutionError`, and calls rejected by the
+    /// network by the kind of error returned.
+    pub fn failures(&self) -> &BTreeMap<String, usize> {
+        &self.failures
+    }
+}
+
+impl fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} calls in {:.2?}: {} succeeded, {} failed, {:.2} TPS",
+            self.total(),
+            self.elapsed,
+            self.succeeded,
+            self.failed(),
+            self.tps()
+        )?;
+        writeln!(
+            f,
+            "latency: p50 {:.2?}, p90 {:.2?}, p99 {:.2?}, max {:.2?}",
+            self.latency(50.0),
+            self.latency(90.0),
+            self.latency(99.0),
+            self.latency(100.0)
+        )?;
+        for (name, count) in &self.calls {
+            writeln!(f, "  {name}: {count} calls")?;
+        }
+        for (reason, count) in &self.failures {
+            writeln!(f, "  failed with {reason}: {count}")?;
+        }
+        Ok(())
+    }
+}
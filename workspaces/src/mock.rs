@@ -0,0 +1,496 @@
+//! Generator for mock contracts, which are stub contracts that expose a chosen set of
+//! methods, record every call made into them and reply with canned responses. These
+//! allow testing how a contract integrates with its counterparties without having to
+//! build and deploy the real (and often heavy) counterparty contracts.
+//!
+//! ```ignore
+//! let wasm = MockContract::new()
+//!     .method_returns_json("ft_transfer_call", &serde_json::json!("0"))
+//!     .view_method_returns_json("ft_balance_of", &serde_json::json!("100"))
+//!     .to_wasm();
+//! let token = worker.dev_deploy(&wasm).await?;
+//!
+//! // ... exercise the contract under test against `token` ...
+//!
+//! let calls = MockContract::calls(&token).await?;
+//! assert_eq!(calls[0].method, "ft_transfer_call");
+//! ```
+//!
+//! The wasm is assembled by hand, so no compiler toolchain is required to generate it.
+
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+
+use serde::de::DeserializeOwned;
+
+use crate::error::ErrorKind;
+use crate::result::Result;
+use crate::types::{AccountId, NearToken};
+use crate::Contract;
+
+/// Storage key holding the amount of calls recorded so far.
+const COUNT_KEY: &[u8] = b"__mock_count";
+
+/// Tags prefixed to the storage keys of each recorded call, which are followed by the
+/// index of the call as a little-endian u64.
+const TAG_METHOD: u8 = b'm';
+const TAG_ARGS: u8 = b'a';
+const TAG_PREDECESSOR: u8 = b'p';
+const TAG_DEPOSIT: u8 = b'd';
+
+/// Builder for the wasm of a mock contract. Methods added via [`MockContract::method`]
+/// and friends record each call into the contract state, which can be read back with
+/// [`MockContract::calls`]. Since view calls cannot write to state, methods meant to
+/// be called as views have to be added via [`MockContract::view_method_returns`] and
+/// friends instead, and are not recorded.
+#[derive(Clone, Debug, Default)]
+pub struct MockContract {
+    methods: Vec<MockMethod>,
+}
+
+#[derive(Clone, Debug)]
+struct MockMethod {
+    name: String,
+    response: Option<Vec<u8>>,
+    record: bool,
+}
+
+impl MockContract {
+    /// Create a new mock contract that does not expose any methods yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expose a method called `name` that records each call and returns nothing.
+    pub fn method(self, name: &str) -> Self {
+        self.add(name, None, true)
+    }
+
+    /// Expose a method called `name` that records each call and returns `response`
+    /// as is.
+    pub fn method_returns(self, name: &str, response: impl Into<Vec<u8>>) -> Self {
+        self.add(name, Some(response.into()), true)
+    }
+
+    /// Expose a method called `name` that records each call and returns `response`
+    /// serialized as JSON.
+    pub fn method_returns_json(self, name: &str, response: &serde_json::Value) -> Self {
+        self.method_returns(name, response.to_string())
+    }
+
+    /// Expose a view method called `name` that returns `response` as is.
+    pub fn view_method_returns(self, name: &str, response: impl Into<Vec<u8>>) -> Self {
+        self.add(name, Some(response.into()), false)
+    }
+
+    /// Expose a view method called `name` that returns `response` serialized as JSON.
+    pub fn view_method_returns_json(self, name: &str, response: &serde_json::Value) -> Self {
+        self.view_method_returns(name, response.to_string())
+    }
+
+    fn add(mut self, name: &str, response: Option<Vec<u8>>, record: bool) -> Self {
+        // Later definitions of a method replace the earlier ones, since exports have
+        // to be unique within a wasm module.
+        self.methods.retain(|method| method.name != name);
+        self.methods.push(MockMethod {
+            name: name.into(),
+            response,
+            record,
+        });
+        self
+    }
+
+    /// Generate the wasm of the mock contract, ready to be deployed. The canned responses
+    /// and the arguments of calls share the memory the runtime hands to contracts, which is
+    /// 64MiB on nearcore, so both are bounded by that rather than by the size of the wasm.
+    pub fn to_wasm(&self) -> Vec<u8> {
+        wasm::assemble(&self.methods)
+    }
+
+    /// Read back all the calls recorded by a deployed mock contract, in the order in
+    /// which they were executed.
+    pub async fn calls(contract: &Contract) -> Result<Vec<MockCall>> {
+        let state = contract.view_state().await?;
+        let count = match state.get(COUNT_KEY) {
+            Some(count) => u64::from_le_bytes(count.as_slice().try_into().map_err(|_| {
+                ErrorKind::DataConversion.message("invalid call count in mock contract state")
+            })?),
+            None => return Ok(Vec::new()),
+        };
+
+        let mut fields = BTreeMap::new();
+        for (key, value) in state {
+            if key.len() == 9 {
+                let index = u64::from_le_bytes(key[1..].try_into().unwrap());
+                fields.insert((index, key[0]), value);
+            }
+        }
+
+        let mut calls = Vec::with_capacity(count as usize);
+        for index in 0..count {
+            let mut field = |tag: u8| {
+                fields.remove(&(index, tag)).ok_or_else(|| {
+                    ErrorKind::DataConversion
+                        .message(format!("mock contract state is missing call #{index}"))
+                })
+            };
+
+            let method = String::from_utf8(field(TAG_METHOD)?)
+                .map_err(|e| ErrorKind::DataConversion.custom(e))?;
+            let args = field(TAG_ARGS)?;
+            let predecessor = String::from_utf8(field(TAG_PREDECESSOR)?)
+                .map_err(|e| ErrorKind::DataConversion.custom(e))?
+                .parse()
+                .map_err(|e| ErrorKind::DataConversion.custom(e))?;
+            let deposit = u128::from_le_bytes(field(TAG_DEPOSIT)?.try_into().map_err(|_| {
+                ErrorKind::DataConversion.message("invalid deposit in mock contract state")
+            })?);
+
+            calls.push(MockCall {
+                method,
+                args,
+                deposit: NearToken::from_yoctonear(deposit),
+                predecessor,
+            });
+        }
+
+        Ok(calls)
+    }
+}
+
+/// A call recorded by a mock contract, as returned by [`MockContract::calls`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct MockCall {
+    /// Name of the method that was called.
+    pub method: String,
+    /// Raw arguments the method was called with.
+    pub args: Vec<u8>,
+    /// Amount of tokens attached to the call.
+    pub deposit: NearToken,
+    /// Account that made the call.
+    pub predecessor: AccountId,
+}
+
+impl MockCall {
+    /// Deserialize the arguments of the call from JSON.
+    pub fn args_json<T: DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_slice(&self.args).map_err(|e| ErrorKind::DataConversion.custom(e))
+    }
+
+    /// Deserialize the arguments of the call from borsh.
+    pub fn args_borsh<T: borsh::BorshDeserialize>(&self) -> Result<T> {
+        borsh::BorshDeserialize::try_from_slice(&self.args)
+            .map_err(|e| ErrorKind::DataConversion.custom(e))
+    }
+}
+
+/// Minimal wasm assembler for the mock contracts. Every method is a thin export that
+/// optionally calls into a shared `record` function and then returns its canned
+/// response, which lives in a data segment.
+mod wasm {
+    use super::{MockMethod, COUNT_KEY, TAG_ARGS, TAG_DEPOSIT, TAG_METHOD, TAG_PREDECESSOR};
+
+    // Memory layout of the scratch space, data segments are placed after it.
+    const COUNTER_PTR: u32 = 0;
+    const KEY_PTR: u32 = 16;
+    const KEY_LEN: u32 = 9;
+    const DEPOSIT_PTR: u32 = 32;
+    const DATA_PTR: u32 = 64;
+
+    /// Size of a page of wasm memory.
+    const PAGE_SIZE: u32 = 64 * 1024;
+
+    // Indices of the imported host functions, in the order they are imported.
+    const READ_REGISTER: u32 = 0;
+    const REGISTER_LEN: u32 = 1;
+    const INPUT: u32 = 2;
+    const PREDECESSOR_ACCOUNT_ID: u32 = 3;
+    const ATTACHED_DEPOSIT: u32 = 4;
+    const STORAGE_WRITE: u32 = 5;
+    const STORAGE_READ: u32 = 6;
+    const VALUE_RETURN: u32 = 7;
+    const RECORD: u32 = 8;
+
+    const I32: u8 = 0x7f;
+    const I64: u8 = 0x7e;
+
+    // (param, result) pairs of each function type.
+    const TYPES: &[(&[u8], &[u8])] = &[
+        (&[I64, I64], &[]),
+        (&[I64], &[I64]),
+        (&[I64], &[]),
+        (&[I64, I64, I64, I64, I64], &[I64]),
+        (&[I64, I64, I64], &[I64]),
+        (&[], &[]),
+        (&[I32, I32], &[]),
+    ];
+    const TYPE_METHOD: u32 = 5;
+    const TYPE_RECORD: u32 = 6;
+
+    const IMPORTS: &[(&str, u32)] = &[
+        ("read_register", 0),
+        ("register_len", 1),
+        ("input", 2),
+        ("predecessor_account_id", 2),
+        ("attached_deposit", 2),
+        ("storage_write", 3),
+        ("storage_read", 4),
+        ("value_return", 0),
+    ];
+
+    pub(super) fn assemble(methods: &[MockMethod]) -> Vec<u8> {
+        // Lay out the data segments: the counter key, then the name and response of
+        // each method. Arguments and account ids are read into the heap after them.
+        let mut data = COUNT_KEY.to_vec();
+        let mut layout = Vec::with_capacity(methods.len());
+        for method in methods {
+            let name = (DATA_PTR + data.len() as u32, method.name.len() as u32);
+            data.extend_from_slice(method.name.as_bytes());
+            let response = method.response.as_ref().map(|response| {
+                let ptr = DATA_PTR + data.len() as u32;
+                data.extend_from_slice(response);
+                (ptr, response.len() as u32)
+            });
+            layout.push((name, response));
+        }
+        let heap_ptr = (DATA_PTR + data.len() as u32 + 7) & !7;
+
+        let mut module = b"\0asm".to_vec();
+        module.extend_from_slice(&1u32.to_le_bytes());
+
+        section(&mut module, 1, TYPES.len(), |out| {
+            for (params, results) in TYPES {
+                out.push(0x60);
+                bytes(out, params);
+                bytes(out, results);
+            }
+        });
+
+        // Memory has to be imported rather than declared, as required by the runtime. The
+        // runtime swaps in memory of its own configured size, so the declared amount is
+        // only a minimum, which has to fit the data segments for the module to be valid.
+        let pages = (heap_ptr + PAGE_SIZE - 1) / PAGE_SIZE;
+        section(&mut module, 2, IMPORTS.len() + 1, |out| {
+            bytes(out, b"env");
+            bytes(out, b"memory");
+            out.extend_from_slice(&[0x02, 0x00]);
+            uleb(out, pages);
+            for (name, ty) in IMPORTS {
+                bytes(out, b"env");
+                bytes(out, name.as_bytes());
+                out.push(0x00);
+                uleb(out, *ty);
+            }
+        });
+
+        section(&mut module, 3, methods.len() + 1, |out| {
+            uleb(out, TYPE_RECORD);
+            for _ in methods {
+                uleb(out, TYPE_METHOD);
+            }
+        });
+
+        section(&mut module, 7, methods.len(), |out| {
+            for (i, method) in methods.iter().enumerate() {
+                bytes(out, method.name.as_bytes());
+                out.push(0x00);
+                uleb(out, RECORD + 1 + i as u32);
+            }
+        });
+
+        section(&mut module, 10, methods.len() + 1, |out| {
+            function(out, &[I64], &record_body(heap_ptr));
+            for (method, (name, response)) in methods.iter().zip(&layout) {
+                let mut body = Vec::new();
+                if method.record {
+                    i32_const(&mut body, name.0);
+                    i32_const(&mut body, name.1);
+                    call(&mut body, RECORD);
+                }
+                if let Some((ptr, len)) = response {
+                    i64_const(&mut body, *len as u64);
+                    i64_const(&mut body, *ptr as u64);
+                    call(&mut body, VALUE_RETURN);
+                }
+                function(out, &[], &body);
+            }
+        });
+
+        section(&mut module, 11, 1, |out| {
+            out.push(0x00);
+            i32_const(out, DATA_PTR);
+            out.push(0x0b);
+            bytes(out, &data);
+        });
+
+        module
+    }
+
+    /// Body of `record(name_ptr: i32, name_len: i32)`, which bumps the call counter and
+    /// writes the method name, arguments, predecessor and deposit of the current call
+    /// under keys tagged with the index of the call.
+    fn record_body(heap_ptr: u32) -> Vec<u8> {
+        const NAME_PTR: u32 = 0;
+        const NAME_LEN: u32 = 1;
+        const LEN: u32 = 2;
+
+        let mut body = Vec::new();
+        let b = &mut body;
+
+        // Load the counter into scratch space if a call has been recorded before.
+        i64_const(b, COUNT_KEY.len() as u64);
+        i64_const(b, DATA_PTR as u64);
+        i64_const(b, 0);
+        call(b, STORAGE_READ);
+        b.extend_from_slice(&[0x50, 0x45, 0x04, 0x40]); // i64.eqz, i32.eqz, if
+        i64_const(b, 0);
+        i64_const(b, COUNTER_PTR as u64);
+        call(b, READ_REGISTER);
+        b.push(0x0b); // end
+
+        // Place the index of the call after the tag byte of the key.
+        i32_const(b, KEY_PTR + 1);
+        i32_const(b, COUNTER_PTR);
+        b.extend_from_slice(&[0x29, 0x00, 0x00]); // i64.load
+        b.extend_from_slice(&[0x37, 0x00, 0x00]); // i64.store
+
+        set_tag(b, TAG_METHOD);
+        i64_const(b, KEY_LEN as u64);
+        i64_const(b, KEY_PTR as u64);
+        local_get(b, NAME_LEN);
+        b.push(0xad); // i64.extend_i32_u
+        local_get(b, NAME_PTR);
+        b.push(0xad); // i64.extend_i32_u
+        storage_write(b);
+
+        for (tag, host_fn) in [(TAG_ARGS, INPUT), (TAG_PREDECESSOR, PREDECESSOR_ACCOUNT_ID)] {
+            i64_const(b, 0);
+            call(b, host_fn);
+            i64_const(b, 0);
+            call(b, REGISTER_LEN);
+            b.push(0x21); // local.set
+            uleb(b, LEN);
+            i64_const(b, 0);
+            i64_const(b, heap_ptr as u64);
+            call(b, READ_REGISTER);
+
+            set_tag(b, tag);
+            i64_const(b, KEY_LEN as u64);
+            i64_const(b, KEY_PTR as u64);
+            local_get(b, LEN);
+            i64_const(b, heap_ptr as u64);
+            storage_write(b);
+        }
+
+        i64_const(b, DEPOSIT_PTR as u64);
+        call(b, ATTACHED_DEPOSIT);
+        set_tag(b, TAG_DEPOSIT);
+        i64_const(b, KEY_LEN as u64);
+        i64_const(b, KEY_PTR as u64);
+        i64_const(b, 16);
+        i64_const(b, DEPOSIT_PTR as u64);
+        storage_write(b);
+
+        // Bump the counter and persist it.
+        i32_const(b, COUNTER_PTR);
+        i32_const(b, COUNTER_PTR);
+        b.extend_from_slice(&[0x29, 0x00, 0x00]); // i64.load
+        i64_const(b, 1);
+        b.push(0x7c); // i64.add
+        b.extend_from_slice(&[0x37, 0x00, 0x00]); // i64.store
+        i64_const(b, COUNT_KEY.len() as u64);
+        i64_const(b, DATA_PTR as u64);
+        i64_const(b, 8);
+        i64_const(b, COUNTER_PTR as u64);
+        storage_write(b);
+
+        body
+    }
+
+    fn set_tag(out: &mut Vec<u8>, tag: u8) {
+        i32_const(out, KEY_PTR);
+        i32_const(out, tag as u32);
+        out.extend_from_slice(&[0x3a, 0x00, 0x00]); // i32.store8
+    }
+
+    /// Finish a `storage_write` whose first four arguments are already on the stack,
+    /// discarding the evicted value and the result.
+    fn storage_write(out: &mut Vec<u8>) {
+        i64_const(out, 1);
+        call(out, STORAGE_WRITE);
+        out.push(0x1a); // drop
+    }
+
+    fn section(module: &mut Vec<u8>, id: u8, count: usize, f: impl FnOnce(&mut Vec<u8>)) {
+        let mut content = Vec::new();
+        uleb(&mut content, count as u32);
+        f(&mut content);
+        module.push(id);
+        uleb(module, content.len() as u32);
+        module.extend_from_slice(&content);
+    }
+
+    fn function(out: &mut Vec<u8>, locals: &[u8], body: &[u8]) {
+        let mut func = Vec::new();
+        uleb(&mut func, locals.len() as u32);
+        for local in locals {
+            uleb(&mut func, 1);
+            func.push(*local);
+        }
+        func.extend_from_slice(body);
+        func.push(0x0b); // end
+        uleb(out, func.len() as u32);
+        out.extend_from_slice(&func);
+    }
+
+    fn bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+        uleb(out, bytes.len() as u32);
+        out.extend_from_slice(bytes);
+    }
+
+    fn call(out: &mut Vec<u8>, func: u32) {
+        out.push(0x10);
+        uleb(out, func);
+    }
+
+    fn local_get(out: &mut Vec<u8>, local: u32) {
+        out.push(0x20);
+        uleb(out, local);
+    }
+
+    fn i32_const(out: &mut Vec<u8>, value: u32) {
+        out.push(0x41);
+        sleb(out, value as i32 as i64);
+    }
+
+    fn i64_const(out: &mut Vec<u8>, value: u64) {
+        out.push(0x42);
+        sleb(out, value as i64);
+    }
+
+    fn uleb(out: &mut Vec<u8>, mut value: u32) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                out.push(byte);
+                return;
+            }
+            out.push(byte | 0x80);
+        }
+    }
+
+    fn sleb(out: &mut Vec<u8>, mut value: i64) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            let done = (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0);
+            if done {
+                out.push(byte);
+                return;
+            }
+            out.push(byte | 0x80);
+        }
+    }
+}
@@ -1,9 +1,16 @@
 use std::future::{Future, IntoFuture};
 use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
-use crate::network::Sandbox;
+use crate::network::{Faucet, Mainnet, Provider, Sandbox, Testnet};
+use crate::rpc::client::DEFAULT_STALE_TX_RETRIES;
+use crate::rpc::transport::TransportConfig;
+use crate::types::{AccountId, Gas, NearToken, TxExecutionStatus};
 use crate::{Network, Worker};
 
+use super::config::{GenesisAccount, HomeDir, SandboxConfig, ROOT_ACCOUNT_BALANCE};
 use super::server::ValidatorKey;
 
 pub(crate) type BoxFuture<'a, T> = std::pin::Pin<Box<dyn Future<Output = T> + Send + 'a>>;
@@ -30,6 +37,21 @@ pub struct NetworkBuilder<'a, T> {
     pub(crate) rpc_addr: Option<String>,
     pub(crate) validator_key: Option<ValidatorKey>,
     pub(crate) api_key: Option<String>,
+    pub(crate) transport: TransportConfig,
+    pub(crate) stale_tx_retries: u32,
+    pub(crate) wait_until: Option<TxExecutionStatus>,
+    pub(crate) remote_host: Option<String>,
+    pub(crate) docker_image: Option<String>,
+    pub(crate) faucet: Option<Arc<dyn Faucet>>,
+    pub(crate) startup_timeout: Option<Duration>,
+    pub(crate) health_check_interval: Option<Duration>,
+    pub(crate) startup_retries: u32,
+    pub(crate) port_collision_retries: u32,
+    pub(crate) sandbox_config: SandboxConfig,
+    pub(crate) seed_dump: Option<PathBuf>,
+    pub(crate) sandbox_version: Option<String>,
+    /// RPC addresses of the other nodes of the cluster the node is part of.
+    pub(crate) patch_peers: Vec<String>,
     _network: PhantomData<T>,
 }
 
@@ -56,6 +78,20 @@ impl<'a, T> NetworkBuilder<'a, T> {
             rpc_addr: None,
             validator_key: None,
             api_key: None,
+            transport: TransportConfig::default(),
+            stale_tx_retries: DEFAULT_STALE_TX_RETRIES,
+            wait_until: None,
+            remote_host: None,
+            docker_image: None,
+            faucet: None,
+            startup_timeout: None,
+            health_check_interval: None,
+            startup_retries: 0,
+            port_collision_retries: 3,
+            sandbox_config: SandboxConfig::default(),
+            seed_dump: None,
+            sandbox_version: None,
+            patch_peers: Vec::new(),
             _network: PhantomData,
         }
     }
@@ -81,6 +117,65 @@ impl<'a, T> NetworkBuilder<'a, T> {
         self.api_key = Some(api_key.into());
         self
     }
+
+    /// Sets the configuration of the HTTP transport used to talk to the RPC service,
+    /// such as using HTTP/2 or tuning the connection pool and keep-alive settings.
+    pub fn transport(mut self, transport: TransportConfig) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Sets how many times a transaction gets re-signed and resubmitted after being rejected
+    /// for a stale nonce or an expired block hash, which commonly happens when the same key
+    /// is used concurrently or the network is slow. The number of retries a transaction
+    /// took is reported by [`ExecutionFinalResult::retries`]. Defaults to 3, where 0 opts
+    /// out of retrying altogether.
+    ///
+    /// [`ExecutionFinalResult::retries`]: crate::result::ExecutionFinalResult::retries
+    pub fn stale_tx_retries(mut self, retries: u32) -> Self {
+        self.stale_tx_retries = retries;
+        self
+    }
+
+    /// Sets how far along the execution of transactions has to be before `transact` returns,
+    /// which can be overridden per transaction with `wait_until` on the transaction itself.
+    /// By default, `transact` returns once the transaction and all of its receipts have been
+    /// executed. See [`TxExecutionStatus`] for the levels.
+    pub fn wait_until(mut self, wait_until: TxExecutionStatus) -> Self {
+        self.wait_until = Some(wait_until);
+        self
+    }
+
+    fn with_provider(mut self, rpc_addr: String, provider: &Provider) -> Self {
+        self.rpc_addr = Some(rpc_addr);
+        if let Some((name, value)) = provider.auth_header() {
+            self.transport = self.transport.header(name, &value);
+        }
+        self
+    }
+}
+
+impl NetworkBuilder<'_, Mainnet> {
+    /// Use one of the well-known RPC providers instead of the default RPC node provided
+    /// by near.org. This sets the RPC addr along with the headers required to authenticate.
+    pub fn provider(self, provider: Provider) -> Self {
+        self.with_provider(provider.mainnet_url(), &provider)
+    }
+}
+
+impl NetworkBuilder<'_, Testnet> {
+    /// Use one of the well-known RPC providers instead of the default RPC node provided
+    /// by near.org. This sets the RPC addr along with the headers required to authenticate.
+    pub fn provider(self, provider: Provider) -> Self {
+        self.with_provider(provider.testnet_url(), &provider)
+    }
+
+    /// Sets the service used to create top level accounts, which by default is the helper
+    /// service provided by near.org. Useful for private networks or custom faucet services.
+    pub fn faucet(mut self, faucet: impl Faucet + 'static) -> Self {
+        self.faucet = Some(Arc::new(faucet));
+        self
+    }
 }
 
 // So far, only Sandbox makes use of validator_key.
@@ -94,4 +189,365 @@ impl NetworkBuilder<'_, Sandbox> {
         self.validator_key = Some(validator_key);
         self
     }
+
+    /// Launch the sandbox node on a remote machine over SSH instead of locally, which is
+    /// useful for running heavyweight test suites on a shared machine. `destination` is
+    /// passed as-is to `ssh` (e.g. `user@host`), where `host` also has to be reachable over
+    /// HTTP from the machine running the tests. The remote machine is required to have
+    /// `neard-sandbox` and `python3` available in its `PATH`, and key based authentication
+    /// set up. The node runs out of a fresh directory under `/tmp` on the remote machine,
+    /// which gets removed once the node is dropped.
+    pub fn remote_ssh(mut self, destination: &str) -> Self {
+        self.remote_host = Some(destination.into());
+        self
+    }
+
+    /// Launch the sandbox node in a Docker container running `image` instead of running
+    /// the `neard-sandbox` binary on the host, which helps where no prebuilt binary is
+    /// available for the host, such as on ARM machines. The RPC and network ports of the
+    /// node get mapped to free ports on the host, or the ones pinned with `rpc_port` and
+    /// `net_port`. Images without a tag get tagged with the sandbox version. The image is
+    /// required to have `neard-sandbox` available in its `PATH`, and `docker` has to be
+    /// available in the `PATH` of the machine running the tests. Like nodes launched with
+    /// `remote_ssh`, the home directory of the node is out of reach, so genesis and
+    /// config options do not apply. See also [`sandbox_docker`](crate::sandbox_docker).
+    pub fn docker_image(mut self, image: &str) -> Self {
+        self.docker_image = Some(image.into());
+        self
+    }
+
+    /// Sets how long to wait for the RPC service of the sandbox to come up before giving up.
+    /// Defaults to 10 seconds, or the value of the `NEAR_RPC_TIMEOUT_SECS` env variable.
+    pub fn startup_timeout(mut self, timeout: Duration) -> Self {
+        self.startup_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the interval in between checks on whether the RPC service of the sandbox is up.
+    /// Defaults to 500 milliseconds.
+    pub fn health_check_interval(mut self, interval: Duration) -> Self {
+        self.health_check_interval = Some(interval);
+        self
+    }
+
+    /// Restart the sandbox with fresh ports up to `retries` times when it does not come up
+    /// within the startup timeout, instead of failing right away. This only applies to
+    /// sandboxes spawned by workspaces itself. Defaults to 0.
+    pub fn startup_retries(mut self, retries: u32) -> Self {
+        self.startup_retries = retries;
+        self
+    }
+
+    /// Restart the sandbox with fresh ports up to `retries` times when the node exits
+    /// before its RPC service comes up, which happens when the ports picked for it were
+    /// taken by another process in the meantime. This only applies to sandboxes spawned
+    /// by workspaces itself. Defaults to 3.
+    pub fn port_collision_retries(mut self, retries: u32) -> Self {
+        self.port_collision_retries = retries;
+        self
+    }
+
+    /// Sets the chain id of the sandbox, instead of the one generated on initialization.
+    /// Useful for testing contracts or signing schemes that bind payloads to a chain id.
+    /// This only applies to sandboxes spawned by workspaces itself.
+    pub fn chain_id(mut self, chain_id: &str) -> Self {
+        self.sandbox_config.chain_id = Some(chain_id.into());
+        self
+    }
+
+    /// Derives the keys of the sandbox validator from `seed` rather than generating random
+    /// ones, which makes the randomness of the chain reproducible between runs for contracts
+    /// relying on `env::random_seed`.
+    ///
+    /// Each block carries a random value which the validator computes by signing the random
+    /// value of the block before it with a VRF. With the validator key pinned, the block at
+    /// a given height thus gets the same random value every run, as found in the
+    /// [`header`](crate::Block::header) of the block. The seed a
+    /// contract sees through `env::random_seed` mixes in the id of the receipt being
+    /// executed, which is derived from the hash of the transaction. It is only reproducible
+    /// when the transaction is as well, such as when signed against the same block hash
+    /// and nonce and landing at the same height. Sandboxes with different seeds, or without
+    /// one, get unrelated random values. This only applies to sandboxes spawned locally by
+    /// workspaces.
+    pub fn with_random_seed(mut self, seed: &str) -> Self {
+        self.sandbox_config.random_seed = Some(seed.into());
+        self
+    }
+
+    /// Sets the version of the `neard-sandbox` binary to run, such as `"1.35.0"`, instead
+    /// of the version this crate ships with. The binary gets downloaded on first use and
+    /// cached, so that test suites can run against different protocol releases side by
+    /// side. Ignored when the binary to run is given through the `NEAR_SANDBOX_BIN_PATH`
+    /// environment variable. This only applies to sandboxes spawned by workspaces, either
+    /// locally or in Docker, where it is the tag untagged images get pinned to.
+    pub fn version(mut self, version: &str) -> Self {
+        self.sandbox_version = Some(version.into());
+        self
+    }
+
+    /// Only produce blocks on demand with [`Worker::produce_block`] and
+    /// [`Worker::produce_blocks`], instead of on a timer. This makes the order in which
+    /// receipts execute, and the blocks they land in, reproducible between runs. Note that
+    /// transactions sent with `transact` wait on blocks getting produced, so they have to be
+    /// sent with `transact_async` and followed up with producing blocks instead. This only
+    /// applies to sandboxes spawned by workspaces itself.
+    ///
+    /// [`Worker::produce_block`]: crate::Worker::produce_block
+    /// [`Worker::produce_blocks`]: crate::Worker::produce_blocks
+    pub fn manual_block_production(mut self) -> Self {
+        self.sandbox_config.manual_blocks = true;
+        self
+    }
+
+    /// Sets the time the sandbox takes to produce a block, such as 100ms to speed up test
+    /// suites waiting on many blocks, or a couple of seconds for block timestamps to advance
+    /// like they do on a live network. The node defaults to producing a block roughly every
+    /// 600ms. Cannot be used along with [`NetworkBuilder::manual_block_production`]. This
+    /// only applies to sandboxes spawned by workspaces itself.
+    pub fn block_time(mut self, block_time: Duration) -> Self {
+        self.sandbox_config.block_time = Some(block_time);
+        self
+    }
+
+    /// Pins the port the RPC service of the sandbox listens on, for tools such as indexers
+    /// or wallets that need a stable address to attach to. Startup fails if the port is
+    /// taken. This only applies to sandboxes spawned locally by workspaces.
+    pub fn rpc_port(mut self, port: u16) -> Self {
+        self.sandbox_config.ports.rpc = Some(port);
+        self
+    }
+
+    /// Pins the port the sandbox listens on for other nodes. Startup fails if the port is
+    /// taken. This only applies to sandboxes spawned locally by workspaces.
+    pub fn net_port(mut self, port: u16) -> Self {
+        self.sandbox_config.ports.net = Some(port);
+        self
+    }
+
+    /// Picks the ports of the sandbox that are not pinned out of `range`, rather than any
+    /// port the OS hands out, such as to stay within the ports a firewall lets through.
+    /// This only applies to sandboxes spawned locally by workspaces.
+    pub fn port_range(mut self, range: std::ops::Range<u16>) -> Self {
+        self.sandbox_config.ports.range = Some(range);
+        self
+    }
+
+    /// Sets the number of blocks in an epoch of the sandbox. Lowering it makes epoch
+    /// boundaries, such as validator set changes or staking rewards, quick to reach with
+    /// [`Worker::fast_forward`](crate::Worker::fast_forward). This only applies to
+    /// sandboxes spawned by workspaces itself.
+    pub fn epoch_length(mut self, blocks: u64) -> Self {
+        self.sandbox_config
+            .set_genesis("epoch_length", serde_json::json!(blocks));
+        self
+    }
+
+    /// Sets the gas limit of a chunk in the sandbox, which caps how much gas the receipts
+    /// executed in a block can burn. This only applies to sandboxes spawned by workspaces
+    /// itself.
+    pub fn gas_limit(mut self, gas: Gas) -> Self {
+        self.sandbox_config
+            .set_genesis("gas_limit", serde_json::json!(gas.as_gas()));
+        self
+    }
+
+    /// Sets the cost of storing a byte of state in the sandbox, which is the balance an
+    /// account has to lock up per byte it stores. This only applies to sandboxes spawned
+    /// by workspaces itself. Startup fails if the node does not pick the cost up from its
+    /// genesis, instead of leaving tests to run against the default cost.
+    pub fn storage_cost_per_byte(mut self, cost: NearToken) -> Self {
+        self.sandbox_config.storage_cost_per_byte = Some(cost);
+        self.sandbox_config.set_genesis(
+            "runtime_config",
+            serde_json::json!({
+                "storage_amount_per_byte": cost.as_yoctonear().to_string(),
+            }),
+        );
+        self
+    }
+
+    /// Sets the total supply of NEAR in the sandbox, such as for contracts computing shares
+    /// of the supply. The balance of the validator account `test.near` makes up for the
+    /// difference to the balances of the other accounts in genesis, so startup fails if
+    /// those add up to more than `total_supply`. This only applies to sandboxes spawned by
+    /// workspaces itself.
+    pub fn total_supply(mut self, total_supply: NearToken) -> Self {
+        self.sandbox_config.total_supply = Some(total_supply);
+        self
+    }
+
+    /// Sets the rate the total supply inflates by at most per year, as the fraction
+    /// `numerator / denominator`, which drives the rewards validators and the protocol
+    /// treasury get at the end of each epoch. This only applies to sandboxes spawned by
+    /// workspaces itself.
+    pub fn max_inflation_rate(mut self, numerator: i32, denominator: i32) -> Self {
+        self.sandbox_config.set_genesis(
+            "max_inflation_rate",
+            serde_json::json!([numerator, denominator]),
+        );
+        self
+    }
+
+    /// Sets the fraction `numerator / denominator` of the rewards minted at the end of each
+    /// epoch which goes to the protocol treasury rather than the validators. This only
+    /// applies to sandboxes spawned by workspaces itself.
+    pub fn protocol_reward_rate(mut self, numerator: i32, denominator: i32) -> Self {
+        self.sandbox_config.set_genesis(
+            "protocol_reward_rate",
+            serde_json::json!([numerator, denominator]),
+        );
+        self
+    }
+
+    /// Sets the minimum price of a unit of gas in the sandbox, which is also the price the
+    /// chain starts out with. This only applies to sandboxes spawned by workspaces itself.
+    pub fn min_gas_price(mut self, price: NearToken) -> Self {
+        self.sandbox_config.set_genesis(
+            "min_gas_price",
+            serde_json::json!(price.as_yoctonear().to_string()),
+        );
+        self
+    }
+
+    /// Pins the price of a unit of gas in the sandbox to `price` for as long as it runs,
+    /// rather than letting it follow how full blocks are, such as to check how a contract
+    /// computing refunds or deposits off of the gas price copes with pricier gas. The gas
+    /// price is part of every block header going back to genesis, so it can only be
+    /// picked up front and not changed on a running sandbox. This only applies to
+    /// sandboxes spawned by workspaces itself.
+    pub fn gas_price(mut self, price: NearToken) -> Self {
+        let price = serde_json::json!(price.as_yoctonear().to_string());
+        self.sandbox_config
+            .set_genesis("min_gas_price", price.clone());
+        self.sandbox_config.set_genesis("max_gas_price", price);
+        self
+    }
+
+    /// Starts the sandbox out at protocol version `version` rather than the latest one the
+    /// binary supports, such as to check how a contract fares across the activation of a
+    /// protocol feature. The sandbox validator votes for the latest version right away, so
+    /// the chain upgrades on its own a couple of epochs in, which
+    /// [`Worker::upgrade_protocol`](crate::Worker::upgrade_protocol) fast forwards to. Tests
+    /// running against the older version should keep within the first epoch, raising the
+    /// epoch length if need be. This only applies to sandboxes spawned by workspaces itself.
+    pub fn protocol_version(mut self, version: u32) -> Self {
+        self.sandbox_config
+            .set_genesis("protocol_version", serde_json::json!(version));
+        self
+    }
+
+    /// Runs the sandbox with more than one shard, splitting the account space at
+    /// `boundary_accounts`. Accounts sorting before the first boundary account land on
+    /// shard 0, the ones from the first up to the second boundary account on shard 1, and
+    /// so on. Receipts between accounts on different shards take a block longer to get
+    /// through, like they do on a live network, so this helps testing how contracts cope
+    /// with that. Where an account lands can be checked with
+    /// [`Worker::shard_id`](crate::Worker::shard_id). This only applies to sandboxes
+    /// spawned by workspaces itself.
+    /// ```ignore
+    /// // Puts accounts from "mm" onwards, such as "zed.test.near", on a shard of their own.
+    /// let worker = near_workspaces::sandbox()
+    ///     .shard_layout(vec!["mm".parse()?])
+    ///     .await?;
+    /// ```
+    pub fn shard_layout(mut self, boundary_accounts: Vec<AccountId>) -> Self {
+        let mut boundary_accounts = boundary_accounts;
+        boundary_accounts.sort();
+        boundary_accounts.dedup();
+        self.sandbox_config.boundary_accounts = Some(boundary_accounts);
+        self
+    }
+
+    /// Bakes account `id` holding `balance` into the genesis of the sandbox, which is a
+    /// lot quicker than creating accounts one transaction at a time when a test needs
+    /// many of them. The account can be retrieved once the sandbox is up with
+    /// [`Worker::genesis_account`](crate::Worker::genesis_account). Use
+    /// [`NetworkBuilder::genesis_account`] to pick its key or deploy a contract to it.
+    /// This only applies to sandboxes spawned by workspaces itself.
+    /// ```ignore
+    /// let alice: AccountId = "alice.test.near".parse()?;
+    /// let worker = near_workspaces::sandbox()
+    ///     .with_genesis_account(alice.clone(), NearToken::from_near(10_000))
+    ///     .await?;
+    /// let alice = worker.genesis_account(&alice)?;
+    /// ```
+    pub fn with_genesis_account(self, id: AccountId, balance: NearToken) -> Self {
+        self.genesis_account(GenesisAccount::new(id, balance))
+    }
+
+    /// Bakes `account` into the genesis of the sandbox. See
+    /// [`NetworkBuilder::with_genesis_account`] for more details.
+    pub fn genesis_account(mut self, account: GenesisAccount) -> Self {
+        self.sandbox_config.genesis_accounts.push(account);
+        self
+    }
+
+    /// Uses `id` as the root account of the sandbox in place of `test.near`, such that
+    /// tests can lay out a realistic tree of accounts such as `myapp.near` and
+    /// `sub.myapp.near` off of [`Worker::root_account`](crate::Worker::root_account). The
+    /// account gets baked into genesis with a balance on par with `test.near`, signing
+    /// with the same key dev accounts get. This only applies to sandboxes spawned by
+    /// workspaces itself.
+    /// ```ignore
+    /// let worker = near_workspaces::sandbox()
+    ///     .root_account("myapp.near".parse()?)
+    ///     .await?;
+    /// let app = worker.root_account()?;
+    /// let sub = app.create_subaccount("sub").transact().await?.into_result()?;
+    /// assert_eq!(sub.id().as_str(), "sub.myapp.near");
+    /// ```
+    pub fn root_account(mut self, id: AccountId) -> Self {
+        self.sandbox_config.root_account = Some(id.clone());
+        self.genesis_account(GenesisAccount::new(id, ROOT_ACCOUNT_BALANCE))
+    }
+
+    /// Overrides `value` at `key` in the genesis of the sandbox, for any genesis parameter
+    /// without a dedicated method on the builder. Objects get merged with what was already
+    /// overridden at `key`. This only applies to sandboxes spawned by workspaces itself.
+    /// ```ignore
+    /// let worker = near_workspaces::sandbox()
+    ///     .genesis("max_inflation_rate", serde_json::json!([1, 40]))
+    ///     .await?;
+    /// ```
+    pub fn genesis(mut self, key: &str, value: serde_json::Value) -> Self {
+        self.sandbox_config.set_genesis(key, value);
+        self
+    }
+
+    /// Create the home directory of the sandbox inside of `dir` rather than the temp dir,
+    /// such as a RAM-backed filesystem mounted on a CI runner. This only applies to
+    /// sandboxes spawned locally by workspaces.
+    pub fn home_dir_in(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.sandbox_config.home_parent = Some(dir.into());
+        self
+    }
+
+    /// Set up the home directory of the sandbox at `path`, which is kept around along with
+    /// the chain data once the sandbox is dropped. The chain can then be looked into after
+    /// a test failed, or picked back up with [`sandbox_from_home`](crate::sandbox_from_home).
+    /// This only applies to sandboxes spawned locally by workspaces.
+    pub fn keep_home_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.sandbox_config.home = Some(HomeDir::Keep(path.into()));
+        self
+    }
+
+    /// Place the home directory of the sandbox on the RAM-backed filesystem at `/dev/shm`,
+    /// which speeds up IO heavy test suites and avoids wearing out the disk. The home
+    /// directory is removed once the sandbox is dropped, to free up the memory it holds.
+    /// Fails on startup if no such filesystem is available, in which case one can be
+    /// mounted and passed to [`NetworkBuilder::home_dir_in`] instead. This only applies to
+    /// sandboxes spawned locally by workspaces.
+    pub fn tmpfs(mut self) -> Self {
+        self.sandbox_config.tmpfs = true;
+        self
+    }
+
+    /// Seed the sandbox with all of the accounts, contracts and contract data out of the
+    /// state dump at `path` once it is up, which stands up a "mini mainnet" with realistic
+    /// state for integration tests. See the [`dump`](crate::dump) module for how to produce
+    /// a dump out of the data directory of a node.
+    pub fn seed_from_dump(mut self, path: impl Into<PathBuf>) -> Self {
+        self.seed_dump = Some(path.into());
+        self
+    }
 }
@@ -0,0 +1,162 @@
+//! A small local network of sandbox validators, for testing behavior a single node cannot
+//! reproduce such as validator rotation, missed chunks or nodes dropping out. See
+//! [`sandbox_cluster`](crate::sandbox_cluster) for how to spin one up.
+
+use std::fs::File;
+use std::path::Path;
+
+use crate::error::{ErrorKind, SandboxErrorCode};
+use crate::network::config::{set_boot_nodes, set_sandbox_configs, PortConfig};
+use crate::network::sandbox::validator_signer;
+use crate::network::server::{init_localnet, SandboxServer};
+use crate::network::{Sandbox, ValidatorKey};
+use crate::result::Result;
+use crate::rpc::client::Client;
+use crate::rpc::transport::TransportConfig;
+use crate::types::AccountId;
+use crate::Worker;
+
+/// Handle to a validator node of a sandbox cluster. The node runs for as long as the
+/// handle is around.
+pub struct SandboxNode {
+    account_id: AccountId,
+    server: SandboxServer,
+    /// Version of the `neard-sandbox` binary the node runs.
+    version: String,
+    /// RPC addresses of the other nodes of the cluster.
+    peers: Vec<String>,
+}
+
+impl SandboxNode {
+    /// The account the node validates as.
+    pub fn account_id(&self) -> &AccountId {
+        &self.account_id
+    }
+
+    /// Address of the RPC service of the node.
+    pub fn rpc_addr(&self) -> String {
+        self.server.rpc_addr()
+    }
+
+    /// Port of the RPC service of the node.
+    pub fn rpc_port(&self) -> Option<u16> {
+        self.server.rpc_port()
+    }
+
+    /// Port the node talks to the other nodes over.
+    pub fn net_port(&self) -> Option<u16> {
+        self.server.net_port()
+    }
+
+    /// Home directory of the node.
+    pub fn home_dir(&self) -> Result<&Path> {
+        self.server.home_dir().ok_or_else(|| {
+            SandboxErrorCode::InitFailure.message("cluster node has no local home directory")
+        })
+    }
+
+    /// Stop the node, such as to have it miss its turn producing blocks and chunks. The
+    /// rest of the cluster keeps producing blocks as long as more than two thirds of the
+    /// stake stays online.
+    pub async fn stop(&self) -> Result<()> {
+        self.server.stop().await
+    }
+
+    /// Start a stopped node back up, waiting for its RPC service to come up. The node
+    /// catches up with the rest of the cluster from there.
+    pub async fn start(&self) -> Result<()> {
+        self.server.start()?;
+        Client::new(&self.rpc_addr(), None, &TransportConfig::default())?
+            .wait_for_rpc()
+            .await
+    }
+
+    /// Grab a [`Worker`] talking to this node, with the validator account of this node as
+    /// its root account. State patched through the worker gets patched into every node of
+    /// the cluster, since nodes with diverging state would fail to agree on blocks.
+    pub async fn worker(&self) -> Result<Worker<Sandbox>> {
+        let mut builder = crate::sandbox()
+            .rpc_addr(&self.rpc_addr())
+            .version(&self.version)
+            .validator_key(ValidatorKey::HomeDir(self.home_dir()?.to_path_buf()));
+        builder.patch_peers = self.peers.clone();
+        builder.await
+    }
+}
+
+impl std::fmt::Debug for SandboxNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SandboxNode")
+            .field("account_id", &self.account_id)
+            .field("rpc_port", &self.rpc_port())
+            .field("net_port", &self.net_port())
+            .finish()
+    }
+}
+
+pub(crate) async fn spawn_cluster(
+    version: &str,
+    validators: usize,
+) -> Result<(Worker<Sandbox>, Vec<SandboxNode>)> {
+    if validators == 0 {
+        return Err(SandboxErrorCode::InitFailure.message("a cluster needs at least one validator"));
+    }
+
+    let mut nodes: Vec<SandboxNode> = Vec::with_capacity(validators);
+    for home_dir in init_localnet(version, validators).await? {
+        set_sandbox_configs(&home_dir)?;
+        // Every node finds the rest of the cluster through the first one.
+        if let Some(first) = nodes.first() {
+            let net_port = first.net_port().ok_or_else(|| {
+                SandboxErrorCode::InitFailure.message("cluster node has no net port")
+            })?;
+            let boot_node = format!(
+                "{}@127.0.0.1:{}",
+                node_public_key(first.home_dir()?)?,
+                net_port
+            );
+            set_boot_nodes(&home_dir, &boot_node)?;
+        }
+
+        let account_id = validator_signer(&ValidatorKey::HomeDir(home_dir.clone()))?.account_id;
+        let server =
+            SandboxServer::spawn_in(version, home_dir, None, &PortConfig::default()).await?;
+        nodes.push(SandboxNode {
+            account_id,
+            server,
+            version: version.to_string(),
+            peers: Vec::new(),
+        });
+    }
+
+    let addrs: Vec<String> = nodes.iter().map(SandboxNode::rpc_addr).collect();
+    for (i, node) in nodes.iter_mut().enumerate() {
+        node.peers = addrs
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != i)
+            .map(|(_, addr)| addr.clone())
+            .collect();
+    }
+
+    for node in &mut nodes {
+        Client::new(&node.rpc_addr(), None, &TransportConfig::default())?
+            .wait_for_rpc()
+            .await?;
+        node.server.unlock_lockfiles()?;
+    }
+    let worker = nodes[0].worker().await?;
+
+    Ok((worker, nodes))
+}
+
+/// Public key the node at `home_dir` identifies itself with to other nodes.
+fn node_public_key(home_dir: &Path) -> Result<String> {
+    let file = File::open(home_dir.join("node_key.json")).map_err(|e| ErrorKind::Io.custom(e))?;
+    let key: serde_json::Value =
+        serde_json::from_reader(file).map_err(|e| ErrorKind::DataConversion.custom(e))?;
+    key["public_key"]
+        .as_str()
+        .map(String::from)
+        .ok_or_else(|| ErrorKind::DataConversion.message("node_key.json is missing the public key"))
+}
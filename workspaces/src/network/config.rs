@@ -12,27 +12,399 @@
 
 use std::fs::File;
 use std::io::BufReader;
-use std::path::Path;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use serde_json::Value;
+use near_primitives::state_record::StateRecord;
+use serde_json::{Map, Value};
 
-use crate::error::ErrorKind;
-use crate::Result;
+use crate::error::{ErrorKind, SandboxErrorCode};
+use crate::network::DEV_ACCOUNT_SEED;
+use crate::types::{AccessKey, AccountId, KeyType, NearToken, SecretKey};
+use crate::{CryptoHash, Result};
+
+/// RAM-backed filesystem available on most Linux distributions.
+const TMPFS_DIR: &str = "/dev/shm";
+
+/// Balance of a root account baked into genesis, on par with what the validator account
+/// of a fresh sandbox holds.
+pub(crate) const ROOT_ACCOUNT_BALANCE: NearToken = NearToken::from_near(1_000_000_000);
+
+/// Delay between blocks produced on a timer, for nodes only producing blocks on demand.
+const MANUAL_BLOCK_DELAY_SECS: u64 = 24 * 60 * 60;
+
+/// Configuration of a sandbox node spawned by workspaces, applied to its home directory
+/// before the node gets started up.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SandboxConfig {
+    pub(crate) chain_id: Option<String>,
+    /// Entries to override in `genesis.json`, merged over the generated one.
+    pub(crate) genesis: Map<String, Value>,
+    /// Accounts to add to the records of `genesis.json`.
+    pub(crate) genesis_accounts: Vec<GenesisAccount>,
+    /// Total supply of the chain, made up for by the balance of the validator account.
+    pub(crate) total_supply: Option<NearToken>,
+    /// Storage cost per byte set in genesis, checked against what the node charges once up.
+    pub(crate) storage_cost_per_byte: Option<NearToken>,
+    /// Directory to create the home directory of the node in, instead of the temp dir.
+    pub(crate) home_parent: Option<PathBuf>,
+    /// Whether to place the home directory of the node on a RAM-backed filesystem.
+    pub(crate) tmpfs: bool,
+    /// Home directory to run the node out of, instead of a fresh temporary one.
+    pub(crate) home: Option<HomeDir>,
+    /// Whether blocks only get produced on demand, rather than on a timer.
+    pub(crate) manual_blocks: bool,
+    /// Time the node takes to produce a block, in place of the default of nearcore.
+    pub(crate) block_time: Option<Duration>,
+    pub(crate) ports: PortConfig,
+    /// Accounts splitting the account space into shards, for running more than one shard.
+    pub(crate) boundary_accounts: Option<Vec<AccountId>>,
+    /// Genesis account to use as the root account, in place of the validator account.
+    pub(crate) root_account: Option<AccountId>,
+    /// Seed to derive the keys of the node from, which drive the randomness of its blocks.
+    pub(crate) random_seed: Option<String>,
+}
+
+/// Ports for the node to listen on, where unset ones are picked at random.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PortConfig {
+    pub(crate) rpc: Option<u16>,
+    pub(crate) net: Option<u16>,
+    /// Range to pick the ports that are not pinned out of.
+    pub(crate) range: Option<Range<u16>>,
+}
+
+/// Home directory at a path given by the user.
+#[derive(Clone, Debug)]
+pub(crate) enum HomeDir {
+    /// Set up a fresh home directory at the path, which is kept once the node is gone.
+    Keep(PathBuf),
+    /// Run the node out of an existing home directory, picking up the chain where it
+    /// was left off.
+    Reopen(PathBuf),
+}
+
+impl SandboxConfig {
+    /// The directory to create the home directory of the node in, where `None` stands
+    /// for the default temp dir.
+    pub(crate) fn home_parent(&self) -> Result<Option<PathBuf>> {
+        if self.tmpfs {
+            let tmpfs = Path::new(TMPFS_DIR);
+            if !tmpfs.is_dir() {
+                return Err(SandboxErrorCode::InitFailure.message(format!(
+                    "no RAM-backed filesystem found at {TMPFS_DIR}, mount one and point \
+                     `home_dir_in` to it instead"
+                )));
+            }
+            return Ok(Some(tmpfs.to_path_buf()));
+        }
+
+        Ok(self.home_parent.clone())
+    }
+
+    /// Override `value` at `key` in the genesis of the node, merging it with what was
+    /// overridden there before if both are objects.
+    pub(crate) fn set_genesis(&mut self, key: &str, value: Value) {
+        match self.genesis.get_mut(key) {
+            Some(current) => json_patch::merge(current, &value),
+            None => {
+                self.genesis.insert(key.to_string(), value);
+            }
+        }
+    }
+
+    /// Apply the configuration to the genesis of the node located at `home_dir`.
+    pub(crate) fn apply(&self, home_dir: impl AsRef<Path>) -> Result<()> {
+        let mut genesis = self.genesis.clone();
+        if let Some(chain_id) = &self.chain_id {
+            genesis.insert("chain_id".into(), chain_id.clone().into());
+        }
+        if !genesis.is_empty() {
+            overwrite_file(&home_dir, "genesis.json", Value::Object(genesis))?;
+        }
+        if !self.genesis_accounts.is_empty() {
+            add_genesis_accounts(&home_dir, &self.genesis_accounts)?;
+        }
+        if let Some(total_supply) = self.total_supply {
+            set_total_supply(&home_dir, total_supply)?;
+        }
+        if let Some(boundary_accounts) = &self.boundary_accounts {
+            set_shard_layout(&home_dir, boundary_accounts)?;
+        }
+        match (self.manual_blocks, self.block_time) {
+            (true, Some(_)) => {
+                return Err(SandboxErrorCode::InitFailure
+                    .message("`block_time` cannot be used along with `manual_block_production`"));
+            }
+            (true, None) => {
+                // Push back producing blocks on a timer far enough for it to never happen
+                // within a test, leaving fast forwarding as the only way to produce blocks.
+                let delay = Duration::from_secs(MANUAL_BLOCK_DELAY_SECS);
+                set_block_delays(&home_dir, delay, delay, delay)?;
+            }
+            (false, Some(block_time)) => {
+                // Keep roughly to the ratios between the delays nearcore defaults to, where a block
+                // gets produced at the minimum delay as long as there are chunks for it.
+                set_block_delays(&home_dir, block_time, block_time * 3, block_time * 10)?;
+            }
+            (false, None) => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// An account baked into the genesis of a sandbox, so that it exists from the very first
+/// block without having to be created by a transaction. Added to a sandbox with
+/// `sandbox().genesis_account(account)`, and retrieved once the sandbox is up with
+/// [`Worker::genesis_account`](crate::Worker::genesis_account).
+#[derive(Clone, Debug)]
+pub struct GenesisAccount {
+    pub(crate) id: AccountId,
+    balance: NearToken,
+    pub(crate) secret_key: SecretKey,
+    code: Option<Vec<u8>>,
+}
+
+impl GenesisAccount {
+    /// An account `id` holding `balance`, with the same full access key as dev accounts
+    /// get unless another one is given with [`GenesisAccount::secret_key`].
+    pub fn new(id: AccountId, balance: NearToken) -> Self {
+        Self {
+            id,
+            balance,
+            secret_key: SecretKey::from_seed(KeyType::ED25519, DEV_ACCOUNT_SEED),
+            code: None,
+        }
+    }
+
+    /// Sets the secret key of the full access key of the account.
+    pub fn secret_key(mut self, secret_key: SecretKey) -> Self {
+        self.secret_key = secret_key;
+        self
+    }
+
+    /// Deploys `wasm` to the account, so that it starts out as a contract.
+    pub fn code(mut self, wasm: impl Into<Vec<u8>>) -> Self {
+        self.code = Some(wasm.into());
+        self
+    }
+
+    fn into_records(self) -> Vec<StateRecord> {
+        let code_hash = self
+            .code
+            .as_deref()
+            .map(CryptoHash::hash_bytes)
+            .unwrap_or_default();
+        let mut records = vec![
+            StateRecord::Account {
+                account_id: self.id.clone(),
+                account: near_primitives::account::Account::new(
+                    self.balance.as_yoctonear(),
+                    0,
+                    near_primitives::hash::CryptoHash(code_hash.0),
+                    // Storage usage gets computed by the node when applying genesis.
+                    0,
+                ),
+            },
+            StateRecord::AccessKey {
+                account_id: self.id.clone(),
+                public_key: self.secret_key.public_key().into(),
+                access_key: AccessKey::full_access().into(),
+            },
+        ];
+        if let Some(code) = self.code {
+            records.push(StateRecord::Contract {
+                account_id: self.id,
+                code,
+            });
+        }
+        records
+    }
+}
+
+/// Add the records of `accounts` to `$home_dir/genesis.json`, along with their balances
+/// to the total supply which the node checks the records against.
+fn add_genesis_accounts(home_dir: impl AsRef<Path>, accounts: &[GenesisAccount]) -> Result<()> {
+    let path = home_dir.as_ref().join("genesis.json");
+    let genesis_file = File::open(&path).map_err(|err| ErrorKind::Io.custom(err))?;
+    let mut genesis: Value = serde_json::from_reader(BufReader::new(genesis_file))
+        .map_err(|err| ErrorKind::DataConversion.custom(err))?;
+
+    let total_supply = genesis["total_supply"]
+        .as_str()
+        .and_then(|supply| supply.parse::<u128>().ok())
+        .ok_or_else(|| {
+            SandboxErrorCode::InitFailure.message("genesis.json is missing the total supply")
+        })?;
+    let added: u128 = accounts
+        .iter()
+        .map(|account| account.balance.as_yoctonear())
+        .sum();
+    genesis["total_supply"] = Value::String((total_supply + added).to_string());
+
+    let records = genesis["records"].as_array_mut().ok_or_else(|| {
+        SandboxErrorCode::InitFailure.message("genesis.json does not hold its records inline")
+    })?;
+    for account in accounts {
+        for record in account.clone().into_records() {
+            records.push(
+                serde_json::to_value(record)
+                    .map_err(|err| ErrorKind::DataConversion.custom(err))?,
+            );
+        }
+    }
+
+    let genesis_file = File::create(&path).map_err(|err| ErrorKind::Io.custom(err))?;
+    serde_json::to_writer(genesis_file, &genesis).map_err(|err| ErrorKind::Io.custom(err))?;
+
+    Ok(())
+}
+
+/// Set the total supply of the chain in `$home_dir/genesis.json` to `total_supply`, taking
+/// the difference out of or adding it to the balance of the validator account, since the
+/// node checks the total supply against the balances of the accounts.
+fn set_total_supply(home_dir: impl AsRef<Path>, total_supply: NearToken) -> Result<()> {
+    let path = home_dir.as_ref().join("genesis.json");
+    let genesis_file = File::open(&path).map_err(|err| ErrorKind::Io.custom(err))?;
+    let mut genesis: Value = serde_json::from_reader(BufReader::new(genesis_file))
+        .map_err(|err| ErrorKind::DataConversion.custom(err))?;
+
+    let current = genesis["total_supply"]
+        .as_str()
+        .and_then(|supply| supply.parse::<u128>().ok())
+        .ok_or_else(|| {
+            SandboxErrorCode::InitFailure.message("genesis.json is missing the total supply")
+        })?;
+    let validator = genesis["validators"][0]["account_id"]
+        .as_str()
+        .map(String::from)
+        .ok_or_else(|| {
+            SandboxErrorCode::InitFailure.message("genesis.json is missing its validators")
+        })?;
+    let records = genesis["records"].as_array_mut().ok_or_else(|| {
+        SandboxErrorCode::InitFailure.message("genesis.json does not hold its records inline")
+    })?;
+    let account = records
+        .iter_mut()
+        .find_map(|record| match record.get_mut("Account") {
+            Some(account) if account["account_id"] == validator.as_str() => {
+                Some(&mut account["account"])
+            }
+            _ => None,
+        })
+        .ok_or_else(|| {
+            SandboxErrorCode::InitFailure.message(format!(
+                "genesis.json is missing the account of {validator}"
+            ))
+        })?;
+    let amount = account["amount"]
+        .as_str()
+        .and_then(|amount| amount.parse::<u128>().ok())
+        .ok_or_else(|| {
+            SandboxErrorCode::InitFailure.message(format!(
+                "genesis.json is missing the balance of {validator}"
+            ))
+        })?;
+    let amount = (amount + total_supply.as_yoctonear())
+        .checked_sub(current)
+        .ok_or_else(|| {
+            SandboxErrorCode::InitFailure.message(format!(
+                "total supply of {total_supply} is less than the balances of the accounts \
+                 other than {validator} add up to"
+            ))
+        })?;
+    account["amount"] = Value::String(amount.to_string());
+    genesis["total_supply"] = Value::String(total_supply.as_yoctonear().to_string());
+
+    let genesis_file = File::create(&path).map_err(|err| ErrorKind::Io.custom(err))?;
+    serde_json::to_writer(genesis_file, &genesis).map_err(|err| ErrorKind::Io.custom(err))?;
+
+    Ok(())
+}
+
+/// Split the account space of the chain in `$home_dir/genesis.json` into shards at
+/// `boundary_accounts`, with every shard getting as many producer seats as there are in total.
+fn set_shard_layout(home_dir: impl AsRef<Path>, boundary_accounts: &[AccountId]) -> Result<()> {
+    let path = home_dir.as_ref().join("genesis.json");
+    let genesis_file = File::open(&path).map_err(|err| ErrorKind::Io.custom(err))?;
+    let genesis: Value = serde_json::from_reader(BufReader::new(genesis_file))
+        .map_err(|err| ErrorKind::DataConversion.custom(err))?;
+    let seats = genesis["num_block_producer_seats"]
+        .as_u64()
+        .ok_or_else(|| {
+            SandboxErrorCode::InitFailure.message("genesis.json is missing the producer seats")
+        })?;
+
+    let num_shards = boundary_accounts.len() + 1;
+    overwrite_file(
+        home_dir,
+        "genesis.json",
+        serde_json::json!({
+            "shard_layout": {
+                "V1": {
+                    "boundary_accounts": boundary_accounts,
+                    "shards_split_map": null,
+                    "to_parent_shard_map": null,
+                    "version": 1,
+                },
+            },
+            "num_block_producer_seats_per_shard": vec![seats; num_shards],
+            "avg_hidden_validator_seats_per_shard": vec![0; num_shards],
+        }),
+    )
+}
+
+/// Chain id to tag the logs of nodes with when their genesis does not have one.
+const DEFAULT_CHAIN_ID: &str = "sandbox";
+
+/// The chain id in `$home_dir/genesis.json`, or [`DEFAULT_CHAIN_ID`] when the genesis does not
+/// have one or cannot be read, since the chain id is only used to tell nodes apart.
+pub(crate) fn chain_id(home_dir: impl AsRef<Path>) -> String {
+    let path = home_dir.as_ref().join("genesis.json");
+    let genesis = File::open(&path)
+        .map_err(|err| ErrorKind::Io.custom(err))
+        .and_then(|file| {
+            serde_json::from_reader::<_, Value>(BufReader::new(file))
+                .map_err(|err| ErrorKind::DataConversion.custom(err))
+        });
+    match genesis {
+        Ok(genesis) => match genesis["chain_id"].as_str() {
+            Some(chain_id) => chain_id.to_string(),
+            None => DEFAULT_CHAIN_ID.to_string(),
+        },
+        Err(err) => {
+            tracing::debug!(
+                target: "workspaces",
+                "failed to read the chain id out of {:?}: {}",
+                path,
+                err
+            );
+            DEFAULT_CHAIN_ID.to_string()
+        }
+    }
+}
 
 /// Overwrite the $home_dir/config.json file over a set of entries. `value` will be used per (key, value) pair
 /// where value can also be another dict. This recursively sets all entry in `value` dict to the config
 /// dict, and saves back into `home_dir` at the end of the day.
 fn overwrite(home_dir: impl AsRef<Path>, value: Value) -> Result<()> {
+    overwrite_file(home_dir, "config.json", value)
+}
+
+/// Similar to [`overwrite`], but for any JSON file in $home_dir such as genesis.json.
+fn overwrite_file(home_dir: impl AsRef<Path>, file_name: &str, value: Value) -> Result<()> {
     let home_dir = home_dir.as_ref();
     let config_file =
-        File::open(home_dir.join("config.json")).map_err(|err| ErrorKind::Io.custom(err))?;
+        File::open(home_dir.join(file_name)).map_err(|err| ErrorKind::Io.custom(err))?;
     let config = BufReader::new(config_file);
     let mut config: Value =
         serde_json::from_reader(config).map_err(|err| ErrorKind::DataConversion.custom(err))?;
 
     json_patch::merge(&mut config, &value);
     let config_file =
-        File::create(home_dir.join("config.json")).map_err(|err| ErrorKind::Io.custom(err))?;
+        File::create(home_dir.join(file_name)).map_err(|err| ErrorKind::Io.custom(err))?;
     serde_json::to_writer(config_file, &config).map_err(|err| ErrorKind::Io.custom(err))?;
 
     Ok(())
@@ -56,6 +428,37 @@ where
     }
 }
 
+/// Point the node at `home_dir` to the nodes to connect to on startup, given as a comma
+/// separated list of `public_key@addr` entries.
+pub(crate) fn set_boot_nodes(home_dir: impl AsRef<Path>, boot_nodes: &str) -> Result<()> {
+    overwrite(
+        home_dir,
+        serde_json::json!({ "network": { "boot_nodes": boot_nodes } }),
+    )
+}
+
+/// Set the delays the node at `home_dir` produces blocks with: at least `min` after the
+/// previous block, and at most `max` after it while waiting on chunks, or `wait` while
+/// waiting on approvals.
+fn set_block_delays(
+    home_dir: impl AsRef<Path>,
+    min: Duration,
+    max: Duration,
+    wait: Duration,
+) -> Result<()> {
+    let delay = |d: Duration| serde_json::json!({ "secs": d.as_secs(), "nanos": d.subsec_nanos() });
+    overwrite(
+        home_dir,
+        serde_json::json!({
+            "consensus": {
+                "min_block_production_delay": delay(min),
+                "max_block_production_delay": delay(max),
+                "max_block_wait_delay": delay(wait),
+            }
+        }),
+    )
+}
+
 /// Set extra configs for the sandbox defined by workspaces.
 pub(crate) fn set_sandbox_configs(home_dir: impl AsRef<Path>) -> Result<()> {
     overwrite(
@@ -17,7 +17,9 @@ impl FromNetworkBuilder for Custom {
         let rpc_url = build
             .rpc_addr
             .expect("rpc address should be provided for custom network");
-        let client = Client::new(&rpc_url, build.api_key)?;
+        let client = Client::new(&rpc_url, build.api_key, &build.transport)?
+            .with_stale_tx_retries(build.stale_tx_retries)
+            .with_wait_until(build.wait_until);
         client.wait_for_rpc().await?;
 
         Ok(Self {
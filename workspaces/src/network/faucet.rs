@@ -0,0 +1,61 @@
+//! Account creation services used to create top level accounts on networks where we do
+//! not own the keys of the registrar, such as testnet.
+
+use async_trait::async_trait;
+use url::Url;
+
+use crate::result::Result;
+use crate::rpc::tool;
+use crate::rpc::transport::TransportConfig;
+use crate::types::{AccountId, PublicKey};
+
+/// Service that is able to create new top level accounts on a network on our behalf. This
+/// is what [`Worker::create_tla`] and [`Worker::dev_create_account`] use on testnet, and can
+/// be supplied to a network builder to plug in a custom account creation service:
+/// ```ignore
+/// let worker = near_workspaces::testnet()
+///     .rpc_addr("https://rpc.my-private-network.org")
+///     .faucet(HelperFaucet::new("https://helper.my-private-network.org".parse()?))
+///     .await?;
+/// ```
+///
+/// [`Worker::create_tla`]: crate::Worker::create_tla
+/// [`Worker::dev_create_account`]: crate::Worker::dev_create_account
+#[async_trait]
+pub trait Faucet: Send + Sync {
+    /// Create the account `id` with `pk` as its full access key.
+    async fn create_account(&self, id: AccountId, pk: PublicKey) -> Result<()>;
+}
+
+/// [`Faucet`] speaking the protocol of the account creation helper service provided by
+/// near.org, which is also what self hosted instances of the helper service speak.
+#[derive(Clone, Debug)]
+pub struct HelperFaucet {
+    http_client: reqwest::Client,
+    url: Url,
+}
+
+impl HelperFaucet {
+    /// Create a new faucet that talks to the helper service located at `url`.
+    pub fn new(url: Url) -> Self {
+        Self::with_client(reqwest::Client::new(), url)
+    }
+
+    /// Create a new faucet that talks to the helper service located at `url` through the
+    /// proxy and root certificates of `transport`, such as the one the network is
+    /// configured with. Fails if the transport is invalid.
+    pub fn with_transport(url: Url, transport: &TransportConfig) -> Result<Self> {
+        Ok(Self::with_client(transport.build_http_client()?, url))
+    }
+
+    pub(crate) fn with_client(http_client: reqwest::Client, url: Url) -> Self {
+        Self { http_client, url }
+    }
+}
+
+#[async_trait]
+impl Faucet for HelperFaucet {
+    async fn create_account(&self, id: AccountId, pk: PublicKey) -> Result<()> {
+        tool::url_create_account(&self.http_client, self.url.clone(), id, pk).await
+    }
+}
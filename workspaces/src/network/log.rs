@@ -0,0 +1,160 @@
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tracing::Level;
+
+use crate::error::ErrorKind;
+use crate::result::Result;
+
+/// How often the log file of a node is checked for new lines to forward to `tracing`.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Handle to the log file of a sandbox node spawned by workspaces, which everything the
+/// node prints ends up in. How much the node logs is controlled with the
+/// `NEAR_ENABLE_SANDBOX_LOG` and `NEAR_SANDBOX_LOG` environment variables, where only
+/// errors get logged by default.
+#[derive(Clone, Debug)]
+pub struct NodeLog {
+    path: PathBuf,
+}
+
+impl NodeLog {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Path to the log file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read the whole log, as written so far.
+    pub fn read(&self) -> Result<String> {
+        fs::read_to_string(&self.path).map_err(|e| {
+            ErrorKind::Io.full(format!("failed to read sandbox log {:?}", self.path), e)
+        })
+    }
+
+    /// Read the last `lines` lines of the log, which is usually where the node reported
+    /// what went wrong.
+    pub fn tail(&self, lines: usize) -> Result<Vec<String>> {
+        let log = self.read()?;
+        let all: Vec<_> = log.lines().collect();
+        let start = all.len().saturating_sub(lines);
+        Ok(all[start..].iter().map(|line| line.to_string()).collect())
+    }
+
+    /// Read the complete lines written to the log past byte `offset`, along with the
+    /// offset right past the last of them.
+    pub(crate) fn read_from(&self, offset: u64) -> Result<(Vec<String>, u64)> {
+        let mut file = File::open(&self.path).map_err(|e| {
+            ErrorKind::Io.full(format!("failed to open sandbox log {:?}", self.path), e)
+        })?;
+        let mut buf = Vec::new();
+        file.seek(SeekFrom::Start(offset))
+            .and_then(|_| file.read_to_end(&mut buf))
+            .map_err(|e| {
+                ErrorKind::Io.full(format!("failed to read sandbox log {:?}", self.path), e)
+            })?;
+
+        // Leave a line that is still being written to for the next read.
+        let complete = match buf.iter().rposition(|b| *b == b'\n') {
+            Some(pos) => pos + 1,
+            None => 0,
+        };
+        let lines = String::from_utf8_lossy(&buf[..complete])
+            .lines()
+            .map(|line| line.to_string())
+            .collect();
+        Ok((lines, offset + complete as u64))
+    }
+}
+
+/// Forwards what a node writes to its log file to `tracing` as it comes in, under the
+/// `neard` target, until dropped. This gets node output into the output of tests
+/// capturing `tracing` events, such as the ones using `test-log`. Events carry the chain id
+/// of the node in their `chain_id` field, to tell apart sandboxes running side by side.
+pub(crate) struct LogForwarder {
+    stop: Arc<AtomicBool>,
+}
+
+impl LogForwarder {
+    /// Start forwarding the lines written to `log` from here on.
+    pub(crate) fn spawn(log: NodeLog, chain_id: String) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        // A thread rather than a task, since nodes can outlive the runtime they got
+        // spawned on, such as shared ones.
+        let spawned = std::thread::Builder::new()
+            .name("sandbox-log".into())
+            .spawn(move || follow(&log, &chain_id, &stopped));
+        if let Err(err) = spawned {
+            tracing::warn!(target: "workspaces", "failed to forward sandbox logs: {}", err);
+        }
+
+        Self { stop }
+    }
+}
+
+impl Drop for LogForwarder {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+fn follow(log: &NodeLog, chain_id: &str, stop: &AtomicBool) {
+    // Earlier runs out of the same home directory have been forwarded already.
+    let mut offset = fs::metadata(log.path()).map(|m| m.len()).unwrap_or(0);
+    while !stop.load(Ordering::SeqCst) {
+        if let Ok((lines, next)) = log.read_from(offset) {
+            lines.iter().for_each(|line| forward(line, chain_id));
+            offset = next;
+        }
+        std::thread::sleep(FOLLOW_INTERVAL);
+    }
+}
+
+/// Forward a line of the node log at the level the node logged it at. Lines without a level
+/// of their own, such as the ones of a multi-line message, are forwarded at the info level.
+fn forward(line: &str, chain_id: &str) {
+    match level_of(line) {
+        Some(Level::ERROR) => tracing::error!(target: "neard", chain_id, "{}", line),
+        Some(Level::WARN) => tracing::warn!(target: "neard", chain_id, "{}", line),
+        Some(Level::DEBUG) => tracing::debug!(target: "neard", chain_id, "{}", line),
+        Some(Level::TRACE) => tracing::trace!(target: "neard", chain_id, "{}", line),
+        _ => tracing::info!(target: "neard", chain_id, "{}", line),
+    }
+}
+
+/// Level of a line of the node log, which the node writes out right after the timestamp
+/// the line starts with, colored in when `NEAR_SANDBOX_LOG_STYLE` asks for it.
+fn level_of(line: &str) -> Option<Level> {
+    let level = line.split_whitespace().nth(1)?;
+    match strip_ansi(level).as_str() {
+        "ERROR" => Some(Level::ERROR),
+        "WARN" => Some(Level::WARN),
+        "INFO" => Some(Level::INFO),
+        "DEBUG" => Some(Level::DEBUG),
+        "TRACE" => Some(Level::TRACE),
+        _ => None,
+    }
+}
+
+/// Remove the ANSI escape sequences coloring `text` in.
+fn strip_ansi(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Escape sequences run up to the letter ending them, such as the `m` of colors.
+            chars.by_ref().find(|c| c.is_ascii_alphabetic());
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
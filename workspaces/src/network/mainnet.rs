@@ -30,7 +30,9 @@ pub struct Mainnet {
 impl FromNetworkBuilder for Mainnet {
     async fn from_builder<'a>(build: NetworkBuilder<'a, Self>) -> Result<Self> {
         let rpc_url = build.rpc_addr.unwrap_or_else(|| RPC_URL.into());
-        let client = Client::new(&rpc_url, build.api_key)?;
+        let client = Client::new(&rpc_url, build.api_key, &build.transport)?
+            .with_stale_tx_retries(build.stale_tx_retries)
+            .with_wait_until(build.wait_until);
         client.wait_for_rpc().await?;
 
         Ok(Self {
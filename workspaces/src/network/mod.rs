@@ -2,27 +2,47 @@
 //!
 //! Currently the builtin network types are [`Mainnet`], [`Testnet`], and [`Sandbox`].
 
-mod config;
+pub(crate) mod config;
 mod info;
-mod sandbox;
-mod server;
+mod log;
+pub(crate) mod sandbox;
+pub(crate) mod server;
+mod snapshot;
+pub(crate) mod stats;
 
 pub(crate) mod builder;
 pub(crate) mod variants;
 
 pub mod betanet;
+pub mod cluster;
 pub mod custom;
+pub mod faucet;
 pub mod mainnet;
+pub mod pool;
+pub mod provider;
+pub mod shared;
 pub mod testnet;
 
+#[cfg(feature = "testcontainers")]
+pub mod testcontainers;
+
 pub(crate) use variants::DEV_ACCOUNT_SEED;
 
 pub use self::betanet::Betanet;
+pub use self::cluster::SandboxNode;
+pub use self::config::GenesisAccount;
 pub use self::custom::Custom;
+pub use self::faucet::{Faucet, HelperFaucet};
 pub use self::info::Info;
+pub use self::log::NodeLog;
 pub use self::mainnet::Mainnet;
+pub use self::pool::{SandboxLease, SandboxPool};
+pub use self::provider::Provider;
 pub use self::sandbox::Sandbox;
 pub use self::server::{pick_unused_port, ValidatorKey};
+pub use self::shared::{Namespace, SharedSandbox};
+pub use self::snapshot::SnapshotId;
+pub use self::stats::SandboxStats;
 pub use self::testnet::Testnet;
 pub use self::variants::{
     AllowDevAccountCreation, NetworkClient, NetworkInfo, TopLevelAccountCreator,
@@ -0,0 +1,248 @@
+//! Pool of sandbox nodes that are booted ahead of time, so that tests do not have to pay
+//! the startup cost of a node each time they need a fresh sandbox.
+
+use std::ops::Deref;
+use std::sync::Arc;
+
+use tokio::runtime::{Handle, Runtime};
+use tokio::sync::{mpsc, Mutex};
+
+use crate::error::{ErrorKind, SandboxErrorCode};
+use crate::network::builder::NetworkBuilder;
+use crate::network::{Sandbox, SnapshotId};
+use crate::result::Result;
+use crate::Worker;
+
+type Factory = dyn Fn() -> NetworkBuilder<'static, Sandbox> + Send + Sync;
+
+/// Keeps a number of sandbox nodes booted and hands them out to tests on demand. Every
+/// lease gets a node of its own that no other test has touched, and a replacement node
+/// starts booting in the background as soon as one is leased. A leased node is shut down
+/// once its [`Worker`] and everything created from it are dropped, so state never leaks
+/// from one lease into the next.
+///
+/// Nodes are booted on a runtime owned by the pool, which makes it possible to share a
+/// single pool between tests that each run on their own runtime, such as the ones from
+/// `#[tokio::test]`:
+/// ```ignore
+/// static POOL: std::sync::OnceLock<SandboxPool> = std::sync::OnceLock::new();
+///
+/// #[tokio::test]
+/// async fn test_something() -> anyhow::Result<()> {
+///     let worker = POOL.get_or_init(|| SandboxPool::new(4)).lease().await?;
+///     // ...
+///     Ok(())
+/// }
+/// ```
+///
+/// Pools created with [`SandboxPool::recycling`] can also hand out nodes through
+/// [`SandboxPool::checkout`], which go back into the pool with their state reset once
+/// the test is done with them, rather than getting replaced by freshly booted ones.
+pub struct SandboxPool {
+    shared: Arc<Shared>,
+    ready: Mutex<mpsc::Receiver<Result<Pooled>>>,
+    runtime: Option<Runtime>,
+}
+
+/// State of a pool shared with the tasks booting and recycling its nodes.
+struct Shared {
+    factory: Arc<Factory>,
+    sender: mpsc::Sender<Result<Pooled>>,
+    handle: Handle,
+    recycle: bool,
+}
+
+/// A booted node waiting in the pool, along with the snapshot of the state it booted
+/// with for pools recycling their nodes.
+struct Pooled {
+    worker: Worker<Sandbox>,
+    baseline: Option<SnapshotId>,
+}
+
+impl SandboxPool {
+    /// Create a pool keeping `size` sandbox nodes with the default configuration booted.
+    pub fn new(size: usize) -> Self {
+        Self::with_builder(size, crate::sandbox)
+    }
+
+    /// Create a pool keeping `size` sandbox nodes booted, each one configured through the
+    /// builder returned by `builder`:
+    /// ```ignore
+    /// let pool = SandboxPool::with_builder(4, || near_workspaces::sandbox().tmpfs());
+    /// ```
+    pub fn with_builder<F>(size: usize, builder: F) -> Self
+    where
+        F: Fn() -> NetworkBuilder<'static, Sandbox> + Send + Sync + 'static,
+    {
+        Self::build(size, Arc::new(builder), false)
+    }
+
+    /// Create a pool keeping `size` sandbox nodes booted like [`SandboxPool::with_builder`],
+    /// which also recycles the nodes handed out through [`SandboxPool::checkout`]. Every
+    /// node gets snapshotted once it has booted, which it is restored to when it comes
+    /// back to the pool. Restoring a node is quicker than booting a new one, which adds up
+    /// for suites running a lot of tests. Nodes have to be spawned by workspaces on this
+    /// machine for them to be snapshotted.
+    /// ```ignore
+    /// let pool = SandboxPool::recycling(4, near_workspaces::sandbox);
+    /// ```
+    pub fn recycling<F>(size: usize, builder: F) -> Self
+    where
+        F: Fn() -> NetworkBuilder<'static, Sandbox> + Send + Sync + 'static,
+    {
+        Self::build(size, Arc::new(builder), true)
+    }
+
+    fn build(size: usize, factory: Arc<Factory>, recycle: bool) -> Self {
+        let runtime = background_runtime("sandbox-pool");
+        let size = size.max(1);
+        let (sender, ready) = mpsc::channel(size);
+        let shared = Arc::new(Shared {
+            factory,
+            sender,
+            handle: runtime.handle().clone(),
+            recycle,
+        });
+        for _ in 0..size {
+            shared.boot();
+        }
+
+        Self {
+            shared,
+            ready: Mutex::new(ready),
+            runtime: Some(runtime),
+        }
+    }
+
+    /// Lease a booted sandbox node from the pool, waiting for one to finish booting if
+    /// all of them are currently leased out.
+    pub async fn lease(&self) -> Result<Worker<Sandbox>> {
+        let pooled = self.next().await?;
+        self.shared.boot();
+        Ok(pooled.worker)
+    }
+
+    /// Check out a booted sandbox node from the pool, waiting for one to become available
+    /// if all of them are currently in use. For pools created with
+    /// [`SandboxPool::recycling`], the node goes back into the pool once the returned
+    /// [`SandboxLease`] is dropped, with its state restored to how it was when the node
+    /// booted. Workers cloned off of the lease should be dropped along with it, since the
+    /// node they talk to gets reset underneath them. Other pools treat this the same as
+    /// [`SandboxPool::lease`].
+    /// ```ignore
+    /// let worker = pool.checkout().await?;
+    /// let contract = worker.dev_deploy(&wasm).await?;
+    /// // ...
+    /// drop(worker); // back to the pool, without the contract
+    /// ```
+    pub async fn checkout(&self) -> Result<SandboxLease> {
+        let pooled = self.next().await?;
+        if pooled.baseline.is_none() {
+            self.shared.boot();
+        }
+        Ok(SandboxLease {
+            pooled: Some(pooled),
+            shared: self.shared.clone(),
+        })
+    }
+
+    async fn next(&self) -> Result<Pooled> {
+        let pooled = self.ready.lock().await.recv().await.ok_or_else(|| {
+            ErrorKind::Other.message("sandbox pool is no longer booting any nodes")
+        })?;
+
+        pooled.map_err(|e| SandboxErrorCode::InitFailure.full("pooled sandbox failed to boot", e))
+    }
+}
+
+impl Shared {
+    /// Boot a new node in the background and add it to the pool once it is up.
+    fn boot(self: &Arc<Self>) {
+        let shared = self.clone();
+        self.handle.spawn(async move {
+            let pooled = shared.boot_node().await;
+            // The pool being gone means that nobody is waiting for the node anymore.
+            let _ = shared.sender.send(pooled).await;
+        });
+    }
+
+    async fn boot_node(&self) -> Result<Pooled> {
+        let worker = (self.factory)().await?;
+        // Pools are usually held onto by statics, which never get dropped.
+        worker.workspace.server.kill_on_exit();
+        let baseline = match self.recycle {
+            true => Some(worker.snapshot().await?),
+            false => None,
+        };
+
+        Ok(Pooled { worker, baseline })
+    }
+
+    /// Reset a node that is done being used in the background and put it back into the
+    /// pool, or boot a replacement for it if it cannot be reset.
+    fn recycle(self: &Arc<Self>, pooled: Pooled) {
+        let baseline = match pooled.baseline {
+            Some(baseline) => baseline,
+            None => return,
+        };
+        let shared = self.clone();
+        self.handle.spawn(async move {
+            match pooled.worker.restore(baseline).await {
+                Ok(()) => {
+                    let _ = shared.sender.send(Ok(pooled)).await;
+                }
+                Err(err) => {
+                    tracing::warn!(target: "workspaces", "failed to recycle pooled sandbox: {}", err);
+                    shared.boot();
+                }
+            }
+        });
+    }
+}
+
+impl Drop for SandboxPool {
+    fn drop(&mut self) {
+        // Dropping a runtime blocks, which is not allowed from within an async context.
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+/// A sandbox node checked out of a [`SandboxPool`] with [`SandboxPool::checkout`], which
+/// goes back into the pool when dropped. Dereferences to the [`Worker`] of the node.
+pub struct SandboxLease {
+    pooled: Option<Pooled>,
+    shared: Arc<Shared>,
+}
+
+impl Deref for SandboxLease {
+    type Target = Worker<Sandbox>;
+
+    fn deref(&self) -> &Self::Target {
+        &self
+            .pooled
+            .as_ref()
+            .expect("lease holds its node until dropped")
+            .worker
+    }
+}
+
+impl Drop for SandboxLease {
+    fn drop(&mut self) {
+        if let Some(pooled) = self.pooled.take() {
+            self.shared.recycle(pooled);
+        }
+    }
+}
+
+/// Runtime running in the background for nodes that are shared between tests, which
+/// usually run on runtimes of their own that get shut down as soon as the test is over.
+pub(crate) fn background_runtime(name: &str) -> Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name(name)
+        .enable_all()
+        .build()
+        .expect("failed to build a background runtime for sandbox nodes")
+}
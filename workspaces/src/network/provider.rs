@@ -0,0 +1,64 @@
+//! Presets for well-known RPC providers, so that their endpoints and authentication
+//! don't have to be copied into every test suite. Use them like so:
+//! ```ignore
+//! let worker = near_workspaces::mainnet()
+//!     .provider(Provider::FastNear(api_key))
+//!     .await?;
+//! ```
+
+/// Well-known RPC providers that can be used in place of the default RPC nodes provided
+/// by near.org. Only mainnet and testnet are supported by the presets.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum Provider {
+    /// FastNear RPC, authenticated with the given API key as a bearer token.
+    FastNear(String),
+    /// Lava's public NEAR RPC gateway, which does not require authentication.
+    Lava,
+    /// QuickNode endpoint. QuickNode embeds the authentication token into the endpoint
+    /// URL, so this is the full endpoint URL given in the QuickNode dashboard.
+    QuickNode(String),
+    /// Pagoda's archival RPC, authenticated with the given API key.
+    PagodaArchival(String),
+}
+
+impl Provider {
+    /// URL of the mainnet RPC service of this provider.
+    pub fn mainnet_url(&self) -> String {
+        match self {
+            Self::FastNear(_) => "https://rpc.mainnet.fastnear.com".into(),
+            Self::Lava => "https://near.lava.build".into(),
+            Self::QuickNode(endpoint) => endpoint.clone(),
+            Self::PagodaArchival(_) => "https://near-mainnet.api.pagoda.co/rpc/v1/".into(),
+        }
+    }
+
+    /// URL of the testnet RPC service of this provider.
+    pub fn testnet_url(&self) -> String {
+        match self {
+            Self::FastNear(_) => "https://rpc.testnet.fastnear.com".into(),
+            Self::Lava => "https://near-testnet.lava.build".into(),
+            Self::QuickNode(endpoint) => endpoint.clone(),
+            Self::PagodaArchival(_) => "https://near-testnet.api.pagoda.co/rpc/v1/".into(),
+        }
+    }
+
+    /// Name and value of the header used to authenticate against this provider, if any.
+    pub fn auth_header(&self) -> Option<(&'static str, String)> {
+        match self {
+            Self::FastNear(key) => Some(("Authorization", format!("Bearer {key}"))),
+            Self::PagodaArchival(key) => Some(("x-api-key", key.clone())),
+            Self::Lava | Self::QuickNode(_) => None,
+        }
+    }
+
+    /// Approximate amount of requests per second allowed by the free tier of this
+    /// provider, if the provider documents one. This is only a hint to help sizing
+    /// test suites, and is not enforced by workspaces.
+    pub fn rate_limit_hint(&self) -> Option<u32> {
+        match self {
+            Self::QuickNode(_) => Some(15),
+            Self::FastNear(_) | Self::Lava | Self::PagodaArchival(_) => None,
+        }
+    }
+}
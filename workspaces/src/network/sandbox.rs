@@ -1,25 +1,28 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
-use std::str::FromStr;
 
 use async_trait::async_trait;
 use near_jsonrpc_client::methods::sandbox_fast_forward::RpcSandboxFastForwardRequest;
-use near_jsonrpc_client::methods::sandbox_patch_state::RpcSandboxPatchStateRequest;
 use near_primitives::state_record::StateRecord;
 use near_sandbox_utils as sandbox;
 
 use super::builder::{FromNetworkBuilder, NetworkBuilder};
-use super::server::ValidatorKey;
+use super::log::NodeLog;
+use super::server::{ValidatorKey, LOG_FILE};
+use super::snapshot::{SnapshotId, Snapshots};
 use super::{AllowDevAccountCreation, NetworkClient, NetworkInfo, TopLevelAccountCreator};
-use crate::error::SandboxErrorCode;
+use crate::error::{ErrorKind, SandboxErrorCode};
 use crate::network::server::SandboxServer;
 use crate::network::Info;
 use crate::result::{Execution, ExecutionFinalResult, Result};
-use crate::rpc::client::Client;
-use crate::types::{AccountId, InMemorySigner, NearToken, SecretKey};
+use crate::rpc::client::{rpc_timeout, Client, DEFAULT_HEALTH_CHECK_INTERVAL};
+use crate::types::{AccessKey, AccountId, InMemorySigner, NearToken, SecretKey};
 use crate::{Account, Contract, Network, Worker};
 
 // Constant taken from nearcore crate to avoid dependency
 const DEFAULT_DEPOSIT: NearToken = NearToken::from_near(100);
+// Constant taken from nearcore crate to avoid dependency
+const MIN_ALLOWED_TOP_LEVEL_ACCOUNT_LENGTH: usize = 32;
 /// Local sandboxed environment/network, which can be used to test without interacting with
 /// networks that are online such as mainnet and testnet. Look at [`workspaces::sandbox`]
 /// for how to spin up a sandboxed network and interact with it.
@@ -30,60 +33,137 @@ pub struct Sandbox {
     client: Client,
     info: Info,
     version: Option<String>,
+    snapshots: tokio::sync::Mutex<Snapshots>,
+    genesis_accounts: HashMap<AccountId, SecretKey>,
+    /// Genesis account standing in for the validator account as the root account.
+    root_account: Option<AccountId>,
+    /// Offset into the node log up to which lines were handed out by `take_node_logs`.
+    logs_taken: std::sync::Mutex<u64>,
 }
 
 impl Sandbox {
     pub(crate) fn root_signer(&self) -> Result<InMemorySigner> {
-        match &self.server.validator_key {
-            ValidatorKey::HomeDir(home_dir) => {
-                let path = home_dir.join("validator_key.json");
-                InMemorySigner::from_file(&path)
-            }
-            ValidatorKey::Known(account_id, secret_key) => Ok(InMemorySigner::from_secret_key(
-                account_id.clone(),
-                secret_key.clone(),
-            )),
+        match &self.root_account {
+            Some(id) => self.genesis_signer(id),
+            None => validator_signer(&self.server.validator_key),
         }
     }
+
+    /// Signer of an account baked into genesis with `NetworkBuilder::genesis_account`.
+    pub(crate) fn genesis_signer(&self, id: &AccountId) -> Result<InMemorySigner> {
+        let secret_key = self.genesis_accounts.get(id).ok_or_else(|| {
+            ErrorKind::DataConversion.message(format!("{id} is not a genesis account"))
+        })?;
+        Ok(InMemorySigner::from_secret_key(
+            id.clone(),
+            secret_key.clone(),
+        ))
+    }
+
     pub(crate) async fn from_builder_with_version<'a>(
         build: NetworkBuilder<'a, Self>,
         version: &str,
     ) -> Result<Self> {
-        // Check the conditions of the provided rpc_url and validator_key
-        let mut server = match (build.rpc_addr, build.validator_key) {
-            // Connect to a provided sandbox:
-            (Some(rpc_url), Some(validator_key)) => {
-                SandboxServer::connect(rpc_url, validator_key).await?
-            }
+        let timeout = match build.startup_timeout {
+            Some(timeout) => timeout,
+            None => rpc_timeout()?,
+        };
+        let interval = build
+            .health_check_interval
+            .unwrap_or(DEFAULT_HEALTH_CHECK_INTERVAL);
 
-            // Spawn a new sandbox since rpc_url and home_dir weren't specified:
-            (None, None) => SandboxServer::run_new_with_version(version).await?,
+        let mut attempt = 0;
+        let mut collisions = 0;
+        let (mut server, client) = loop {
+            let mut server = Self::start_server(&build, version).await?;
+            let client = Client::new(&server.rpc_addr(), build.api_key.clone(), &build.transport)?
+                .with_stale_tx_retries(build.stale_tx_retries)
+                .with_wait_until(build.wait_until)
+                .with_patch_peers(&build.patch_peers);
+            let startup = tokio::select! {
+                res = client.wait_for_rpc_with(timeout, interval) => Ok(res),
+                status = server.wait_for_exit() => Err(status),
+            };
+            let err = match startup {
+                Ok(Ok(())) => break (server, client),
+                Ok(Err(err)) => err,
+                Err(status) => {
+                    // The node exiting before its RPC is up is most likely due to its ports
+                    // having been taken by another process after we picked them.
+                    let err = SandboxErrorCode::RunFailure.message(format!(
+                        "sandbox exited before its RPC service came up with status={status:?}"
+                    ));
+                    if Self::spawns_locally(&build) && collisions < build.port_collision_retries {
+                        collisions += 1;
+                        tracing::warn!(
+                            target: "workspaces",
+                            "{err}, retrying with new ports ({collisions}/{})",
+                            build.port_collision_retries,
+                        );
+                        continue;
+                    }
+                    err
+                }
+            };
 
-            // Missing inputted parameters for sandbox:
-            (Some(rpc_url), None) => {
-                return Err(SandboxErrorCode::InitFailure.message(format!(
-                    "Custom rpc_url={rpc_url} requires validator_key set."
-                )));
-            }
-            (None, Some(validator_key)) => {
-                return Err(SandboxErrorCode::InitFailure.message(format!(
-                    "Custom validator_key={validator_key:?} requires rpc_url set."
-                )));
+            // Only sandboxes we spawned locally can be restarted with fresh ports. The
+            // failed server gets dropped here, which kills the node.
+            if Self::spawns_locally(&build) && attempt < build.startup_retries {
+                attempt += 1;
+                tracing::warn!(
+                    target: "workspaces",
+                    "sandbox failed to start up, retrying ({attempt}/{}): {err}",
+                    build.startup_retries,
+                );
+                continue;
             }
+            return Err(err);
         };
 
-        let client = Client::new(&server.rpc_addr(), build.api_key)?;
-        client.wait_for_rpc().await?;
-
         // Server locks some ports on startup due to potential port collision, so we need
         // to unlock the lockfiles after RPC is ready. Not necessarily needed here since
         // they get unlocked anyways on the server's drop, but it is nice to clean up the
         // lockfiles as soon as possible.
         server.unlock_lockfiles()?;
 
+        // Genesis overrides only reach the nodes spawned locally.
+        let storage_cost = build
+            .sandbox_config
+            .storage_cost_per_byte
+            .filter(|_| Self::spawns_locally(&build));
+        if let Some(cost) = storage_cost {
+            let charged = client.storage_amount_per_byte().await?;
+            if charged != cost {
+                return Err(SandboxErrorCode::InitFailure.message(format!(
+                    "sandbox {version} charges {} per byte of storage instead of the {} set \
+                     in its genesis",
+                    charged.as_yoctonear(),
+                    cost.as_yoctonear(),
+                )));
+            }
+        }
+
+        if let Some(path) = &build.seed_dump {
+            let seeded = crate::dump::seed(&client, path.clone()).await?;
+            tracing::info!(target: "workspaces", "seeded sandbox with {seeded} records from {path:?}");
+        }
+
+        let genesis_accounts = build
+            .sandbox_config
+            .genesis_accounts
+            .iter()
+            .map(|account| (account.id.clone(), account.secret_key.clone()))
+            .collect();
+        let root_account = build.sandbox_config.root_account.clone();
+
         let info = Info {
             name: build.name.into(),
-            root_id: AccountId::from_str("test.near").unwrap(),
+            // Nodes set up with `neard init` validate as `test.near`, but the ones we attach
+            // to could be running as any validator.
+            root_id: match &root_account {
+                Some(id) => id.clone(),
+                None => validator_signer(&server.validator_key)?.account_id,
+            },
             keystore_path: PathBuf::from(".near-credentials/sandbox/"),
             rpc_url: url::Url::parse(&server.rpc_addr()).expect("url is hardcoded"),
         };
@@ -93,10 +173,91 @@ impl Sandbox {
             client,
             info,
             version: Some(version.to_string()),
+            snapshots: Default::default(),
+            logs_taken: Default::default(),
+            genesis_accounts,
+            root_account,
         })
     }
 }
 
+impl Sandbox {
+    fn spawns_locally(build: &NetworkBuilder<'_, Self>) -> bool {
+        build.rpc_addr.is_none()
+            && build.validator_key.is_none()
+            && build.remote_host.is_none()
+            && build.docker_image.is_none()
+    }
+
+    async fn start_server(
+        build: &NetworkBuilder<'_, Self>,
+        version: &str,
+    ) -> Result<SandboxServer> {
+        // Check the conditions of the provided rpc_url and validator_key
+        let server = match (&build.rpc_addr, &build.validator_key) {
+            // Spawn a new sandbox on a remote machine:
+            (None, None) if build.remote_host.is_some() => {
+                SandboxServer::run_remote(build.remote_host.as_deref().unwrap()).await?
+            }
+            (Some(_), _) | (_, Some(_)) if build.remote_host.is_some() => {
+                return Err(SandboxErrorCode::InitFailure
+                    .message("remote_ssh cannot be used along with rpc_addr or validator_key"));
+            }
+
+            // Spawn a new sandbox in a Docker container:
+            (None, None) if build.docker_image.is_some() => {
+                let image = build.docker_image.as_deref().unwrap();
+                let image = match image.rsplit('/').next().unwrap_or(image).contains(':') {
+                    true => image.to_string(),
+                    false => format!("{image}:{version}"),
+                };
+                SandboxServer::run_docker(&image, &build.sandbox_config.ports).await?
+            }
+            (Some(_), _) | (_, Some(_)) if build.docker_image.is_some() => {
+                return Err(SandboxErrorCode::InitFailure
+                    .message("docker_image cannot be used along with rpc_addr or validator_key"));
+            }
+
+            // Connect to a provided sandbox:
+            (Some(rpc_url), Some(validator_key)) => {
+                SandboxServer::connect(rpc_url.clone(), validator_key.clone()).await?
+            }
+
+            // Spawn a new sandbox since rpc_url and home_dir weren't specified:
+            (None, None) => {
+                SandboxServer::run_new_with_version(version, &build.sandbox_config).await?
+            }
+
+            // Missing inputted parameters for sandbox:
+            (Some(rpc_url), None) => {
+                return Err(SandboxErrorCode::InitFailure.message(format!(
+                    "Custom rpc_url={rpc_url} requires validator_key set."
+                )));
+            }
+            (None, Some(validator_key)) => {
+                return Err(SandboxErrorCode::InitFailure.message(format!(
+                    "Custom validator_key={validator_key:?} requires rpc_url set."
+                )));
+            }
+        };
+
+        Ok(server)
+    }
+}
+
+pub(crate) fn validator_signer(validator_key: &ValidatorKey) -> Result<InMemorySigner> {
+    match validator_key {
+        ValidatorKey::HomeDir(home_dir) => {
+            let path = home_dir.join("validator_key.json");
+            InMemorySigner::from_file(&path)
+        }
+        ValidatorKey::Known(account_id, secret_key) => Ok(InMemorySigner::from_secret_key(
+            account_id.clone(),
+            secret_key.clone(),
+        )),
+    }
+}
+
 impl std::fmt::Debug for Sandbox {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         f.debug_struct("Sandbox")
@@ -112,7 +273,11 @@ impl std::fmt::Debug for Sandbox {
 #[async_trait]
 impl FromNetworkBuilder for Sandbox {
     async fn from_builder<'a>(build: NetworkBuilder<'a, Self>) -> Result<Self> {
-        Self::from_builder_with_version(build, sandbox::DEFAULT_NEAR_SANDBOX_VERSION).await
+        let version = build
+            .sandbox_version
+            .clone()
+            .unwrap_or_else(|| sandbox::DEFAULT_NEAR_SANDBOX_VERSION.to_string());
+        Self::from_builder_with_version(build, &version).await
     }
 }
 
@@ -127,10 +292,16 @@ impl TopLevelAccountCreator for Sandbox {
         sk: SecretKey,
     ) -> Result<Execution<Account>> {
         let root_signer = self.root_signer()?;
-        let outcome = self
-            .client()
-            .create_account(&root_signer, &id, sk.public_key(), DEFAULT_DEPOSIT)
-            .await?;
+        let outcome = if can_create(&root_signer.account_id, &id) {
+            self.client()
+                .create_account(&root_signer, &id, sk.public_key(), DEFAULT_DEPOSIT)
+                .await?
+        } else {
+            self.patch_new_account(&worker, &id, &sk).await?;
+            self.client()
+                .transfer_near(&root_signer, &id, DEFAULT_DEPOSIT)
+                .await?
+        };
 
         let signer = InMemorySigner::from_secret_key(id, sk);
         Ok(Execution {
@@ -147,18 +318,27 @@ impl TopLevelAccountCreator for Sandbox {
         wasm: &[u8],
     ) -> Result<Execution<Contract>> {
         let root_signer = self.root_signer()?;
-        let outcome = self
-            .client()
-            .create_account_and_deploy(
-                &root_signer,
-                &id,
-                sk.public_key(),
-                DEFAULT_DEPOSIT,
-                wasm.into(),
-            )
-            .await?;
+        let signer = InMemorySigner::from_secret_key(id.clone(), sk.clone());
+        let outcome = if can_create(&root_signer.account_id, &id) {
+            self.client()
+                .create_account_and_deploy(
+                    &root_signer,
+                    &id,
+                    sk.public_key(),
+                    DEFAULT_DEPOSIT,
+                    wasm.into(),
+                )
+                .await?
+        } else {
+            self.patch_new_account(&worker, &id, &sk).await?;
+            let funded = self
+                .client()
+                .transfer_near(&root_signer, &id, DEFAULT_DEPOSIT)
+                .await?;
+            ExecutionFinalResult::from_view(funded).into_result()?;
+            self.client().deploy(&signer, &id, wasm.into()).await?
+        };
 
-        let signer = InMemorySigner::from_secret_key(id, sk);
         Ok(Execution {
             result: Contract::new(signer, worker),
             details: ExecutionFinalResult::from_view(outcome),
@@ -166,6 +346,57 @@ impl TopLevelAccountCreator for Sandbox {
     }
 }
 
+/// Whether `signer_id` can create account `id` through a transaction. Top-level accounts
+/// short enough to be reserved for the registrar cannot be, and neither can accounts other
+/// than direct subaccounts of the signer.
+fn can_create(signer_id: &AccountId, id: &AccountId) -> bool {
+    if id.is_top_level() {
+        id.as_str().len() >= MIN_ALLOWED_TOP_LEVEL_ACCOUNT_LENGTH
+    } else {
+        id.is_sub_account_of(signer_id)
+    }
+}
+
+impl Sandbox {
+    /// Bring account `id` into existence with a full access key for `sk` and no balance,
+    /// for accounts the root account cannot create with a transaction, such as short
+    /// top-level ones like `alice` or ones under other accounts like `alice.near`.
+    async fn patch_new_account(
+        &self,
+        worker: &Worker<dyn Network>,
+        id: &AccountId,
+        sk: &SecretKey,
+    ) -> Result<()> {
+        if worker.view_account(id).await.is_ok() {
+            return Err(SandboxErrorCode::PatchStateFailure
+                .message(format!("cannot create {id}, since it exists already")));
+        }
+
+        let records = vec![
+            StateRecord::Account {
+                account_id: id.clone(),
+                account: near_primitives::account::Account::new(
+                    0,
+                    0,
+                    near_primitives::hash::CryptoHash::default(),
+                    0,
+                ),
+            },
+            StateRecord::AccessKey {
+                account_id: id.clone(),
+                public_key: sk.public_key().into(),
+                access_key: AccessKey::full_access().into(),
+            },
+        ];
+        self.client()
+            .patch_state(records)
+            .await
+            .map_err(|e| SandboxErrorCode::PatchStateFailure.custom(e))?;
+
+        Ok(())
+    }
+}
+
 impl NetworkClient for Sandbox {
     fn client(&self) -> &Client {
         &self.client
@@ -195,7 +426,7 @@ impl Sandbox {
         // NOTE: RpcSandboxPatchStateResponse is an empty struct with no fields, so don't do anything with it:
         let _patch_resp = self
             .client()
-            .query(&RpcSandboxPatchStateRequest { records })
+            .patch_state(records)
             .await
             .map_err(|e| SandboxErrorCode::PatchStateFailure.custom(e))?;
 
@@ -212,4 +443,101 @@ impl Sandbox {
 
         Ok(())
     }
+
+    /// Snapshot the state of the sandbox by stopping the node, copying its data directory
+    /// and starting it back up.
+    pub(crate) async fn snapshot(&self) -> Result<SnapshotId> {
+        let home_dir = self.local_home_dir()?;
+        let mut snapshots = self.snapshots.lock().await;
+
+        self.server.stop().await?;
+        let taken = snapshots.take(home_dir, self.server.rpc_port());
+        self.server.start()?;
+        self.client().wait_for_rpc().await?;
+
+        taken
+    }
+
+    /// Restore the state of the sandbox to snapshot `id`, by stopping the node, swapping
+    /// its data directory for the snapshot and starting it back up.
+    pub(crate) async fn restore(&self, id: SnapshotId) -> Result<()> {
+        let home_dir = self.local_home_dir()?;
+        let snapshots = self.snapshots.lock().await;
+        if !snapshots.contains(id, home_dir, self.server.rpc_port()) {
+            return Err(SandboxErrorCode::SnapshotFailure
+                .message(format!("no snapshot {id:?} was taken of this sandbox")));
+        }
+
+        self.server.stop().await?;
+        let restored = snapshots.restore(id, home_dir);
+        self.server.start()?;
+        self.client().wait_for_rpc().await?;
+
+        // Nonces of access keys went back along with the rest of the state.
+        self.client().clear_nonce_cache().await;
+        restored
+    }
+
+    pub(crate) async fn stop(&self) -> Result<()> {
+        self.ensure_local()?;
+        self.server.stop().await
+    }
+
+    pub(crate) async fn restart(&self) -> Result<()> {
+        self.ensure_local()?;
+        self.server.stop().await?;
+        self.server.start()?;
+        self.client().wait_for_rpc().await
+    }
+
+    fn ensure_local(&self) -> Result<()> {
+        match self.server.home_dir() {
+            Some(_) => Ok(()),
+            None => Err(SandboxErrorCode::RunFailure
+                .message("only sandboxes spawned locally can be stopped and restarted")),
+        }
+    }
+
+    /// Home directory of the node, if it was spawned locally or attached to along with
+    /// its home directory.
+    pub(crate) fn home_dir(&self) -> Option<&std::path::Path> {
+        self.server.home_dir().or(match &self.server.validator_key {
+            ValidatorKey::HomeDir(home_dir) => Some(home_dir.as_path()),
+            ValidatorKey::Known(..) => None,
+        })
+    }
+
+    /// Read the JSON file `file_name` out of the home directory of the node.
+    pub(crate) fn read_home_file(&self, file_name: &str) -> Result<serde_json::Value> {
+        let home_dir = self.home_dir().ok_or_else(|| {
+            ErrorKind::Io.message("home directory of the sandbox is not available")
+        })?;
+        let path = home_dir.join(file_name);
+        let file = std::fs::File::open(&path)
+            .map_err(|e| ErrorKind::Io.full(format!("failed to open {path:?}"), e))?;
+        serde_json::from_reader(std::io::BufReader::new(file))
+            .map_err(|e| ErrorKind::DataConversion.full(format!("failed to parse {path:?}"), e))
+    }
+
+    pub(crate) fn node_log(&self) -> Result<NodeLog> {
+        let home_dir = self.server.home_dir().ok_or_else(|| {
+            ErrorKind::Io.message("only sandboxes spawned locally have their logs captured")
+        })?;
+        Ok(NodeLog::new(home_dir.join(LOG_FILE)))
+    }
+
+    pub(crate) fn take_node_logs(&self) -> Result<Vec<String>> {
+        let log = self.node_log()?;
+        let mut taken = self.logs_taken.lock().unwrap();
+        let (lines, offset) = log.read_from(*taken)?;
+        *taken = offset;
+        Ok(lines)
+    }
+
+    fn local_home_dir(&self) -> Result<&std::path::Path> {
+        self.server.home_dir().ok_or_else(|| {
+            SandboxErrorCode::SnapshotFailure
+                .message("only sandboxes spawned locally can be snapshotted")
+        })
+    }
 }
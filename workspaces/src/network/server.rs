@@ -1,17 +1,24 @@
 use std::fs::File;
 use std::net::{Ipv4Addr, SocketAddrV4};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
 
 use crate::error::{ErrorKind, SandboxErrorCode};
+use crate::network::config::{HomeDir, PortConfig, SandboxConfig};
+use crate::network::log::{LogForwarder, NodeLog};
 use crate::result::Result;
-use crate::types::SecretKey;
+use crate::types::{KeyType, SecretKey};
 
 use fs2::FileExt;
 
 use near_account_id::AccountId;
 use reqwest::Url;
 use tempfile::TempDir;
-use tokio::process::Child;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{Child, ChildStdout, Command};
 
 use tracing::info;
 
@@ -21,6 +28,83 @@ use tokio::net::TcpListener;
 // Must be an IP address as `neard` expects socket address for network address.
 const DEFAULT_RPC_HOST: &str = "127.0.0.1";
 
+/// Home directory of nodes run in Docker, within their container.
+const DOCKER_HOME_DIR: &str = "/srv/near";
+
+/// Ports nodes run in Docker listen on within their container, which get mapped to free
+/// ports on the host.
+const DOCKER_RPC_PORT: u16 = 3030;
+const DOCKER_NET_PORT: u16 = 24567;
+
+/// Image nodes get run in with [`crate::sandbox_docker`]. It is never run untagged: images
+/// without a tag get pinned to the sandbox version, so that the node in the container runs
+/// the same protocol version as the binary this crate would run on the host.
+pub(crate) const DEFAULT_DOCKER_IMAGE: &str = "nearprotocol/sandbox";
+
+/// How long to wait for a stopped node to shut down gracefully before killing it.
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Seed supplied to `neard-sandbox init --test-seed` for nodes that we do not have direct
+/// access to the home directory of, so that the validator key can be derived from it.
+pub(crate) const TEST_SEED: &str = "near-workspaces";
+
+/// Name of the file in the home directory of a node spawned locally that its output goes to.
+pub(crate) const LOG_FILE: &str = "neard.log";
+
+/// Python snippet run on remote machines to pick two free ports, printed space separated.
+const PICK_PORTS_PY: &str = "import socket; s = [socket.socket() for _ in range(2)]; \
+    [x.bind((\"\", 0)) for x in s]; print(*[x.getsockname()[1] for x in s])";
+
+/// Marker of the line a remote machine reports the home directory and ports of its node on.
+const REMOTE_READY: &str = "near-workspaces-remote-ready";
+
+/// How long to wait for a remote machine to report the home directory and ports of its node.
+const REMOTE_READY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Node run on a remote machine over SSH with [`SandboxServer::run_remote`].
+struct RemoteNode {
+    destination: String,
+    home_dir: String,
+}
+
+/// Read the home directory, RPC port and network port a remote machine reported for its
+/// node, skipping over anything printed before them such as login banners. The rest of
+/// the output keeps getting drained, so that the node never blocks on a full pipe.
+async fn read_remote_ready(stdout: ChildStdout) -> Result<(String, u16, u16)> {
+    let mut lines = BufReader::new(stdout).lines();
+    let ready = tokio::time::timeout(REMOTE_READY_TIMEOUT, async {
+        while let Some(line) = lines.next_line().await? {
+            let mut parts = line.split_whitespace();
+            if parts.next() != Some(REMOTE_READY) {
+                continue;
+            }
+            let home_dir = parts.next().map(str::to_string);
+            let rpc_port = parts.next().and_then(|port| port.parse().ok());
+            let net_port = parts.next().and_then(|port| port.parse().ok());
+            if let (Some(home_dir), Some(rpc_port), Some(net_port)) = (home_dir, rpc_port, net_port)
+            {
+                return Ok(Some((home_dir, rpc_port, net_port)));
+            }
+        }
+        Ok::<_, std::io::Error>(None)
+    })
+    .await
+    .map_err(|_| {
+        SandboxErrorCode::InitFailure.message(format!(
+            "remote machine did not report its ports within {REMOTE_READY_TIMEOUT:?}"
+        ))
+    })?
+    .map_err(|e| SandboxErrorCode::InitFailure.full("failed to read the output of ssh", e))?
+    .ok_or_else(|| {
+        SandboxErrorCode::InitFailure
+            .message("ssh exited before the remote machine reported its ports")
+    })?;
+
+    let mut stdout = lines.into_inner();
+    tokio::spawn(async move { tokio::io::copy(&mut stdout, &mut tokio::io::sink()).await });
+    Ok(ready)
+}
+
 fn rpc_socket(port: u16) -> String {
     format!("{DEFAULT_RPC_HOST}:{}", port)
 }
@@ -56,15 +140,88 @@ async fn acquire_unused_port() -> Result<(u16, File)> {
     }
 }
 
+/// Acquire and lock `port` if it is free.
+async fn acquire_port(port: u16) -> Result<(u16, File)> {
+    let addr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, port);
+    TcpListener::bind(addr)
+        .await
+        .map_err(|err| ErrorKind::Io.full(format!("port {port} is not available"), err))?;
+    let lockpath = std::env::temp_dir().join(format!("near-sandbox-port{}.lock", port));
+    let lockfile = File::create(lockpath).map_err(|err| {
+        ErrorKind::Io.full(format!("failed to create lockfile for port {}", port), err)
+    })?;
+    lockfile.try_lock_exclusive().map_err(|err| {
+        ErrorKind::Io.full(format!("port {port} is locked by another sandbox"), err)
+    })?;
+    Ok((port, lockfile))
+}
+
+/// Acquire the port pinned with `pinned`, a free port in the range of `ports` or otherwise
+/// any free port, other than `taken`.
+async fn acquire_configured_port(
+    pinned: Option<u16>,
+    ports: &PortConfig,
+    taken: Option<u16>,
+) -> Result<(u16, File)> {
+    if let Some(port) = pinned {
+        return acquire_port(port).await;
+    }
+    let range = match &ports.range {
+        Some(range) => range.clone(),
+        None => return acquire_unused_port().await,
+    };
+
+    for port in range.clone() {
+        if Some(port) == taken || Some(port) == ports.rpc || Some(port) == ports.net {
+            continue;
+        }
+        if let Ok(acquired) = acquire_port(port).await {
+            return Ok(acquired);
+        }
+    }
+    Err(ErrorKind::Io.message(format!(
+        "no free port left in the range {}..{}",
+        range.start, range.end
+    )))
+}
+
 #[allow(dead_code)]
 async fn init_home_dir() -> Result<TempDir> {
-    init_home_dir_with_version(sandbox::DEFAULT_NEAR_SANDBOX_VERSION).await
+    init_home_dir_with_version(sandbox::DEFAULT_NEAR_SANDBOX_VERSION, None, None).await
 }
 
-async fn init_home_dir_with_version(version: &str) -> Result<TempDir> {
-    let home_dir = tempfile::tempdir().map_err(|e| ErrorKind::Io.custom(e))?;
+async fn init_home_dir_with_version(
+    version: &str,
+    parent: Option<&Path>,
+    test_seed: Option<&str>,
+) -> Result<TempDir> {
+    let home_dir = match parent {
+        Some(parent) => tempfile::tempdir_in(parent),
+        None => tempfile::tempdir(),
+    }
+    .map_err(|e| ErrorKind::Io.custom(e))?;
 
-    let output = sandbox::init_with_version(&home_dir, version)
+    init_home_dir_at(version, home_dir.path(), test_seed).await?;
+    Ok(home_dir)
+}
+
+/// Initialize a node at `home_dir`, deriving its keys from `test_seed` if given rather
+/// than generating random ones.
+async fn init_home_dir_at(version: &str, home_dir: &Path, test_seed: Option<&str>) -> Result<()> {
+    let child = match test_seed {
+        Some(test_seed) => {
+            let options = &[
+                "--home",
+                home_dir.to_str().expect("home_dir is valid utf8"),
+                "init",
+                "--test-seed",
+                test_seed,
+            ];
+            sandbox::run_with_options_with_version(options, version)
+        }
+        None => sandbox::init_with_version(home_dir, version),
+    };
+    let output = child
         .map_err(|e| SandboxErrorCode::InitFailure.custom(e))?
         .wait_with_output()
         .await
@@ -72,23 +229,126 @@ async fn init_home_dir_with_version(version: &str) -> Result<TempDir> {
 
     info!(target: "workspaces", "sandbox init: {:?}", output);
 
-    Ok(home_dir)
+    Ok(())
+}
+
+/// Set up the home directories of a local network of `validators` nodes sharing the same
+/// genesis, returning the home directory of each node. The validators are `node0`,
+/// `node1` and so on, in the order of the home directories.
+pub(crate) async fn init_localnet(version: &str, validators: usize) -> Result<Vec<PathBuf>> {
+    suppress_sandbox_logs_if_required();
+
+    let root = tempfile::tempdir()
+        .map_err(|e| ErrorKind::Io.custom(e))?
+        .into_path();
+    let validators_arg = validators.to_string();
+    let options = &[
+        "--home",
+        root.as_os_str().to_str().expect("home_dir is valid utf8"),
+        "localnet",
+        "--v",
+        &validators_arg,
+        "--n",
+        "0",
+    ];
+    let output = sandbox::run_with_options_with_version(options, version)
+        .map_err(|e| SandboxErrorCode::InitFailure.custom(e))?
+        .wait_with_output()
+        .await
+        .map_err(|e| SandboxErrorCode::InitFailure.custom(e))?;
+
+    info!(target: "workspaces", "sandbox localnet init: {:?}", output);
+    if !output.status.success() {
+        return Err(SandboxErrorCode::InitFailure.message(format!(
+            "failed to set up a local network of {validators} validators: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    Ok((0..validators)
+        .map(|i| root.join(format!("node{i}")))
+        .collect())
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 #[non_exhaustive]
 pub enum ValidatorKey {
     HomeDir(PathBuf),
     Known(AccountId, SecretKey),
 }
 
+impl ValidatorKey {
+    /// The validator key of a sandbox node initialized with `--test-seed` set to [`TEST_SEED`].
+    pub(crate) fn from_test_seed() -> Self {
+        Self::Known(
+            "test.near".parse().unwrap(),
+            SecretKey::from_seed(KeyType::ED25519, TEST_SEED),
+        )
+    }
+}
+
+/// Everything needed to spawn a local node again, after it was stopped.
+struct LocalNode {
+    home_dir: PathBuf,
+    version: String,
+    rpc_addr: String,
+    net_addr: String,
+}
+
+impl LocalNode {
+    fn spawn(&self) -> Result<Child> {
+        let bin_path = sandbox::ensure_sandbox_bin_with_version(&self.version)
+            .map_err(|e| SandboxErrorCode::RunFailure.custom(e))?;
+        // Append, so that the logs of earlier runs out of the same home directory are kept.
+        let log = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.home_dir.join(LOG_FILE))
+            .map_err(|e| ErrorKind::Io.full("failed to open sandbox log file", e))?;
+        let log_err = log
+            .try_clone()
+            .map_err(|e| ErrorKind::Io.full("failed to open sandbox log file", e))?;
+
+        let mut command = Command::new(bin_path);
+        command
+            .arg("--home")
+            .arg(&self.home_dir)
+            .args([
+                "run",
+                "--rpc-addr",
+                &self.rpc_addr,
+                "--network-addr",
+                &self.net_addr,
+            ])
+            .stdout(log)
+            .stderr(log_err);
+        command.envs(sandbox_log_env());
+
+        command
+            .spawn()
+            .map_err(|e| SandboxErrorCode::RunFailure.custom(e))
+    }
+}
+
 pub struct SandboxServer {
     pub(crate) validator_key: ValidatorKey,
     rpc_addr: Url,
     net_port: Option<u16>,
     rpc_port_lock: Option<File>,
     net_port_lock: Option<File>,
-    process: Option<Child>,
+    process: Mutex<Option<Child>>,
+    /// Set for nodes spawned locally, which can be stopped and started again.
+    local: Option<LocalNode>,
+    kill_on_exit: AtomicBool,
+    /// Home directory that gets removed along with the server, which is only done for
+    /// RAM-backed ones to not hold onto memory after the node is gone.
+    home_dir: Option<TempDir>,
+    /// Name of the Docker container the node runs in, if it runs in one.
+    container: Option<String>,
+    /// Node running on a remote machine, whose home directory gets removed along with it.
+    remote: Option<RemoteNode>,
+    /// Forwards the log of nodes spawned locally to `tracing`.
+    _log_forwarder: Option<LogForwarder>,
 }
 
 impl SandboxServer {
@@ -104,28 +364,88 @@ impl SandboxServer {
             net_port: None,
             rpc_port_lock: None,
             net_port_lock: None,
-            process: None,
+            process: Mutex::new(None),
+            local: None,
+            kill_on_exit: AtomicBool::new(false),
+            home_dir: None,
+            container: None,
+            remote: None,
+            _log_forwarder: None,
         })
     }
 
     /// Run a new SandboxServer, spawning the sandbox node in the process.
     #[allow(dead_code)]
     pub(crate) async fn run_new() -> Result<Self> {
-        Self::run_new_with_version(sandbox::DEFAULT_NEAR_SANDBOX_VERSION).await
+        Self::run_new_with_version(
+            sandbox::DEFAULT_NEAR_SANDBOX_VERSION,
+            &SandboxConfig::default(),
+        )
+        .await
     }
 
-    pub(crate) async fn run_new_with_version(version: &str) -> Result<Self> {
+    pub(crate) async fn run_new_with_version(
+        version: &str,
+        config: &SandboxConfig,
+    ) -> Result<Self> {
         // Suppress logs for the sandbox binary by default:
         suppress_sandbox_logs_if_required();
 
-        let home_dir = init_home_dir_with_version(version).await?.into_path();
-        // Configure `$home_dir/config.json` to our liking. Sandbox requires extra settings
-        // for the best user experience, and being able to offer patching large state payloads.
-        crate::network::config::set_sandbox_configs(&home_dir)?;
+        let (home_dir, temp_home_dir) = match &config.home {
+            // The chain in a home directory we reopen has been set up already, so the
+            // configuration is only applied to fresh ones.
+            Some(HomeDir::Reopen(path)) => {
+                if !path.join("genesis.json").is_file() {
+                    return Err(SandboxErrorCode::InitFailure
+                        .message(format!("no sandbox home directory found at {path:?}")));
+                }
+                (path.clone(), None)
+            }
+            Some(HomeDir::Keep(path)) => {
+                if config.tmpfs {
+                    return Err(SandboxErrorCode::InitFailure
+                        .message("`keep_home_dir` cannot be used along with `tmpfs`"));
+                }
+                std::fs::create_dir_all(path).map_err(|e| ErrorKind::Io.custom(e))?;
+                init_home_dir_at(version, path, config.random_seed.as_deref()).await?;
+                (path.clone(), None)
+            }
+            None => {
+                let home_dir = init_home_dir_with_version(
+                    version,
+                    config.home_parent()?.as_deref(),
+                    config.random_seed.as_deref(),
+                )
+                .await?;
+                if config.tmpfs {
+                    (home_dir.path().to_path_buf(), Some(home_dir))
+                } else {
+                    (home_dir.into_path(), None)
+                }
+            }
+        };
+        if !matches!(config.home, Some(HomeDir::Reopen(_))) {
+            // Configure `$home_dir/config.json` to our liking. Sandbox requires extra settings
+            // for the best user experience, and being able to offer patching large state
+            // payloads.
+            crate::network::config::set_sandbox_configs(&home_dir)?;
+            config.apply(&home_dir)?;
+        }
+
+        Self::spawn_in(version, home_dir, temp_home_dir, &config.ports).await
+    }
 
+    /// Spawn a node out of `home_dir`, which is expected to be set up already.
+    pub(crate) async fn spawn_in(
+        version: &str,
+        home_dir: PathBuf,
+        temp_home_dir: Option<TempDir>,
+        ports: &PortConfig,
+    ) -> Result<Self> {
         // Try running the server with the follow provided rpc_ports and net_ports
-        let (rpc_port, rpc_port_lock) = acquire_unused_port().await?;
-        let (net_port, net_port_lock) = acquire_unused_port().await?;
+        let (rpc_port, rpc_port_lock) = acquire_configured_port(ports.rpc, ports, None).await?;
+        let (net_port, net_port_lock) =
+            acquire_configured_port(ports.net, ports, Some(rpc_port)).await?;
         // It's important that the address doesn't have a scheme, since the sandbox expects
         // a valid socket address.
         let rpc_addr = rpc_socket(rpc_port);
@@ -133,21 +453,15 @@ impl SandboxServer {
 
         info!(target: "workspaces", "Starting up sandbox at localhost:{}", rpc_port);
 
-        let options = &[
-            "--home",
-            home_dir
-                .as_os_str()
-                .to_str()
-                .expect("home_dir is valid utf8"),
-            "run",
-            "--rpc-addr",
-            &rpc_addr,
-            "--network-addr",
-            &net_addr,
-        ];
-
-        let child = sandbox::run_with_options_with_version(options, version)
-            .map_err(|e| SandboxErrorCode::RunFailure.custom(e))?;
+        let local = LocalNode {
+            home_dir: home_dir.clone(),
+            version: version.to_string(),
+            rpc_addr: rpc_addr.clone(),
+            net_addr,
+        };
+        let chain_id = crate::network::config::chain_id(&home_dir);
+        let log_forwarder = LogForwarder::spawn(NodeLog::new(home_dir.join(LOG_FILE)), chain_id);
+        let child = local.spawn()?;
 
         info!(target: "workspaces", "Started up sandbox at localhost:{} with pid={:?}", rpc_port, child.id());
 
@@ -161,7 +475,130 @@ impl SandboxServer {
             net_port: Some(net_port),
             rpc_port_lock: Some(rpc_port_lock),
             net_port_lock: Some(net_port_lock),
-            process: Some(child),
+            process: Mutex::new(Some(child)),
+            local: Some(local),
+            kill_on_exit: AtomicBool::new(false),
+            home_dir: temp_home_dir,
+            container: None,
+            remote: None,
+            _log_forwarder: Some(log_forwarder),
+        })
+    }
+
+    /// Run a new SandboxServer on a remote machine over SSH. `destination` is anything `ssh`
+    /// accepts as a destination, such as `user@host`, where `host` is required to also be
+    /// reachable over HTTP from this machine. `neard-sandbox` and `python3` are expected to
+    /// be available in the `PATH` of the remote machine, the latter to pick free ports.
+    pub(crate) async fn run_remote(destination: &str) -> Result<Self> {
+        let host = destination
+            .rsplit('@')
+            .next()
+            .unwrap_or(destination)
+            .to_string();
+
+        // Only the remote machine knows which of its ports are free, so it picks them along
+        // with the home directory, and reports them back before starting up the node.
+        let script = format!(
+            "home_dir=$(mktemp -d /tmp/near-sandbox-XXXXXX) && \
+             ports=$(python3 -c '{PICK_PORTS_PY}') && \
+             echo {REMOTE_READY} $home_dir $ports && \
+             neard-sandbox --home $home_dir init --test-seed {TEST_SEED} && \
+             neard-sandbox --home $home_dir run --rpc-addr 0.0.0.0:${{ports% *}} \
+             --network-addr 0.0.0.0:${{ports#* }}"
+        );
+
+        info!(target: "workspaces", "Starting up sandbox at {} over ssh", host);
+
+        // Allocate a tty with `-tt`, so that the remote node gets shut down along with the
+        // ssh session when the process is killed.
+        let mut child = Command::new("ssh")
+            .args(["-tt", destination, &script])
+            .stdout(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| SandboxErrorCode::RunFailure.full("failed to spawn ssh", e))?;
+
+        let stdout = child.stdout.take().expect("stdout of ssh is piped");
+        let (home_dir, rpc_port, net_port) = read_remote_ready(stdout).await?;
+
+        info!(target: "workspaces", "Started up sandbox at {}:{} over ssh", host, rpc_port);
+
+        let rpc_addr = Url::parse(&format!("http://{host}:{rpc_port}")).map_err(|e| {
+            SandboxErrorCode::InitFailure.full(format!("Invalid remote host={host}"), e)
+        })?;
+
+        Ok(Self {
+            validator_key: ValidatorKey::from_test_seed(),
+            rpc_addr,
+            net_port: Some(net_port),
+            rpc_port_lock: None,
+            net_port_lock: None,
+            process: Mutex::new(Some(child)),
+            local: None,
+            kill_on_exit: AtomicBool::new(false),
+            home_dir: None,
+            container: None,
+            remote: Some(RemoteNode {
+                destination: destination.to_string(),
+                home_dir,
+            }),
+            _log_forwarder: None,
+        })
+    }
+
+    /// Run a new SandboxServer inside of a Docker container running `image`, with the RPC
+    /// and network ports of the node mapped to ports on the host. The image is required to
+    /// have `neard-sandbox` available in its `PATH`.
+    pub(crate) async fn run_docker(image: &str, ports: &PortConfig) -> Result<Self> {
+        let (rpc_port, rpc_port_lock) = acquire_configured_port(ports.rpc, ports, None).await?;
+        let (net_port, net_port_lock) =
+            acquire_configured_port(ports.net, ports, Some(rpc_port)).await?;
+        let container = format!("near-workspaces-sandbox-{rpc_port}");
+        let script = format!(
+            "neard-sandbox --home {DOCKER_HOME_DIR} init --test-seed {TEST_SEED} && \
+             neard-sandbox --home {DOCKER_HOME_DIR} run --rpc-addr 0.0.0.0:{DOCKER_RPC_PORT} \
+             --network-addr 0.0.0.0:{DOCKER_NET_PORT}"
+        );
+
+        info!(target: "workspaces", "Starting up sandbox at localhost:{} in docker image {}", rpc_port, image);
+
+        let mut command = Command::new("docker");
+        command.args([
+            "run",
+            "--rm",
+            "--name",
+            &container,
+            "--publish",
+            &format!("{DEFAULT_RPC_HOST}:{rpc_port}:{DOCKER_RPC_PORT}"),
+            "--publish",
+            &format!("{DEFAULT_RPC_HOST}:{net_port}:{DOCKER_NET_PORT}"),
+        ]);
+        for (key, value) in sandbox_log_env() {
+            command.args(["--env", &format!("{key}={value}")]);
+        }
+        let child = command
+            .args([image, "sh", "-c", &script])
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .map_err(|e| SandboxErrorCode::RunFailure.full("failed to spawn docker", e))?;
+
+        let rpc_addr: Url = format!("http://{}", rpc_socket(rpc_port))
+            .parse()
+            .expect("static scheme and host name with variable u16 port numbers form valid urls");
+
+        Ok(Self {
+            validator_key: ValidatorKey::from_test_seed(),
+            rpc_addr,
+            net_port: Some(net_port),
+            rpc_port_lock: Some(rpc_port_lock),
+            net_port_lock: Some(net_port_lock),
+            process: Mutex::new(Some(child)),
+            local: None,
+            kill_on_exit: AtomicBool::new(false),
+            home_dir: None,
+            container: Some(container),
+            remote: None,
+            _log_forwarder: None,
         })
     }
 
@@ -191,6 +628,90 @@ impl SandboxServer {
         Ok(())
     }
 
+    /// Wait for the spawned sandbox node to exit, returning its exit status. Never resolves
+    /// if the node was not spawned by us.
+    pub(crate) async fn wait_for_exit(&mut self) -> Option<ExitStatus> {
+        match self.process.get_mut().unwrap() {
+            Some(child) => child.wait().await.ok(),
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Make sure the spawned node gets killed when the process exits, even if the server
+    /// is never dropped. This is needed for servers held onto by statics, which outlive
+    /// the test binary otherwise.
+    pub(crate) fn kill_on_exit(&self) {
+        self.kill_on_exit.store(true, Ordering::SeqCst);
+        if let Some(pid) = self.process.lock().unwrap().as_ref().and_then(Child::id) {
+            exit_kill::register(pid);
+        }
+    }
+
+    /// Process id of the node, if it was spawned locally and is running.
+    pub(crate) fn pid(&self) -> Option<u32> {
+        self.process.lock().unwrap().as_ref().and_then(Child::id)
+    }
+
+    /// Home directory of the node, if it was spawned locally.
+    pub(crate) fn home_dir(&self) -> Option<&Path> {
+        self.local.as_ref().map(|local| local.home_dir.as_path())
+    }
+
+    /// Stop the node, giving it a chance to flush its state to disk before it gets killed.
+    /// The node can be started up again with [`SandboxServer::start`].
+    pub(crate) async fn stop(&self) -> Result<()> {
+        let mut child = match self.process.lock().unwrap().take() {
+            Some(child) => child,
+            None => return Ok(()),
+        };
+
+        info!(target: "workspaces", "Stopping sandbox: pid={:?}", child.id());
+        if let Some(pid) = child.id() {
+            exit_kill::unregister(pid);
+            #[cfg(unix)]
+            // SAFETY: sending a signal has no memory safety implications.
+            unsafe {
+                libc::kill(pid as libc::pid_t, libc::SIGTERM);
+            }
+        }
+
+        if tokio::time::timeout(STOP_TIMEOUT, child.wait())
+            .await
+            .is_err()
+        {
+            child
+                .kill()
+                .await
+                .map_err(|e| SandboxErrorCode::RunFailure.full("failed to stop sandbox", e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Start a node stopped with [`SandboxServer::stop`] back up, with the same home
+    /// directory and ports it ran with before. Only nodes spawned locally can be started.
+    pub(crate) fn start(&self) -> Result<()> {
+        let local = self.local.as_ref().ok_or_else(|| {
+            SandboxErrorCode::RunFailure.message("only sandboxes spawned locally can be restarted")
+        })?;
+
+        let mut process = self.process.lock().unwrap();
+        if process.is_some() {
+            return Err(SandboxErrorCode::AlreadyStarted.into());
+        }
+
+        let child = local.spawn()?;
+        info!(target: "workspaces", "Restarted sandbox at {} with pid={:?}", local.rpc_addr, child.id());
+        if let Some(pid) = child.id() {
+            if self.kill_on_exit.load(Ordering::SeqCst) {
+                exit_kill::register(pid);
+            }
+        }
+        *process = Some(child);
+
+        Ok(())
+    }
+
     pub fn rpc_port(&self) -> Option<u16> {
         self.rpc_addr.port()
     }
@@ -206,17 +727,98 @@ impl SandboxServer {
 
 impl Drop for SandboxServer {
     fn drop(&mut self) {
-        if let Some(mut child) = self.process.take() {
+        if let Some(mut child) = self.process.get_mut().unwrap().take() {
             info!(
                 target: "workspaces",
                 "Cleaning up sandbox: pid={:?}",
                 child.id()
             );
 
+            if let Some(pid) = child.id() {
+                exit_kill::unregister(pid);
+            }
             child.start_kill().expect("failed to kill sandbox");
             let _ = child.try_wait();
         }
+        // Killing the docker client leaves the container running, so it gets removed
+        // through docker itself.
+        if let Some(container) = self.container.take() {
+            info!(target: "workspaces", "Removing sandbox container: {}", container);
+            let _ = std::process::Command::new("docker")
+                .args(["rm", "--force", &container])
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .status();
+        }
+        // The remote node is gone along with the ssh session, but not its home directory.
+        if let Some(remote) = self.remote.take() {
+            info!(
+                target: "workspaces",
+                "Removing sandbox home directory {} on {}",
+                remote.home_dir,
+                remote.destination
+            );
+            let _ = std::process::Command::new("ssh")
+                .args([&remote.destination, "rm", "-rf", &remote.home_dir])
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .status();
+        }
+    }
+}
+
+/// Registry of nodes to kill on process exit, see [`SandboxServer::kill_on_exit`].
+#[cfg(unix)]
+mod exit_kill {
+    use std::sync::{Mutex, Once};
+
+    static PIDS: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+    static REGISTER: Once = Once::new();
+
+    extern "C" fn kill_all() {
+        if let Ok(pids) = PIDS.lock() {
+            for pid in pids.iter() {
+                // SAFETY: sending a signal has no memory safety implications.
+                unsafe {
+                    libc::kill(*pid as libc::pid_t, libc::SIGKILL);
+                }
+            }
+        }
+    }
+
+    pub(super) fn register(pid: u32) {
+        // SAFETY: `kill_all` is a valid function to run on exit.
+        REGISTER.call_once(|| unsafe {
+            libc::atexit(kill_all);
+        });
+        if let Ok(mut pids) = PIDS.lock() {
+            pids.push(pid);
+        }
     }
+
+    pub(super) fn unregister(pid: u32) {
+        if let Ok(mut pids) = PIDS.lock() {
+            pids.retain(|registered| *registered != pid);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod exit_kill {
+    pub(super) fn register(_pid: u32) {}
+    pub(super) fn unregister(_pid: u32) {}
+}
+
+/// Environment of a sandbox node carrying the sandbox specific log settings over, which
+/// `neard` reads under the names of the standard ones.
+fn sandbox_log_env() -> Vec<(&'static str, String)> {
+    [
+        ("RUST_LOG", "NEAR_SANDBOX_LOG"),
+        ("RUST_LOG_STYLE", "NEAR_SANDBOX_LOG_STYLE"),
+    ]
+    .iter()
+    .filter_map(|&(key, var)| Some((key, std::env::var(var).ok()?)))
+    .collect()
 }
 
 /// Turn off neard-sandbox logs by default. Users can turn them back on with
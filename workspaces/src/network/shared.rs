@@ -0,0 +1,271 @@
+//! A single sandbox node shared by all the tests of a test binary, where each test works
+//! under its own account namespace. This is a cheaper alternative to spinning up a node
+//! per test for suites made up of many small tests.
+
+use std::future::IntoFuture;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+
+use tokio::runtime::{Handle, Runtime};
+use tokio::sync::{Mutex, OnceCell};
+
+use crate::error::{ErrorKind, SandboxErrorCode};
+use crate::network::builder::NetworkBuilder;
+use crate::network::pool::background_runtime;
+use crate::network::{Sandbox, ValidatorKey};
+use crate::result::Result;
+use crate::types::{AccountId, KeyType, NearToken, SecretKey};
+use crate::{Account, Contract, Worker};
+
+type Factory = dyn Fn() -> NetworkBuilder<'static, Sandbox> + Send + Sync;
+
+/// Balance given to each namespace, out of which its accounts are funded.
+const NAMESPACE_BALANCE: NearToken = NearToken::from_near(1000);
+/// Balance given to each account created within a namespace.
+const ACCOUNT_BALANCE: NearToken = NearToken::from_near(10);
+
+/// Sandbox node shared between tests, handing out a [`Namespace`] to each of them. The
+/// node is booted on first use and lives as long as the `SharedSandbox` does, which is
+/// usually for as long as the test binary runs. For the common case of sharing a node
+/// with the default configuration, use [`shared_sandbox`](crate::shared_sandbox) instead.
+pub struct SharedSandbox {
+    factory: Box<Factory>,
+    node: OnceCell<SharedNode>,
+    // Namespaces are created with the root account, whose nonce would otherwise be raced
+    // on by tests creating their namespaces at the same time.
+    create_lock: Mutex<()>,
+    counter: AtomicU64,
+    runtime: Option<Runtime>,
+}
+
+struct SharedNode {
+    // Keeps the node alive.
+    _worker: Worker<Sandbox>,
+    rpc_addr: String,
+    validator_key: ValidatorKey,
+    root_id: AccountId,
+}
+
+impl SharedSandbox {
+    /// Share a sandbox node with the default configuration.
+    pub fn new() -> Self {
+        Self::with_builder(crate::sandbox)
+    }
+
+    /// Share a sandbox node configured through the builder returned by `builder`.
+    pub fn with_builder<F>(builder: F) -> Self
+    where
+        F: Fn() -> NetworkBuilder<'static, Sandbox> + Send + Sync + 'static,
+    {
+        Self {
+            factory: Box::new(builder),
+            node: OnceCell::new(),
+            create_lock: Mutex::new(()),
+            counter: AtomicU64::new(0),
+            runtime: Some(background_runtime("shared-sandbox")),
+        }
+    }
+
+    /// Create a new namespace on the shared node, booting the node first if this is the
+    /// first namespace being created.
+    pub async fn namespace(&self) -> Result<Namespace> {
+        let node = self.node.get_or_try_init(|| self.boot()).await?;
+
+        // Connect separately for every namespace, since the connections of a client do
+        // not outlive the runtime of the test that made them.
+        let worker = crate::sandbox()
+            .rpc_addr(&node.rpc_addr)
+            .validator_key(node.validator_key.clone())
+            .await?;
+        let root = worker.root_account()?;
+
+        let index = self.counter.fetch_add(1, Ordering::Relaxed);
+        let sk = SecretKey::from_random(KeyType::ED25519);
+        let account = {
+            let _guard = self.create_lock.lock().await;
+            root.create_subaccount(&format!("ns{index}"))
+                .initial_balance(NAMESPACE_BALANCE)
+                .keys(sk)
+                .transact()
+                .await?
+                .into_result()?
+        };
+
+        Ok(Namespace {
+            worker,
+            account,
+            accounts: StdMutex::new(Vec::new()),
+            counter: AtomicU64::new(0),
+            root_id: node.root_id.clone(),
+            rpc_addr: node.rpc_addr.clone(),
+            validator_key: node.validator_key.clone(),
+            cleanup: self.handle(),
+        })
+    }
+
+    async fn boot(&self) -> Result<SharedNode> {
+        let runtime = self
+            .runtime
+            .as_ref()
+            .expect("runtime lives as long as the sandbox");
+        let builder = (self.factory)();
+        let worker = runtime.spawn(builder.into_future()).await.map_err(|e| {
+            SandboxErrorCode::InitFailure.full("failed to boot shared sandbox", e)
+        })??;
+
+        // Shared sandboxes are usually held onto by statics, which never get dropped.
+        worker.workspace.server.kill_on_exit();
+        Ok(SharedNode {
+            rpc_addr: worker.rpc_addr(),
+            validator_key: worker.workspace.server.validator_key.clone(),
+            root_id: worker.root_account()?.id().clone(),
+            _worker: worker,
+        })
+    }
+
+    fn handle(&self) -> Option<Handle> {
+        self.runtime
+            .as_ref()
+            .map(|runtime| runtime.handle().clone())
+    }
+}
+
+impl Default for SharedSandbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for SharedSandbox {
+    fn drop(&mut self) {
+        // Dropping a runtime blocks, which is not allowed from within an async context.
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+/// Isolated slice of a [`SharedSandbox`] for a single test. All accounts created through
+/// a namespace are sub-accounts of the namespace account, such as `alice.ns3.test.near`,
+/// so tests sharing a node never step on each other's accounts. The accounts and the
+/// namespace account itself are deleted once the namespace is dropped.
+pub struct Namespace {
+    worker: Worker<Sandbox>,
+    account: Account,
+    accounts: StdMutex<Vec<(AccountId, SecretKey)>>,
+    counter: AtomicU64,
+    root_id: AccountId,
+    rpc_addr: String,
+    validator_key: ValidatorKey,
+    cleanup: Option<Handle>,
+}
+
+impl Namespace {
+    /// The worker connected to the shared node, for everything other than creating
+    /// accounts. Note that accounts created directly through it are not cleaned up.
+    pub fn worker(&self) -> &Worker<Sandbox> {
+        &self.worker
+    }
+
+    /// The id of the namespace account, which all the accounts of the namespace are
+    /// sub-accounts of.
+    pub fn id(&self) -> &AccountId {
+        self.account.id()
+    }
+
+    /// The namespace account, which all the accounts of the namespace are sub-accounts of.
+    pub fn account(&self) -> &Account {
+        &self.account
+    }
+
+    /// Create the account `{name}.{namespace}`, funded with 10 NEAR.
+    pub async fn create_account(&self, name: &str) -> Result<Account> {
+        let sk = SecretKey::from_random(KeyType::ED25519);
+        let account = self
+            .account
+            .create_subaccount(name)
+            .initial_balance(ACCOUNT_BALANCE)
+            .keys(sk.clone())
+            .transact()
+            .await?
+            .into_result()?;
+
+        self.accounts
+            .lock()
+            .map_err(|_| ErrorKind::Other.message("namespace accounts lock poisoned"))?
+            .push((account.id().clone(), sk));
+        Ok(account)
+    }
+
+    /// Create an account with a generated name within the namespace and deploy `wasm`
+    /// to it.
+    pub async fn dev_deploy(&self, wasm: &[u8]) -> Result<Contract> {
+        let index = self.counter.fetch_add(1, Ordering::Relaxed);
+        let account = self.create_account(&format!("dev{index}")).await?;
+        account.deploy(wasm).await?.into_result()
+    }
+}
+
+impl Drop for Namespace {
+    fn drop(&mut self) {
+        let cleanup = match &self.cleanup {
+            Some(cleanup) => cleanup,
+            None => return,
+        };
+
+        let accounts = match self.accounts.lock() {
+            Ok(mut accounts) => std::mem::take(&mut *accounts),
+            Err(_) => return,
+        };
+        let namespace = match self.account.secret_key() {
+            Some(sk) => (self.account.id().clone(), sk.clone()),
+            None => return,
+        };
+        let root_id = self.root_id.clone();
+        let rpc_addr = self.rpc_addr.clone();
+        let validator_key = self.validator_key.clone();
+
+        // The runtime of the test is likely shutting down, so clean up on the runtime of
+        // the shared sandbox with a connection of its own.
+        cleanup.spawn(async move {
+            let result: Result<()> = async {
+                let worker = crate::sandbox()
+                    .rpc_addr(&rpc_addr)
+                    .validator_key(validator_key)
+                    .await?;
+                for (id, sk) in accounts {
+                    Account::from_secret_key(id, sk, &worker)
+                        .delete_account(&namespace.0)
+                        .await?;
+                }
+                Account::from_secret_key(namespace.0.clone(), namespace.1, &worker)
+                    .delete_account(&root_id)
+                    .await?;
+                Ok(())
+            }
+            .await;
+
+            if let Err(err) = result {
+                tracing::warn!(
+                    target: "workspaces",
+                    "failed to clean up namespace {}: {}",
+                    namespace.0,
+                    err
+                );
+            }
+        });
+    }
+}
+
+/// Process wide [`SharedSandbox`] used by [`shared_sandbox`](crate::shared_sandbox).
+static SHARED: StdMutex<Option<Arc<SharedSandbox>>> = StdMutex::new(None);
+
+pub(crate) async fn shared_namespace() -> Result<Namespace> {
+    let shared = {
+        let mut shared = SHARED.lock()?;
+        shared
+            .get_or_insert_with(|| Arc::new(SharedSandbox::new()))
+            .clone()
+    };
+    shared.namespace().await
+}
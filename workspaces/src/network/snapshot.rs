@@ -0,0 +1,114 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tempfile::TempDir;
+
+use crate::error::SandboxErrorCode;
+use crate::result::Result;
+
+/// Directory within the home directory of a node that holds the state of the chain.
+const DATA_DIR: &str = "data";
+
+/// Identifies a snapshot of the state of a sandbox, taken with
+/// [`Worker::snapshot`](crate::Worker::snapshot) and restored with
+/// [`Worker::restore`](crate::Worker::restore). Carries the identity of the sandbox it was
+/// taken from, its RPC port and home directory, so that it cannot be restored into another.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct SnapshotId {
+    rpc_port: Option<u16>,
+    home_dir: u64,
+    index: usize,
+}
+
+impl SnapshotId {
+    /// Whether this snapshot was taken of the sandbox listening on `rpc_port` and living in
+    /// `home_dir`.
+    fn taken_of(&self, home_dir: &Path, rpc_port: Option<u16>) -> bool {
+        self.rpc_port == rpc_port && self.home_dir == hash_path(home_dir)
+    }
+}
+
+/// Snapshots taken of the state of a sandbox, which are copies of the data directory
+/// of the node kept around until the sandbox is dropped.
+#[derive(Default)]
+pub(crate) struct Snapshots {
+    dir: Option<TempDir>,
+    taken: usize,
+}
+
+impl Snapshots {
+    /// Copy the data directory out of `home_dir` of the node listening on `rpc_port`. The
+    /// node is expected to be stopped.
+    pub(crate) fn take(&mut self, home_dir: &Path, rpc_port: Option<u16>) -> Result<SnapshotId> {
+        if self.dir.is_none() {
+            self.dir = Some(tempfile::tempdir().map_err(|e| {
+                SandboxErrorCode::SnapshotFailure.full("failed to create snapshot directory", e)
+            })?);
+        }
+        let id = SnapshotId {
+            rpc_port,
+            home_dir: hash_path(home_dir),
+            index: self.taken,
+        };
+        let path = self.path(id).expect("snapshot directory was just created");
+        copy_dir(&home_dir.join(DATA_DIR), &path).map_err(|e| {
+            SandboxErrorCode::SnapshotFailure.full(format!("failed to take snapshot {id:?}"), e)
+        })?;
+        self.taken += 1;
+
+        Ok(id)
+    }
+
+    /// Whether snapshot `id` was taken from the sandbox in `home_dir` listening on `rpc_port`.
+    pub(crate) fn contains(&self, id: SnapshotId, home_dir: &Path, rpc_port: Option<u16>) -> bool {
+        self.dir.is_some() && id.index < self.taken && id.taken_of(home_dir, rpc_port)
+    }
+
+    /// Replace the data directory in `home_dir` with the one of snapshot `id`. The node is
+    /// expected to be stopped.
+    pub(crate) fn restore(&self, id: SnapshotId, home_dir: &Path) -> Result<()> {
+        let path = self
+            .path(id)
+            .filter(|_| id.index < self.taken)
+            .ok_or_else(|| {
+                SandboxErrorCode::SnapshotFailure.message(format!("no such snapshot {id:?}"))
+            })?;
+
+        let data_dir = home_dir.join(DATA_DIR);
+        fs::remove_dir_all(&data_dir)
+            .and_then(|_| copy_dir(&path, &data_dir))
+            .map_err(|e| {
+                SandboxErrorCode::SnapshotFailure
+                    .full(format!("failed to restore snapshot {id:?}"), e)
+            })
+    }
+
+    fn path(&self, id: SnapshotId) -> Option<PathBuf> {
+        self.dir
+            .as_ref()
+            .map(|dir| dir.path().join(id.index.to_string()))
+    }
+}
+
+fn hash_path(path: &Path) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
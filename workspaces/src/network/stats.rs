@@ -0,0 +1,70 @@
+use std::fs;
+use std::path::Path;
+
+use crate::types::{BlockHeight, Gas};
+
+/// Snapshot of the resources a sandbox node takes up, as returned by
+/// [`Worker::stats`](crate::Worker::stats). Some of them are only known for some kinds of
+/// sandbox, as noted on each of them.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct SandboxStats {
+    /// Height of the latest block.
+    pub block_height: BlockHeight,
+    /// Gas burnt by the chunks of the latest block, summed over all shards.
+    pub chunk_gas_used: Gas,
+    /// Gas the chunks of the latest block could have burnt at most, summed over all shards.
+    pub chunk_gas_limit: Gas,
+    /// Size in bytes of the data directory of the node, which holds the state of the chain.
+    /// Only known for nodes with their home directory on this machine.
+    pub state_size: Option<u64>,
+    /// Resident memory in bytes of the node process. Only known for nodes spawned locally
+    /// by workspaces on Linux.
+    pub memory: Option<u64>,
+}
+
+impl SandboxStats {
+    /// Fraction of the gas the chunks of the latest block could have burnt, which they
+    /// burnt, from `0.0` for empty chunks up to `1.0` for full ones.
+    pub fn chunk_utilization(&self) -> f64 {
+        if self.chunk_gas_limit.as_gas() == 0 {
+            return 0.0;
+        }
+        self.chunk_gas_used.as_gas() as f64 / self.chunk_gas_limit.as_gas() as f64
+    }
+}
+
+/// Total size in bytes of the files under `path`.
+pub(crate) fn dir_size(path: &Path) -> std::io::Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() {
+            dir_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(size)
+}
+
+/// Resident memory in bytes of the process `pid`, as reported by `/proc`.
+#[cfg(target_os = "linux")]
+pub(crate) fn resident_memory(pid: u32) -> Option<u64> {
+    let status = fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+    let kib = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn resident_memory(_pid: u32) -> Option<u64> {
+    None
+}
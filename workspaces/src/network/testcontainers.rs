@@ -0,0 +1,113 @@
+//! Integration with [`testcontainers`](https://docs.rs/testcontainers) so that projects
+//! already managing their integration test dependencies with it can manage the sandbox
+//! node the same way. Requires the `testcontainers` feature.
+//!
+//! ```ignore
+//! use near_workspaces::network::testcontainers::{connect, SandboxImage};
+//!
+//! let docker = testcontainers::clients::Cli::default();
+//! let container = docker.run(SandboxImage::new("my-registry/near-sandbox", "latest"));
+//! let worker = connect(&container).await?;
+//! ```
+
+use std::collections::HashMap;
+
+use testcontainers::core::WaitFor;
+use testcontainers::{Container, Image, ImageArgs};
+
+use crate::network::server::TEST_SEED;
+use crate::network::{Sandbox, ValidatorKey};
+use crate::result::Result;
+use crate::Worker;
+
+/// Port the RPC service of the sandbox node listens on inside of the container.
+pub const RPC_PORT: u16 = 3030;
+
+/// Seed used to generate the validator key of the containerized sandbox node. Knowing the
+/// seed lets us derive the key of the root account without reading it out of the container.
+pub const VALIDATOR_SEED: &str = TEST_SEED;
+
+const HOME_DIR: &str = "/srv/near-sandbox";
+
+/// Image of a sandbox node. The image is expected to have `sh` and the `neard-sandbox`
+/// binary available in its `PATH`.
+#[derive(Clone, Debug)]
+pub struct SandboxImage {
+    name: String,
+    tag: String,
+    env_vars: HashMap<String, String>,
+}
+
+impl SandboxImage {
+    /// Create a new [`SandboxImage`] out of the image `name` and `tag`.
+    pub fn new(name: &str, tag: &str) -> Self {
+        Self {
+            name: name.into(),
+            tag: tag.into(),
+            env_vars: HashMap::new(),
+        }
+    }
+
+    /// Set an environment variable of the sandbox node, such as `NEAR_SANDBOX_LOG`.
+    pub fn with_env_var(mut self, key: &str, value: &str) -> Self {
+        self.env_vars.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// Arguments supplied to the sandbox image, which initializes and then runs the node.
+#[derive(Clone, Debug, Default)]
+pub struct SandboxArgs;
+
+impl ImageArgs for SandboxArgs {
+    fn into_iterator(self) -> Box<dyn Iterator<Item = String>> {
+        let script = format!(
+            "neard-sandbox --home {HOME_DIR} init --test-seed {VALIDATOR_SEED} && \
+             neard-sandbox --home {HOME_DIR} run --rpc-addr 0.0.0.0:{RPC_PORT}"
+        );
+        Box::new(vec!["-c".to_string(), script].into_iter())
+    }
+}
+
+impl Image for SandboxImage {
+    type Args = SandboxArgs;
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn tag(&self) -> String {
+        self.tag.clone()
+    }
+
+    fn ready_conditions(&self) -> Vec<WaitFor> {
+        // Readiness of the RPC service is checked by the worker itself when connecting.
+        vec![WaitFor::Nothing]
+    }
+
+    fn env_vars(&self) -> Box<dyn Iterator<Item = (&String, &String)> + '_> {
+        Box::new(self.env_vars.iter())
+    }
+
+    fn entrypoint(&self) -> Option<String> {
+        Some("sh".into())
+    }
+
+    fn expose_ports(&self) -> Vec<u16> {
+        vec![RPC_PORT]
+    }
+}
+
+/// The validator key of a sandbox node started from a [`SandboxImage`].
+pub fn validator_key() -> ValidatorKey {
+    ValidatorKey::from_test_seed()
+}
+
+/// Connect a [`Worker`] to the sandbox node running inside of `container`.
+pub async fn connect(container: &Container<'_, SandboxImage>) -> Result<Worker<Sandbox>> {
+    let rpc_port = container.get_host_port_ipv4(RPC_PORT);
+    crate::sandbox()
+        .rpc_addr(&format!("http://127.0.0.1:{rpc_port}"))
+        .validator_key(validator_key())
+        .await
+}
@@ -1,5 +1,7 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::Arc;
 
 use async_trait::async_trait;
 use near_gas::NearGas;
@@ -7,11 +9,12 @@ use url::Url;
 
 use near_primitives::views::ExecutionStatusView;
 
+use crate::error::ErrorKind;
 use crate::network::builder::{FromNetworkBuilder, NetworkBuilder};
-use crate::network::Info;
 use crate::network::{AllowDevAccountCreation, NetworkClient, NetworkInfo, TopLevelAccountCreator};
+use crate::network::{Faucet, HelperFaucet, Info};
 use crate::result::{Execution, ExecutionDetails, ExecutionFinalResult, ExecutionOutcome, Result};
-use crate::rpc::{client::Client, tool};
+use crate::rpc::client::Client;
 use crate::types::{AccountId, InMemorySigner, NearToken, SecretKey};
 use crate::{Account, Contract, CryptoHash, Network, Worker};
 
@@ -34,17 +37,28 @@ pub const ARCHIVAL_URL: &str = "https://archival-rpc.testnet.near.org";
 pub struct Testnet {
     client: Client,
     info: Info,
+    faucet: Arc<dyn Faucet>,
 }
 
 #[async_trait]
 impl FromNetworkBuilder for Testnet {
     async fn from_builder<'a>(build: NetworkBuilder<'a, Self>) -> Result<Self> {
         let rpc_url = build.rpc_addr.unwrap_or_else(|| RPC_URL.into());
-        let client = Client::new(&rpc_url, build.api_key)?;
+        let client = Client::new(&rpc_url, build.api_key, &build.transport)?
+            .with_stale_tx_retries(build.stale_tx_retries)
+            .with_wait_until(build.wait_until);
         client.wait_for_rpc().await?;
+        let faucet = match build.faucet {
+            Some(faucet) => faucet,
+            None => Arc::new(HelperFaucet::with_transport(
+                Url::parse(HELPER_URL).unwrap(),
+                &build.transport,
+            )?),
+        };
 
         Ok(Self {
             client,
+            faucet,
             info: Info {
                 name: build.name.into(),
                 root_id: AccountId::from_str("testnet").unwrap(),
@@ -75,8 +89,9 @@ impl TopLevelAccountCreator for Testnet {
         sk: SecretKey,
         // TODO: return Account only, but then you don't get metadata info for it...
     ) -> Result<Execution<Account>> {
-        let url = Url::parse(HELPER_URL).unwrap();
-        tool::url_create_account(url, id.clone(), sk.public_key()).await?;
+        self.faucet
+            .create_account(id.clone(), sk.public_key())
+            .await?;
         let signer = InMemorySigner::from_secret_key(id, sk);
 
         Ok(Execution {
@@ -99,7 +114,10 @@ impl TopLevelAccountCreator for Testnet {
                         status: ExecutionStatusView::SuccessValue(Vec::new()),
                     },
                     receipts: Vec::new(),
+                    calls: None,
+                    predecessors: BTreeMap::new(),
                 },
+                retries: 0,
             },
         })
     }
@@ -134,3 +152,58 @@ impl NetworkInfo for Testnet {
         &self.info
     }
 }
+
+impl Worker<Testnet> {
+    /// Create the human-readable account `id`, such as `alice.testnet`, through the
+    /// `testnet` registrar contract like wallets do, rather than through the helper
+    /// service [`Worker::create_tla`] goes through. This gets accounts with predictable
+    /// names for tests that hand them over to people afterwards. `funder` pays `deposit`
+    /// to the registrar, which goes on to become the balance of the new account, so it
+    /// has to cover the storage the account takes up.
+    /// ```ignore
+    /// let worker = near_workspaces::testnet().await?;
+    /// let funder = Account::from_file("./funder.json", &worker)?;
+    /// let alice = worker
+    ///     .create_named_account(&funder, "alice-e2e.testnet".parse()?, sk, NearToken::from_near(1))
+    ///     .await?
+    ///     .into_result()?;
+    /// ```
+    pub async fn create_named_account(
+        &self,
+        funder: &Account,
+        id: AccountId,
+        sk: SecretKey,
+        deposit: NearToken,
+    ) -> Result<Execution<Account>> {
+        let registrar = self.info().root_id.clone();
+        if !id.is_sub_account_of(&registrar) {
+            return Err(ErrorKind::DataConversion.message(format!(
+                "{id} cannot be created by the registrar, since it is not directly under {registrar}"
+            )));
+        }
+
+        let outcome = funder
+            .call(&registrar, "create_account")
+            .args_json(serde_json::json!({
+                "new_account_id": id,
+                "new_public_key": sk.public_key(),
+            }))
+            .deposit(deposit)
+            .max_gas()
+            .transact()
+            .await?;
+        // The registrar refunds the deposit and returns false if the account was taken.
+        let created: bool = outcome.clone().json()?;
+        if !created {
+            return Err(ErrorKind::Execution.message(format!(
+                "registrar {registrar} failed to create {id}, which is likely taken already"
+            )));
+        }
+
+        let signer = InMemorySigner::from_secret_key(id, sk);
+        Ok(Execution {
+            result: Account::new(signer, self.clone().coerce()),
+            details: outcome,
+        })
+    }
+}
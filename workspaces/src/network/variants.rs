@@ -41,6 +41,11 @@ impl<T> Worker<T>
 where
     T: DevNetwork + TopLevelAccountCreator + 'static,
 {
+    /// Create the top-level account `id` with a full access key for `sk`, funded by the
+    /// root account of the network. On sandbox, this takes any account id, including short
+    /// ones like `alice` which only the registrar could create otherwise, and ones outside
+    /// of the root account like `alice.near`, for tests where the naming of accounts
+    /// matters such as access control lists.
     pub async fn create_tla(&self, id: AccountId, sk: SecretKey) -> Result<Execution<Account>> {
         let res = self
             .workspace
@@ -80,14 +85,69 @@ where
 
     pub async fn dev_create_account(&self) -> Result<Account> {
         let (id, sk) = self.dev_generate().await;
-        let account = self.create_tla(id.clone(), sk).await?;
-        Ok(account.into_result()?)
+        self.dev_create_account_with_key(id, sk).await
+    }
+
+    /// Creates a dev account signing with a new random key of `key_type`, such as
+    /// [`KeyType::SECP256K1`] to exercise contracts verifying secp256k1 signatures.
+    pub async fn dev_create_account_with_key_type(&self, key_type: KeyType) -> Result<Account> {
+        let (id, _) = self.dev_generate().await;
+        self.dev_create_account_with_key(id, SecretKey::from_random(key_type))
+            .await
+    }
+
+    async fn dev_create_account_with_key(&self, id: AccountId, sk: SecretKey) -> Result<Account> {
+        let account = self.create_tla(id.clone(), sk).await?.into_result()?;
+        if let Some(policy) = &self.top_up {
+            policy.track(&id)?;
+        }
+        Ok(account)
     }
 
     pub async fn dev_deploy(&self, wasm: &[u8]) -> Result<Contract> {
         let (id, sk) = self.dev_generate().await;
-        let contract = self.create_tla_and_deploy(id.clone(), sk, wasm).await?;
-        Ok(contract.into_result()?)
+        let contract = self
+            .create_tla_and_deploy(id.clone(), sk, wasm)
+            .await?
+            .into_result()?;
+        if let Some(policy) = &self.top_up {
+            policy.track(&id)?;
+        }
+        Ok(contract)
+    }
+
+    /// Creates a dev account and deploys the wasm file located at `url` to it, such as the
+    /// wasm attached to a GitHub release of a third party contract. The downloaded file has
+    /// to match the hex encoded `expected_sha256` hash, and is cached so that it only has to
+    /// be downloaded once. The download goes through the transport configured for the
+    /// network, such as its proxy and headers.
+    pub async fn dev_deploy_url(&self, url: &str, expected_sha256: &str) -> Result<Contract> {
+        let wasm =
+            crate::rpc::tool::fetch_wasm(self.client().http_client(), url, expected_sha256).await?;
+        self.dev_deploy(&wasm).await
+    }
+
+    /// Creates a dev account, deploys `wasm` to it and calls the init `function` with `args`
+    /// serialized as JSON. The outcome of the init call is returned alongside the contract,
+    /// so call [`Execution::into_result`] to make sure the contract was initialized.
+    pub async fn dev_deploy_and_init<U: serde::Serialize>(
+        &self,
+        wasm: &[u8],
+        function: &str,
+        args: U,
+    ) -> Result<Execution<Contract>> {
+        let contract = self.dev_deploy(wasm).await?;
+        let details = contract
+            .call(function)
+            .args_json(args)
+            .max_gas()
+            .transact()
+            .await?;
+
+        Ok(Execution {
+            result: contract,
+            details,
+        })
     }
 }
 
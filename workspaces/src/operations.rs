@@ -1,6 +1,6 @@
 //! All operation types that are generated/used when making transactions or view calls.
 
-use crate::error::{ErrorKind, RpcErrorCode};
+use crate::error::{Error, ErrorKind, RpcErrorCode};
 use crate::result::{Execution, ExecutionFinalResult, Result, ViewResultDetails};
 use crate::rpc::client::{
     send_batch_tx_and_retry, send_batch_tx_async_and_retry, DEFAULT_CALL_DEPOSIT,
@@ -8,10 +8,11 @@ use crate::rpc::client::{
 };
 use crate::rpc::query::{Query, ViewFunction};
 use crate::types::{
-    AccessKey, AccountId, Gas, InMemorySigner, KeyType, NearToken, PublicKey, SecretKey,
+    AccessKey, AccessKeyPermission, AccountId, Gas, InMemorySigner, KeyType, NearToken, PublicKey,
+    SecretKey, TxExecutionStatus,
 };
-use crate::worker::Worker;
-use crate::{Account, CryptoHash, Network};
+use crate::worker::{attached_gas, check_gas, fork_on_miss, top_up_signer, Worker};
+use crate::{Account, Contract, CryptoHash, Network};
 
 use near_account_id::ParseAccountError;
 use near_gas::NearGas;
@@ -22,13 +23,16 @@ use near_primitives::transaction::{
     DeployContractAction, FunctionCallAction, StakeAction, TransferAction,
 };
 use near_primitives::views::FinalExecutionOutcomeView;
+use std::collections::HashMap;
 use std::convert::TryInto;
 use std::fmt;
 use std::future::IntoFuture;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::task::Poll;
 
-const MAX_GAS: NearGas = NearGas::from_tgas(300);
+pub(crate) const MAX_GAS: NearGas = NearGas::from_tgas(300);
 
 /// A set of arguments we can provide to a transaction, containing
 /// the function name, arguments, the amount of gas to use and deposit.
@@ -119,6 +123,9 @@ pub struct Transaction {
     receiver_id: AccountId,
     // Result used to defer errors in argument parsing to later when calling into transact
     actions: Result<Vec<Action>>,
+    keys: Vec<SecretKey>,
+    wait_until: Option<TxExecutionStatus>,
+    fetch_receipts: bool,
 }
 
 impl Transaction {
@@ -132,17 +139,35 @@ impl Transaction {
             signer,
             receiver_id,
             actions: Ok(Vec::new()),
+            keys: Vec::new(),
+            wait_until: None,
+            fetch_receipts: false,
         }
     }
 
+    pub(crate) fn with_keys(mut self, keys: Vec<SecretKey>) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    /// Sign this transaction with the key associated to `pk`, which has to be one of the
+    /// keys the signing [`Account`] holds. Useful for testing function call access keys.
+    pub fn signer_key(mut self, pk: &PublicKey) -> Self {
+        match select_signer(&self.signer, &self.keys, pk) {
+            Ok(signer) => self.signer = signer,
+            Err(err) => self.actions = Err(err),
+        }
+        self
+    }
+
     /// Adds a key to the `receiver_id`'s account, where the public key can be used
     /// later to delete the same key.
-    pub fn add_key(mut self, pk: PublicKey, ak: AccessKey) -> Self {
+    pub fn add_key(mut self, pk: PublicKey, ak: impl Into<AccessKey>) -> Self {
         if let Ok(actions) = &mut self.actions {
             actions.push(
                 AddKeyAction {
                     public_key: pk.into(),
-                    access_key: ak.into(),
+                    access_key: ak.into().into(),
                 }
                 .into(),
             );
@@ -239,12 +264,42 @@ impl Transaction {
         self
     }
 
-    async fn transact_raw(self) -> Result<FinalExecutionOutcomeView> {
-        let view = send_batch_tx_and_retry(
+    /// Adds a raw nearcore [`Action`] to the transaction. Useful for constructing
+    /// transactions with actions that do not have a dedicated method here.
+    #[cfg(feature = "interop_primitives")]
+    pub fn action(mut self, action: Action) -> Self {
+        if let Ok(actions) = &mut self.actions {
+            actions.push(action);
+        }
+        self
+    }
+
+    /// Sets how far along the execution of the transaction has to be before `transact`
+    /// or `transact_async` return, overriding the level the worker was configured with
+    /// through `NetworkBuilder::wait_until`. See [`TxExecutionStatus`] for the levels.
+    pub fn wait_until(mut self, wait_until: TxExecutionStatus) -> Self {
+        self.wait_until = Some(wait_until);
+        self
+    }
+
+    /// Fetch the details of the receipts spawned by the transaction once it executes, which
+    /// is what [`ExecutionFinalResult::assert_called`], [`ExecutionFinalResult::function_calls`]
+    /// and [`GasBudgets`] inspect. This takes another RPC call per transaction, so it is only
+    /// done when asked for.
+    ///
+    /// [`GasBudgets`]: crate::assertions::GasBudgets
+    pub fn with_receipts(mut self) -> Self {
+        self.fetch_receipts = true;
+        self
+    }
+
+    async fn transact_raw(self) -> Result<(FinalExecutionOutcomeView, u32)> {
+        let (view, retries) = send_batch_tx_and_retry(
             self.worker.client(),
             &self.signer,
             &self.receiver_id,
             self.actions?,
+            self.wait_until,
         )
         .await?;
 
@@ -261,15 +316,31 @@ impl Transaction {
             }
         }
 
-        Ok(view)
+        Ok((view, retries))
     }
 
     /// Process the transaction, and return the result of the execution.
     pub async fn transact(self) -> Result<ExecutionFinalResult> {
-        self.transact_raw()
+        fork_on_miss(&self.worker, &self.receiver_id).await?;
+        let worker = self.worker.clone();
+        let sender_id = self.signer.account_id.clone();
+        let fetch_receipts = self.fetch_receipts;
+        let attached = match &self.actions {
+            Ok(actions) => attached_gas(actions),
+            Err(_) => Gas::from_gas(0),
+        };
+        let mut result = self
+            .transact_raw()
             .await
-            .map(ExecutionFinalResult::from_view)
-            .map_err(crate::error::Error::from)
+            .map(|(view, retries)| ExecutionFinalResult::from_view(view).with_retries(retries))
+            .map_err(crate::error::Error::from)?;
+
+        if fetch_receipts {
+            result = attach_receipts(&worker, &sender_id, result).await;
+        }
+        top_up_signer(&worker, &sender_id).await;
+        check_gas(&worker, attached, &result)?;
+        Ok(result)
     }
 
     /// Send the transaction to the network to be processed. This will be done asynchronously
@@ -280,8 +351,37 @@ impl Transaction {
     ///
     /// [`status`]: TransactionStatus::status
     pub async fn transact_async(self) -> Result<TransactionStatus> {
-        send_batch_tx_async_and_retry(self.worker, &self.signer, &self.receiver_id, self.actions?)
-            .await
+        fork_on_miss(&self.worker, &self.receiver_id).await?;
+        let mut status = send_batch_tx_async_and_retry(
+            self.worker,
+            &self.signer,
+            &self.receiver_id,
+            self.actions?,
+            self.wait_until,
+        )
+        .await?;
+        status.fetch_receipts = self.fetch_receipts;
+        Ok(status)
+    }
+
+    /// Split the transaction into what is needed to sign it, surfacing any error deferred
+    /// while building it up.
+    pub(crate) fn into_parts(self) -> Result<(InMemorySigner, AccountId, Vec<Action>)> {
+        Ok((self.signer, self.receiver_id, self.actions?))
+    }
+}
+
+impl From<CallTransaction> for Transaction {
+    fn from(call: CallTransaction) -> Self {
+        let mut tx = Transaction::new(call.worker, call.signer, call.contract_id)
+            .with_keys(call.keys)
+            .call(call.function);
+        if let Some(err) = call.signer_error {
+            tx.actions = Err(err);
+        }
+        tx.wait_until = call.wait_until;
+        tx.fetch_receipts = call.fetch_receipts;
+        tx
     }
 }
 
@@ -292,6 +392,12 @@ pub struct CallTransaction {
     signer: InMemorySigner,
     contract_id: AccountId,
     function: Function,
+    keys: Vec<SecretKey>,
+    // Error in selecting the key to sign with, deferred to when transacting just like
+    // invalid args, but kept apart from the args so that setting them does not clear it.
+    signer_error: Option<Error>,
+    wait_until: Option<TxExecutionStatus>,
+    fetch_receipts: bool,
 }
 
 impl CallTransaction {
@@ -306,7 +412,29 @@ impl CallTransaction {
             signer,
             contract_id,
             function: Function::new(function),
+            keys: Vec::new(),
+            signer_error: None,
+            wait_until: None,
+            fetch_receipts: false,
+        }
+    }
+
+    pub(crate) fn with_keys(mut self, keys: Vec<SecretKey>) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    /// Sign this call with the key associated to `pk`, which has to be one of the keys
+    /// the calling [`Account`] holds. Useful for testing function call access keys.
+    pub fn signer_key(mut self, pk: &PublicKey) -> Self {
+        match select_signer(&self.signer, &self.keys, pk) {
+            Ok(signer) => {
+                self.signer = signer;
+                self.signer_error = None;
+            }
+            Err(err) => self.signer_error = Some(err),
         }
+        self
     }
 
     /// Provide the arguments for the call. These args are serialized bytes from either
@@ -350,28 +478,60 @@ impl CallTransaction {
         self.gas(MAX_GAS)
     }
 
+    /// Sets how far along the execution of the transaction has to be before `transact`
+    /// or `transact_async` return, overriding the level the worker was configured with
+    /// through `NetworkBuilder::wait_until`. See [`TxExecutionStatus`] for the levels.
+    pub fn wait_until(mut self, wait_until: TxExecutionStatus) -> Self {
+        self.wait_until = Some(wait_until);
+        self
+    }
+
+    /// Fetch the details of the receipts spawned by the call once it executes, which
+    /// is what [`ExecutionFinalResult::assert_called`], [`ExecutionFinalResult::function_calls`]
+    /// and [`GasBudgets`] inspect. This takes another RPC call per call, so it is only
+    /// done when asked for.
+    ///
+    /// [`GasBudgets`]: crate::assertions::GasBudgets
+    pub fn with_receipts(mut self) -> Self {
+        self.fetch_receipts = true;
+        self
+    }
+
     /// Finally, send the transaction to the network. This will consume the `CallTransaction`
     /// object and return us the execution details, along with any errors if the transaction
     /// failed in any process along the way.
     pub async fn transact(self) -> Result<ExecutionFinalResult> {
-        let txn = self
-            .worker
-            .client()
-            .call(
-                &self.signer,
-                &self.contract_id,
-                self.function.name.to_string(),
-                self.function.args?,
-                self.function.gas.as_gas(),
-                self.function.deposit,
-            )
-            .await
-            .map(ExecutionFinalResult::from_view)
-            .map_err(crate::error::Error::from)?;
+        if let Some(err) = self.signer_error {
+            return Err(err);
+        }
+        fork_on_miss(&self.worker, &self.contract_id).await?;
+        let txn = send_batch_tx_and_retry(
+            self.worker.client(),
+            &self.signer,
+            &self.contract_id,
+            vec![FunctionCallAction {
+                args: self.function.args?,
+                method_name: self.function.name,
+                gas: self.function.gas.as_gas(),
+                deposit: self.function.deposit.as_yoctonear(),
+            }
+            .into()],
+            self.wait_until,
+        )
+        .await
+        .map(|(view, retries)| ExecutionFinalResult::from_view(view).with_retries(retries))
+        .map_err(crate::error::Error::from)?;
 
         for callback in self.worker.tx_callbacks.iter() {
             callback(txn.total_gas_burnt)?;
         }
+        let txn = if self.fetch_receipts {
+            attach_receipts(&self.worker, &self.signer.account_id, txn).await
+        } else {
+            txn
+        };
+        top_up_signer(&self.worker, &self.signer.account_id).await;
+        check_gas(&self.worker, self.function.gas, &txn)?;
         Ok(txn)
     }
 
@@ -383,7 +543,11 @@ impl CallTransaction {
     ///
     /// [`status`]: TransactionStatus::status
     pub async fn transact_async(self) -> Result<TransactionStatus> {
-        send_batch_tx_async_and_retry(
+        if let Some(err) = self.signer_error {
+            return Err(err);
+        }
+        fork_on_miss(&self.worker, &self.contract_id).await?;
+        let mut status = send_batch_tx_async_and_retry(
             self.worker,
             &self.signer,
             &self.contract_id,
@@ -394,12 +558,16 @@ impl CallTransaction {
                 deposit: self.function.deposit.as_yoctonear(),
             }
             .into()],
+            self.wait_until,
         )
-        .await
+        .await?;
+        status.fetch_receipts = self.fetch_receipts;
+        Ok(status)
     }
 
     /// Instead of transacting the transaction, call into the specified view function.
     pub async fn view(self) -> Result<ViewResultDetails> {
+        fork_on_miss(&self.worker, &self.contract_id).await?;
         Query::new(
             self.worker.client(),
             ViewFunction {
@@ -411,6 +579,337 @@ impl CallTransaction {
     }
 }
 
+/// Default size of the chunks a [`ChunkedUpload`] splits its payload into. This stays
+/// well under the 4 MiB limit the protocol puts on the size of transactions and of the
+/// arguments of a function call.
+pub const DEFAULT_UPLOAD_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Upload a payload too big to fit into a single transaction, such as the code of a DAO
+/// upgrade or a large blob, by calling a staging function on the contract with one chunk
+/// of it at a time, optionally followed by a call applying the staged payload. This is
+/// most commonly constructed from [`Account::upload_chunked`] or [`Contract::upload_chunked`].
+///
+/// How far along the upload is gets tracked by its [`UploadProgress`], which outlives a
+/// failed upload so that the upload can pick up from where it left off through
+/// [`ChunkedUpload::resume`] instead of sending every chunk again:
+/// ```ignore
+/// let upload = contract.upload_chunked("store_chunk", &blob);
+/// let progress = upload.progress();
+/// if upload.apply(Function::new("apply")).transact().await.is_err() {
+///     contract
+///         .upload_chunked("store_chunk", &blob)
+///         .resume(&progress)
+///         .apply(Function::new("apply"))
+///         .transact()
+///         .await?;
+/// }
+/// ```
+///
+/// [`Contract::upload_chunked`]: crate::Contract::upload_chunked
+pub struct ChunkedUpload {
+    worker: Worker<dyn Network>,
+    signer: InMemorySigner,
+    contract_id: AccountId,
+    stage: String,
+    payload: Vec<u8>,
+    chunk_size: usize,
+    chunk_args: Box<dyn Fn(usize, &[u8]) -> Vec<u8> + Send + Sync>,
+    gas: NearGas,
+    deposit: NearToken,
+    apply: Option<Function>,
+    progress: UploadProgress,
+}
+
+impl ChunkedUpload {
+    pub(crate) fn new(
+        worker: Worker<dyn Network>,
+        contract_id: AccountId,
+        signer: InMemorySigner,
+        stage: &str,
+        payload: Vec<u8>,
+    ) -> Self {
+        let progress = UploadProgress::new(payload.len());
+        Self {
+            worker,
+            signer,
+            contract_id,
+            stage: stage.into(),
+            payload,
+            chunk_size: DEFAULT_UPLOAD_CHUNK_SIZE,
+            chunk_args: Box::new(|_offset, chunk| chunk.to_vec()),
+            gas: MAX_GAS,
+            deposit: DEFAULT_CALL_DEPOSIT,
+            apply: None,
+            progress,
+        }
+    }
+
+    /// Specify the size in bytes of the chunks the payload gets split into. Defaults to
+    /// [`DEFAULT_UPLOAD_CHUNK_SIZE`].
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Specify how the arguments of the staging call are made out of the offset of a
+    /// chunk within the payload and the chunk itself. By default, the raw bytes of the
+    /// chunk are passed in as the arguments, which is what contracts reading their input
+    /// with `env::input` expect. Contracts taking JSON can be served with something like:
+    /// ```ignore
+    /// .chunk_args(|offset, chunk| {
+    ///     serde_json::to_vec(&json!({ "offset": offset, "data": base64::encode(chunk) })).unwrap()
+    /// })
+    /// ```
+    pub fn chunk_args(
+        mut self,
+        chunk_args: impl Fn(usize, &[u8]) -> Vec<u8> + Send + Sync + 'static,
+    ) -> Self {
+        self.chunk_args = Box::new(chunk_args);
+        self
+    }
+
+    /// Specify the amount of gas attached to each of the staging calls. Defaults to the
+    /// maximum amount of gas, since storing large chunks is expensive.
+    pub fn gas(mut self, gas: NearGas) -> Self {
+        self.gas = gas;
+        self
+    }
+
+    /// Specify the amount of tokens deposited with each of the staging calls, such as
+    /// to pay for the storage of the chunk.
+    pub fn deposit(mut self, deposit: NearToken) -> Self {
+        self.deposit = deposit;
+        self
+    }
+
+    /// Call `function` on the contract once all of the chunks have been staged, which
+    /// is where the contract would apply the payload, such as deploying staged code.
+    pub fn apply(mut self, function: Function) -> Self {
+        self.apply = Some(function);
+        self
+    }
+
+    /// Pick the upload up from where `progress` left off, skipping over the chunks that
+    /// already made it to the contract. `progress` keeps getting updated by this upload.
+    pub fn resume(mut self, progress: &UploadProgress) -> Self {
+        self.progress = progress.clone();
+        self
+    }
+
+    /// Get a handle to the progress of this upload, which can be used to resume it
+    /// through [`ChunkedUpload::resume`] after it failed.
+    pub fn progress(&self) -> UploadProgress {
+        self.progress.clone()
+    }
+
+    /// Send all of the chunks left to upload one after the other, followed by the call
+    /// applying them if one was specified. Returns the execution details of each of the
+    /// transactions sent. The upload stops at the first chunk that fails, leaving its
+    /// progress right before that chunk.
+    pub async fn transact(mut self) -> Result<Vec<ExecutionFinalResult>> {
+        let apply = self.apply.take();
+        if self.chunk_size == 0 {
+            return Err(ErrorKind::DataConversion.message("chunk size of upload cannot be zero"));
+        }
+        if self.progress.total() != self.payload.len() {
+            return Err(ErrorKind::DataConversion.message(format!(
+                "cannot resume upload of {} bytes with the progress of an upload of {} bytes",
+                self.payload.len(),
+                self.progress.total()
+            )));
+        }
+
+        let mut outcomes = Vec::new();
+        while !self.progress.is_complete() {
+            let offset = self.progress.uploaded();
+            let end = std::cmp::min(offset + self.chunk_size, self.payload.len());
+            let outcome = self
+                .call(&self.stage)
+                .args((self.chunk_args)(offset, &self.payload[offset..end]))
+                .gas(self.gas)
+                .deposit(self.deposit)
+                .transact()
+                .await?;
+            if outcome.is_failure() {
+                outcome.clone().into_result()?;
+            }
+
+            tracing::debug!(
+                target: "workspaces",
+                "uploaded {}/{} bytes to {}",
+                end,
+                self.payload.len(),
+                self.contract_id
+            );
+            self.progress.uploaded.store(end, Ordering::SeqCst);
+            outcomes.push(outcome);
+        }
+
+        if let Some(function) = apply {
+            let mut call = self.call(&function.name);
+            call.function = function;
+            outcomes.push(call.transact().await?);
+        }
+
+        Ok(outcomes)
+    }
+
+    fn call(&self, function: &str) -> CallTransaction {
+        CallTransaction::new(
+            self.worker.clone(),
+            self.contract_id.clone(),
+            self.signer.clone(),
+            function,
+        )
+    }
+}
+
+/// Progress of a [`ChunkedUpload`], shared between the upload and anyone holding onto
+/// this handle. Cloning this yields a handle to the same progress.
+#[derive(Clone, Debug)]
+pub struct UploadProgress {
+    uploaded: Arc<AtomicUsize>,
+    total: usize,
+}
+
+impl UploadProgress {
+    fn new(total: usize) -> Self {
+        Self {
+            uploaded: Arc::new(AtomicUsize::new(0)),
+            total,
+        }
+    }
+
+    /// Number of bytes of the payload that made it to the contract so far.
+    pub fn uploaded(&self) -> usize {
+        self.uploaded.load(Ordering::SeqCst)
+    }
+
+    /// Total size of the payload in bytes.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Whether all of the chunks of the payload made it to the contract.
+    pub fn is_complete(&self) -> bool {
+        self.uploaded() >= self.total
+    }
+}
+
+/// Contract state as a map of its keys to their values, as returned by
+/// [`Contract::view_state`].
+///
+/// [`Contract::view_state`]: crate::Contract::view_state
+pub type ContractState = HashMap<Vec<u8>, Vec<u8>>;
+
+type StateCheck = Box<
+    dyn FnOnce(
+            &ContractState,
+            &ContractState,
+        ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>>
+        + Send,
+>;
+
+/// Upgrade of the code of a contract, as started by [`Contract::upgrade`]. The new code
+/// is deployed over the same account, along with a call to a migration function if one
+/// was specified. Both go out in a single transaction, so a failing migration leaves the
+/// contract running its old code:
+/// ```ignore
+/// let outcome = contract
+///     .upgrade(&new_wasm)
+///     .migrate(Function::new("migrate").max_gas())
+///     .check_state(|before, after| {
+///         if after.len() < before.len() {
+///             return Err("migration dropped state");
+///         }
+///         Ok(())
+///     })
+///     .transact()
+///     .await?;
+/// assert!(outcome.is_success());
+/// ```
+///
+/// [`Contract::upgrade`]: crate::Contract::upgrade
+pub struct UpgradeTransaction {
+    contract: Contract,
+    wasm: Vec<u8>,
+    migrate: Option<Function>,
+    check_state: Option<StateCheck>,
+}
+
+impl UpgradeTransaction {
+    pub(crate) fn new(contract: Contract, wasm: &[u8]) -> Self {
+        Self {
+            contract,
+            wasm: wasm.into(),
+            migrate: None,
+            check_state: None,
+        }
+    }
+
+    /// Call `function` on the contract right after the new code has been deployed, which
+    /// is where the contract would migrate its state over to the layout the new code
+    /// expects.
+    pub fn migrate(mut self, function: Function) -> Self {
+        self.migrate = Some(function);
+        self
+    }
+
+    /// Verify the state of the contract once it has been upgraded. `check` gets handed
+    /// the state of the contract from before the upgrade and from after it, and `transact`
+    /// returns the error it returns. The check only runs for upgrades that succeeded, and
+    /// only once the upgrade has landed, so a failed check does not undo the upgrade: the
+    /// contract is left running the new code over the migrated state.
+    ///
+    /// The state is fetched with [`Contract::view_state`], which regular RPC nodes refuse
+    /// for contracts holding more than 50kB of state. Checking the state of such contracts
+    /// on networks other than sandbox requires an archival node.
+    ///
+    /// [`Contract::view_state`]: crate::Contract::view_state
+    pub fn check_state<F, E>(mut self, check: F) -> Self
+    where
+        F: FnOnce(&ContractState, &ContractState) -> std::result::Result<(), E> + Send + 'static,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        self.check_state = Some(Box::new(move |before, after| {
+            check(before, after).map_err(Into::into)
+        }));
+        self
+    }
+
+    /// Send the upgrade to the network, returning the execution details of the
+    /// transaction deploying the new code. Errors if the state check is not passed, in
+    /// which case the upgrade has landed nonetheless.
+    pub async fn transact(self) -> Result<ExecutionFinalResult> {
+        let before = match self.check_state {
+            Some(_) => Some(self.contract.view_state().await?),
+            None => None,
+        };
+
+        let mut tx = self.contract.batch().deploy(&self.wasm);
+        if let Some(function) = self.migrate {
+            tx = tx.call(function);
+        }
+        let outcome = tx.transact().await?;
+
+        if let (Some(check), Some(before), true) = (self.check_state, before, outcome.is_success())
+        {
+            let after = self.contract.view_state().await?;
+            check(&before, &after).map_err(|e| {
+                ErrorKind::Other.full(
+                    format!(
+                        "state of {} failed the check after upgrading it",
+                        self.contract.id()
+                    ),
+                    e,
+                )
+            })?;
+        }
+
+        Ok(outcome)
+    }
+}
+
 /// Similar to a [`Transaction`], but more specific to creating an account.
 /// This transaction will create a new account with the specified `receiver_id`
 pub struct CreateAccountTransaction<'a, 'b> {
@@ -421,6 +920,7 @@ pub struct CreateAccountTransaction<'a, 'b> {
 
     initial_balance: NearToken,
     secret_key: Option<SecretKey>,
+    keys: Vec<(PublicKey, AccessKeyPermission)>,
 }
 
 impl<'a, 'b> CreateAccountTransaction<'a, 'b> {
@@ -437,6 +937,7 @@ impl<'a, 'b> CreateAccountTransaction<'a, 'b> {
             new_account_id,
             initial_balance: NearToken::from_yoctonear(100000000000000000000000u128),
             secret_key: None,
+            keys: Vec::new(),
         }
     }
 
@@ -453,6 +954,15 @@ impl<'a, 'b> CreateAccountTransaction<'a, 'b> {
         self
     }
 
+    /// Add another access key to the new account, alongside the full access key of its
+    /// secret key, such as a function call key built with [`FunctionCallKey`].
+    ///
+    /// [`FunctionCallKey`]: crate::types::FunctionCallKey
+    pub fn add_key(mut self, pk: PublicKey, permission: impl Into<AccessKeyPermission>) -> Self {
+        self.keys.push((pk, permission.into()));
+        self
+    }
+
     /// Send the transaction to the network. This will consume the `CreateAccountTransaction`
     /// and give us back the details of the execution and finally the new [`Account`] object.
     pub async fn transact(self) -> Result<Execution<Account>> {
@@ -463,15 +973,46 @@ impl<'a, 'b> CreateAccountTransaction<'a, 'b> {
             .try_into()
             .map_err(|e: ParseAccountError| ErrorKind::DataConversion.custom(e))?;
 
-        let outcome = self
-            .worker
-            .client()
-            .create_account(&self.signer, &id, sk.public_key(), self.initial_balance)
-            .await?;
+        let outcome = if self.keys.is_empty() {
+            self.worker
+                .client()
+                .create_account(&self.signer, &id, sk.public_key(), self.initial_balance)
+                .await?
+        } else {
+            let mut actions = vec![
+                CreateAccountAction {}.into(),
+                AddKeyAction {
+                    public_key: sk.public_key().into(),
+                    access_key: AccessKey::full_access().into(),
+                }
+                .into(),
+                TransferAction {
+                    deposit: self.initial_balance.as_yoctonear(),
+                }
+                .into(),
+            ];
+            actions.extend(self.keys.into_iter().map(|(pk, permission)| {
+                Action::from(AddKeyAction {
+                    public_key: pk.into(),
+                    access_key: AccessKey {
+                        nonce: 0,
+                        permission,
+                    }
+                    .into(),
+                })
+            }));
+            send_batch_tx_and_retry(self.worker.client(), &self.signer, &id, actions, None)
+                .await?
+                .0
+        };
 
+        top_up_signer(self.worker, &self.signer.account_id).await;
         let signer = InMemorySigner::from_secret_key(id, sk);
-        let account = Account::new(signer, self.worker.clone());
         let details = ExecutionFinalResult::from_view(outcome);
+        if details.is_success() {
+            self.worker.subaccounts.record(&signer)?;
+        }
+        let account = Account::new(signer, self.worker.clone());
 
         for callback in self.worker.tx_callbacks.iter() {
             callback(details.total_gas_burnt)?;
@@ -493,6 +1034,7 @@ pub struct TransactionStatus {
     worker: Worker<dyn Network>,
     sender_id: AccountId,
     hash: CryptoHash,
+    pub(crate) fetch_receipts: bool,
 }
 
 impl TransactionStatus {
@@ -505,9 +1047,17 @@ impl TransactionStatus {
             worker,
             sender_id: id,
             hash: CryptoHash(hash.0),
+            fetch_receipts: false,
         }
     }
 
+    /// Fetch the details of the receipts spawned by the transaction once it completes. See
+    /// [`Transaction::with_receipts`].
+    pub fn with_receipts(mut self) -> Self {
+        self.fetch_receipts = true;
+        self
+    }
+
     /// Checks the status of the transaction. If an `Err` is returned, then the transaction
     /// is in an unexpected state. The error should have further context. Otherwise, if an
     /// `Ok` value with [`Poll::Pending`] is returned, then the transaction has not finished.
@@ -523,7 +1073,13 @@ impl TransactionStatus {
             .map(ExecutionFinalResult::from_view);
 
         match result {
-            Ok(result) => Ok(Poll::Ready(result)),
+            Ok(mut result) => {
+                if self.fetch_receipts {
+                    result = attach_receipts(&self.worker, &self.sender_id, result).await;
+                }
+                top_up_signer(&self.worker, &self.sender_id).await;
+                Ok(Poll::Ready(result))
+            }
             Err(err) => match err {
                 JsonRpcError::ServerError(JsonRpcServerError::HandlerError(
                     RpcTransactionError::UnknownTransaction { .. },
@@ -561,6 +1117,7 @@ impl fmt::Debug for TransactionStatus {
         f.debug_struct("TransactionStatus")
             .field("sender_id", &self.sender_id)
             .field("hash", &self.hash)
+            .field("fetch_receipts", &self.fetch_receipts)
             .finish()
     }
 }
@@ -573,3 +1130,47 @@ impl IntoFuture for TransactionStatus {
         Box::pin(async { self.wait().await })
     }
 }
+
+/// Find the key associated to `pk` out of the default key of `signer` and the additional
+/// `keys`, and construct a signer out of it.
+fn select_signer(
+    signer: &InMemorySigner,
+    keys: &[SecretKey],
+    pk: &PublicKey,
+) -> Result<InMemorySigner> {
+    if &signer.public_key() == pk {
+        return Ok(signer.clone());
+    }
+    keys.iter()
+        .find(|sk| &sk.public_key() == pk)
+        .map(|sk| InMemorySigner::from_secret_key(signer.account_id.clone(), sk.clone()))
+        .ok_or_else(|| {
+            ErrorKind::DataConversion.message(format!(
+                "account {} does not hold a key for public key {}",
+                signer.account_id, pk
+            ))
+        })
+}
+
+/// Attach the receipts spawned by a transaction to its result, which is what allows the
+/// function calls made during its execution to be inspected. The transaction has been
+/// executed either way, so failing to fetch the receipts is not treated as an error.
+async fn attach_receipts(
+    worker: &Worker<dyn Network>,
+    sender_id: &AccountId,
+    result: ExecutionFinalResult,
+) -> ExecutionFinalResult {
+    let hash = near_primitives::hash::CryptoHash(result.outcome().transaction_hash.0);
+    match worker.client().tx_receipts(sender_id, hash).await {
+        Ok(receipts) => result.with_receipts(receipts),
+        Err(err) => {
+            tracing::warn!(
+                target: "workspaces",
+                "failed to fetch the receipts of transaction {}: {}",
+                result.outcome().transaction_hash,
+                err
+            );
+            result
+        }
+    }
+}
@@ -1,3 +1,6 @@
 //! All traits that are essential to the ease of use of workspaces.
 
 pub use crate::network::TopLevelAccountCreator;
+
+#[cfg(feature = "interop_sdk")]
+pub use crate::types::SdkInterop;
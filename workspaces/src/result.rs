@@ -1,17 +1,19 @@
 //! Result and execution types from results of RPC calls to the network.
 
+use std::collections::BTreeMap;
 use std::fmt;
 
 use near_account_id::AccountId;
 use near_gas::NearGas;
 use near_primitives::errors::TxExecutionError;
 use near_primitives::views::{
-    CallResult, ExecutionOutcomeWithIdView, ExecutionStatusView, FinalExecutionOutcomeView,
-    FinalExecutionStatus,
+    ActionView, CallResult, ExecutionOutcomeWithIdView, ExecutionStatusView,
+    FinalExecutionOutcomeView, FinalExecutionOutcomeWithReceiptView, FinalExecutionStatus,
+    ReceiptEnumView, ReceiptView,
 };
 
 use crate::error::ErrorKind;
-use crate::types::{CryptoHash, Gas, NearToken};
+use crate::types::{BlockHeight, CryptoHash, Gas, NearToken, Receipt};
 
 use base64::{engine::general_purpose, Engine as _};
 
@@ -68,6 +70,10 @@ impl<T> Execution<T> {
 pub(crate) struct ExecutionDetails {
     pub(crate) transaction: ExecutionOutcome,
     pub(crate) receipts: Vec<ExecutionOutcome>,
+    /// Function calls made by the receipts, if their details were fetched.
+    pub(crate) calls: Option<Vec<ReceiptFunctionCall>>,
+    /// Predecessors of the receipts by receipt id, if their details were fetched.
+    pub(crate) predecessors: BTreeMap<CryptoHash, AccountId>,
 }
 
 impl ExecutionDetails {
@@ -117,6 +123,92 @@ impl ExecutionDetails {
             .map(String::as_str)
             .collect()
     }
+
+    /// Grab the typed outcomes of all the receipts generated by this transaction. These
+    /// are the same outcomes as [`Self::receipt_outcomes`], but with their status typed as
+    /// a [`ReceiptStatus`] that can be pattern matched on directly.
+    pub fn typed_receipt_outcomes(&self) -> Vec<ReceiptOutcome> {
+        self.receipts
+            .iter()
+            .map(|outcome| ReceiptOutcome {
+                receipt_id: outcome.transaction_hash,
+                executor_id: outcome.executor_id.clone(),
+                predecessor_id: self.predecessors.get(&outcome.transaction_hash).cloned(),
+                status: ReceiptStatus::from(outcome.status.clone()),
+                logs: outcome.logs.clone(),
+                receipt_ids: outcome.receipt_ids.clone(),
+                gas_burnt: outcome.gas_burnt,
+                tokens_burnt: outcome.tokens_burnt,
+            })
+            .collect()
+    }
+
+    /// Grab all the function calls made by the receipts of this execution.
+    pub fn function_calls(&self) -> &[ReceiptFunctionCall] {
+        self.calls.as_deref().unwrap_or_default()
+    }
+
+    /// Pair each function call with the gas burnt by the receipt that executed it, if
+    /// the details of the receipts were fetched.
+    pub(crate) fn function_call_gas(&self) -> Option<Vec<(&ReceiptFunctionCall, Gas)>> {
+        let calls = self.calls.as_ref()?;
+        Some(
+            calls
+                .iter()
+                .filter_map(|call| {
+                    let outcome = self
+                        .receipts
+                        .iter()
+                        .find(|outcome| outcome.transaction_hash == call.receipt_id)?;
+                    Some((call, outcome.gas_burnt))
+                })
+                .collect(),
+        )
+    }
+
+    #[track_caller]
+    fn assert_called_where(
+        &self,
+        receiver_id: &str,
+        method: &str,
+        args: Option<&serde_json::Value>,
+    ) -> &ReceiptFunctionCall {
+        let calls = match &self.calls {
+            Some(calls) => calls,
+            None => panic!(
+                "cannot assert that `{receiver_id}.{method}` was called: \
+                 the receipts of this execution were not fetched, see `with_receipts`"
+            ),
+        };
+
+        let found = calls.iter().find(|call| {
+            call.receiver_id.as_str() == receiver_id
+                && call.method_name == method
+                && match args {
+                    Some(args) => {
+                        serde_json::from_slice::<serde_json::Value>(&call.args)
+                            .ok()
+                            .as_ref()
+                            == Some(args)
+                    }
+                    None => true,
+                }
+        });
+        match found {
+            Some(call) => call,
+            None => {
+                let expected = match args {
+                    Some(args) => format!("`{receiver_id}.{method}({args})`"),
+                    None => format!("`{receiver_id}.{method}`"),
+                };
+                let made = calls
+                    .iter()
+                    .map(|call| format!("\n  {call}"))
+                    .collect::<String>();
+                panic!("expected a call to {expected}, but the calls made were:{made}")
+            }
+        }
+    }
 }
 
 /// The result after evaluating the status of an execution. This can be [`ExecutionSuccess`]
@@ -158,6 +250,7 @@ pub struct ExecutionFinalResult {
 
     pub(crate) status: FinalExecutionStatus,
     pub(crate) details: ExecutionDetails,
+    pub(crate) retries: u32,
 }
 
 impl fmt::Debug for ExecutionFinalResult {
@@ -167,6 +260,7 @@ impl fmt::Debug for ExecutionFinalResult {
             .field("transaction", &self.details.transaction)
             .field("receipts", &self.details.receipts)
             .field("status", &self.status)
+            .field("retries", &self.retries)
             .finish()
     }
 }
@@ -194,10 +288,63 @@ impl ExecutionFinalResult {
             details: ExecutionDetails {
                 transaction,
                 receipts,
+                calls: None,
+                predecessors: BTreeMap::new(),
             },
+            retries: 0,
         }
     }
 
+    pub(crate) fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Number of times the transaction had to be re-signed and resubmitted after getting
+    /// rejected for a stale nonce or an expired block hash.
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+
+    /// Attach the details of the receipts spawned by the transaction, which is what the
+    /// function calls made during the execution are extracted from.
+    pub(crate) fn with_receipts(mut self, receipts: Vec<ReceiptView>) -> Self {
+        let mut calls = Vec::new();
+        for receipt in receipts {
+            let receipt_id = CryptoHash(receipt.receipt_id.0);
+            self.details
+                .predecessors
+                .insert(receipt_id, receipt.predecessor_id.clone());
+
+            let actions = match receipt.receipt {
+                ReceiptEnumView::Action { actions, .. } => actions,
+                ReceiptEnumView::Data { .. } => continue,
+            };
+            for action in actions {
+                if let ActionView::FunctionCall {
+                    method_name,
+                    args,
+                    gas,
+                    deposit,
+                } = action
+                {
+                    calls.push(ReceiptFunctionCall {
+                        receipt_id,
+                        predecessor_id: receipt.predecessor_id.clone(),
+                        receiver_id: receipt.receiver_id.clone(),
+                        method_name,
+                        args: args.to_vec(),
+                        gas: NearGas::from_gas(gas),
+                        deposit: NearToken::from_yoctonear(deposit),
+                    });
+                }
+            }
+        }
+
+        self.details.calls = Some(calls);
+        self
+    }
+
     /// Converts this object into a [`Result`] holding either [`ExecutionSuccess`] or [`ExecutionFailure`].
     #[allow(clippy::result_large_err)]
     pub fn into_result(self) -> Result<ExecutionSuccess, ExecutionFailure> {
@@ -306,6 +453,42 @@ impl ExecutionFinalResult {
     pub fn logs(&self) -> Vec<&str> {
         self.details.logs()
     }
+
+    /// Grab the typed outcomes of all the receipts generated by this transaction, which
+    /// can be pattern matched on directly. The predecessor of each receipt is only known
+    /// for the results of transactions sent with `with_receipts`.
+    pub fn typed_receipt_outcomes(&self) -> Vec<ReceiptOutcome> {
+        self.details.typed_receipt_outcomes()
+    }
+
+    /// Grab all the function calls made by the receipts of this execution, including
+    /// the call made by the transaction itself. This is empty if the details of the
+    /// receipts were not fetched, which is only done for the results of transactions sent
+    /// with `with_receipts`.
+    pub fn function_calls(&self) -> &[ReceiptFunctionCall] {
+        self.details.function_calls()
+    }
+
+    /// Assert that `method` was called on `receiver_id` at some point during this
+    /// execution, such as by a cross contract call. Returns the matching call so that
+    /// it can be inspected further, or panics with the list of calls that were made.
+    #[track_caller]
+    pub fn assert_called(&self, receiver_id: &str, method: &str) -> &ReceiptFunctionCall {
+        self.details.assert_called_where(receiver_id, method, None)
+    }
+
+    /// Just like `assert_called`, but only matches calls whose arguments equal `args`
+    /// once deserialized from JSON.
+    #[track_caller]
+    pub fn assert_called_with(
+        &self,
+        receiver_id: &str,
+        method: &str,
+        args: &serde_json::Value,
+    ) -> &ReceiptFunctionCall {
+        self.details
+            .assert_called_where(receiver_id, method, Some(args))
+    }
 }
 
 impl ExecutionSuccess {
@@ -332,6 +515,118 @@ impl ExecutionSuccess {
     }
 }
 
+impl ExecutionFailure {
+    /// The raw [`TxExecutionError`] this failure was created from.
+    pub fn error(&self) -> &TxExecutionError {
+        &self.value
+    }
+
+    /// The path of enum variants describing the error, such as
+    /// `ActionError::FunctionCallError::ExecutionError`. This is stable enough to be
+    /// matched on, unlike the `Debug` output of the underlying error.
+    pub fn error_type(&self) -> String {
+        self.failure_details().error_type
+    }
+
+    /// The panic message of the contract, if the failure was caused by a contract
+    /// panicking or aborting during a function call.
+    pub fn panic_message(&self) -> Option<String> {
+        self.failure_details().panic_message
+    }
+
+    /// The id of the first receipt that failed within this execution. Returns `None`
+    /// if the failure happened at the transaction level instead of within a receipt.
+    pub fn receipt_id(&self) -> Option<CryptoHash> {
+        self.details
+            .receipt_failures()
+            .first()
+            .map(|outcome| outcome.transaction_hash)
+    }
+
+    /// Grab the machine-readable details of this failure. Useful for mapping the failure
+    /// into an application specific error type.
+    pub fn failure_details(&self) -> ExecutionFailureDetails {
+        let (path, message) = self.error_path();
+        let panic_message = match path.last().map(String::as_str) {
+            Some("ExecutionError") => message,
+            _ => None,
+        };
+
+        ExecutionFailureDetails {
+            error_type: path.join("::"),
+            panic_message,
+            receipt_id: self.receipt_id(),
+            total_gas_burnt: self.total_gas_burnt,
+        }
+    }
+
+    /// Walk the serialized form of the error, collecting the names of the enum variants
+    /// along the way and the trailing message if there is one.
+    fn error_path(&self) -> (Vec<String>, Option<String>) {
+        let mut path = Vec::new();
+        let mut message = None;
+        let mut value = serde_json::to_value(&self.value).unwrap_or_default();
+        loop {
+            value = match value {
+                serde_json::Value::Object(mut map) => {
+                    if map.len() == 1 {
+                        let (key, inner) = map.into_iter().next().unwrap();
+                        if !key.starts_with(char::is_uppercase) {
+                            break;
+                        }
+                        path.push(key);
+                        inner
+                    } else if let Some(kind) = map.remove("kind") {
+                        kind
+                    } else {
+                        break;
+                    }
+                }
+                serde_json::Value::String(s) => {
+                    if s.starts_with(char::is_uppercase) && s.chars().all(char::is_alphanumeric) {
+                        path.push(s);
+                    } else {
+                        message = Some(s);
+                    }
+                    break;
+                }
+                _ => break,
+            };
+        }
+
+        (path, message)
+    }
+}
+
+/// Machine-readable details of an [`ExecutionFailure`]. Application crates can convert
+/// this into their own error types without having to parse the `Debug` output of the
+/// underlying [`TxExecutionError`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ExecutionFailureDetails {
+    /// Path of the enum variants describing the error, such as
+    /// `ActionError::FunctionCallError::ExecutionError`.
+    pub error_type: String,
+    /// The panic message of the contract, if the contract panicked.
+    pub panic_message: Option<String>,
+    /// The id of the first receipt that failed, if the failure came from a receipt.
+    pub receipt_id: Option<CryptoHash>,
+    /// Total gas burnt by the execution.
+    pub total_gas_burnt: Gas,
+}
+
+impl From<&ExecutionFailure> for ExecutionFailureDetails {
+    fn from(failure: &ExecutionFailure) -> Self {
+        failure.failure_details()
+    }
+}
+
+impl From<ExecutionFailure> for ExecutionFailureDetails {
+    fn from(failure: ExecutionFailure) -> Self {
+        failure.failure_details()
+    }
+}
+
 impl<T> ExecutionResult<T> {
     /// Returns just the transaction outcome.
     pub fn outcome(&self) -> &ExecutionOutcome {
@@ -365,6 +660,232 @@ impl<T> ExecutionResult<T> {
     pub fn logs(&self) -> Vec<&str> {
         self.details.logs()
     }
+
+    /// Grab the typed outcomes of all the receipts generated by this transaction, which
+    /// can be pattern matched on directly. The predecessor of each receipt is only known
+    /// for the results of transactions sent with `with_receipts`.
+    pub fn typed_receipt_outcomes(&self) -> Vec<ReceiptOutcome> {
+        self.details.typed_receipt_outcomes()
+    }
+
+    /// Grab all the function calls made by the receipts of this execution, including
+    /// the call made by the transaction itself. This is empty if the details of the
+    /// receipts were not fetched, which is only done for the results of transactions sent
+    /// with `with_receipts`.
+    pub fn function_calls(&self) -> &[ReceiptFunctionCall] {
+        self.details.function_calls()
+    }
+
+    /// Assert that `method` was called on `receiver_id` at some point during this
+    /// execution, such as by a cross contract call. Returns the matching call so that
+    /// it can be inspected further, or panics with the list of calls that were made.
+    #[track_caller]
+    pub fn assert_called(&self, receiver_id: &str, method: &str) -> &ReceiptFunctionCall {
+        self.details.assert_called_where(receiver_id, method, None)
+    }
+
+    /// Just like `assert_called`, but only matches calls whose arguments equal `args`
+    /// once deserialized from JSON.
+    #[track_caller]
+    pub fn assert_called_with(
+        &self,
+        receiver_id: &str,
+        method: &str,
+        args: &serde_json::Value,
+    ) -> &ReceiptFunctionCall {
+        self.details
+            .assert_called_where(receiver_id, method, Some(args))
+    }
+}
+
+/// Outcome of executing a single receipt, as returned by
+/// [`ExecutionFinalResult::typed_receipt_outcomes`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ReceiptOutcome {
+    /// The id of the receipt.
+    pub receipt_id: CryptoHash,
+    /// The account the receipt was executed on.
+    pub executor_id: AccountId,
+    /// The account that sent the receipt, if the details of the receipts were fetched.
+    pub predecessor_id: Option<AccountId>,
+    /// Whether the receipt succeeded, along with what it returned.
+    pub status: ReceiptStatus,
+    /// Logs emitted while executing the receipt.
+    pub logs: Vec<String>,
+    /// Ids of the receipts generated while executing this receipt.
+    pub receipt_ids: Vec<CryptoHash>,
+    /// The amount of gas burnt executing the receipt.
+    pub gas_burnt: Gas,
+    /// The amount of tokens burnt corresponding to the burnt gas amount.
+    pub tokens_burnt: NearToken,
+}
+
+impl ReceiptOutcome {
+    /// Checks whether the receipt was executed successfully.
+    pub fn is_success(&self) -> bool {
+        matches!(
+            self.status,
+            ReceiptStatus::SuccessValue(_) | ReceiptStatus::SuccessReceiptId(_)
+        )
+    }
+
+    /// Checks whether the receipt failed or its execution state is unknown.
+    pub fn is_failure(&self) -> bool {
+        !self.is_success()
+    }
+}
+
+/// Status of an executed receipt.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ReceiptStatus {
+    /// The receipt succeeded and returned the given raw bytes.
+    SuccessValue(Vec<u8>),
+    /// The receipt succeeded and returned a promise, which resolves to the receipt with
+    /// the given id.
+    SuccessReceiptId(CryptoHash),
+    /// The receipt failed with the given error.
+    Failure(TxExecutionError),
+    /// The execution of the receipt is pending or unknown.
+    Unknown,
+}
+
+impl From<ExecutionStatusView> for ReceiptStatus {
+    fn from(status: ExecutionStatusView) -> Self {
+        match status {
+            ExecutionStatusView::SuccessValue(value) => Self::SuccessValue(value),
+            ExecutionStatusView::SuccessReceiptId(id) => Self::SuccessReceiptId(CryptoHash(id.0)),
+            ExecutionStatusView::Failure(err) => Self::Failure(err),
+            ExecutionStatusView::Unknown => Self::Unknown,
+        }
+    }
+}
+
+/// A function call made by one of the receipts of an execution, as returned by
+/// [`ExecutionFinalResult::function_calls`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ReceiptFunctionCall {
+    /// The id of the receipt that made the call.
+    pub receipt_id: CryptoHash,
+    /// The account that made the call.
+    pub predecessor_id: AccountId,
+    /// The account that was called into.
+    pub receiver_id: AccountId,
+    /// The name of the function that was called.
+    pub method_name: String,
+    /// The raw arguments the function was called with.
+    pub args: Vec<u8>,
+    /// The amount of gas attached to the call.
+    pub gas: Gas,
+    /// The amount of tokens attached to the call.
+    pub deposit: NearToken,
+}
+
+impl ReceiptFunctionCall {
+    /// Deserialize the arguments of the call from JSON.
+    pub fn args_json<T: serde::de::DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_slice(&self.args).map_err(|e| ErrorKind::DataConversion.custom(e))
+    }
+
+    /// Deserialize the arguments of the call from borsh.
+    pub fn args_borsh<T: borsh::BorshDeserialize>(&self) -> Result<T> {
+        borsh::BorshDeserialize::try_from_slice(&self.args)
+            .map_err(|e| ErrorKind::DataConversion.custom(e))
+    }
+}
+
+impl fmt::Display for ReceiptFunctionCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} -> {}.{}({})",
+            self.predecessor_id,
+            self.receiver_id,
+            self.method_name,
+            String::from_utf8_lossy(&self.args)
+        )
+    }
+}
+
+/// The outcome of a transaction along with the full bodies of all the receipts it spawned,
+/// as returned by [`Worker::view_tx_with_receipts`]. Unlike the receipt outcomes within
+/// [`ExecutionFinalResult`], the receipts carry the precise actions and deposits each of
+/// them scheduled to execute on their receiver.
+///
+/// [`Worker::view_tx_with_receipts`]: crate::Worker::view_tx_with_receipts
+#[derive(Clone, Debug)]
+pub struct TransactionWithReceipts {
+    result: ExecutionFinalResult,
+    receipts: Vec<Receipt>,
+}
+
+impl TransactionWithReceipts {
+    pub(crate) fn from_view(view: FinalExecutionOutcomeWithReceiptView) -> Self {
+        Self {
+            result: ExecutionFinalResult::from_view(view.final_outcome)
+                .with_receipts(view.receipts.clone()),
+            receipts: view.receipts.into_iter().map(Receipt::from).collect(),
+        }
+    }
+
+    /// The outcome of the transaction, along with the outcomes of its receipts.
+    pub fn result(&self) -> &ExecutionFinalResult {
+        &self.result
+    }
+
+    /// Take the outcome of the transaction, dropping the bodies of the receipts.
+    pub fn into_result(self) -> ExecutionFinalResult {
+        self.result
+    }
+
+    /// All of the receipts spawned by the transaction, including refunds.
+    pub fn receipts(&self) -> &[Receipt] {
+        &self.receipts
+    }
+
+    /// The receipt spawned by the transaction with the id `receipt_id`, such as one of the
+    /// ids within the receipt outcomes of the transaction.
+    pub fn receipt(&self, receipt_id: &CryptoHash) -> Option<&Receipt> {
+        self.receipts
+            .iter()
+            .find(|receipt| receipt.id() == receipt_id)
+    }
+
+    /// The receipts spawned by the transaction executing on `receiver_id`.
+    pub fn receipts_to<'a>(
+        &'a self,
+        receiver_id: &'a AccountId,
+    ) -> impl Iterator<Item = &'a Receipt> + 'a {
+        self.receipts
+            .iter()
+            .filter(move |receipt| receipt.receiver_id() == receiver_id)
+    }
+}
+
+/// Error returned by [`Worker::submit_all`] when a transaction could not be sent or its
+/// outcome could not be fetched. Transactions sent before the failure were still waited on,
+/// so that their outcomes tell what landed on chain.
+///
+/// [`Worker::submit_all`]: crate::Worker::submit_all
+#[derive(Debug, thiserror::Error)]
+#[error("{error}")]
+#[non_exhaustive]
+pub struct SubmitAllError {
+    /// The outcome of each transaction that was sent, in the order they were given in.
+    /// Transactions after the one that failed to be sent are missing from this.
+    pub results: Vec<Result<ExecutionFinalResult>>,
+    /// The error that stopped the remaining transactions from being sent, or a summary of
+    /// the ones within [`Self::results`] when all of them were sent.
+    #[source]
+    pub error: crate::error::Error,
+}
+
+impl From<SubmitAllError> for crate::error::Error {
+    fn from(err: SubmitAllError) -> Self {
+        err.error
+    }
 }
 
 /// The result from a call into a View function. This contains the contents or
@@ -377,6 +898,10 @@ pub struct ViewResultDetails {
     pub result: Vec<u8>,
     /// Logs generated from the view function.
     pub logs: Vec<String>,
+    /// Height of the block the view function was executed against.
+    pub block_height: BlockHeight,
+    /// Hash of the block the view function was executed against.
+    pub block_hash: CryptoHash,
 }
 
 impl ViewResultDetails {
@@ -397,15 +922,29 @@ impl ViewResultDetails {
     }
 }
 
-impl From<CallResult> for ViewResultDetails {
-    fn from(result: CallResult) -> Self {
+impl ViewResultDetails {
+    pub(crate) fn from_call_result(
+        result: CallResult,
+        block_height: BlockHeight,
+        block_hash: CryptoHash,
+    ) -> Self {
         Self {
             result: result.result,
             logs: result.logs,
+            block_height,
+            block_hash,
         }
     }
 }
 
+/// A bare [`CallResult`] does not say which block it was executed against, so the block
+/// height and hash are left at zero.
+impl From<CallResult> for ViewResultDetails {
+    fn from(result: CallResult) -> Self {
+        Self::from_call_result(result, 0, CryptoHash::default())
+    }
+}
+
 /// The execution outcome of a transaction. This type contains all data relevant to
 /// calling into a function, and getting the results back.
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -1,30 +1,37 @@
 use std::collections::HashMap;
 use std::fmt::Debug;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use base64::{engine::general_purpose, Engine as _};
 
 use crate::types::NearToken;
 use near_gas::NearGas;
 use tokio::sync::RwLock;
 use tokio_retry::strategy::{jitter, ExponentialBackoff};
-use tokio_retry::Retry;
+use tokio_retry::{Retry, RetryIf};
 
-use near_crypto::Signer;
 use near_jsonrpc_client::errors::{JsonRpcError, JsonRpcServerError};
 use near_jsonrpc_client::methods::health::RpcStatusError;
+use near_jsonrpc_client::methods::sandbox_patch_state::RpcSandboxPatchStateRequest;
 use near_jsonrpc_client::methods::tx::RpcTransactionError;
 use near_jsonrpc_client::{methods, JsonRpcClient, MethodCallResult};
+use near_jsonrpc_primitives::errors::{RpcError, RpcRequestValidationErrorKind};
+use near_jsonrpc_primitives::types::config::RpcProtocolConfigError;
 use near_jsonrpc_primitives::types::query::QueryResponseKind;
 use near_primitives::account::{AccessKey, AccessKeyPermission};
 use near_primitives::errors::InvalidTxError;
 use near_primitives::hash::CryptoHash;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::state_record::StateRecord;
 use near_primitives::transaction::{
     Action, AddKeyAction, CreateAccountAction, DeleteAccountAction, DeployContractAction,
-    FunctionCallAction, SignedTransaction, TransferAction,
+    SignedTransaction, Transaction, TransferAction,
 };
-use near_primitives::types::{BlockReference, Finality, Gas};
+use near_primitives::types::{BlockId, BlockReference, EpochReference, Finality};
 use near_primitives::views::{
-    AccessKeyView, BlockView, FinalExecutionOutcomeView, QueryRequest, StatusResponse,
+    AccessKeyView, BlockView, EpochValidatorInfo, FinalExecutionOutcomeView, QueryRequest,
+    ReceiptView, StatusResponse,
 };
 
 #[cfg(feature = "experimental")]
@@ -38,7 +45,7 @@ use {
         types::MaybeBlockId,
         views::{
             validator_stake_view::ValidatorStakeView, FinalExecutionOutcomeWithReceiptView,
-            ReceiptView, StateChangesRequestView,
+            StateChangesRequestView,
         },
     },
 };
@@ -46,12 +53,41 @@ use {
 use crate::error::{Error, ErrorKind, RpcErrorCode};
 use crate::operations::TransactionStatus;
 use crate::result::Result;
-use crate::types::{AccountId, InMemorySigner, Nonce, PublicKey};
+use crate::rpc::transport::TransportConfig;
+use crate::types::{AccountId, InMemorySigner, Nonce, PublicKey, TxExecutionStatus};
 use crate::{Network, Worker};
 
 pub(crate) const DEFAULT_CALL_FN_GAS: NearGas = NearGas::from_tgas(10);
 pub(crate) const DEFAULT_CALL_DEPOSIT: NearToken = NearToken::from_near(0);
 
+/// How many times a transaction rejected for a stale nonce or an expired block hash gets
+/// re-signed and resubmitted by default.
+pub(crate) const DEFAULT_STALE_TX_RETRIES: u32 = 3;
+
+/// Interval in between checks on the status of the RPC service while waiting for it.
+pub(crate) const DEFAULT_HEALTH_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long to wait at most for blocks to become final, on nodes that do not wait for this
+/// themselves.
+const FINALITY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long to wait for the RPC service to be up, which defaults to 10 seconds unless
+/// overridden by the `NEAR_RPC_TIMEOUT_SECS` environment variable.
+pub(crate) fn rpc_timeout() -> Result<Duration> {
+    match std::env::var("NEAR_RPC_TIMEOUT_SECS") {
+        // hard fail on not being able to parse the env var, since this isn't something
+        // the user should handle with the library.
+        Ok(secs) => secs.parse::<u64>().map(Duration::from_secs).map_err(|err| {
+            Error::full(
+                ErrorKind::DataConversion,
+                format!("Failed to parse provided NEAR_RPC_TIMEOUT_SECS={}", secs),
+                err,
+            )
+        }),
+        Err(_) => Ok(Duration::from_secs(10)),
+    }
+}
+
 /// A client that wraps around [`JsonRpcClient`], and provides more capabilities such
 /// as retry w/ exponential backoff and utility functions for sending transactions.
 pub struct Client {
@@ -59,11 +95,31 @@ pub struct Client {
     rpc_client: JsonRpcClient,
     /// AccessKey nonces to reference when sending transactions.
     pub(crate) access_key_nonces: RwLock<HashMap<(AccountId, near_crypto::PublicKey), AtomicU64>>,
+    /// How many times to re-sign and resubmit a transaction that got rejected for a stale
+    /// nonce or an expired block hash.
+    stale_tx_retries: u32,
+    /// How far along the execution of a transaction has to be before `transact` returns,
+    /// where `None` waits for the transaction to be executed through `broadcast_tx_commit`.
+    wait_until: Option<TxExecutionStatus>,
+    /// Whether the node turned out to not support the `send_tx` RPC method, which only
+    /// nodes from nearcore 1.37 on do.
+    legacy_send_tx: AtomicBool,
+    /// Clients of the other nodes of the cluster the node is part of, which state patches
+    /// get sent to as well to keep the state of every node the same.
+    patch_peers: Vec<JsonRpcClient>,
+    /// HTTP client built from the configured transport, which requests that are not RPC
+    /// calls go through as well, such as downloads of contracts.
+    http_client: reqwest::Client,
 }
 
 impl Client {
-    pub(crate) fn new(rpc_addr: &str, api_key: Option<String>) -> Result<Self> {
-        let connector = JsonRpcClient::new_client();
+    pub(crate) fn new(
+        rpc_addr: &str,
+        api_key: Option<String>,
+        transport: &TransportConfig,
+    ) -> Result<Self> {
+        let http_client = transport.build_client()?;
+        let connector = JsonRpcClient::with(http_client.clone());
         let mut rpc_client = connector.connect(rpc_addr);
         if let Some(api_key) = api_key {
             let api_key = near_jsonrpc_client::auth::ApiKey::new(api_key)
@@ -75,14 +131,41 @@ impl Client {
             rpc_client,
             rpc_addr: rpc_addr.into(),
             access_key_nonces: RwLock::new(HashMap::new()),
+            stale_tx_retries: DEFAULT_STALE_TX_RETRIES,
+            wait_until: None,
+            legacy_send_tx: AtomicBool::new(false),
+            patch_peers: Vec::new(),
+            http_client,
         })
     }
 
+    pub(crate) fn with_stale_tx_retries(mut self, retries: u32) -> Self {
+        self.stale_tx_retries = retries;
+        self
+    }
+
+    pub(crate) fn with_wait_until(mut self, wait_until: Option<TxExecutionStatus>) -> Self {
+        self.wait_until = wait_until;
+        self
+    }
+
+    pub(crate) fn with_patch_peers(mut self, peers: &[String]) -> Self {
+        let connector = JsonRpcClient::with(self.http_client.clone());
+        self.patch_peers = peers.iter().map(|addr| connector.connect(addr)).collect();
+        self
+    }
+
+    pub(crate) fn http_client(&self) -> &reqwest::Client {
+        &self.http_client
+    }
+
     pub(crate) async fn query_broadcast_tx(
         &self,
         method: &methods::broadcast_tx_commit::RpcBroadcastTxCommitRequest,
     ) -> MethodCallResult<FinalExecutionOutcomeView, RpcTransactionError> {
-        retry(|| async {
+        // Broadcasting the same transaction again does not help when it is stale, since it
+        // has to be signed again with a fresh nonce and block hash for that.
+        retry_if(|err| !is_stale_tx(err), || async {
             let result = self.rpc_client.call(method).await;
             match &result {
                 Ok(response) => {
@@ -119,6 +202,151 @@ impl Client {
         .await
     }
 
+    /// Send a transaction through the `send_tx` RPC method, which returns once the execution
+    /// of the transaction reached `wait_until`. The response holds the outcome of the
+    /// transaction for every status past [`TxExecutionStatus::Included`]. Nodes older than
+    /// nearcore 1.37 lack `send_tx`, for which this falls back to [`Self::send_tx_legacy`].
+    pub(crate) async fn query_send_tx(
+        &self,
+        tx: &SignedTransaction,
+        wait_until: TxExecutionStatus,
+    ) -> Result<MethodCallResult<serde_json::Value, RpcTransactionError>> {
+        if self.legacy_send_tx.load(Ordering::Relaxed) {
+            return self.send_tx_legacy(tx, wait_until).await;
+        }
+
+        let tx_bytes = borsh::BorshSerialize::try_to_vec(tx)
+            .map_err(|e| ErrorKind::DataConversion.full("failed to serialize transaction", e))?;
+        let method = methods::any::<core::result::Result<serde_json::Value, RpcTransactionError>>(
+            "send_tx",
+            serde_json::json!({
+                "signed_tx_base64": general_purpose::STANDARD.encode(tx_bytes),
+                "wait_until": wait_until.as_rpc_str(),
+            }),
+        );
+
+        let result = retry_if(
+            |err| !is_stale_tx(err),
+            || async {
+                let result = self.rpc_client.call(&method).await;
+                tracing::debug!(
+                    target: "workspaces",
+                    "Sending transaction {} until {:?} resulted in {:?}",
+                    tx.get_hash(),
+                    wait_until,
+                    result
+                );
+                result
+            },
+        )
+        .await;
+
+        match result {
+            Err(err) if is_method_not_found(&err) => {
+                tracing::debug!(
+                    target: "workspaces",
+                    "{} does not support send_tx, falling back to broadcast_tx_commit",
+                    self.rpc_addr
+                );
+                self.legacy_send_tx.store(true, Ordering::Relaxed);
+                self.send_tx_legacy(tx, wait_until).await
+            }
+            result => Ok(result),
+        }
+    }
+
+    /// Send a transaction to a node without the `send_tx` RPC method. Inclusion cannot be
+    /// told apart from submission there, so [`TxExecutionStatus::Included`] returns once the
+    /// transaction was submitted. Every other status waits for the transaction to be
+    /// executed through `broadcast_tx_commit`, and then for the blocks the status requires
+    /// to be final.
+    async fn send_tx_legacy(
+        &self,
+        tx: &SignedTransaction,
+        wait_until: TxExecutionStatus,
+    ) -> Result<MethodCallResult<serde_json::Value, RpcTransactionError>> {
+        if !wait_until.has_outcome() {
+            self.query(&methods::broadcast_tx_async::RpcBroadcastTxAsyncRequest {
+                signed_transaction: tx.clone(),
+            })
+            .await
+            .map_err(|e| RpcErrorCode::BroadcastTxFailure.custom(e))?;
+            return Ok(Ok(serde_json::Value::Null));
+        }
+
+        let outcome = match self
+            .query_broadcast_tx(&methods::broadcast_tx_commit::RpcBroadcastTxCommitRequest {
+                signed_transaction: tx.clone(),
+            })
+            .await
+        {
+            Ok(outcome) => outcome,
+            Err(err) => return Ok(Err(err)),
+        };
+
+        let blocks = match wait_until {
+            TxExecutionStatus::Executed => vec![outcome.transaction_outcome.block_hash],
+            TxExecutionStatus::Final => std::iter::once(&outcome.transaction_outcome)
+                .chain(&outcome.receipts_outcome)
+                .map(|outcome| outcome.block_hash)
+                .collect(),
+            _ => Vec::new(),
+        };
+        self.wait_for_final(&blocks).await?;
+
+        let outcome =
+            serde_json::to_value(outcome).map_err(|e| ErrorKind::DataConversion.custom(e))?;
+        Ok(Ok(outcome))
+    }
+
+    /// Wait until all of `blocks` are final, giving up after [`FINALITY_TIMEOUT`].
+    async fn wait_for_final(&self, blocks: &[CryptoHash]) -> Result<()> {
+        let mut height = 0;
+        for hash in blocks {
+            let block = self.view_block(Some(BlockId::Hash(*hash).into())).await?;
+            height = height.max(block.header.height);
+        }
+
+        let deadline = Instant::now() + FINALITY_TIMEOUT;
+        while self
+            .view_block(Some(Finality::Final.into()))
+            .await?
+            .header
+            .height
+            < height
+        {
+            if Instant::now() >= deadline {
+                return Err(RpcErrorCode::QueryFailure.message(format!(
+                    "block {height} did not become final within {FINALITY_TIMEOUT:?}"
+                )));
+            }
+            tokio::time::sleep(Duration::from_millis(300)).await;
+        }
+        Ok(())
+    }
+
+    /// Balance an account has to hold per byte of storage it uses, as set by the protocol
+    /// config of the network. This goes through the untyped RPC method to not require
+    /// the `experimental` feature.
+    pub(crate) async fn storage_amount_per_byte(&self) -> Result<NearToken> {
+        let method = methods::any::<core::result::Result<serde_json::Value, RpcProtocolConfigError>>(
+            "EXPERIMENTAL_protocol_config",
+            serde_json::json!({ "finality": "final" }),
+        );
+        let config = retry(|| async { self.rpc_client.call(&method).await })
+            .await
+            .map_err(|e| RpcErrorCode::QueryFailure.custom(e))?;
+
+        config["runtime_config"]["storage_amount_per_byte"]
+            .as_str()
+            .and_then(|amount| amount.parse().ok())
+            .map(NearToken::from_yoctonear)
+            .ok_or_else(|| {
+                RpcErrorCode::QueryReturnedInvalidData
+                    .message("protocol config has no valid storage_amount_per_byte")
+            })
+    }
+
     pub(crate) async fn query_nolog<M>(&self, method: M) -> MethodCallResult<M::Response, M::Error>
     where
         M: methods::RpcMethod + Send + Sync,
@@ -145,36 +373,32 @@ impl Client {
         .await
     }
 
-    async fn send_tx_and_retry(
+    /// Patch `records` into the state of the node through `sandbox_patch_state`, along with
+    /// the state of every other node of its cluster, so that none of them diverge.
+    pub(crate) async fn patch_state(
         &self,
-        signer: &InMemorySigner,
-        receiver_id: &AccountId,
-        action: Action,
-    ) -> Result<FinalExecutionOutcomeView> {
-        send_batch_tx_and_retry(self, signer, receiver_id, vec![action]).await
+        records: Vec<StateRecord>,
+    ) -> MethodCallResult<
+        <RpcSandboxPatchStateRequest as methods::RpcMethod>::Response,
+        <RpcSandboxPatchStateRequest as methods::RpcMethod>::Error,
+    > {
+        let method = RpcSandboxPatchStateRequest { records };
+        let patched = self.query(&method).await?;
+        for peer in &self.patch_peers {
+            retry(|| async { peer.call(&method).await }).await?;
+        }
+        Ok(patched)
     }
 
-    pub(crate) async fn call(
+    async fn send_tx_and_retry(
         &self,
         signer: &InMemorySigner,
-        contract_id: &AccountId,
-        method_name: String,
-        args: Vec<u8>,
-        gas: Gas,
-        deposit: NearToken,
+        receiver_id: &AccountId,
+        action: Action,
     ) -> Result<FinalExecutionOutcomeView> {
-        self.send_tx_and_retry(
-            signer,
-            contract_id,
-            FunctionCallAction {
-                args,
-                method_name,
-                gas,
-                deposit: deposit.as_yoctonear(),
-            }
-            .into(),
-        )
-        .await
+        send_batch_tx_and_retry(self, signer, receiver_id, vec![action], None)
+            .await
+            .map(|(view, _retries)| view)
     }
 
     pub(crate) async fn view_block(&self, block_ref: Option<BlockReference>) -> Result<BlockView> {
@@ -244,8 +468,10 @@ impl Client {
                 }
                 .into(),
             ],
+            None,
         )
         .await
+        .map(|(view, _retries)| view)
     }
 
     pub(crate) async fn create_account_and_deploy(
@@ -276,8 +502,10 @@ impl Client {
                 .into(),
                 DeployContractAction { code }.into(),
             ],
+            None,
         )
         .await
+        .map(|(view, _retries)| view)
     }
 
     pub(crate) async fn delete_account(
@@ -323,30 +551,113 @@ impl Client {
         .await
     }
 
+    /// Fetch the outcome of an already executed transaction, along with the full bodies of
+    /// the receipts it spawned.
+    pub(crate) async fn tx_status_with_receipts(
+        &self,
+        sender_id: &AccountId,
+        hash: CryptoHash,
+    ) -> Result<near_primitives::views::FinalExecutionOutcomeWithReceiptView> {
+        self.query(
+            methods::EXPERIMENTAL_tx_status::RpcTransactionStatusRequest {
+                transaction_info: methods::tx::TransactionInfo::TransactionId {
+                    account_id: sender_id.clone(),
+                    hash,
+                },
+            },
+        )
+        .await
+        .map_err(|e| RpcErrorCode::QueryFailure.custom(e))
+    }
+
+    /// Fetch the receipts spawned by an already executed transaction, which carry the
+    /// actions it led to, such as the function calls made along the way.
+    pub(crate) async fn tx_receipts(
+        &self,
+        sender_id: &AccountId,
+        hash: CryptoHash,
+    ) -> Result<Vec<ReceiptView>> {
+        self.tx_status_with_receipts(sender_id, hash)
+            .await
+            .map(|outcome| outcome.receipts)
+    }
+
+    /// Fetch the receipt with the id `receipt_id`, which the node has to have executed.
+    pub(crate) async fn view_receipt(&self, receipt_id: CryptoHash) -> Result<ReceiptView> {
+        self.query(methods::EXPERIMENTAL_receipt::RpcReceiptRequest {
+            receipt_reference: near_jsonrpc_primitives::types::receipts::ReceiptReference {
+                receipt_id,
+            },
+        })
+        .await
+        .map_err(|e| {
+            RpcErrorCode::QueryFailure.full(format!("failed to view receipt {receipt_id}"), e)
+        })
+    }
+
+    /// Fetch the number of blocks in an epoch, as currently configured on the network.
+    pub(crate) async fn epoch_length(&self) -> Result<u64> {
+        self.query(
+            methods::EXPERIMENTAL_protocol_config::RpcProtocolConfigRequest {
+                block_reference: BlockReference::latest(),
+            },
+        )
+        .await
+        .map(|config| config.epoch_length)
+        .map_err(|e| RpcErrorCode::QueryFailure.full("failed to fetch the epoch length", e))
+    }
+
+    /// Fetch how accounts are split into shards, as currently configured on the network.
+    pub(crate) async fn shard_layout(&self) -> Result<ShardLayout> {
+        self.query(
+            methods::EXPERIMENTAL_protocol_config::RpcProtocolConfigRequest {
+                block_reference: BlockReference::latest(),
+            },
+        )
+        .await
+        .map(|config| config.shard_layout)
+        .map_err(|e| RpcErrorCode::QueryFailure.full("failed to fetch the shard layout", e))
+    }
+
+    /// Fetch info on the epoch `epoch_reference` refers to, such as its validators and the
+    /// height it starts at.
+    pub(crate) async fn epoch_validators(
+        &self,
+        epoch_reference: EpochReference,
+    ) -> Result<EpochValidatorInfo> {
+        self.query(methods::validators::RpcValidatorRequest { epoch_reference })
+            .await
+            .map_err(|e| RpcErrorCode::QueryFailure.full("failed to fetch epoch info", e))
+    }
+
+    /// Forget the nonces cached for access keys, so that they get queried from the network
+    /// again. Needed whenever the state of the network gets reset.
+    pub(crate) async fn clear_nonce_cache(&self) {
+        self.access_key_nonces.write().await.clear();
+    }
+
     pub(crate) async fn wait_for_rpc(&self) -> Result<()> {
-        let timeout_secs = match std::env::var("NEAR_RPC_TIMEOUT_SECS") {
-            // hard fail on not being able to parse the env var, since this isn't something
-            // the user should handle with the library.
-            Ok(secs) => secs.parse::<usize>().map_err(|err| {
-                Error::full(
-                    ErrorKind::DataConversion,
-                    format!("Failed to parse provided NEAR_RPC_TIMEOUT_SECS={}", secs),
-                    err,
-                )
-            })?,
-            Err(_) => 10,
-        };
+        self.wait_for_rpc_with(rpc_timeout()?, DEFAULT_HEALTH_CHECK_INTERVAL)
+            .await
+    }
 
-        let retry_strategy =
-            std::iter::repeat_with(|| Duration::from_millis(500)).take(2 * timeout_secs);
+    /// Wait for the RPC service to be up, checking its status every `interval` until
+    /// `timeout` has elapsed.
+    pub(crate) async fn wait_for_rpc_with(
+        &self,
+        timeout: Duration,
+        interval: Duration,
+    ) -> Result<()> {
+        let attempts = timeout.as_millis() / interval.as_millis().max(1);
+        let retry_strategy = std::iter::repeat(interval).take(attempts as usize);
         Retry::spawn(retry_strategy, || async { self.status().await })
             .await
             .map_err(|e| {
                 Error::full(
                     RpcErrorCode::ConnectionFailure.into(),
                     format!(
-                        "Failed to connect to RPC service {} within {} seconds",
-                        self.rpc_addr, timeout_secs
+                        "Failed to connect to RPC service {} within {:?}",
+                        self.rpc_addr, timeout
                     ),
                     e,
                 )
@@ -486,35 +797,49 @@ async fn fetch_tx_nonce(
     client: &Client,
     cache_key: &(AccountId, near_crypto::PublicKey),
 ) -> Result<(CryptoHash, Nonce)> {
-    let nonces = client.access_key_nonces.read().await;
-    if let Some(nonce) = nonces.get(cache_key) {
-        let nonce = nonce.fetch_add(1, Ordering::SeqCst);
-        drop(nonces);
-
+    if let Some(nonce) = cached_tx_nonce(client, cache_key).await {
         // Fetch latest block_hash since the previous one is now invalid for new transactions:
         let block = client.view_block(Some(Finality::Final.into())).await?;
         let block_hash = block.header.hash;
-        Ok((block_hash, nonce + 1))
+        Ok((block_hash, nonce))
     } else {
-        drop(nonces);
+        query_tx_nonce(client, cache_key).await
+    }
+}
 
-        let (account_id, public_key) = cache_key;
-        let (access_key, block_hash) =
-            access_key(client, account_id.clone(), public_key.clone()).await?;
+/// Hands out the next nonce of the access key if it has been cached already.
+async fn cached_tx_nonce(
+    client: &Client,
+    cache_key: &(AccountId, near_crypto::PublicKey),
+) -> Option<Nonce> {
+    let nonces = client.access_key_nonces.read().await;
+    nonces
+        .get(cache_key)
+        .map(|nonce| nonce.fetch_add(1, Ordering::SeqCst) + 1)
+}
 
-        // case where multiple writers end up at the same lock acquisition point and tries
-        // to overwrite the cached value that a previous writer already wrote.
-        let nonce = client
-            .access_key_nonces
-            .write()
-            .await
-            .entry(cache_key.clone())
-            .or_insert_with(|| AtomicU64::new(access_key.nonce + 1))
-            .fetch_max(access_key.nonce + 1, Ordering::SeqCst)
-            .max(access_key.nonce + 1);
+/// Queries the nonce of the access key from the network and caches it, along with the
+/// block hash the access key was queried at.
+async fn query_tx_nonce(
+    client: &Client,
+    cache_key: &(AccountId, near_crypto::PublicKey),
+) -> Result<(CryptoHash, Nonce)> {
+    let (account_id, public_key) = cache_key;
+    let (access_key, block_hash) =
+        access_key(client, account_id.clone(), public_key.clone()).await?;
 
-        Ok((block_hash, nonce))
-    }
+    // case where multiple writers end up at the same lock acquisition point and tries
+    // to overwrite the cached value that a previous writer already wrote.
+    let nonce = client
+        .access_key_nonces
+        .write()
+        .await
+        .entry(cache_key.clone())
+        .or_insert_with(|| AtomicU64::new(access_key.nonce + 1))
+        .fetch_max(access_key.nonce + 1, Ordering::SeqCst)
+        .max(access_key.nonce + 1);
+
+    Ok((block_hash, nonce))
 }
 
 pub(crate) async fn retry<R, E, T, F>(task: F) -> T::Output
@@ -529,85 +854,170 @@ where
     Retry::spawn(retry_strategy, task).await
 }
 
-pub(crate) async fn send_tx(
-    client: &Client,
-    cache_key: &(AccountId, near_crypto::PublicKey),
-    tx: SignedTransaction,
-) -> Result<FinalExecutionOutcomeView> {
-    let result = client
-        .query_broadcast_tx(&methods::broadcast_tx_commit::RpcBroadcastTxCommitRequest {
-            signed_transaction: tx,
-        })
-        .await;
+/// Similar to [`retry`], but only retries errors for which `condition` holds.
+async fn retry_if<R, E, T, F, C>(condition: C, task: F) -> T::Output
+where
+    F: FnMut() -> T + Send,
+    T: core::future::Future<Output = core::result::Result<R, E>> + Send,
+    C: FnMut(&E) -> bool,
+{
+    let retry_strategy = ExponentialBackoff::from_millis(5).map(jitter).take(4);
+
+    RetryIf::spawn(retry_strategy, task, condition).await
+}
 
-    // InvalidNonce, cached nonce is potentially very far behind, so invalidate it.
-    if let Err(JsonRpcError::ServerError(JsonRpcServerError::HandlerError(
-        RpcTransactionError::InvalidTransaction {
-            context: InvalidTxError::InvalidNonce { .. },
+/// Whether the node rejected the request for not knowing the RPC method.
+fn is_method_not_found<E>(err: &JsonRpcError<E>) -> bool {
+    match err {
+        JsonRpcError::ServerError(JsonRpcServerError::RequestValidationError(
+            RpcRequestValidationErrorKind::MethodNotFound { .. },
+        )) => true,
+        // Older nodes report unknown methods with the plain JSON-RPC error code.
+        JsonRpcError::ServerError(JsonRpcServerError::NonContextualError(RpcError {
+            code,
             ..
-        },
-    ))) = &result
-    {
-        let mut nonces = client.access_key_nonces.write().await;
-        nonces.remove(cache_key);
+        })) => *code == -32_601,
+        _ => false,
     }
+}
 
-    result.map_err(|e| RpcErrorCode::BroadcastTxFailure.custom(e))
+/// Whether the transaction got rejected for being signed with a stale nonce or an expired
+/// block hash, in which case it can go through once signed again with fresh ones.
+fn is_stale_tx(err: &JsonRpcError<RpcTransactionError>) -> bool {
+    matches!(
+        err,
+        JsonRpcError::ServerError(JsonRpcServerError::HandlerError(
+            RpcTransactionError::InvalidTransaction {
+                context: InvalidTxError::InvalidNonce { .. } | InvalidTxError::Expired,
+                ..
+            },
+        ))
+    )
 }
 
+/// Signs a transaction of the account of `signer` with the given nonce and block hash.
+async fn sign_transaction(
+    signer: &InMemorySigner,
+    nonce: Nonce,
+    receiver_id: &AccountId,
+    actions: Vec<Action>,
+    block_hash: CryptoHash,
+) -> Result<SignedTransaction> {
+    signer
+        .sign_transaction(Transaction {
+            signer_id: signer.account_id.clone(),
+            public_key: signer.public_key().into(),
+            nonce,
+            receiver_id: receiver_id.clone(),
+            block_hash,
+            actions,
+        })
+        .await
+}
+
+/// Signs and sends a transaction, re-signing and resubmitting it with a fresh nonce and
+/// block hash whenever it gets rejected for a stale one, up to the number of retries the
+/// client is configured with. Returns the outcome along with the number of retries taken.
 pub(crate) async fn send_batch_tx_and_retry(
     client: &Client,
     signer: &InMemorySigner,
     receiver_id: &AccountId,
     actions: Vec<Action>,
-) -> Result<FinalExecutionOutcomeView> {
-    let signer = signer.inner();
-    let cache_key = (signer.account_id.clone(), signer.public_key());
+    wait_until: Option<TxExecutionStatus>,
+) -> Result<(FinalExecutionOutcomeView, u32)> {
+    let cache_key = (signer.account_id.clone(), signer.public_key().into());
+    // The outcome is only known once the transaction got executed, which is the least
+    // that has to be waited for here.
+    let wait_until = wait_until.or(client.wait_until).map(|wait_until| {
+        if wait_until.has_outcome() {
+            wait_until
+        } else {
+            TxExecutionStatus::ExecutedOptimistic
+        }
+    });
 
-    retry(|| async {
+    let mut retries = 0;
+    loop {
         let (block_hash, nonce) = fetch_tx_nonce(client, &cache_key).await?;
-        send_tx(
-            client,
-            &cache_key,
-            SignedTransaction::from_actions(
-                nonce,
-                signer.account_id.clone(),
-                receiver_id.clone(),
-                &signer as &dyn Signer,
-                actions.clone(),
-                block_hash,
-            ),
-        )
-        .await
-    })
-    .await
+        let signed_transaction =
+            sign_transaction(signer, nonce, receiver_id, actions.clone(), block_hash).await?;
+        let result = match wait_until {
+            Some(wait_until) => match client
+                .query_send_tx(&signed_transaction, wait_until)
+                .await?
+            {
+                Ok(outcome) => Ok(serde_json::from_value(outcome).map_err(|e| {
+                    RpcErrorCode::QueryReturnedInvalidData.full("invalid send_tx response", e)
+                })?),
+                Err(err) => Err(err),
+            },
+            None => {
+                client
+                    .query_broadcast_tx(
+                        &methods::broadcast_tx_commit::RpcBroadcastTxCommitRequest {
+                            signed_transaction,
+                        },
+                    )
+                    .await
+            }
+        };
+
+        match result {
+            Ok(view) => break Ok((view, retries)),
+            Err(err) if is_stale_tx(&err) => {
+                // The cached nonce is potentially very far behind, so invalidate it.
+                client.access_key_nonces.write().await.remove(&cache_key);
+                if retries >= client.stale_tx_retries {
+                    break Err(RpcErrorCode::BroadcastTxFailure.custom(err));
+                }
+
+                retries += 1;
+                tracing::warn!(
+                    target: "workspaces",
+                    "transaction from {} was rejected as stale, resubmitting ({retries}/{}): {err:?}",
+                    signer.account_id,
+                    client.stale_tx_retries,
+                );
+            }
+            Err(err) => break Err(RpcErrorCode::BroadcastTxFailure.custom(err)),
+        }
+    }
 }
 
+/// Signs and sends a transaction without waiting for it to be executed. When `wait_until`
+/// is specified, this waits for the execution of the transaction to reach it instead of
+/// returning right after the transaction was submitted.
 pub(crate) async fn send_batch_tx_async_and_retry(
     worker: Worker<dyn Network>,
     signer: &InMemorySigner,
     receiver_id: &AccountId,
     actions: Vec<Action>,
+    wait_until: Option<TxExecutionStatus>,
 ) -> Result<TransactionStatus> {
-    let signer = signer.inner();
-    let cache_key = (signer.account_id.clone(), signer.public_key());
+    let cache_key = (signer.account_id.clone(), signer.public_key().into());
 
     retry(|| async {
         let (block_hash, nonce) = fetch_tx_nonce(worker.client(), &cache_key).await?;
-        let hash = worker
-            .client()
-            .query(&methods::broadcast_tx_async::RpcBroadcastTxAsyncRequest {
-                signed_transaction: SignedTransaction::from_actions(
-                    nonce,
-                    signer.account_id.clone(),
-                    receiver_id.clone(),
-                    &signer as &dyn Signer,
-                    actions.clone(),
-                    block_hash,
-                ),
-            })
-            .await
-            .map_err(|e| RpcErrorCode::BroadcastTxFailure.custom(e))?;
+        let signed_transaction =
+            sign_transaction(signer, nonce, receiver_id, actions.clone(), block_hash).await?;
+        let hash = match wait_until {
+            Some(wait_until) => {
+                let hash = signed_transaction.get_hash();
+                worker
+                    .client()
+                    .query_send_tx(&signed_transaction, wait_until)
+                    .await?
+                    .map_err(|e| RpcErrorCode::BroadcastTxFailure.custom(e))?;
+                hash
+            }
+            None => worker
+                .client()
+                .query(&methods::broadcast_tx_async::RpcBroadcastTxAsyncRequest {
+                    signed_transaction,
+                })
+                .await
+                .map_err(|e| RpcErrorCode::BroadcastTxFailure.custom(e))?,
+        };
 
         Ok(TransactionStatus::new(
             worker.clone(),
@@ -617,3 +1027,60 @@ pub(crate) async fn send_batch_tx_async_and_retry(
     })
     .await
 }
+
+/// Signs and submits all of `txs` back-to-back without waiting on any of them to be
+/// executed. All of the transactions reference the same block, and the nonces of each
+/// access key are handed out in the order the transactions are given in, so that
+/// transactions signed with the same key are not rejected for being out of order.
+pub(crate) async fn send_batch_txs_async(
+    worker: &Worker<dyn Network>,
+    txs: Vec<(InMemorySigner, AccountId, Vec<Action>)>,
+) -> (Vec<TransactionStatus>, Option<Error>) {
+    let mut statuses = Vec::with_capacity(txs.len());
+    let err = send_each_tx_async(worker, txs, &mut statuses).await.err();
+    (statuses, err)
+}
+
+/// Sends `txs` one after the other, pushing the status of each sent one to `statuses` so
+/// that the ones sent before a failure can still be waited on by the caller.
+async fn send_each_tx_async(
+    worker: &Worker<dyn Network>,
+    txs: Vec<(InMemorySigner, AccountId, Vec<Action>)>,
+    statuses: &mut Vec<TransactionStatus>,
+) -> Result<()> {
+    let client = worker.client();
+    let block_hash = client
+        .view_block(Some(Finality::Final.into()))
+        .await?
+        .header
+        .hash;
+
+    for (signer, receiver_id, actions) in txs {
+        let cache_key = (signer.account_id.clone(), signer.public_key().into());
+        let nonce = match cached_tx_nonce(client, &cache_key).await {
+            Some(nonce) => nonce,
+            None => query_tx_nonce(client, &cache_key).await?.1,
+        };
+        let tx = sign_transaction(&signer, nonce, &receiver_id, actions, block_hash).await?;
+
+        // Resubmitting the exact same signed transaction is harmless, since the network
+        // only ever executes it once.
+        let hash = retry(|| async {
+            client
+                .query(&methods::broadcast_tx_async::RpcBroadcastTxAsyncRequest {
+                    signed_transaction: tx.clone(),
+                })
+                .await
+                .map_err(|e| RpcErrorCode::BroadcastTxFailure.custom(e))
+        })
+        .await?;
+
+        statuses.push(TransactionStatus::new(
+            worker.clone(),
+            signer.account_id.clone(),
+            hash,
+        ));
+    }
+
+    Ok(())
+}
@@ -3,5 +3,6 @@ pub(crate) mod tool;
 
 pub mod patch;
 pub mod query;
+pub mod transport;
 
 pub type BoxFuture<'a, T> = std::pin::Pin<Box<dyn std::future::Future<Output = T> + Send + 'a>>;
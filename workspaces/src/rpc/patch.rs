@@ -1,12 +1,16 @@
-use near_jsonrpc_client::methods::sandbox_patch_state::RpcSandboxPatchStateRequest;
 use near_primitives::state_record::StateRecord;
 use near_primitives::types::{BlockId, BlockReference};
 use near_token::NearToken;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
-use crate::error::SandboxErrorCode;
+use crate::dump::StateDump;
+use crate::error::{ErrorKind, SandboxErrorCode};
 use crate::network::{Sandbox, DEV_ACCOUNT_SEED};
+use crate::operations::Function;
+use crate::result::ExecutionFinalResult;
 use crate::types::account::AccountDetails;
-use crate::types::{BlockHeight, KeyType, PublicKey, SecretKey};
+use crate::types::{BlockHeight, Gas, KeyType, PublicKey, SecretKey};
 use crate::{AccessKey, AccountDetailsPatch, Result};
 use crate::{AccountId, Contract, CryptoHash, InMemorySigner, Network, Worker};
 
@@ -19,12 +23,15 @@ use crate::{AccountId, Contract, CryptoHash, InMemorySigner, Network, Worker};
 /// [`Transaction`]: crate::operations::Transaction
 pub struct ImportContractTransaction<'a> {
     account_id: &'a AccountId,
-    from_network: Worker<dyn Network>,
+    source: ImportSource<'a>,
     into_network: Worker<Sandbox>,
 
     /// Whether to grab data down from the other contract or not
     import_data: bool,
 
+    /// Whether to grab the access keys of the other account or not
+    import_access_keys: bool,
+
     /// Initial balance of the account. If None, uses what is specified
     /// from the other account instead.
     initial_balance: Option<NearToken>,
@@ -35,17 +42,44 @@ pub struct ImportContractTransaction<'a> {
     into_account_id: Option<AccountId>,
 }
 
+/// Where an [`ImportContractTransaction`] imports the contract from.
+enum ImportSource<'a> {
+    Network(Worker<dyn Network>),
+    Dump(&'a StateDump),
+}
+
 impl<'a> ImportContractTransaction<'a> {
     pub(crate) fn new(
         account_id: &'a AccountId,
         from_network: Worker<dyn Network>,
         into_network: Worker<Sandbox>,
+    ) -> Self {
+        Self::with_source(
+            account_id,
+            ImportSource::Network(from_network),
+            into_network,
+        )
+    }
+
+    pub(crate) fn from_dump(
+        account_id: &'a AccountId,
+        dump: &'a StateDump,
+        into_network: Worker<Sandbox>,
+    ) -> Self {
+        Self::with_source(account_id, ImportSource::Dump(dump), into_network)
+    }
+
+    fn with_source(
+        account_id: &'a AccountId,
+        source: ImportSource<'a>,
+        into_network: Worker<Sandbox>,
     ) -> Self {
         ImportContractTransaction {
             account_id,
-            from_network,
+            source,
             into_network,
             import_data: false,
+            import_access_keys: false,
             initial_balance: None,
             block_ref: None,
             into_account_id: None,
@@ -55,7 +89,8 @@ impl<'a> ImportContractTransaction<'a> {
     /// Specify at which block height to import the contract from. This is usable with
     /// any network this object is importing from, but be aware that only archival
     /// networks will have the full history while networks like mainnet or testnet
-    /// only has the history from 5 or less epochs ago.
+    /// only has the history from 5 or less epochs ago. Has no effect when importing
+    /// from a state dump, which only holds the state of the block it was taken at.
     pub fn block_height(mut self, block_height: BlockHeight) -> Self {
         self.block_ref = Some(BlockId::Height(block_height).into());
         self
@@ -64,7 +99,8 @@ impl<'a> ImportContractTransaction<'a> {
     /// Specify at which block hash to import the contract from. This is usable with
     /// any network this object is importing from, but be aware that only archival
     /// networks will have the full history while networks like mainnet or testnet
-    /// only has the history from 5 or less epochs ago.
+    /// only has the history from 5 or less epochs ago. Has no effect when importing
+    /// from a state dump, which only holds the state of the block it was taken at.
     pub fn block_hash(mut self, block_hash: CryptoHash) -> Self {
         self.block_ref =
             Some(BlockId::Hash(near_primitives::hash::CryptoHash(block_hash.0)).into());
@@ -75,12 +111,23 @@ impl<'a> ImportContractTransaction<'a> {
     /// contract itself. This is useful for testing current network state or state
     /// at a specific block. Note that there is a limit of 50kb of state data that
     /// can be pulled down using the usual RPC service. To get beyond this, our own
-    /// RPC node has to be spun up and used instead.
+    /// RPC node has to be spun up and used instead, or the contract has to be imported
+    /// from a [`StateDump`] of it.
     pub fn with_data(mut self) -> Self {
         self.import_data = true;
         self
     }
 
+    /// Along with importing the contract code, this will import the access keys of the
+    /// account, so that it can also be signed for with the keys held for it on the network
+    /// this transaction is importing from. The key workspaces signs with is added to the
+    /// account regardless. Has no effect when importing from a state dump, which does not
+    /// hold access keys.
+    pub fn with_access_keys(mut self) -> Self {
+        self.import_access_keys = true;
+        self
+    }
+
     /// Specifies the balance of the contract. This will override the balance currently
     /// on the network this transaction is importing from.
     pub fn initial_balance(mut self, initial_balance: NearToken) -> Self {
@@ -104,10 +151,34 @@ impl<'a> ImportContractTransaction<'a> {
         let sk = SecretKey::from_seed(KeyType::ED25519, DEV_ACCOUNT_SEED);
         let pk = sk.public_key();
         let signer = InMemorySigner::from_secret_key(into_account_id.clone(), sk);
-        let block_ref = self.block_ref.unwrap_or_else(BlockReference::latest);
 
-        let mut account_view = self
-            .from_network
+        let patch = match &self.source {
+            ImportSource::Network(from_network) => {
+                let block_ref = self
+                    .block_ref
+                    .clone()
+                    .unwrap_or_else(BlockReference::latest);
+                self.patch_from_network(from_network, into_account_id, block_ref)
+                    .await?
+            }
+            ImportSource::Dump(dump) => self.patch_from_dump(dump, into_account_id)?,
+        };
+
+        patch
+            .access_key(pk, AccessKey::full_access())
+            .transact()
+            .await?;
+        Ok(Contract::new(signer, self.into_network.coerce()))
+    }
+
+    async fn patch_from_network(
+        &self,
+        from_network: &Worker<dyn Network>,
+        into_account_id: &AccountId,
+        block_ref: BlockReference,
+    ) -> Result<PatchTransaction> {
+        let from_account_id = self.account_id;
+        let mut account_view = from_network
             .view_account(from_account_id)
             .block_reference(block_ref.clone())
             .await?;
@@ -118,21 +189,30 @@ impl<'a> ImportContractTransaction<'a> {
         }
 
         let mut patch = PatchTransaction::new(&self.into_network, into_account_id.clone())
-            .account(account_view.into())
-            .access_key(pk, AccessKey::full_access());
+            .account(account_view.into());
 
         if code_hash != CryptoHash::default() {
-            let code = self
-                .from_network
+            let code = from_network
                 .view_code(from_account_id)
                 .block_reference(block_ref.clone())
                 .await?;
             patch = patch.code(&code);
         }
 
+        if self.import_access_keys {
+            let access_keys = from_network
+                .view_access_keys(from_account_id)
+                .block_reference(block_ref.clone())
+                .await?;
+            patch = patch.access_keys(
+                access_keys
+                    .into_iter()
+                    .map(|info| (info.public_key, info.access_key)),
+            );
+        }
+
         if self.import_data {
-            let states = self
-                .from_network
+            let states = from_network
                 .view_state(from_account_id)
                 .block_reference(block_ref)
                 .await?;
@@ -144,8 +224,52 @@ impl<'a> ImportContractTransaction<'a> {
             );
         }
 
-        patch.transact().await?;
-        Ok(Contract::new(signer, self.into_network.coerce()))
+        Ok(patch)
+    }
+
+    fn patch_from_dump(
+        &self,
+        dump: &StateDump,
+        into_account_id: &AccountId,
+    ) -> Result<PatchTransaction> {
+        let dumped = dump.account(self.account_id)?;
+        let mut account: AccountDetails = dumped
+            .account
+            .clone()
+            .ok_or_else(|| {
+                ErrorKind::DataConversion.message(format!(
+                    "state dump holds no account record for {}",
+                    self.account_id
+                ))
+            })?
+            .into();
+        if let Some(initial_balance) = self.initial_balance {
+            account.balance = initial_balance;
+        }
+
+        let mut patch = PatchTransaction::new(&self.into_network, into_account_id.clone())
+            .account(account.into());
+
+        if let Some(code) = &dumped.code {
+            patch = patch.code(code);
+        }
+
+        if self.import_data {
+            patch
+                .records
+                .extend(dumped.data.iter().cloned().map(|record| match record {
+                    StateRecord::Data {
+                        data_key, value, ..
+                    } => StateRecord::Data {
+                        account_id: into_account_id.clone(),
+                        data_key,
+                        value,
+                    },
+                    other => other,
+                }));
+        }
+
+        Ok(patch)
     }
 }
 
@@ -325,17 +449,648 @@ impl PatchTransaction {
 
         self.worker
             .client()
-            .query(&RpcSandboxPatchStateRequest {
-                records: records.clone(),
-            })
+            .patch_state(records.clone())
             .await
             .map_err(|err| SandboxErrorCode::PatchStateFailure.custom(err))?;
 
         self.worker
             .client()
-            .query(&RpcSandboxPatchStateRequest { records })
+            .patch_state(records)
             .await
             .map_err(|err| SandboxErrorCode::PatchStateFailure.custom(err))?;
         Ok(())
     }
 }
+
+/// Default limit on the size of a batch of records patched in by a [`PatchStateBuilder`],
+/// as measured by their JSON encoding. This stays well under the payload limit RPC nodes
+/// have by default.
+pub const DEFAULT_PATCH_BATCH_SIZE: usize = 4 * 1024 * 1024;
+
+/// Patches records into the state of any number of accounts in the sandbox at once, such
+/// as when importing a large set of contract data. Records are accumulated up front and
+/// patched in over as many `sandbox_patch_state` requests as it takes to keep each of them
+/// within [`PatchStateBuilder::batch_size`], rather than one request per record or one
+/// request that is too large for the node to take in. Constructed from
+/// [`Worker::bulk_patch`].
+///
+/// How far along the patching is gets tracked by its [`PatchProgress`]:
+/// ```ignore
+/// let mut patch = worker.bulk_patch();
+/// for (key, value) in &entries {
+///     patch = patch.state(contract.id(), key, value);
+/// }
+/// let progress = patch.progress();
+/// tokio::spawn(async move { /* report progress.patched() out of progress.total() */ });
+/// patch.transact().await?;
+/// ```
+pub struct PatchStateBuilder {
+    worker: Worker<Sandbox>,
+    accounts: Vec<(AccountId, AccountDetailsPatch)>,
+    code_hashes: Vec<(AccountId, CryptoHash)>,
+    records: Vec<StateRecord>,
+    batch_size: usize,
+    progress: PatchProgress,
+}
+
+impl PatchStateBuilder {
+    pub(crate) fn new(worker: &Worker<Sandbox>) -> Self {
+        Self {
+            worker: worker.clone(),
+            accounts: Vec::new(),
+            code_hashes: Vec::new(),
+            records: Vec::new(),
+            batch_size: DEFAULT_PATCH_BATCH_SIZE,
+            progress: PatchProgress::new(),
+        }
+    }
+
+    /// Patch and overwrite the details of account `account_id`, creating the account if
+    /// it does not exist yet. Details left unset in `account` are zeroed out.
+    pub fn account(mut self, account_id: &AccountId, account: AccountDetailsPatch) -> Self {
+        self.accounts.push((account_id.clone(), account));
+        self
+    }
+
+    /// Set the code of account `account_id` to `wasm_bytes`, which also points the code
+    /// hash of the account to it.
+    pub fn code(mut self, account_id: &AccountId, wasm_bytes: &[u8]) -> Self {
+        self.code_hashes
+            .push((account_id.clone(), CryptoHash::hash_bytes(wasm_bytes)));
+        self.records.push(StateRecord::Contract {
+            account_id: account_id.clone(),
+            code: wasm_bytes.to_vec(),
+        });
+        self
+    }
+
+    /// Add or overwrite the access key `pk` of account `account_id`.
+    pub fn access_key(mut self, account_id: &AccountId, pk: PublicKey, ak: AccessKey) -> Self {
+        self.records.push(StateRecord::AccessKey {
+            account_id: account_id.clone(),
+            public_key: pk.into(),
+            access_key: ak.into(),
+        });
+        self
+    }
+
+    /// Set the entry under `key` in the state of the contract `account_id` to `value`.
+    pub fn state(mut self, account_id: &AccountId, key: &[u8], value: &[u8]) -> Self {
+        self.records.push(StateRecord::Data {
+            account_id: account_id.clone(),
+            data_key: key.to_vec().into(),
+            value: value.to_vec().into(),
+        });
+        self
+    }
+
+    /// Set a series of entries in the state of the contract `account_id`. Similar to
+    /// [`PatchStateBuilder::state`], but for many entries at once.
+    pub fn states<'b, 'c, I>(mut self, account_id: &AccountId, states: I) -> Self
+    where
+        I: IntoIterator<Item = (&'b [u8], &'c [u8])>,
+    {
+        self.records
+            .extend(states.into_iter().map(|(key, value)| StateRecord::Data {
+                account_id: account_id.clone(),
+                data_key: key.to_vec().into(),
+                value: value.to_vec().into(),
+            }));
+        self
+    }
+
+    /// Specify the limit on the size in bytes of each batch of records patched in, as
+    /// measured by their JSON encoding. Defaults to [`DEFAULT_PATCH_BATCH_SIZE`]. Records
+    /// larger than the limit on their own, such as large contract code, get a batch of
+    /// their own.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Get a handle to the progress of the patching, which keeps getting updated as each
+    /// batch of records makes it into the sandbox.
+    pub fn progress(&self) -> PatchProgress {
+        self.progress.clone()
+    }
+
+    /// Patch all of the records into the sandbox, one batch after the other. Account
+    /// details go in first, since the rest of the records are patched onto accounts.
+    pub async fn transact(self) -> Result<()> {
+        let mut accounts: Vec<(AccountId, AccountDetails)> = self
+            .accounts
+            .into_iter()
+            .map(|(id, patch)| (id, patch.into()))
+            .collect();
+        for (id, code_hash) in self.code_hashes {
+            match accounts
+                .iter_mut()
+                .rev()
+                .find(|(account_id, _)| *account_id == id)
+            {
+                Some((_, account)) => account.code_hash = code_hash,
+                None => {
+                    // Code patched onto an existing account has to be pointed to by it.
+                    let mut account = self.worker.view_account(&id).await?;
+                    account.code_hash = code_hash;
+                    accounts.push((id, account));
+                }
+            }
+        }
+
+        let records: Vec<StateRecord> = accounts
+            .into_iter()
+            .map(|(account_id, account)| StateRecord::Account {
+                account_id,
+                account: account.into_near_account(),
+            })
+            .chain(self.records)
+            .collect();
+        self.progress.total.store(records.len(), Ordering::SeqCst);
+
+        let mut batch = Vec::new();
+        let mut batch_bytes = 0;
+        for record in records {
+            let size = serde_json::to_vec(&record)
+                .map_err(|e| ErrorKind::DataConversion.custom(e))?
+                .len();
+            if !batch.is_empty() && batch_bytes + size > self.batch_size {
+                self.patch_batch(std::mem::take(&mut batch)).await?;
+                batch_bytes = 0;
+            }
+            batch.push(record);
+            batch_bytes += size;
+        }
+        if !batch.is_empty() {
+            self.patch_batch(batch).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn patch_batch(&self, records: Vec<StateRecord>) -> Result<()> {
+        let len = records.len();
+        self.worker
+            .client()
+            .patch_state(records)
+            .await
+            .map_err(|err| SandboxErrorCode::PatchStateFailure.custom(err))?;
+
+        let patched = self.progress.patched.fetch_add(len, Ordering::SeqCst) + len;
+        tracing::debug!(
+            target: "workspaces",
+            "patched {}/{} records into sandbox",
+            patched,
+            self.progress.total()
+        );
+        Ok(())
+    }
+}
+
+/// Progress of a [`PatchStateBuilder`], shared between the builder and anyone holding
+/// onto this handle. Cloning this yields a handle to the same progress.
+#[derive(Clone, Debug)]
+pub struct PatchProgress {
+    patched: Arc<AtomicUsize>,
+    total: Arc<AtomicUsize>,
+}
+
+impl PatchProgress {
+    fn new() -> Self {
+        Self {
+            patched: Default::default(),
+            total: Default::default(),
+        }
+    }
+
+    /// Number of records that made it into the sandbox so far.
+    pub fn patched(&self) -> usize {
+        self.patched.load(Ordering::SeqCst)
+    }
+
+    /// Total number of records to patch in, which is only known once patching started.
+    pub fn total(&self) -> usize {
+        self.total.load(Ordering::SeqCst)
+    }
+
+    /// Whether all of the records made it into the sandbox.
+    pub fn is_complete(&self) -> bool {
+        self.total() > 0 && self.patched() >= self.total()
+    }
+}
+
+/// Number of entries patched into sandbox per request when bloating the state of a contract.
+const BLOAT_CHUNK_SIZE: u64 = 1000;
+
+/// Storage the protocol charges for on top of the bytes of the key and value of each
+/// entry, which is `num_extra_bytes_record` in the runtime config.
+const STORAGE_BYTES_PER_RECORD: u64 = 40;
+
+/// Writes a large number of synthetic entries into the state of a contract through
+/// `patch_state`, for testing how a contract behaves over large collections, such as
+/// pagination, iteration limits and gas usage, without having to send thousands of
+/// transactions to set it up. Constructed from [`Worker::bloat_state`].
+///
+/// By default, entry `i` gets stored under the key prefix followed by `i` as big-endian
+/// bytes, which keeps the entries ordered when iterating over them, and holds a value of
+/// [`BloatStateTransaction::value_size`] bytes. Contracts storing their collections with
+/// a specific layout can have the entries generated through [`BloatStateTransaction::entry`].
+pub struct BloatStateTransaction {
+    worker: Worker<Sandbox>,
+    account_id: AccountId,
+    entries: u64,
+    prefix: Vec<u8>,
+    value_size: usize,
+    #[allow(clippy::type_complexity)]
+    entry: Option<Box<dyn Fn(u64) -> (Vec<u8>, Vec<u8>) + Send + Sync>>,
+    update_storage_usage: bool,
+}
+
+impl BloatStateTransaction {
+    pub(crate) fn new(worker: &Worker<Sandbox>, account_id: AccountId, entries: u64) -> Self {
+        Self {
+            worker: worker.clone(),
+            account_id,
+            entries,
+            prefix: Vec::new(),
+            value_size: 32,
+            entry: None,
+            update_storage_usage: true,
+        }
+    }
+
+    /// Prefix the keys of the entries with `prefix`, such as the prefix of the collection
+    /// the entries should show up in. Defaults to no prefix.
+    pub fn key_prefix(mut self, prefix: &[u8]) -> Self {
+        self.prefix = prefix.to_vec();
+        self
+    }
+
+    /// Size in bytes of the value of each entry. Defaults to 32 bytes.
+    pub fn value_size(mut self, value_size: usize) -> Self {
+        self.value_size = value_size;
+        self
+    }
+
+    /// Generate the key and value of entry `i` with `entry`, instead of the default layout.
+    /// This overrides [`BloatStateTransaction::key_prefix`] and [`BloatStateTransaction::value_size`].
+    pub fn entry<F>(mut self, entry: F) -> Self
+    where
+        F: Fn(u64) -> (Vec<u8>, Vec<u8>) + Send + Sync + 'static,
+    {
+        self.entry = Some(Box::new(entry));
+        self
+    }
+
+    /// Whether to add the storage taken up by the entries to the storage usage of the
+    /// account, which is what the contract pays storage staking for. This is on by default,
+    /// since removing entries the account was never charged for makes its storage usage
+    /// underflow. This assumes none of the keys exist yet.
+    pub fn update_storage_usage(mut self, update: bool) -> Self {
+        self.update_storage_usage = update;
+        self
+    }
+
+    /// Write all of the entries into the state of the contract.
+    pub async fn transact(self) -> Result<()> {
+        let mut storage_usage = 0;
+        let mut start = 0;
+        while start < self.entries {
+            let end = std::cmp::min(start + BLOAT_CHUNK_SIZE, self.entries);
+            let records = (start..end)
+                .map(|i| {
+                    let (key, value) = self.generate(i);
+                    storage_usage += (key.len() + value.len()) as u64 + STORAGE_BYTES_PER_RECORD;
+                    StateRecord::Data {
+                        account_id: self.account_id.clone(),
+                        data_key: key.into(),
+                        value: value.into(),
+                    }
+                })
+                .collect();
+
+            self.worker
+                .client()
+                .patch_state(records)
+                .await
+                .map_err(|err| {
+                    SandboxErrorCode::PatchStateFailure
+                        .full(format!("failed to bloat state of {}", self.account_id), err)
+                })?;
+            start = end;
+        }
+
+        if self.update_storage_usage && storage_usage > 0 {
+            self.worker
+                .patch(&self.account_id)
+                .account_from_current(move |mut account| {
+                    account.storage_usage += storage_usage;
+                    account.into()
+                })
+                .transact()
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    fn generate(&self, i: u64) -> (Vec<u8>, Vec<u8>) {
+        match &self.entry {
+            Some(entry) => entry(i),
+            None => {
+                let mut key = self.prefix.clone();
+                key.extend_from_slice(&i.to_be_bytes());
+                (key, vec![(i % 256) as u8; self.value_size])
+            }
+        }
+    }
+}
+
+/// Number of keys removed per function call when deleting state, which keeps each call
+/// well within the gas limit of a function call.
+const DELETE_STATE_CHUNK_SIZE: usize = 200;
+
+/// Contract temporarily swapped onto accounts to remove keys from their state, since
+/// sandbox can only patch entries in and not out. `delete_state` takes a sequence of
+/// keys, each prefixed by its length as a little-endian `u32`, and removes them:
+/// ```text
+/// (module
+///   (import "env" "input" (func $input (param i64)))
+///   (import "env" "register_len" (func $register_len (param i64) (result i64)))
+///   (import "env" "read_register" (func $read_register (param i64 i64)))
+///   (import "env" "storage_remove" (func $storage_remove (param i64 i64 i64) (result i64)))
+///   (import "env" "memory" (memory 1))
+///   (func (export "delete_state") (local $ptr i64) (local $len i64) (local $key_len i64)
+///     (call $input (i64.const 0))
+///     (local.set $len (call $register_len (i64.const 0)))
+///     (call $read_register (i64.const 0) (i64.const 0))
+///     (block (loop
+///       (br_if 1 (i64.ge_u (local.get $ptr) (local.get $len)))
+///       (local.set $key_len (i64.load32_u (i32.wrap_i64 (local.get $ptr))))
+///       (drop (call $storage_remove
+///         (local.get $key_len) (i64.add (local.get $ptr) (i64.const 4)) (i64.const 1)))
+///       (local.set $ptr (i64.add (i64.add (local.get $ptr) (i64.const 4)) (local.get $key_len)))
+///       (br 0)))))
+/// ```
+const DELETE_STATE_WASM: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x19, 0x05, 0x60, 0x01, 0x7e, 0x00, 0x60,
+    0x01, 0x7e, 0x01, 0x7e, 0x60, 0x02, 0x7e, 0x7e, 0x00, 0x60, 0x03, 0x7e, 0x7e, 0x7e, 0x01, 0x7e,
+    0x60, 0x00, 0x00, 0x02, 0x57, 0x05, 0x03, 0x65, 0x6e, 0x76, 0x05, 0x69, 0x6e, 0x70, 0x75, 0x74,
+    0x00, 0x00, 0x03, 0x65, 0x6e, 0x76, 0x0c, 0x72, 0x65, 0x67, 0x69, 0x73, 0x74, 0x65, 0x72, 0x5f,
+    0x6c, 0x65, 0x6e, 0x00, 0x01, 0x03, 0x65, 0x6e, 0x76, 0x0d, 0x72, 0x65, 0x61, 0x64, 0x5f, 0x72,
+    0x65, 0x67, 0x69, 0x73, 0x74, 0x65, 0x72, 0x00, 0x02, 0x03, 0x65, 0x6e, 0x76, 0x0e, 0x73, 0x74,
+    0x6f, 0x72, 0x61, 0x67, 0x65, 0x5f, 0x72, 0x65, 0x6d, 0x6f, 0x76, 0x65, 0x00, 0x03, 0x03, 0x65,
+    0x6e, 0x76, 0x06, 0x6d, 0x65, 0x6d, 0x6f, 0x72, 0x79, 0x02, 0x00, 0x01, 0x03, 0x02, 0x01, 0x04,
+    0x07, 0x10, 0x01, 0x0c, 0x64, 0x65, 0x6c, 0x65, 0x74, 0x65, 0x5f, 0x73, 0x74, 0x61, 0x74, 0x65,
+    0x00, 0x04, 0x0a, 0x43, 0x01, 0x41, 0x01, 0x03, 0x7e, 0x42, 0x00, 0x10, 0x00, 0x42, 0x00, 0x10,
+    0x01, 0x21, 0x01, 0x42, 0x00, 0x42, 0x00, 0x10, 0x02, 0x02, 0x40, 0x03, 0x40, 0x20, 0x00, 0x20,
+    0x01, 0x5a, 0x0d, 0x01, 0x20, 0x00, 0xa7, 0x35, 0x02, 0x00, 0x21, 0x02, 0x20, 0x02, 0x20, 0x00,
+    0x42, 0x04, 0x7c, 0x42, 0x01, 0x10, 0x03, 0x1a, 0x20, 0x00, 0x42, 0x04, 0x7c, 0x20, 0x02, 0x7c,
+    0x21, 0x00, 0x0c, 0x00, 0x0b, 0x0b, 0x0b,
+];
+
+/// Remove `keys` from the state of the contract `account_id`, by swapping in a contract
+/// removing them, calling into it and swapping the original code back in. Removing them
+/// through the runtime keeps the storage usage of the account in line with its state.
+pub(crate) async fn delete_state(
+    worker: &Worker<Sandbox>,
+    account_id: &AccountId,
+    keys: Vec<Vec<u8>>,
+) -> Result<()> {
+    if keys.is_empty() {
+        return Ok(());
+    }
+    let code = current_code(worker, account_id).await?;
+    let root = worker.root_account()?;
+
+    worker.patch_code(account_id, DELETE_STATE_WASM).await?;
+    let mut deleted = Ok(());
+    for chunk in keys.chunks(DELETE_STATE_CHUNK_SIZE) {
+        let mut args = Vec::new();
+        for key in chunk {
+            args.extend_from_slice(&(key.len() as u32).to_le_bytes());
+            args.extend_from_slice(key);
+        }
+        deleted = match root
+            .call(account_id, "delete_state")
+            .args(args)
+            .max_gas()
+            .transact()
+            .await
+        {
+            Ok(outcome) => outcome.into_result().map(drop).map_err(|e| {
+                SandboxErrorCode::PatchStateFailure
+                    .full(format!("failed to delete state of {account_id}"), e)
+            }),
+            Err(err) => Err(err),
+        };
+        if deleted.is_err() {
+            break;
+        }
+    }
+
+    // Put the original code back in place, even if deleting failed halfway through.
+    restore_code(worker, account_id, code).await?;
+    deleted
+}
+
+/// The code of account `account_id`, or `None` if it has no contract deployed to it.
+async fn current_code(worker: &Worker<Sandbox>, account_id: &AccountId) -> Result<Option<Vec<u8>>> {
+    let account = worker.view_account(account_id).await?;
+    if account.code_hash == CryptoHash::default() {
+        return Ok(None);
+    }
+    worker.view_code(account_id).await.map(Some)
+}
+
+/// Put `code` retrieved with `current_code` back onto account `account_id`, after a helper
+/// contract got swapped in for it.
+async fn restore_code(
+    worker: &Worker<Sandbox>,
+    account_id: &AccountId,
+    code: Option<Vec<u8>>,
+) -> Result<()> {
+    match code {
+        Some(code) => worker.patch_code(account_id, &code).await,
+        None => {
+            worker
+                .patch(account_id)
+                .account_from_current(|account| {
+                    AccountDetailsPatch::from(account).code_hash(CryptoHash::default())
+                })
+                .transact()
+                .await
+        }
+    }
+}
+
+/// Contract temporarily swapped onto the account receipts get injected from, which sends
+/// out a receipt for each entry passed to `inject_receipts`. An entry is laid out as the
+/// little-endian `u32` lengths of the receiver id, method name and arguments, followed by
+/// the gas as a `u64` and the deposit as a `u128`, and then the receiver id, method name
+/// and arguments themselves. Entries without a method name get sent as transfers.
+///
+/// All of the entries get read into memory at once. The single page of memory the module
+/// imports is only a minimum, which the runtime swaps for memory of its own configured
+/// size (64MiB on nearcore), so the entries are bounded by the transaction size limit
+/// long before they run out of memory:
+/// ```text
+/// (module
+///   (import "env" "input" (func $input (param i64)))
+///   (import "env" "register_len" (func $register_len (param i64) (result i64)))
+///   (import "env" "read_register" (func $read_register (param i64 i64)))
+///   (import "env" "promise_batch_create" (func $create (param i64 i64) (result i64)))
+///   (import "env" "promise_batch_action_transfer" (func $transfer (param i64 i64)))
+///   (import "env" "promise_batch_action_function_call"
+///     (func $function_call (param i64 i64 i64 i64 i64 i64 i64)))
+///   (import "env" "memory" (memory 1))
+///   (func (export "inject_receipts")
+///     (local $ptr i64) (local $len i64) (local $p i32)
+///     (local $receiver_len i64) (local $method_len i64) (local $args_len i64)
+///     (local $promise i64) (local $body i64)
+///     (call $input (i64.const 0))
+///     (local.set $len (call $register_len (i64.const 0)))
+///     (call $read_register (i64.const 0) (i64.const 0))
+///     (block (loop
+///       (br_if 1 (i64.ge_u (local.get $ptr) (local.get $len)))
+///       (local.set $p (i32.wrap_i64 (local.get $ptr)))
+///       (local.set $receiver_len (i64.load32_u align=1 (local.get $p)))
+///       (local.set $method_len (i64.load32_u offset=4 align=1 (local.get $p)))
+///       (local.set $args_len (i64.load32_u offset=8 align=1 (local.get $p)))
+///       (local.set $body (i64.add (local.get $ptr) (i64.const 36)))
+///       (local.set $promise (call $create (local.get $receiver_len) (local.get $body)))
+///       (if (i64.eqz (local.get $method_len))
+///         (then (call $transfer
+///           (local.get $promise) (i64.add (local.get $ptr) (i64.const 20))))
+///         (else (call $function_call
+///           (local.get $promise)
+///           (local.get $method_len) (i64.add (local.get $body) (local.get $receiver_len))
+///           (local.get $args_len)
+///           (i64.add (i64.add (local.get $body) (local.get $receiver_len)) (local.get $method_len))
+///           (i64.add (local.get $ptr) (i64.const 20))
+///           (i64.load offset=12 align=1 (local.get $p)))))
+///       (local.set $ptr (i64.add (i64.add (i64.add (local.get $body) (local.get $receiver_len))
+///         (local.get $method_len)) (local.get $args_len)))
+///       (br 0)))))
+/// ```
+const INJECT_RECEIPTS_WASM: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x22, 0x06, 0x60, 0x01, 0x7e, 0x00, 0x60,
+    0x01, 0x7e, 0x01, 0x7e, 0x60, 0x02, 0x7e, 0x7e, 0x00, 0x60, 0x02, 0x7e, 0x7e, 0x01, 0x7e, 0x60,
+    0x07, 0x7e, 0x7e, 0x7e, 0x7e, 0x7e, 0x7e, 0x7e, 0x00, 0x60, 0x00, 0x00, 0x02, 0xaa, 0x01, 0x07,
+    0x03, 0x65, 0x6e, 0x76, 0x05, 0x69, 0x6e, 0x70, 0x75, 0x74, 0x00, 0x00, 0x03, 0x65, 0x6e, 0x76,
+    0x0c, 0x72, 0x65, 0x67, 0x69, 0x73, 0x74, 0x65, 0x72, 0x5f, 0x6c, 0x65, 0x6e, 0x00, 0x01, 0x03,
+    0x65, 0x6e, 0x76, 0x0d, 0x72, 0x65, 0x61, 0x64, 0x5f, 0x72, 0x65, 0x67, 0x69, 0x73, 0x74, 0x65,
+    0x72, 0x00, 0x02, 0x03, 0x65, 0x6e, 0x76, 0x14, 0x70, 0x72, 0x6f, 0x6d, 0x69, 0x73, 0x65, 0x5f,
+    0x62, 0x61, 0x74, 0x63, 0x68, 0x5f, 0x63, 0x72, 0x65, 0x61, 0x74, 0x65, 0x00, 0x03, 0x03, 0x65,
+    0x6e, 0x76, 0x1d, 0x70, 0x72, 0x6f, 0x6d, 0x69, 0x73, 0x65, 0x5f, 0x62, 0x61, 0x74, 0x63, 0x68,
+    0x5f, 0x61, 0x63, 0x74, 0x69, 0x6f, 0x6e, 0x5f, 0x74, 0x72, 0x61, 0x6e, 0x73, 0x66, 0x65, 0x72,
+    0x00, 0x02, 0x03, 0x65, 0x6e, 0x76, 0x22, 0x70, 0x72, 0x6f, 0x6d, 0x69, 0x73, 0x65, 0x5f, 0x62,
+    0x61, 0x74, 0x63, 0x68, 0x5f, 0x61, 0x63, 0x74, 0x69, 0x6f, 0x6e, 0x5f, 0x66, 0x75, 0x6e, 0x63,
+    0x74, 0x69, 0x6f, 0x6e, 0x5f, 0x63, 0x61, 0x6c, 0x6c, 0x00, 0x04, 0x03, 0x65, 0x6e, 0x76, 0x06,
+    0x6d, 0x65, 0x6d, 0x6f, 0x72, 0x79, 0x02, 0x00, 0x01, 0x03, 0x02, 0x01, 0x05, 0x07, 0x13, 0x01,
+    0x0f, 0x69, 0x6e, 0x6a, 0x65, 0x63, 0x74, 0x5f, 0x72, 0x65, 0x63, 0x65, 0x69, 0x70, 0x74, 0x73,
+    0x00, 0x06, 0x0a, 0x8f, 0x01, 0x01, 0x8c, 0x01, 0x03, 0x02, 0x7e, 0x01, 0x7f, 0x05, 0x7e, 0x42,
+    0x00, 0x10, 0x00, 0x42, 0x00, 0x10, 0x01, 0x21, 0x01, 0x42, 0x00, 0x42, 0x00, 0x10, 0x02, 0x02,
+    0x40, 0x03, 0x40, 0x20, 0x00, 0x20, 0x01, 0x5a, 0x0d, 0x01, 0x20, 0x00, 0xa7, 0x21, 0x02, 0x20,
+    0x02, 0x35, 0x00, 0x00, 0x21, 0x03, 0x20, 0x02, 0x35, 0x00, 0x04, 0x21, 0x04, 0x20, 0x02, 0x35,
+    0x00, 0x08, 0x21, 0x05, 0x20, 0x00, 0x42, 0x24, 0x7c, 0x21, 0x07, 0x20, 0x03, 0x20, 0x07, 0x10,
+    0x03, 0x21, 0x06, 0x20, 0x04, 0x50, 0x04, 0x40, 0x20, 0x06, 0x20, 0x00, 0x42, 0x14, 0x7c, 0x10,
+    0x04, 0x05, 0x20, 0x06, 0x20, 0x04, 0x20, 0x07, 0x20, 0x03, 0x7c, 0x20, 0x05, 0x20, 0x07, 0x20,
+    0x03, 0x7c, 0x20, 0x04, 0x7c, 0x20, 0x00, 0x42, 0x14, 0x7c, 0x20, 0x02, 0x29, 0x00, 0x0c, 0x10,
+    0x05, 0x0b, 0x20, 0x07, 0x20, 0x03, 0x7c, 0x20, 0x04, 0x7c, 0x20, 0x05, 0x7c, 0x21, 0x00, 0x0c,
+    0x00, 0x0b, 0x0b, 0x0b,
+];
+
+/// Sends receipts to any account as if they came from another account, without that
+/// account having to run a contract making them. This gets to callback handlers and
+/// orderings of receipts that are otherwise hard to set up, such as a contract getting
+/// called by an account it trusts, or calls arriving in a particular order. Constructed
+/// from [`Worker::inject_receipts`].
+///
+/// The receipts get sent out by temporarily swapping a contract onto the account they come
+/// from, calling into it and swapping the original code back in, so they see that account
+/// as their predecessor. The account they come from has to exist and pays for any deposits
+/// attached, while the signer they see is the root account of the sandbox. Receipts get
+/// sent out in the order they were added, within a single receipt of the account they
+/// come from.
+/// ```ignore
+/// let outcome = worker
+///     .inject_receipts(dex.id())
+///     .call(token.id(), Function::new("ft_on_transfer").args_json(args).gas(Gas::from_tgas(50)))
+///     .transfer(alice.id(), NearToken::from_near(1))
+///     .transact()
+///     .await?;
+/// ```
+pub struct InjectReceiptsTransaction<'a> {
+    worker: &'a Worker<Sandbox>,
+    predecessor_id: AccountId,
+    receipts: Vec<(AccountId, Option<Function>, NearToken)>,
+}
+
+impl<'a> InjectReceiptsTransaction<'a> {
+    pub(crate) fn new(worker: &'a Worker<Sandbox>, predecessor_id: AccountId) -> Self {
+        Self {
+            worker,
+            predecessor_id,
+            receipts: Vec::new(),
+        }
+    }
+
+    /// Send a receipt calling `function` on `receiver_id`, with the gas and deposit attached
+    /// to `function`.
+    pub fn call(mut self, receiver_id: &AccountId, function: Function) -> Self {
+        let deposit = function.deposit;
+        self.receipts
+            .push((receiver_id.clone(), Some(function), deposit));
+        self
+    }
+
+    /// Send a receipt transferring `amount` to `receiver_id`.
+    pub fn transfer(mut self, receiver_id: &AccountId, amount: NearToken) -> Self {
+        self.receipts.push((receiver_id.clone(), None, amount));
+        self
+    }
+
+    /// Send out the receipts, returning the outcome of the transaction that sent them out,
+    /// which holds the outcomes of the receipts themselves.
+    pub async fn transact(self) -> Result<ExecutionFinalResult> {
+        let predecessor_id = &self.predecessor_id;
+        let mut args = Vec::new();
+        for (receiver_id, function, amount) in self.receipts {
+            if receiver_id == *predecessor_id {
+                // The receipt would run on the swapped in contract, rather than the original.
+                return Err(SandboxErrorCode::PatchStateFailure.message(format!(
+                    "cannot inject receipts from {predecessor_id} to itself"
+                )));
+            }
+            let (method, fn_args, gas) = match function {
+                Some(function) => (function.name.into_bytes(), function.args?, function.gas),
+                None => (Vec::new(), Vec::new(), Gas::from_gas(0)),
+            };
+            args.extend_from_slice(&(receiver_id.as_str().len() as u32).to_le_bytes());
+            args.extend_from_slice(&(method.len() as u32).to_le_bytes());
+            args.extend_from_slice(&(fn_args.len() as u32).to_le_bytes());
+            args.extend_from_slice(&gas.as_gas().to_le_bytes());
+            args.extend_from_slice(&amount.as_yoctonear().to_le_bytes());
+            args.extend_from_slice(receiver_id.as_str().as_bytes());
+            args.extend_from_slice(&method);
+            args.extend_from_slice(&fn_args);
+        }
+
+        let code = current_code(self.worker, predecessor_id).await?;
+        let root = self.worker.root_account()?;
+        self.worker
+            .patch_code(predecessor_id, INJECT_RECEIPTS_WASM)
+            .await?;
+        let outcome = root
+            .call(predecessor_id, "inject_receipts")
+            .args(args)
+            .max_gas()
+            .transact()
+            .await;
+
+        // Put the original code back in place, even if the receipts failed to go out.
+        restore_code(self.worker, predecessor_id, code).await?;
+        outcome
+    }
+}
@@ -33,6 +33,7 @@
 
 use std::collections::HashMap;
 use std::fmt::{Debug, Display};
+use std::sync::Arc;
 
 use near_account_id::AccountId;
 use near_jsonrpc_client::methods::query::RpcQueryResponse;
@@ -50,6 +51,7 @@ use crate::rpc::client::Client;
 use crate::rpc::{tool, BoxFuture};
 use crate::types::account::AccountDetails;
 use crate::types::{AccessKey, AccessKeyInfo, BlockHeight, Finality, PublicKey, ShardId};
+use crate::worker::Fork;
 use crate::{Block, Chunk, CryptoHash, Result};
 
 /// `Query` object allows creating queries into the network of our choice. This object is
@@ -60,6 +62,7 @@ pub struct Query<'a, T> {
     pub(crate) method: T,
     pub(crate) client: &'a Client,
     pub(crate) block_ref: Option<BlockReference>,
+    pub(crate) fork: Option<Arc<Fork>>,
 }
 
 impl<'a, T> Query<'a, T> {
@@ -68,9 +71,16 @@ impl<'a, T> Query<'a, T> {
             method,
             client,
             block_ref: None,
+            fork: None,
         }
     }
 
+    /// Pull the account the query reads from in from the chain `fork` is off of first.
+    pub(crate) fn with_fork(mut self, fork: Option<Arc<Fork>>) -> Self {
+        self.fork = fork;
+        self
+    }
+
     /// Specify at which block height to query from. Note that only archival
     /// networks will have the full history while networks like mainnet or testnet will
     /// only have the history from 5 or less epochs ago.
@@ -86,6 +96,14 @@ impl<'a, T> Query<'a, T> {
         self.block_ref = Some(BlockId::Hash(near_primitives::hash::CryptoHash(hash.0)).into());
         self
     }
+
+    /// Specify at which block to query from, by either its height or hash. Note that only
+    /// archival networks will have the full history while networks like mainnet or testnet
+    /// will only have the history from 5 or less epochs ago.
+    pub fn block_id(mut self, id: impl Into<crate::types::BlockId>) -> Self {
+        self.block_ref = Some(id.into().into());
+        self
+    }
 }
 
 // Constrained to RpcQueryRequest, since methods like GasPrice only take block_id but not Finality.
@@ -120,6 +138,9 @@ where
 
     fn into_future(self) -> Self::IntoFuture {
         Box::pin(async move {
+            if let (Some(fork), Some(account_id)) = (&self.fork, self.method.account_id()) {
+                fork.pull(self.client, account_id).await?;
+            }
             let block_reference = self.block_ref.unwrap_or_else(BlockReference::latest);
             let resp = self
                 .client
@@ -152,6 +173,12 @@ pub trait ProcessQuery {
     /// Convert the response from the RPC request to a type of our choosing, mainly to conform
     /// to workspaces related types from the near-primitives or json types from the network.
     fn from_response(resp: <Self::Method as RpcMethod>::Response) -> Result<Self::Output>;
+
+    /// Account the query reads from, if any, which sandboxes forked off of another chain
+    /// pull in before running the query.
+    fn account_id(&self) -> Option<&AccountId> {
+        None
+    }
 }
 
 pub struct ViewFunction {
@@ -189,6 +216,10 @@ impl ProcessQuery for ViewFunction {
     type Method = methods::query::RpcQueryRequest;
     type Output = ViewResultDetails;
 
+    fn account_id(&self) -> Option<&AccountId> {
+        Some(&self.account_id)
+    }
+
     fn into_request(self, block_reference: BlockReference) -> Result<Self::Method> {
         Ok(Self::Method {
             block_reference,
@@ -202,7 +233,11 @@ impl ProcessQuery for ViewFunction {
 
     fn from_response(resp: RpcQueryResponse) -> Result<Self::Output> {
         match resp.kind {
-            QueryResponseKind::CallResult(result) => Ok(result.into()),
+            QueryResponseKind::CallResult(result) => Ok(ViewResultDetails::from_call_result(
+                result,
+                resp.block_height,
+                resp.block_hash.into(),
+            )),
             _ => Err(RpcErrorCode::QueryReturnedInvalidData.message("while querying account")),
         }
     }
@@ -238,6 +273,10 @@ impl ProcessQuery for ViewCode {
     type Method = methods::query::RpcQueryRequest;
     type Output = Vec<u8>;
 
+    fn account_id(&self) -> Option<&AccountId> {
+        Some(&self.account_id)
+    }
+
     fn into_request(self, block_reference: BlockReference) -> Result<Self::Method> {
         Ok(Self::Method {
             block_reference,
@@ -259,6 +298,10 @@ impl ProcessQuery for ViewAccount {
     type Method = methods::query::RpcQueryRequest;
     type Output = AccountDetails;
 
+    fn account_id(&self) -> Option<&AccountId> {
+        Some(&self.account_id)
+    }
+
     fn into_request(self, block_reference: BlockReference) -> Result<Self::Method> {
         Ok(Self::Method {
             block_reference,
@@ -293,6 +336,10 @@ impl ProcessQuery for ViewState {
     type Method = methods::query::RpcQueryRequest;
     type Output = HashMap<Vec<u8>, Vec<u8>>;
 
+    fn account_id(&self) -> Option<&AccountId> {
+        Some(&self.account_id)
+    }
+
     fn into_request(self, block_reference: BlockReference) -> Result<Self::Method> {
         Ok(Self::Method {
             block_reference,
@@ -334,6 +381,10 @@ impl ProcessQuery for ViewAccessKey {
     type Method = methods::query::RpcQueryRequest;
     type Output = AccessKey;
 
+    fn account_id(&self) -> Option<&AccountId> {
+        Some(&self.account_id)
+    }
+
     fn into_request(self, block_reference: BlockReference) -> Result<Self::Method> {
         Ok(Self::Method {
             block_reference,
@@ -356,6 +407,10 @@ impl ProcessQuery for ViewAccessKeyList {
     type Method = methods::query::RpcQueryRequest;
     type Output = Vec<AccessKeyInfo>;
 
+    fn account_id(&self) -> Option<&AccountId> {
+        Some(&self.account_id)
+    }
+
     fn into_request(self, block_reference: BlockReference) -> Result<Self::Method> {
         Ok(Self::Method {
             block_reference,
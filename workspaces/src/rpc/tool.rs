@@ -1,11 +1,12 @@
 use std::collections::HashMap;
 use std::convert::TryInto;
-use std::fs::File;
+use std::fs::{File, OpenOptions};
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use chrono::Utc;
 use rand::Rng;
+use sha2::Digest;
 use url::Url;
 
 use near_crypto::SecretKey;
@@ -36,6 +37,7 @@ pub(crate) fn random_account_id() -> AccountId {
 }
 
 pub(crate) async fn url_create_account(
+    http_client: &reqwest::Client,
     helper_url: Url,
     account_id: AccountId,
     pk: PublicKey,
@@ -43,7 +45,7 @@ pub(crate) async fn url_create_account(
     let helper_url = helper_url.join("account").unwrap();
 
     // TODO(maybe): need this in near-jsonrpc-client as well:
-    let _resp = reqwest::Client::new()
+    let _resp = http_client
         .post(helper_url)
         .header("Content-Type", "application/json")
         .body(
@@ -61,13 +63,36 @@ pub(crate) async fn url_create_account(
 }
 
 pub(crate) fn write_cred_to_file(path: &Path, id: &AccountId, sk: &SecretKey) -> Result<()> {
-    let mut file = File::create(path).map_err(|err| {
+    let file = File::create(path).map_err(|err| {
         ErrorKind::Io.full(
             format!("failed to open {path:?} for writing credentials"),
             err,
         )
     })?;
+    write_cred(file, id, sk)
+}
+
+/// Same as [`write_cred_to_file`], but refusing to overwrite credentials already stored at
+/// `path`. The file gets created in the same step as checking for it, so that credentials
+/// stored in the meantime by another process do not get overwritten either.
+pub(crate) fn write_new_cred_to_file(path: &Path, id: &AccountId, sk: &SecretKey) -> Result<()> {
+    let file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .map_err(|err| match err.kind() {
+            std::io::ErrorKind::AlreadyExists => ErrorKind::Io.message(format!(
+                "refusing to overwrite the credentials of {id} already stored at {path:?}"
+            )),
+            _ => ErrorKind::Io.full(
+                format!("failed to open {path:?} for writing credentials"),
+                err,
+            ),
+        })?;
+    write_cred(file, id, sk)
+}
 
+fn write_cred(mut file: File, id: &AccountId, sk: &SecretKey) -> Result<()> {
     #[cfg(unix)]
     {
         use std::os::unix::prelude::PermissionsExt;
@@ -96,3 +121,58 @@ pub(crate) fn write_cred_to_file(path: &Path, id: &AccountId, sk: &SecretKey) ->
     file.write_all(&content)
         .map_err(|err| ErrorKind::Io.full("Failed to write a key file", err))
 }
+
+/// Directory where wasm files downloaded by [`fetch_wasm`] are cached. This can be set with
+/// the `NEAR_WORKSPACES_CACHE_DIR` env variable, and defaults to a directory in the system's
+/// temporary directory.
+fn wasm_cache_dir() -> PathBuf {
+    match std::env::var("NEAR_WORKSPACES_CACHE_DIR") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => std::env::temp_dir().join("near-workspaces"),
+    }
+    .join("wasm")
+}
+
+/// Download the wasm file located at `url` through `http`, verifying that its contents have
+/// the expected hex encoded sha256 hash. Downloads are cached by their hash, so subsequent
+/// calls with the same hash do not download the file again.
+pub(crate) async fn fetch_wasm(
+    http: &reqwest::Client,
+    url: &str,
+    expected_sha256: &str,
+) -> Result<Vec<u8>> {
+    let expected_sha256 = expected_sha256.to_lowercase();
+    let cache_path = wasm_cache_dir().join(format!("{expected_sha256}.wasm"));
+    if let Ok(wasm) = tokio::fs::read(&cache_path).await {
+        if format!("{:x}", sha2::Sha256::digest(&wasm)) == expected_sha256 {
+            return Ok(wasm);
+        }
+    }
+
+    let wasm = http
+        .get(url)
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status())
+        .map_err(|e| ErrorKind::Io.full(format!("failed to download wasm from {url}"), e))?
+        .bytes()
+        .await
+        .map_err(|e| ErrorKind::Io.full(format!("failed to download wasm from {url}"), e))?
+        .to_vec();
+
+    let actual_sha256 = format!("{:x}", sha2::Sha256::digest(&wasm));
+    if actual_sha256 != expected_sha256 {
+        return Err(ErrorKind::DataConversion.message(format!(
+            "wasm downloaded from {url} has sha256={actual_sha256}, expected {expected_sha256}"
+        )));
+    }
+
+    // Caching is best effort, failing to write to the cache should not fail the deploy.
+    if let Some(dir) = cache_path.parent() {
+        if tokio::fs::create_dir_all(dir).await.is_ok() {
+            let _ = tokio::fs::write(&cache_path, &wasm).await;
+        }
+    }
+
+    Ok(wasm)
+}
@@ -0,0 +1,195 @@
+//! Configuration for the HTTP transport used by the RPC client of a [`Worker`]. The
+//! defaults are fine for the majority of tests, but suites that fire a large amount of
+//! small requests at hosted endpoints can benefit from tuning the connection pool,
+//! keep-alive or switching over to HTTP/2. Environments that can only reach RPC
+//! providers through a proxy can also configure the proxy and additional root
+//! certificates here.
+//!
+//! The transport of a network applies to every request workspaces sends on its behalf,
+//! which besides RPC calls covers the account creation helper of testnet and the wasm
+//! downloads of [`dev_deploy_url`]. The one exception is the download of the sandbox
+//! binary, done by `near-sandbox-utils` before any network exists. Where it cannot be
+//! downloaded directly, point `NEAR_SANDBOX_BIN_PATH` to a binary fetched beforehand.
+//!
+//! [`Worker`]: crate::Worker
+//! [`dev_deploy_url`]: crate::Worker::dev_deploy_url
+
+use std::time::Duration;
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+
+use crate::error::ErrorKind;
+use crate::result::Result;
+
+/// Configuration of the HTTP transport used to talk to the RPC service of a network.
+/// Supply it to a network builder like so:
+/// ```ignore
+/// let worker = near_workspaces::testnet()
+///     .transport(TransportConfig::new().http2().pool_max_idle_per_host(32))
+///     .await?;
+/// ```
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct TransportConfig {
+    pub(crate) http2: bool,
+    pub(crate) pool_max_idle_per_host: Option<usize>,
+    pub(crate) pool_idle_timeout: Option<Duration>,
+    pub(crate) tcp_keepalive: Option<Duration>,
+    pub(crate) http2_keep_alive_interval: Option<Duration>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) proxy: Option<String>,
+    pub(crate) root_certificates: Vec<RootCertificate>,
+    pub(crate) headers: Vec<(String, String)>,
+}
+
+/// Encoding of an additional root certificate trusted by the HTTP client.
+#[derive(Clone, Debug)]
+pub(crate) enum RootCertificate {
+    Pem(Vec<u8>),
+    Der(Vec<u8>),
+}
+
+impl TransportConfig {
+    /// Create a new [`TransportConfig`] with the default settings of the HTTP client.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only use HTTP/2 when talking to the RPC service. This multiplexes all requests over
+    /// a single connection, but requires the RPC service to support HTTP/2.
+    pub fn http2(mut self) -> Self {
+        self.http2 = true;
+        self
+    }
+
+    /// Sets the maximum amount of idle connections kept alive per host in the connection pool.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Sets how long an idle connection is kept around in the pool before being closed.
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the interval of TCP keep-alive probes sent over idle connections.
+    pub fn tcp_keepalive(mut self, interval: Duration) -> Self {
+        self.tcp_keepalive = Some(interval);
+        self
+    }
+
+    /// Sets the interval of HTTP/2 keep-alive pings. Only used alongside [`Self::http2`].
+    pub fn http2_keep_alive_interval(mut self, interval: Duration) -> Self {
+        self.http2_keep_alive_interval = Some(interval);
+        self
+    }
+
+    /// Sets the timeout of each individual request sent to the RPC service.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Route all requests through the proxy located at `url`. Note that by default,
+    /// the proxy specified by the `HTTP_PROXY`/`HTTPS_PROXY` environment variables
+    /// is already used if set.
+    pub fn proxy(mut self, url: &str) -> Self {
+        self.proxy = Some(url.into());
+        self
+    }
+
+    /// Trust an additional root certificate in PEM format. Useful when requests are
+    /// routed through a proxy that re-signs TLS traffic with its own certificate.
+    pub fn add_root_certificate_pem(mut self, pem: &[u8]) -> Self {
+        self.root_certificates
+            .push(RootCertificate::Pem(pem.to_vec()));
+        self
+    }
+
+    /// Trust an additional root certificate in DER format. Useful when requests are
+    /// routed through a proxy that re-signs TLS traffic with its own certificate.
+    pub fn add_root_certificate_der(mut self, der: &[u8]) -> Self {
+        self.root_certificates
+            .push(RootCertificate::Der(der.to_vec()));
+        self
+    }
+
+    /// Send an additional header along with every request to the RPC service, such as
+    /// the authentication header required by some RPC providers.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Builder for HTTP clients that only sets up how to connect to a host, which is
+    /// the proxy and root certificates. This is also used for requests that are not
+    /// RPC calls, such as the ones to the testnet helper.
+    pub(crate) fn connection_builder(&self) -> Result<reqwest::ClientBuilder> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(url) = &self.proxy {
+            let proxy = reqwest::Proxy::all(url).map_err(|e| {
+                ErrorKind::DataConversion.full(format!("invalid proxy url={url}"), e)
+            })?;
+            builder = builder.proxy(proxy);
+        }
+        for cert in &self.root_certificates {
+            let cert = match cert {
+                RootCertificate::Pem(pem) => reqwest::Certificate::from_pem(pem),
+                RootCertificate::Der(der) => reqwest::Certificate::from_der(der),
+            }
+            .map_err(|e| ErrorKind::DataConversion.full("invalid root certificate", e))?;
+            builder = builder.add_root_certificate(cert);
+        }
+
+        Ok(builder)
+    }
+
+    pub(crate) fn build_http_client(&self) -> Result<reqwest::Client> {
+        self.connection_builder()?
+            .build()
+            .map_err(|e| ErrorKind::Other.full("failed to build the HTTP client", e))
+    }
+
+    pub(crate) fn build_client(&self) -> Result<reqwest::Client> {
+        let mut builder = self.connection_builder()?;
+        if self.http2 {
+            builder = builder.http2_prior_knowledge();
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if let Some(interval) = self.tcp_keepalive {
+            builder = builder.tcp_keepalive(interval);
+        }
+        if let Some(interval) = self.http2_keep_alive_interval {
+            builder = builder.http2_keep_alive_interval(interval);
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        // The RPC client only sets the content type on clients it builds itself, so clients
+        // handed to it have to come with it already.
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| {
+                ErrorKind::DataConversion.full(format!("invalid header name={name}"), e)
+            })?;
+            let value = HeaderValue::from_str(value).map_err(|e| {
+                ErrorKind::DataConversion.full(format!("invalid value for header={name}"), e)
+            })?;
+            headers.insert(name, value);
+        }
+
+        builder
+            .default_headers(headers)
+            .build()
+            .map_err(|e| ErrorKind::Other.full("failed to build the HTTP client for RPC", e))
+    }
+}
@@ -0,0 +1,191 @@
+use std::io::Read;
+
+use jsonschema::JSONSchema;
+use near_abi::{AbiFunction, AbiFunctionKind, AbiParameters, AbiRoot, AbiType};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::error::ErrorKind;
+use crate::result::Result;
+use crate::Contract;
+
+/// Name of the function that contracts built with an embedded ABI export, which returns
+/// the zstd compressed JSON of the ABI.
+const CONTRACT_ABI_FUNCTION: &str = "__contract_abi";
+
+impl Contract {
+    /// Fetch the [near-abi](https://github.com/near/abi) schema the current contract
+    /// embeds, such as when built with `cargo near build --embed-abi`. Errors if the
+    /// contract does not embed one.
+    pub async fn abi(&self) -> Result<AbiRoot> {
+        let compressed = self
+            .view(CONTRACT_ABI_FUNCTION)
+            .await
+            .map_err(|e| {
+                ErrorKind::DataConversion
+                    .full(format!("contract {} does not embed an ABI", self.id()), e)
+            })?
+            .result;
+
+        let mut abi = Vec::new();
+        zstd::Decoder::new(compressed.as_slice())
+            .and_then(|mut decoder| decoder.read_to_end(&mut abi))
+            .map_err(|e| ErrorKind::DataConversion.full("failed to decompress ABI", e))?;
+        serde_json::from_slice(&abi).map_err(|e| ErrorKind::DataConversion.custom(e))
+    }
+
+    /// Call `function` of the current contract with `args`, checking that both the
+    /// arguments and the returned value match the types the ABI of the contract states
+    /// for them. Mismatches are reported with the offending fields, instead of as a
+    /// failure to deserialize within the contract or of the returned value. View
+    /// functions are called through a view call, and the others through a transaction
+    /// signed by the contract's own account secret key.
+    ///
+    /// Requires the contract to embed its ABI, see [`Contract::abi`], which gets fetched on
+    /// every call. Use [`Contract::call_typed_with_abi`] to fetch it only once for many calls.
+    pub async fn call_typed<Args, Ret>(&self, function: &str, args: Args) -> Result<Ret>
+    where
+        Args: Serialize,
+        Ret: DeserializeOwned,
+    {
+        let abi = self.abi().await?;
+        self.call_typed_with_abi(&abi, function, args).await
+    }
+
+    /// Same as [`Contract::call_typed`], but checking against the already fetched `abi`,
+    /// such as the one returned by [`Contract::abi`], instead of fetching it from the
+    /// contract. The ABI can also come from elsewhere, for contracts that do not embed one.
+    pub async fn call_typed_with_abi<Args, Ret>(
+        &self,
+        abi: &AbiRoot,
+        function: &str,
+        args: Args,
+    ) -> Result<Ret>
+    where
+        Args: Serialize,
+        Ret: DeserializeOwned,
+    {
+        let schema = &abi.body.root_schema;
+        let function = abi
+            .body
+            .functions
+            .iter()
+            .find(|f| f.name == function)
+            .ok_or_else(|| {
+                ErrorKind::DataConversion.message(format!(
+                    "ABI of {} has no function `{}`",
+                    self.id(),
+                    function
+                ))
+            })?;
+        let definitions = serde_json::to_value(&schema.definitions)
+            .map_err(|e| ErrorKind::DataConversion.custom(e))?;
+
+        let args = match serde_json::to_value(args) {
+            // Functions without parameters are called with `()`, which stands for no arguments.
+            Ok(Value::Null) => json!({}),
+            Ok(args) => args,
+            Err(err) => return Err(ErrorKind::DataConversion.custom(err)),
+        };
+        validate(
+            &args_schema(function, &definitions)?,
+            &args,
+            &format!("arguments of `{}`", function.name),
+        )?;
+
+        let result = match function.kind {
+            AbiFunctionKind::View => self.view(&function.name).args_json(args).await?.result,
+            AbiFunctionKind::Call => self
+                .call(&function.name)
+                .args_json(args)
+                .max_gas()
+                .transact()
+                .await?
+                .raw_bytes()?,
+        };
+
+        let result = match &function.result {
+            Some(AbiType::Json { type_schema }) => {
+                let result: Value = serde_json::from_slice(&result)
+                    .map_err(|e| ErrorKind::DataConversion.custom(e))?;
+                let schema = serde_json::to_value(type_schema)
+                    .map_err(|e| ErrorKind::DataConversion.custom(e))?;
+                validate(
+                    &with_definitions(schema, &definitions),
+                    &result,
+                    &format!("value returned by `{}`", function.name),
+                )?;
+                result
+            }
+            Some(AbiType::Borsh { .. }) => {
+                return Err(ErrorKind::DataConversion.message(format!(
+                    "`{}` returns borsh, which cannot be checked as JSON",
+                    function.name
+                )))
+            }
+            None => Value::Null,
+        };
+        serde_json::from_value(result).map_err(|e| ErrorKind::DataConversion.custom(e))
+    }
+}
+
+/// JSON schema of the object holding the arguments of `function`. Arguments that accept
+/// `null` can be left out, just as serde allows for `Option` fields.
+fn args_schema(function: &AbiFunction, definitions: &Value) -> Result<Value> {
+    let args = match &function.params {
+        AbiParameters::Json { args } => args,
+        AbiParameters::Borsh { .. } => {
+            return Err(ErrorKind::DataConversion.message(format!(
+                "`{}` takes borsh arguments, which cannot be checked as JSON",
+                function.name
+            )))
+        }
+    };
+
+    let mut properties = serde_json::Map::new();
+    let mut required = Vec::new();
+    for arg in args {
+        let schema = serde_json::to_value(&arg.type_schema)
+            .map_err(|e| ErrorKind::DataConversion.custom(e))?;
+        let accepts_null = JSONSchema::compile(&with_definitions(schema.clone(), definitions))
+            .map(|schema| schema.is_valid(&Value::Null))
+            .unwrap_or(false);
+        if !accepts_null {
+            required.push(arg.name.clone());
+        }
+        properties.insert(arg.name.clone(), schema);
+    }
+
+    let schema = json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    });
+    Ok(with_definitions(schema, definitions))
+}
+
+/// Attach the type definitions of the ABI to `schema`, which the `$ref`s within it
+/// point into.
+fn with_definitions(mut schema: Value, definitions: &Value) -> Value {
+    if let Some(object) = schema.as_object_mut() {
+        object.insert("definitions".into(), definitions.clone());
+    }
+    schema
+}
+
+fn validate(schema: &Value, instance: &Value, what: &str) -> Result<()> {
+    let schema = JSONSchema::compile(schema).map_err(|e| {
+        ErrorKind::DataConversion.message(format!("invalid ABI schema for {what}: {e}"))
+    })?;
+    if let Err(errors) = schema.validate(instance) {
+        let errors = errors
+            .map(|e| format!("{} at `{}`", e, e.instance_path))
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(
+            ErrorKind::DataConversion.message(format!("{what} failed to match the ABI: {errors}"))
+        );
+    }
+    Ok(())
+}
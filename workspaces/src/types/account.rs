@@ -1,16 +1,23 @@
 use std::fmt;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use near_primitives::views::AccountView;
 
 use crate::error::ErrorKind;
+use crate::network::{Info, NetworkInfo};
 use crate::rpc::query::{
     Query, ViewAccessKey, ViewAccessKeyList, ViewAccount, ViewCode, ViewFunction, ViewState,
 };
-use crate::types::{AccountId, InMemorySigner, NearToken, PublicKey, SecretKey};
+use crate::types::{
+    AccessKey, AccessKeyPermission, AccountId, InMemorySigner, KeyType, NearToken, PublicKey,
+    SecretKey, Signer,
+};
+use crate::worker::top_up_signer;
 use crate::{BlockHeight, CryptoHash, Network, Worker};
 
-use crate::operations::{CallTransaction, CreateAccountTransaction, Transaction};
+use crate::operations::{
+    CallTransaction, ChunkedUpload, CreateAccountTransaction, Transaction, UpgradeTransaction,
+};
 use crate::result::{Execution, ExecutionFinalResult, Result};
 
 /// `Account` is directly associated to an account in the network provided by the
@@ -20,6 +27,8 @@ use crate::result::{Execution, ExecutionFinalResult, Result};
 pub struct Account {
     signer: InMemorySigner,
     worker: Worker<dyn Network>,
+    // Additional keys that can be selected to sign transactions with.
+    keys: Vec<SecretKey>,
 }
 
 impl fmt::Debug for Account {
@@ -31,7 +40,9 @@ impl fmt::Debug for Account {
 }
 
 impl Account {
-    /// Create a new account with the given path to the credentials JSON file
+    /// Create a new account with the given path to the credentials JSON file, in the
+    /// format near-cli stores keys in: an object holding the `account_id`, `public_key`
+    /// and `private_key` (or `secret_key`) of the account.
     pub fn from_file(
         path: impl AsRef<Path>,
         worker: &Worker<impl Network + 'static>,
@@ -40,20 +51,57 @@ impl Account {
         Ok(Self::new(signer, worker.clone().coerce()))
     }
 
+    /// Create the account `id` out of the credentials near-cli stored for it, which are
+    /// looked up at `~/.near-credentials/<network>/<id>.json` with `<network>` being the
+    /// network of `worker`, such as `testnet`. See [`Account::from_file`] for the format.
+    /// ```ignore
+    /// let worker = near_workspaces::testnet().await?;
+    /// let alice = Account::from_near_credentials(&"alice.testnet".parse()?, &worker)?;
+    /// ```
+    pub fn from_near_credentials(
+        id: &AccountId,
+        worker: &Worker<impl Network + 'static>,
+    ) -> Result<Self> {
+        let path = near_credentials_dir(worker.info())?.join(format!("{id}.json"));
+        Self::from_file(path, worker)
+    }
+
     /// Create an [`Account`] object from an [`AccountId`] and [`SecretKey`].
     pub fn from_secret_key(
         id: AccountId,
         sk: SecretKey,
         worker: &Worker<impl Network + 'static>,
     ) -> Self {
-        Self {
-            signer: InMemorySigner::from_secret_key(id, sk),
-            worker: worker.clone().coerce(),
-        }
+        Self::new(
+            InMemorySigner::from_secret_key(id, sk),
+            worker.clone().coerce(),
+        )
+    }
+
+    /// Create the account `id` signing its transactions with an external [`Signer`], such
+    /// as the `LedgerSigner` of the `ledger` feature, instead of a key held in memory.
+    /// ```ignore
+    /// let worker = near_workspaces::mainnet().await?;
+    /// let ledger = LedgerSigner::from_default_path().await?;
+    /// let account = Account::from_signer("alice.near".parse()?, ledger, &worker);
+    /// ```
+    pub fn from_signer(
+        id: AccountId,
+        signer: impl Signer + 'static,
+        worker: &Worker<impl Network + 'static>,
+    ) -> Self {
+        Self::new(
+            InMemorySigner::from_signer(id, signer),
+            worker.clone().coerce(),
+        )
     }
 
     pub(crate) fn new(signer: InMemorySigner, worker: Worker<dyn Network>) -> Self {
-        Self { signer, worker }
+        Self {
+            signer,
+            worker,
+            keys: Vec::new(),
+        }
     }
 
     /// Grab the current account identifier
@@ -78,6 +126,7 @@ impl Account {
             self.signer.clone(),
             function,
         )
+        .with_keys(self.keys.clone())
     }
 
     /// View call to a specified contract function. Returns a result which can
@@ -98,6 +147,22 @@ impl Account {
             .await
     }
 
+    /// Create the implicit account of `sk` by transferring `amount` to it from this account,
+    /// and get back an [`Account`] signing with `sk`. See [`PublicKey::implicit_account_id`]
+    /// for which keys have an implicit account.
+    pub async fn create_implicit_account(
+        &self,
+        sk: SecretKey,
+        amount: NearToken,
+    ) -> Result<Execution<Account>> {
+        let id = sk.public_key().implicit_account_id()?;
+        let details = self.transfer_near(&id, amount).await?;
+        Ok(Execution {
+            result: Account::new(InMemorySigner::from_secret_key(id, sk), self.worker.clone()),
+            details,
+        })
+    }
+
     /// Deletes the current account, and returns the execution details of this
     /// transaction. The beneficiary will receive the funds of the account deleted
     pub async fn delete_account(self, beneficiary_id: &AccountId) -> Result<ExecutionFinalResult> {
@@ -106,11 +171,64 @@ impl Account {
             .await
     }
 
+    /// Deletes the current account along with the sub-accounts anywhere under it that were
+    /// created through workspaces, deepest ones first, and returns the execution details
+    /// of each of the deletions. The beneficiary receives the funds of all of the deleted
+    /// accounts. Sub-accounts are deleted with the key they were created with, and the ones
+    /// that no longer exist are skipped. Handy for cleaning up after test runs on testnet.
+    ///
+    /// To be able to do so, the worker keeps the secret keys of every sub-account created
+    /// through it in memory for as long as the worker or any of its clones live, and only
+    /// lets go of them once the sub-accounts get deleted.
+    ///
+    /// Deletion stops at the first account that fails to be deleted or viewed, which leaves
+    /// its parents in place and returns the error.
+    pub async fn delete_tree(
+        self,
+        beneficiary_id: &AccountId,
+    ) -> Result<Vec<ExecutionFinalResult>> {
+        let mut outcomes = Vec::new();
+        for signer in self.worker.subaccounts.descendants(self.id())? {
+            let id = signer.account_id.clone();
+            match self.worker.view_account(&id).await {
+                Ok(_) => {}
+                Err(err) if err.is_unknown_account() => {
+                    self.worker.subaccounts.remove(&id)?;
+                    continue;
+                }
+                Err(err) => return Err(err),
+            }
+
+            let outcome = self
+                .worker
+                .delete_account(&id, &signer, beneficiary_id)
+                .await?;
+            if outcome.is_failure() {
+                outcome.clone().into_result()?;
+            }
+            tracing::debug!(target: "workspaces", "deleted {} of the tree of {}", id, self.id());
+            outcomes.push(outcome);
+        }
+
+        let outcome = self.delete_account(beneficiary_id).await?;
+        if outcome.is_failure() {
+            outcome.clone().into_result()?;
+        }
+        outcomes.push(outcome);
+        Ok(outcomes)
+    }
+
     /// Views the current account's details such as balance and storage usage.
     pub fn view_account(&self) -> Query<'_, ViewAccount> {
         self.worker.view_account(self.id())
     }
 
+    /// Breaks the balance of the current account down into what pays for storage, what can
+    /// be spent and what is staked. See [`BalanceBreakdown`].
+    pub async fn balance_breakdown(&self) -> Result<BalanceBreakdown> {
+        self.worker.balance_breakdown(self.id()).await
+    }
+
     /// Views the current accounts's access key, given the [`PublicKey`] associated to it.
     pub fn view_access_key(&self, pk: &PublicKey) -> Query<'_, ViewAccessKey> {
         Query::new(
@@ -120,6 +238,7 @@ impl Account {
                 public_key: pk.clone(),
             },
         )
+        .with_fork(self.worker.fork.clone())
     }
 
     /// Views all the [`AccessKey`]s of the current account. This will return a list of
@@ -133,6 +252,7 @@ impl Account {
                 account_id: self.id().clone(),
             },
         )
+        .with_fork(self.worker.fork.clone())
     }
 
     /// Create a new sub account. Returns a [`CreateAccountTransaction`] object
@@ -150,6 +270,22 @@ impl Account {
         )
     }
 
+    /// Get a handle to the subaccount "{name}.{account_id}" without creating it, along
+    /// with a randomly generated local key for it. The subaccount can be referenced by its
+    /// id right away, and be created later on through [`Subaccount::create`].
+    pub fn subaccount(&self, name: &str) -> Result<Subaccount> {
+        let id: AccountId = format!("{}.{}", name, self.id())
+            .parse()
+            .map_err(|e| ErrorKind::DataConversion.custom(e))?;
+
+        Ok(Subaccount {
+            parent: self.clone(),
+            name: name.into(),
+            id,
+            secret_key: SecretKey::from_random(KeyType::ED25519),
+        })
+    }
+
     /// Deploy contract code or WASM bytes to the account, and return us a new
     /// [`Contract`] object that we can use to interact with the contract.
     pub async fn deploy(&self, wasm: &[u8]) -> Result<Execution<Contract>> {
@@ -158,6 +294,7 @@ impl Account {
             .client()
             .deploy(&self.signer, self.id(), wasm.as_ref().into())
             .await?;
+        top_up_signer(&self.worker, self.id()).await;
 
         Ok(Execution {
             result: Contract::new(self.signer().clone(), self.worker.clone()),
@@ -176,25 +313,262 @@ impl Account {
             self.signer().clone(),
             contract_id.clone(),
         )
+        .with_keys(self.keys.clone())
+    }
+
+    /// Upload `payload` to the contract provided by `contract_id` in chunks, calling the
+    /// staging function `stage` with one chunk at a time. Returns a [`ChunkedUpload`]
+    /// object that we can use to specify the rest of the upload, such as the call that
+    /// applies the payload once it has been staged.
+    pub fn upload_chunked(
+        &self,
+        contract_id: &AccountId,
+        stage: &str,
+        payload: &[u8],
+    ) -> ChunkedUpload {
+        ChunkedUpload::new(
+            self.worker.clone(),
+            contract_id.clone(),
+            self.signer.clone(),
+            stage,
+            payload.to_vec(),
+        )
     }
 
     /// Store the credentials of this account locally in the directory provided.
     pub async fn store_credentials(&self, save_dir: impl AsRef<Path> + Send) -> Result<()> {
-        let savepath = save_dir.as_ref();
-        std::fs::create_dir_all(&save_dir).map_err(|e| ErrorKind::Io.custom(e))?;
-        let savepath = savepath.join(format!("{}.json", self.id()));
-        crate::rpc::tool::write_cred_to_file(&savepath, self.id(), &self.secret_key().0)
+        self.write_credentials(save_dir.as_ref(), true)
+    }
+
+    /// Store the credentials of this account where near-cli looks for them, which is
+    /// `~/.near-credentials/<network>/<id>.json`, so that the account can be inspected
+    /// with near-cli after the test. Sandbox accounts are stored under `sandbox`, which
+    /// near-cli can be pointed at with a custom network connection. Errors out if
+    /// credentials of the account are stored there already, since they might hold a key
+    /// that is in use elsewhere. See [`Account::overwrite_near_credentials`] to replace them.
+    pub async fn store_near_credentials(&self) -> Result<()> {
+        self.write_credentials(&near_credentials_dir(self.worker.info())?, false)
+    }
+
+    /// Same as [`Account::store_near_credentials`], but replacing the credentials of the
+    /// account that are stored already.
+    pub async fn overwrite_near_credentials(&self) -> Result<()> {
+        self.write_credentials(&near_credentials_dir(self.worker.info())?, true)
+    }
+
+    fn write_credentials(&self, save_dir: &Path, overwrite: bool) -> Result<()> {
+        std::fs::create_dir_all(save_dir).map_err(|e| ErrorKind::Io.custom(e))?;
+        let savepath = save_dir.join(format!("{}.json", self.id()));
+        let sk = self.signer.secret_key().ok_or_else(|| {
+            ErrorKind::DataConversion
+                .message("accounts signing with an external signer have no credentials to store")
+        })?;
+        if overwrite {
+            crate::rpc::tool::write_cred_to_file(&savepath, self.id(), &sk.0)
+        } else {
+            crate::rpc::tool::write_new_cred_to_file(&savepath, self.id(), &sk.0)
+        }
     }
 
-    /// Get the keys of this account. The public key can be retrieved from the secret key.
-    pub fn secret_key(&self) -> &SecretKey {
-        &self.signer.secret_key
+    /// Get the secret key of this account, or `None` for accounts signing with an external
+    /// [`Signer`], which has no key in memory. Use [`Account::public_key`] to get the public
+    /// key of any account.
+    pub fn secret_key(&self) -> Option<&SecretKey> {
+        self.signer.secret_key()
+    }
+
+    /// Public key of the key this account signs transactions with by default.
+    pub fn public_key(&self) -> PublicKey {
+        self.signer.public_key()
     }
 
     /// Sets the [`SecretKey`] of this account. Future transactions will be signed
     /// using this newly provided key.
     pub fn set_secret_key(&mut self, sk: SecretKey) {
-        self.signer.secret_key = sk;
+        self.signer = InMemorySigner::from_secret_key(self.signer.account_id.clone(), sk);
+    }
+
+    /// Adds a key to the set of keys this account is able to sign transactions with. Use
+    /// `signer_key` on [`CallTransaction`] or [`Transaction`] to select which key signs a
+    /// transaction, which otherwise defaults to the key provided by [`Account::secret_key`].
+    ///
+    /// Note that this does not add the key to the account on chain, which has to be done
+    /// separately such as with [`Transaction::add_key`].
+    pub fn add_signer_key(&mut self, sk: SecretKey) {
+        if !self.keys.contains(&sk) {
+            self.keys.push(sk);
+        }
+    }
+
+    /// Public keys of all the keys this account is able to sign transactions with, which
+    /// includes the default key along with the ones added with [`Account::add_signer_key`].
+    pub fn signer_keys(&self) -> Vec<PublicKey> {
+        std::iter::once(self.public_key())
+            .chain(self.keys.iter().map(SecretKey::public_key))
+            .collect()
+    }
+
+    /// Adds the key of `pk` to this account on chain with the given `permission`. Use
+    /// [`Account::add_signing_key`] instead to also sign transactions with the new key.
+    pub async fn add_key(
+        &self,
+        pk: PublicKey,
+        permission: impl Into<AccessKeyPermission>,
+    ) -> Result<ExecutionFinalResult> {
+        self.batch(self.id())
+            .add_key(
+                pk,
+                AccessKey {
+                    nonce: 0,
+                    permission: permission.into(),
+                },
+            )
+            .transact()
+            .await
+    }
+
+    /// Adds the key `sk` to this account on chain with the given `permission`. Once it has
+    /// been added, it is one of the keys this account is able to sign transactions with,
+    /// which can be selected with `signer_key` on [`CallTransaction`] or [`Transaction`].
+    pub async fn add_signing_key(
+        &mut self,
+        sk: SecretKey,
+        permission: impl Into<AccessKeyPermission>,
+    ) -> Result<ExecutionFinalResult> {
+        let result = self.add_key(sk.public_key(), permission).await?;
+        if result.is_success() {
+            self.add_signer_key(sk);
+        }
+        Ok(result)
+    }
+
+    /// Replace the default key of this account with a new random key of the same type. See
+    /// [`Account::rotate_key_to`].
+    pub async fn rotate_key(&mut self) -> Result<ExecutionFinalResult> {
+        let sk = SecretKey::from_random(self.public_key().key_type());
+        self.rotate_key_to(sk).await
+    }
+
+    /// Replace the default key of this account with `sk`, by adding `sk` as a full access key
+    /// and deleting the current default key in a single transaction. Once the transaction
+    /// succeeded, this account signs with `sk`. If it failed, neither key changed on chain
+    /// and this account keeps signing with its current key.
+    pub async fn rotate_key_to(&mut self, sk: SecretKey) -> Result<ExecutionFinalResult> {
+        let result = self
+            .batch(self.id())
+            .add_key(sk.public_key(), AccessKey::full_access())
+            .delete_key(self.public_key())
+            .transact()
+            .await?;
+        if result.is_success() {
+            self.keys.retain(|key| key != &sk);
+            self.set_secret_key(sk);
+        }
+        Ok(result)
+    }
+
+    /// Deletes the key of `pk` from this account on chain. Once it has been deleted, it is
+    /// no longer one of the keys this account is able to sign transactions with. Deleting
+    /// the default key leaves the account signing with a key that does not exist anymore,
+    /// so switch to another one with [`Account::set_secret_key`] afterwards.
+    pub async fn delete_key(&mut self, pk: &PublicKey) -> Result<ExecutionFinalResult> {
+        let result = self
+            .batch(self.id())
+            .delete_key(pk.clone())
+            .transact()
+            .await?;
+        if result.is_success() {
+            self.keys.retain(|sk| &sk.public_key() != pk);
+        }
+        Ok(result)
+    }
+}
+
+/// Directory near-cli stores the credentials of accounts on the network described by
+/// `info` in, which is `~/.near-credentials/<network>/`.
+fn near_credentials_dir(info: &Info) -> Result<PathBuf> {
+    let home = std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .ok_or_else(|| ErrorKind::Io.message("could not find the home directory"))?;
+    Ok(Path::new(&home).join(&info.keystore_path))
+}
+
+/// Handle to a subaccount that does not have to exist yet, as returned by
+/// [`Account::subaccount`]. This allows referencing the full tree of accounts a test makes
+/// use of up front, such as using their ids as receivers, while only creating the accounts
+/// once they are needed. The handle comes with a local key the account gets created with.
+#[derive(Clone)]
+pub struct Subaccount {
+    parent: Account,
+    name: String,
+    id: AccountId,
+    secret_key: SecretKey,
+}
+
+impl fmt::Debug for Subaccount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Subaccount").field("id", &self.id).finish()
+    }
+}
+
+impl Subaccount {
+    /// The id of the subaccount, in the form of "{name}.{parent_account_id}".
+    pub fn id(&self) -> &AccountId {
+        &self.id
+    }
+
+    /// The id of the account this is a subaccount of.
+    pub fn parent_id(&self) -> &AccountId {
+        self.parent.id()
+    }
+
+    /// The local key the subaccount gets created with, and signs transactions with.
+    pub fn secret_key(&self) -> &SecretKey {
+        &self.secret_key
+    }
+
+    /// Use `secret_key` as the key of the subaccount, instead of the randomly generated one.
+    pub fn with_key(mut self, secret_key: SecretKey) -> Self {
+        self.secret_key = secret_key;
+        self
+    }
+
+    /// Get a handle to a subaccount of this subaccount, which can only be created once
+    /// this subaccount has been.
+    pub fn subaccount(&self, name: &str) -> Result<Subaccount> {
+        self.as_account().subaccount(name)
+    }
+
+    /// Create the subaccount out of its parent, with the key of this handle. Returns a
+    /// [`CreateAccountTransaction`] for specifying further details such as the initial
+    /// balance of the subaccount.
+    pub fn create(&self) -> CreateAccountTransaction<'_, '_> {
+        self.parent
+            .create_subaccount(&self.name)
+            .keys(self.secret_key.clone())
+    }
+
+    /// Views the details of the subaccount, which fails if it has not been created yet.
+    pub fn view_account(&self) -> Query<'_, ViewAccount> {
+        self.parent.worker.view_account(&self.id)
+    }
+
+    /// Whether the subaccount has been created on the network.
+    pub async fn exists(&self) -> Result<bool> {
+        match self.view_account().await {
+            Ok(_) => Ok(true),
+            Err(err) if err.is_unknown_account() => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Get an [`Account`] signing with the key of this handle, for interacting with the
+    /// subaccount once it has been created.
+    pub fn as_account(&self) -> Account {
+        Account::new(
+            InMemorySigner::from_secret_key(self.id.clone(), self.secret_key.clone()),
+            self.parent.worker.clone(),
+        )
     }
 }
 
@@ -224,6 +598,16 @@ impl Contract {
         Self::account(Account::from_secret_key(id, sk, worker))
     }
 
+    /// Create a [`Contract`] object from an [`AccountId`] and an external [`Signer`]. See
+    /// [`Account::from_signer`].
+    pub fn from_signer(
+        id: AccountId,
+        signer: impl Signer + 'static,
+        worker: &Worker<impl Network + 'static>,
+    ) -> Self {
+        Self::account(Account::from_signer(id, signer, worker))
+    }
+
     pub(crate) fn new(signer: InMemorySigner, worker: Worker<dyn Network>) -> Self {
         Self {
             account: Account::new(signer, worker),
@@ -290,6 +674,12 @@ impl Contract {
         self.account.worker.view_account(self.id())
     }
 
+    /// Breaks the balance of the current contract down into what pays for storage, what
+    /// can be spent and what is staked. See [`BalanceBreakdown`].
+    pub async fn balance_breakdown(&self) -> Result<BalanceBreakdown> {
+        self.account.balance_breakdown().await
+    }
+
     /// Views the current contract's access key, given the [`PublicKey`] associated to it.
     pub fn view_access_key(&self, pk: &PublicKey) -> Query<'_, ViewAccessKey> {
         self.account.view_access_key(pk)
@@ -316,6 +706,78 @@ impl Contract {
     pub fn batch(&self) -> Transaction {
         self.account.batch(self.id())
     }
+
+    /// Upgrade the current contract by deploying `wasm` over it, using the contract's
+    /// own account secret key to do the signing. Returns an [`UpgradeTransaction`] that
+    /// can be given a migration function to call and a check of the resulting state.
+    pub fn upgrade(&self, wasm: &[u8]) -> UpgradeTransaction {
+        UpgradeTransaction::new(self.clone(), wasm)
+    }
+
+    /// Upload `payload` to the current contract in chunks, calling the staging function
+    /// `stage` with one chunk at a time and using the contract's own account secret key
+    /// to do the signing. See [`Account::upload_chunked`] for more details.
+    pub fn upload_chunked(&self, stage: &str, payload: &[u8]) -> ChunkedUpload {
+        self.account.upload_chunked(self.id(), stage, payload)
+    }
+
+    /// Summarizes how much storage the current contract takes up, such as the total
+    /// storage usage of the account, the number of keys in its state and the size of its
+    /// code. Useful for asserting that contract functions stay within a storage budget.
+    /// All of it is read at the same block, so that the numbers add up even while other
+    /// transactions land in between the queries.
+    pub async fn storage_report(&self) -> Result<StorageReport> {
+        let block = self.account.worker.view_block().await?;
+        let details = self.view_account().block_hash(*block.hash()).await?;
+        let state = self.view_state().block_hash(*block.hash()).await?;
+        let code = self.view_code().block_hash(*block.hash()).await?;
+
+        Ok(StorageReport {
+            block_height: block.height(),
+            storage_usage: details.storage_usage,
+            state_keys: state.len(),
+            state_bytes: state.iter().map(|(k, v)| k.len() + v.len()).sum(),
+            code_size: code.len(),
+        })
+    }
+}
+
+/// Breakdown of the balance of an account, as returned by [`Account::balance_breakdown`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct BalanceBreakdown {
+    /// Balance of the account that is not staked, which includes the part reserved to pay
+    /// for storage. This is what [`AccountDetails::balance`] holds.
+    pub total: NearToken,
+    /// Part of `total` the account cannot spend since it pays for the storage the account
+    /// uses. Storage is paid for by the staked balance first, and only the remainder is
+    /// reserved out of `total`.
+    pub storage_locked: NearToken,
+    /// Part of `total` the account is free to spend, such as on transfers and gas.
+    pub liquid: NearToken,
+    /// Balance of the account locked for staking.
+    pub staked: NearToken,
+    /// Total amount of bytes the account takes up in storage.
+    pub storage_usage: u64,
+    /// Balance an account has to hold per byte of storage it uses.
+    pub storage_cost_per_byte: NearToken,
+}
+
+/// Storage usage of a contract, as returned by [`Contract::storage_report`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct StorageReport {
+    /// Height of the block the report was read at.
+    pub block_height: BlockHeight,
+    /// Total amount of bytes the account takes up in storage, which is what storage
+    /// staking is charged for. This includes the code, state and access keys.
+    pub storage_usage: u64,
+    /// Number of keys stored in the contract state.
+    pub state_keys: usize,
+    /// Total amount of bytes of all the keys and values stored in the contract state.
+    pub state_bytes: usize,
+    /// Size of the deployed contract code in bytes.
+    pub code_size: usize,
 }
 
 /// Details of an Account or Contract. This is an non-exhaustive list of items
@@ -409,6 +871,25 @@ impl AccountDetails {
         }
     }
 
+    /// Break the balance of the account down into the parts that pay for storage and can
+    /// be spent, given the cost of storing a byte on the network.
+    pub fn balance_breakdown(&self, storage_cost_per_byte: NearToken) -> BalanceBreakdown {
+        let storage_cost = storage_cost_per_byte
+            .as_yoctonear()
+            .saturating_mul(self.storage_usage as u128);
+        let storage_locked = storage_cost
+            .saturating_sub(self.locked.as_yoctonear())
+            .min(self.balance.as_yoctonear());
+        BalanceBreakdown {
+            total: self.balance,
+            storage_locked: NearToken::from_yoctonear(storage_locked),
+            liquid: NearToken::from_yoctonear(self.balance.as_yoctonear() - storage_locked),
+            staked: self.locked,
+            storage_usage: self.storage_usage,
+            storage_cost_per_byte,
+        }
+    }
+
     pub(crate) fn into_near_account(self) -> near_primitives::account::Account {
         near_primitives::account::Account::new(
             self.balance.as_yoctonear(),
@@ -437,6 +918,18 @@ impl From<AccountView> for AccountDetails {
     }
 }
 
+impl From<near_primitives::account::Account> for AccountDetails {
+    fn from(account: near_primitives::account::Account) -> Self {
+        Self {
+            balance: NearToken::from_yoctonear(account.amount()),
+            locked: NearToken::from_yoctonear(account.locked()),
+            code_hash: CryptoHash(account.code_hash().0),
+            storage_usage: account.storage_usage(),
+            storage_paid_at: 0,
+        }
+    }
+}
+
 impl From<AccountDetailsPatch> for AccountDetails {
     fn from(value: AccountDetailsPatch) -> Self {
         Self {
@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+
+use crate::error::ErrorKind;
+use crate::result::Result;
+use crate::types::{AccountId, NearToken};
+use crate::{CryptoHash, Network, Worker};
+
+/// Invariant checker asserting that NEAR tokens are conserved between the accounts it
+/// tracks over the course of a test. Tokens can only leave the set of tracked accounts
+/// by being burnt for gas, so the total held by the tracked accounts before, minus what
+/// they hold afterwards, has to equal the amount of tokens burnt on the network in the
+/// meantime. A mismatch points to leaks such as lost refunds or double payouts.
+///
+/// Every account whose balance changes has to be tracked for the check to hold, this
+/// includes contracts since they receive a portion of the gas burnt by calls into them.
+/// Accounts that are created during the test can be tracked ahead of time, in which case
+/// they start out with a zero balance. Minting at epoch boundaries is not accounted for,
+/// so keep the checked span within a single epoch.
+///
+/// Example
+/// ```rust, ignore, no_run
+/// let worker = near_workspaces::sandbox().await?;
+/// let alice = worker.dev_create_account().await?;
+/// let bob = worker.dev_create_account().await?;
+///
+/// let checker = BalanceChecker::new(&worker, [alice.id(), bob.id()]).await?;
+/// alice.transfer_near(bob.id(), NearToken::from_near(1)).await?.into_result()?;
+/// checker.check().await?;
+/// ```
+pub struct BalanceChecker {
+    worker: Worker<dyn Network>,
+    start: Snapshot,
+}
+
+/// Balances of the tracked accounts and the total supply at a specific block.
+#[derive(Clone, Debug)]
+struct Snapshot {
+    total_supply: NearToken,
+    balances: BTreeMap<AccountId, NearToken>,
+}
+
+/// Outcome of comparing the balances of the tracked accounts, as returned by
+/// [`BalanceChecker::report`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct BalanceReport {
+    /// Total amount of tokens held by the tracked accounts when the checker was created.
+    pub held_before: NearToken,
+    /// Total amount of tokens held by the tracked accounts at the time of the report.
+    pub held_after: NearToken,
+    /// Amount of tokens burnt on the network in between.
+    pub burnt: NearToken,
+    /// Changes in balance of each tracked account, in yoctoNEAR.
+    pub deltas: BTreeMap<AccountId, i128>,
+}
+
+impl BalanceReport {
+    /// Amount of yoctoNEAR that is unaccounted for. Positive when tokens went missing
+    /// and negative when tokens appeared out of nowhere.
+    pub fn imbalance(&self) -> i128 {
+        self.held_before.as_yoctonear() as i128
+            - self.held_after.as_yoctonear() as i128
+            - self.burnt.as_yoctonear() as i128
+    }
+
+    /// Whether all the tokens are accounted for.
+    pub fn is_conserved(&self) -> bool {
+        self.imbalance() == 0
+    }
+}
+
+impl BalanceChecker {
+    /// Start tracking the balances of `accounts` from the latest block onwards.
+    pub async fn new<'a, T>(
+        worker: &Worker<T>,
+        accounts: impl IntoIterator<Item = &'a AccountId>,
+    ) -> Result<Self>
+    where
+        T: Network + 'static,
+    {
+        let worker = worker.clone().coerce();
+        let accounts = accounts.into_iter().cloned().collect::<Vec<_>>();
+        let start = Self::snapshot(&worker, accounts).await?;
+        Ok(Self { worker, start })
+    }
+
+    /// Compare the current balances of the tracked accounts against the ones from when
+    /// the checker was created.
+    pub async fn report(&self) -> Result<BalanceReport> {
+        let accounts = self.start.balances.keys().cloned().collect();
+        let end = Self::snapshot(&self.worker, accounts).await?;
+
+        let deltas = end
+            .balances
+            .iter()
+            .map(|(id, after)| {
+                let before = self.start.balances[id];
+                let delta = after.as_yoctonear() as i128 - before.as_yoctonear() as i128;
+                (id.clone(), delta)
+            })
+            .collect();
+        let burnt = self
+            .start
+            .total_supply
+            .as_yoctonear()
+            .saturating_sub(end.total_supply.as_yoctonear());
+
+        Ok(BalanceReport {
+            held_before: sum(self.start.balances.values()),
+            held_after: sum(end.balances.values()),
+            burnt: NearToken::from_yoctonear(burnt),
+            deltas,
+        })
+    }
+
+    /// Check that all tokens are accounted for, returning an error describing the
+    /// imbalance and the changes in balance of each tracked account otherwise.
+    pub async fn check(&self) -> Result<BalanceReport> {
+        let report = self.report().await?;
+        if !report.is_conserved() {
+            return Err(ErrorKind::Other.message(format!(
+                "balances are not conserved: imbalance of {} yoctoNEAR, burnt={}, deltas={:?}",
+                report.imbalance(),
+                report.burnt,
+                report.deltas,
+            )));
+        }
+
+        Ok(report)
+    }
+
+    async fn snapshot(worker: &Worker<dyn Network>, accounts: Vec<AccountId>) -> Result<Snapshot> {
+        // Read everything from the same block, so that the balances line up with the supply.
+        let block = worker.view_block().await?;
+        let block_hash = *block.hash();
+
+        let mut balances = BTreeMap::new();
+        for id in accounts {
+            let balance = match worker.view_account(&id).block_hash(block_hash).await {
+                Ok(details) => NearToken::from_yoctonear(
+                    details.balance.as_yoctonear() + details.locked.as_yoctonear(),
+                ),
+                // Accounts that are yet to be created hold nothing.
+                Err(err) if err.is_unknown_account() => NearToken::from_yoctonear(0),
+                Err(err) => return Err(err),
+            };
+            balances.insert(id, balance);
+        }
+
+        Ok(Snapshot {
+            total_supply: block.header().total_supply(),
+            balances,
+        })
+    }
+}
+
+fn sum<'a>(balances: impl Iterator<Item = &'a NearToken>) -> NearToken {
+    NearToken::from_yoctonear(balances.map(|balance| balance.as_yoctonear()).sum())
+}
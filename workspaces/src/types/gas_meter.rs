@@ -71,3 +71,13 @@ impl GasMeter {
         Ok(())
     }
 }
+
+/// What a gas watchdog set up through [`Worker::gas_watchdog`] does about transactions
+/// burning more than its threshold of their attached gas.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GasWatchdogMode {
+    /// Log a warning, but otherwise let the transaction through.
+    Warn,
+    /// Fail the transaction with an error, even though it executed successfully.
+    Fail,
+}
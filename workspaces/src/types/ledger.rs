@@ -0,0 +1,75 @@
+use std::str::FromStr;
+
+use async_trait::async_trait;
+
+use crate::error::ErrorKind;
+use crate::result::Result;
+use crate::types::{KeyType, PublicKey, Signature, Signer, DEFAULT_SEED_PHRASE_HD_PATH};
+
+/// Signs transactions with the ED25519 key a Ledger device holds at a derivation path,
+/// so that transactions can be sent to mainnet without the key ever leaving the device.
+/// Each transaction has to be confirmed on the device, which is a blocking operation and
+/// makes this only suited for workflows with a handful of transactions.
+///
+/// Accounts signing with a Ledger are created by passing this to [`Account::from_signer`].
+///
+/// [`Account::from_signer`]: crate::Account::from_signer
+#[derive(Clone, Debug)]
+pub struct LedgerSigner {
+    hd_path: slip10::BIP32Path,
+    public_key: PublicKey,
+}
+
+impl LedgerSigner {
+    /// Connect to the Ledger device plugged into this machine, and look up the public key
+    /// of the key it holds at `hd_path`, such as [`DEFAULT_SEED_PHRASE_HD_PATH`].
+    pub async fn new(hd_path: &str) -> Result<Self> {
+        let hd_path = slip10::BIP32Path::from_str(hd_path).map_err(|e| {
+            ErrorKind::DataConversion.message(format!("invalid HD path {hd_path:?}: {e:?}"))
+        })?;
+
+        let path = hd_path.clone();
+        let public_key = tokio::task::spawn_blocking(move || near_ledger::get_public_key(path))
+            .await
+            .map_err(|e| ErrorKind::Other.custom(e))?
+            .map_err(|e| {
+                ErrorKind::Other.message(format!("failed to get the public key from Ledger: {e:?}"))
+            })?;
+        let public_key = PublicKey(near_crypto::PublicKey::ED25519(
+            near_crypto::ED25519PublicKey(public_key.to_bytes()),
+        ));
+
+        Ok(Self {
+            hd_path,
+            public_key,
+        })
+    }
+
+    /// Connect to the Ledger device at the derivation path the NEAR wallets use by default.
+    pub async fn from_default_path() -> Result<Self> {
+        Self::new(DEFAULT_SEED_PHRASE_HD_PATH).await
+    }
+}
+
+#[async_trait]
+impl Signer for LedgerSigner {
+    fn public_key(&self) -> PublicKey {
+        self.public_key.clone()
+    }
+
+    /// Have the device sign the transaction in `payload`, which it shows for confirmation
+    /// first.
+    async fn sign(&self, payload: &[u8]) -> Result<Signature> {
+        let payload = payload.to_vec();
+        let path = self.hd_path.clone();
+        let signature =
+            tokio::task::spawn_blocking(move || near_ledger::sign_transaction(payload, path))
+                .await
+                .map_err(|e| ErrorKind::Other.custom(e))?
+                .map_err(|e| {
+                    ErrorKind::Other
+                        .message(format!("failed to sign the transaction with Ledger: {e:?}"))
+                })?;
+        Signature::from_parts(KeyType::ED25519, &signature)
+    }
+}
@@ -3,18 +3,33 @@
 //! changes on the library consumer side is needed. Just keep using these types defined here as-is.
 
 pub(crate) mod account;
+pub(crate) mod balance_checker;
 pub(crate) mod block;
 pub(crate) mod chunk;
 pub(crate) mod gas_meter;
+pub(crate) mod multisig;
+pub(crate) mod receipt;
+pub(crate) mod signer;
+pub(crate) mod status;
 
 #[cfg(feature = "interop_sdk")]
 mod sdk;
 
+#[cfg(feature = "interop_primitives")]
+mod primitives;
+
+#[cfg(feature = "ledger")]
+mod ledger;
+
+#[cfg(feature = "abi")]
+mod abi;
+
 use std::convert::TryFrom;
 use std::fmt::{self, Debug, Display};
 use std::io;
 use std::path::Path;
 use std::str::FromStr;
+use std::sync::Arc;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 pub use near_account_id::AccountId;
@@ -25,10 +40,24 @@ use sha2::Digest;
 use crate::error::{Error, ErrorKind};
 use crate::result::Result;
 
-pub use self::account::{AccountDetails, AccountDetailsPatch};
+pub use self::account::{AccountDetails, AccountDetailsPatch, BalanceBreakdown, StorageReport};
+pub use self::balance_checker::{BalanceChecker, BalanceReport};
 pub use self::chunk::{Chunk, ChunkHeader};
 
-pub use self::gas_meter::{GasHook, GasMeter};
+pub use self::gas_meter::{GasHook, GasMeter, GasWatchdogMode};
+pub use self::multisig::{Multisig, MultisigRequest, RequestId};
+pub use self::receipt::{Receipt, ReceiptAction};
+pub use self::signer::{Signature, Signer};
+pub use self::status::{NodeStatus, SyncInfo};
+
+#[cfg(feature = "interop_sdk")]
+pub use self::sdk::SdkInterop;
+
+#[cfg(feature = "ledger")]
+pub use self::ledger::LedgerSigner;
+
+#[cfg(feature = "abi")]
+pub use near_abi::AbiRoot;
 
 /// Nonce is a unit used to determine the order of transactions in the pool.
 pub type Nonce = u64;
@@ -50,7 +79,7 @@ fn from_base58(s: &str) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sy
 }
 
 /// Key types supported for either a [`SecretKey`] or [`PublicKey`]
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
 #[non_exhaustive]
 pub enum KeyType {
     ED25519 = 0,
@@ -172,6 +201,22 @@ impl PublicKey {
     pub fn key_data(&self) -> &[u8] {
         self.0.key_data()
     }
+
+    /// Get the implicit account id of this key, which is the hex encoding of its key data.
+    /// Implicit accounts come into existence when they first receive tokens, and have a
+    /// full access key of the key they are derived from. Only ED25519 keys have implicit
+    /// accounts.
+    pub fn implicit_account_id(&self) -> Result<AccountId> {
+        if self.key_type() != KeyType::ED25519 {
+            return Err(ErrorKind::DataConversion.message(format!(
+                "only ED25519 keys have implicit accounts, got a {} key",
+                self.key_type()
+            )));
+        }
+
+        let id: String = self.key_data().iter().map(|b| format!("{b:02x}")).collect();
+        id.parse().map_err(|e| ErrorKind::DataConversion.custom(e))
+    }
 }
 
 impl Display for PublicKey {
@@ -221,6 +266,11 @@ impl BorshDeserialize for PublicKey {
     }
 }
 
+/// Derivation path the NEAR wallets and near-cli derive the key of a seed phrase at, which
+/// is also where Ledger devices hold the key of NEAR accounts by default.
+#[cfg(any(feature = "seed-phrase", feature = "ledger"))]
+pub const DEFAULT_SEED_PHRASE_HD_PATH: &str = "m/44'/397'/0'";
+
 /// Secret key of an account on chain. Usually created along with a [`PublicKey`]
 /// to form a keypair associated to the account. To generate a new keypair, use
 /// one of the creation methods found here, such as [`SecretKey::from_seed`]
@@ -244,6 +294,47 @@ impl SecretKey {
         Self(near_crypto::SecretKey::from_seed(key_type, seed))
     }
 
+    /// Derive the ED25519 secret key of a BIP-39 seed phrase at the given SLIP-10
+    /// derivation path, the same way the NEAR wallets and near-cli do. These derive
+    /// keys at [`DEFAULT_SEED_PHRASE_HD_PATH`] unless told otherwise. Requires the
+    /// `seed-phrase` feature.
+    /// ```
+    /// use near_workspaces::types::{SecretKey, DEFAULT_SEED_PHRASE_HD_PATH};
+    ///
+    /// let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    /// let sk = SecretKey::from_seed_phrase(phrase, DEFAULT_SEED_PHRASE_HD_PATH)?;
+    /// # Ok::<(), near_workspaces::error::Error>(())
+    /// ```
+    #[cfg(feature = "seed-phrase")]
+    pub fn from_seed_phrase(phrase: &str, hd_path: &str) -> Result<Self> {
+        let mnemonic = bip39::Mnemonic::parse(phrase)
+            .map_err(|e| ErrorKind::DataConversion.full("invalid seed phrase", e))?;
+        let path = slip10::BIP32Path::from_str(hd_path).map_err(|e| {
+            ErrorKind::DataConversion.message(format!("invalid HD path {hd_path:?}: {e:?}"))
+        })?;
+        let derived =
+            slip10::derive_key_from_path(&mnemonic.to_seed(""), slip10::Curve::Ed25519, &path)
+                .map_err(|e| {
+                    ErrorKind::DataConversion
+                        .message(format!("failed to derive key at {hd_path:?}: {e:?}"))
+                })?;
+
+        let secret = ed25519_dalek::SecretKey::from_bytes(&derived.key)
+            .map_err(|e| ErrorKind::DataConversion.custom(e))?;
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        let mut keypair = [0; ed25519_dalek::KEYPAIR_LENGTH];
+        keypair[..ed25519_dalek::SECRET_KEY_LENGTH].copy_from_slice(secret.as_bytes());
+        keypair[ed25519_dalek::SECRET_KEY_LENGTH..].copy_from_slice(public.as_bytes());
+        Ok(Self(near_crypto::SecretKey::ED25519(
+            near_crypto::ED25519SecretKey(keypair),
+        )))
+    }
+
+    /// Sign `data` with this key.
+    pub fn sign(&self, data: &[u8]) -> Signature {
+        Signature(self.0.sign(data))
+    }
+
     /// Generate a new secret key provided the [`KeyType`]. This will use OS provided entropy
     /// to generate the key.
     pub fn from_random(key_type: KeyType) -> Self {
@@ -269,17 +360,54 @@ impl FromStr for SecretKey {
     }
 }
 
+/// Signer of the transactions of an account. Despite the name, which it keeps for
+/// compatibility, this either holds the secret key of the account in memory, or hands
+/// transactions over to an external [`Signer`] when created with
+/// [`InMemorySigner::from_signer`].
 #[derive(Clone)]
 pub struct InMemorySigner {
     pub(crate) account_id: AccountId,
-    pub(crate) secret_key: SecretKey,
+    pub(crate) key: SignerKey,
+}
+
+/// Key an [`InMemorySigner`] signs transactions with.
+#[derive(Clone)]
+pub(crate) enum SignerKey {
+    Secret(SecretKey),
+    External(Arc<dyn Signer>),
 }
 
 impl InMemorySigner {
     pub fn from_secret_key(account_id: AccountId, secret_key: SecretKey) -> Self {
         Self {
             account_id,
-            secret_key,
+            key: SignerKey::Secret(secret_key),
+        }
+    }
+
+    /// Create a signer for `account_id` that has its transactions signed by an external
+    /// [`Signer`], instead of holding a key in memory.
+    pub fn from_signer(account_id: AccountId, signer: impl Signer + 'static) -> Self {
+        Self {
+            account_id,
+            key: SignerKey::External(Arc::new(signer)),
+        }
+    }
+
+    /// Public key of the key this signer signs transactions with.
+    pub fn public_key(&self) -> PublicKey {
+        match &self.key {
+            SignerKey::Secret(sk) => sk.public_key(),
+            SignerKey::External(signer) => signer.public_key(),
+        }
+    }
+
+    /// Secret key this signer signs transactions with, which is not known to signers
+    /// backed by an external [`Signer`].
+    pub(crate) fn secret_key(&self) -> Option<&SecretKey> {
+        match &self.key {
+            SignerKey::Secret(sk) => Some(sk),
+            SignerKey::External(_) => None,
         }
     }
 
@@ -292,11 +420,31 @@ impl InMemorySigner {
         ))
     }
 
-    pub(crate) fn inner(&self) -> near_crypto::InMemorySigner {
-        near_crypto::InMemorySigner::from_secret_key(
-            self.account_id.clone(),
-            self.secret_key.0.clone(),
-        )
+    /// Sign `tx`, which has to be a transaction of the account of this signer.
+    pub(crate) async fn sign_transaction(
+        &self,
+        tx: near_primitives::transaction::Transaction,
+    ) -> Result<near_primitives::transaction::SignedTransaction> {
+        let (hash, _) = tx.get_hash_and_size();
+        let signature = match &self.key {
+            SignerKey::Secret(sk) => sk.0.sign(hash.as_ref()),
+            SignerKey::External(signer) => {
+                let payload = tx
+                    .try_to_vec()
+                    .map_err(|e| ErrorKind::DataConversion.custom(e))?;
+                let signature = signer.sign(&payload).await?;
+                if !signature.0.verify(hash.as_ref(), &tx.public_key) {
+                    return Err(ErrorKind::DataConversion.message(format!(
+                        "signer of {} returned an invalid signature for key {}",
+                        self.account_id, tx.public_key
+                    )));
+                }
+                signature.0
+            }
+        };
+        Ok(near_primitives::transaction::SignedTransaction::new(
+            signature, tx,
+        ))
     }
 }
 
@@ -370,7 +518,7 @@ impl From<near_primitives::hash::CryptoHash> for CryptoHash {
 /// is identified by a unique (within the account) public key. One account may have large number of
 /// access keys. Access keys allow to act on behalf of the account by restricting transactions
 /// that can be issued.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct AccessKey {
     /// The nonce for this access key.
     /// NOTE: In some cases the access key needs to be recreated. If the new access key reuses the
@@ -407,7 +555,7 @@ impl AccessKey {
 }
 
 /// Similar to an [`AccessKey`], but also has the [`PublicKey`] associated with it.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct AccessKeyInfo {
     pub public_key: PublicKey,
     pub access_key: AccessKey,
@@ -423,7 +571,7 @@ impl From<near_primitives::views::AccessKeyInfoView> for AccessKeyInfo {
 }
 
 /// Defines permissions for AccessKey
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum AccessKeyPermission {
     FunctionCall(FunctionCallPermission),
 
@@ -436,7 +584,7 @@ pub enum AccessKeyPermission {
 /// The permission can limit the allowed balance to be spent on the prepaid gas.
 /// It also restrict the account ID of the receiver for this function call.
 /// It also can restrict the method name for the allowed function calls.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct FunctionCallPermission {
     /// Allowance is a balance limit to use by this access key to pay for function call gas and
     /// transaction fees. When this access key is used, both account balance and the allowance is
@@ -458,6 +606,75 @@ pub struct FunctionCallPermission {
     pub method_names: Vec<String>,
 }
 
+/// Builder for the permission of a function call access key, which can be passed to
+/// [`Account::add_key`] or used as an [`AccessKey`]. Without [`FunctionCallKey::methods`]
+/// the key can call any method of the receiver, and without [`FunctionCallKey::allowance`]
+/// its allowance is unlimited.
+/// ```
+/// use near_workspaces::types::{FunctionCallKey, NearToken};
+///
+/// let permission = FunctionCallKey::new(&"counter.test.near".parse()?)
+///     .methods(["increment"])
+///     .allowance(NearToken::from_near(1));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// [`Account::add_key`]: crate::Account::add_key
+#[derive(Clone, Debug)]
+pub struct FunctionCallKey {
+    receiver_id: AccountId,
+    method_names: Vec<String>,
+    allowance: Option<NearToken>,
+}
+
+impl FunctionCallKey {
+    /// Start building a key that can only call methods of `receiver_id`.
+    pub fn new(receiver_id: &AccountId) -> Self {
+        Self {
+            receiver_id: receiver_id.clone(),
+            method_names: Vec::new(),
+            allowance: None,
+        }
+    }
+
+    /// Restrict the key to calling the given methods, in addition to the ones provided
+    /// before.
+    pub fn methods<I, S>(mut self, methods: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.method_names
+            .extend(methods.into_iter().map(Into::into));
+        self
+    }
+
+    /// Limit the amount the key can spend on the gas of its transactions.
+    pub fn allowance(mut self, allowance: NearToken) -> Self {
+        self.allowance = Some(allowance);
+        self
+    }
+}
+
+impl From<FunctionCallKey> for AccessKeyPermission {
+    fn from(key: FunctionCallKey) -> Self {
+        Self::FunctionCall(FunctionCallPermission {
+            allowance: key.allowance,
+            receiver_id: key.receiver_id.into(),
+            method_names: key.method_names,
+        })
+    }
+}
+
+impl From<FunctionCallKey> for AccessKey {
+    fn from(key: FunctionCallKey) -> Self {
+        Self {
+            nonce: 0,
+            permission: key.into(),
+        }
+    }
+}
+
 impl From<AccessKey> for near_primitives::account::AccessKey {
     fn from(access_key: AccessKey) -> Self {
         Self {
@@ -527,3 +744,73 @@ impl From<Finality> for near_primitives::types::BlockReference {
         value.into()
     }
 }
+
+/// How far along the execution of a transaction has to be before sending it returns, which
+/// is the `wait_until` parameter of the `send_tx` RPC method. Waiting for less lets tests
+/// run faster, at the cost of weaker guarantees about the state the transaction left
+/// behind. Nodes older than nearcore 1.37, which lack `send_tx`, get the same statuses
+/// waited for by polling, where [`TxExecutionStatus::Included`] only waits for the
+/// transaction to be submitted.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum TxExecutionStatus {
+    /// The transaction has been included into a block. Its outcome is not known yet, so
+    /// this only applies to `transact_async`, while `transact` waits for
+    /// [`TxExecutionStatus::ExecutedOptimistic`] at the least.
+    Included,
+    /// The transaction and all of its receipts have been executed in optimistic blocks,
+    /// which might still get reorganized.
+    ExecutedOptimistic,
+    /// The transaction and all of its receipts have been executed, and the block the
+    /// transaction got included into is final.
+    Executed,
+    /// All of the blocks the transaction and its receipts were executed in are final.
+    Final,
+}
+
+impl TxExecutionStatus {
+    pub(crate) fn as_rpc_str(self) -> &'static str {
+        match self {
+            Self::Included => "INCLUDED",
+            Self::ExecutedOptimistic => "EXECUTED_OPTIMISTIC",
+            Self::Executed => "EXECUTED",
+            Self::Final => "FINAL",
+        }
+    }
+
+    /// Whether the outcome of the transaction is known by the time this status is reached.
+    pub(crate) fn has_outcome(self) -> bool {
+        !matches!(self, Self::Included)
+    }
+}
+
+/// Identifier of a block, either by its height or its hash.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BlockId {
+    Height(BlockHeight),
+    Hash(CryptoHash),
+}
+
+impl From<BlockHeight> for BlockId {
+    fn from(height: BlockHeight) -> Self {
+        Self::Height(height)
+    }
+}
+
+impl From<CryptoHash> for BlockId {
+    fn from(hash: CryptoHash) -> Self {
+        Self::Hash(hash)
+    }
+}
+
+impl From<BlockId> for near_primitives::types::BlockReference {
+    fn from(value: BlockId) -> Self {
+        let value = match value {
+            BlockId::Height(height) => near_primitives::types::BlockId::Height(height),
+            BlockId::Hash(hash) => {
+                near_primitives::types::BlockId::Hash(near_primitives::hash::CryptoHash(hash.0))
+            }
+        };
+        value.into()
+    }
+}
@@ -0,0 +1,244 @@
+use base64::{engine::general_purpose, Engine as _};
+use serde_json::{json, Value};
+
+use crate::error::ErrorKind;
+use crate::operations::Function;
+use crate::result::{ExecutionFinalResult, Result};
+use crate::types::{
+    AccessKeyPermission, AccountId, FunctionCallKey, NearToken, PublicKey, SecretKey,
+};
+use crate::{Account, Contract};
+
+/// Methods of the multisig contract its member keys are allowed to call.
+const MEMBER_METHODS: [&str; 4] = [
+    "add_request",
+    "delete_request",
+    "confirm",
+    "add_request_and_confirm",
+];
+
+/// Identifier the multisig contract assigns to each request.
+pub type RequestId = u32;
+
+/// Account running the multisig contract of
+/// [near/core-contracts](https://github.com/near/core-contracts/tree/master/multisig), which
+/// executes a request once enough of its member keys confirmed it. Each member is an access
+/// key of the multisig account that is only allowed to call the multisig contract itself.
+///
+/// Example
+/// ```rust, ignore, no_run
+/// let worker = near_workspaces::sandbox().await?;
+/// let account = worker.dev_create_account().await?;
+/// let members = vec![
+///     SecretKey::from_random(KeyType::ED25519),
+///     SecretKey::from_random(KeyType::ED25519),
+/// ];
+/// let multisig = Multisig::deploy(&account, &multisig_wasm, members.clone(), 2).await?;
+///
+/// let request = MultisigRequest::new(bob.id()).transfer(NearToken::from_near(1));
+/// let id = multisig.propose(&members[0].public_key(), request).await?;
+/// multisig.confirm(&members[1].public_key(), id).await?.into_result()?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct Multisig {
+    contract: Contract,
+    members: Vec<PublicKey>,
+}
+
+impl Multisig {
+    /// Deploy `wasm`, the multisig contract, onto `account` and initialize it to require
+    /// `num_confirmations` confirmations out of the given `members` keys, which are added
+    /// to the account. The account keeps its own full access key, unlike production
+    /// multisig accounts which delete it, so that tests keep control over the account.
+    pub async fn deploy(
+        account: &Account,
+        wasm: &[u8],
+        members: Vec<SecretKey>,
+        num_confirmations: u32,
+    ) -> Result<Self> {
+        let permission = FunctionCallKey::new(account.id()).methods(MEMBER_METHODS);
+        let mut tx = account.batch(account.id()).deploy(wasm).call(
+            Function::new("new").args_json(json!({ "num_confirmations": num_confirmations })),
+        );
+        for member in &members {
+            tx = tx.add_key(member.public_key(), permission.clone());
+        }
+        tx.transact().await?.into_result()?;
+
+        let mut contract = Contract::account(account.clone());
+        for member in &members {
+            contract.as_account_mut().add_signer_key(member.clone());
+        }
+        Ok(Self {
+            contract,
+            members: members.iter().map(SecretKey::public_key).collect(),
+        })
+    }
+
+    /// The multisig account, signing with its own full access key.
+    pub fn contract(&self) -> &Contract {
+        &self.contract
+    }
+
+    /// Public keys of the members of the multisig.
+    pub fn members(&self) -> &[PublicKey] {
+        &self.members
+    }
+
+    /// Have `member` add `request` and confirm it right away, returning the id of the
+    /// request. The request gets executed along if a single confirmation is enough.
+    pub async fn propose(&self, member: &PublicKey, request: MultisigRequest) -> Result<RequestId> {
+        let request = request.into_json()?;
+        self.contract
+            .call("add_request_and_confirm")
+            .args_json(json!({ "request": request }))
+            .signer_key(member)
+            .max_gas()
+            .transact()
+            .await?
+            .into_result()?
+            .json()
+    }
+
+    /// Have `member` confirm the request of `request_id`, which executes it once it has
+    /// been confirmed enough times.
+    pub async fn confirm(
+        &self,
+        member: &PublicKey,
+        request_id: RequestId,
+    ) -> Result<ExecutionFinalResult> {
+        self.contract
+            .call("confirm")
+            .args_json(json!({ "request_id": request_id }))
+            .signer_key(member)
+            .max_gas()
+            .transact()
+            .await
+    }
+
+    /// Have `member` delete the request of `request_id`, which is only allowed once the
+    /// request has been pending for long enough.
+    pub async fn delete_request(
+        &self,
+        member: &PublicKey,
+        request_id: RequestId,
+    ) -> Result<ExecutionFinalResult> {
+        self.contract
+            .call("delete_request")
+            .args_json(json!({ "request_id": request_id }))
+            .signer_key(member)
+            .transact()
+            .await
+    }
+
+    /// Ids of the requests that are pending confirmation.
+    pub async fn request_ids(&self) -> Result<Vec<RequestId>> {
+        self.contract.view("list_request_ids").await?.json()
+    }
+
+    /// Public keys of the members that confirmed the request of `request_id` so far.
+    pub async fn confirmations(&self, request_id: RequestId) -> Result<Vec<PublicKey>> {
+        self.contract
+            .view("get_confirmations")
+            .args_json(json!({ "request_id": request_id }))
+            .await?
+            .json()
+    }
+}
+
+/// Request to a [`Multisig`], made up of actions that are all sent to `receiver_id`
+/// once the request has been confirmed.
+#[derive(Debug)]
+pub struct MultisigRequest {
+    receiver_id: AccountId,
+    // Result used to defer errors in argument parsing to later when proposing the request.
+    actions: Result<Vec<Value>>,
+}
+
+impl MultisigRequest {
+    /// Start a request with actions sent to `receiver_id`.
+    pub fn new(receiver_id: &AccountId) -> Self {
+        Self {
+            receiver_id: receiver_id.clone(),
+            actions: Ok(Vec::new()),
+        }
+    }
+
+    fn action(mut self, action: Value) -> Self {
+        if let Ok(actions) = &mut self.actions {
+            actions.push(action);
+        }
+        self
+    }
+
+    /// Transfer `amount` to the receiver.
+    pub fn transfer(self, amount: NearToken) -> Self {
+        self.action(json!({
+            "type": "Transfer",
+            "amount": amount.as_yoctonear().to_string(),
+        }))
+    }
+
+    /// Call into `function` of the receiver.
+    pub fn call(mut self, function: Function) -> Self {
+        let args = match function.args {
+            Ok(args) => args,
+            Err(err) => {
+                self.actions = Err(err);
+                return self;
+            }
+        };
+
+        self.action(json!({
+            "type": "FunctionCall",
+            "method_name": function.name,
+            "args": general_purpose::STANDARD.encode(args),
+            "deposit": function.deposit.as_yoctonear().to_string(),
+            "gas": function.gas.as_gas().to_string(),
+        }))
+    }
+
+    /// Add the key of `pk` to the receiver, which has to be the multisig account itself.
+    pub fn add_key(self, pk: PublicKey, permission: impl Into<AccessKeyPermission>) -> Self {
+        let mut action = json!({
+            "type": "AddKey",
+            "public_key": pk,
+        });
+        if let AccessKeyPermission::FunctionCall(permission) = permission.into() {
+            action["permission"] = json!({
+                "allowance": permission.allowance.map(|a| a.as_yoctonear().to_string()),
+                "receiver_id": permission.receiver_id,
+                "method_names": permission.method_names,
+            });
+        }
+        self.action(action)
+    }
+
+    /// Delete the key of `pk` from the receiver, which has to be the multisig account itself.
+    pub fn delete_key(self, pk: PublicKey) -> Self {
+        self.action(json!({
+            "type": "DeleteKey",
+            "public_key": pk,
+        }))
+    }
+
+    /// Change the number of confirmations the multisig requires, which has to be requested
+    /// with the multisig account itself as the receiver.
+    pub fn set_num_confirmations(self, num_confirmations: u32) -> Self {
+        self.action(json!({
+            "type": "SetNumConfirmations",
+            "num_confirmations": num_confirmations,
+        }))
+    }
+
+    fn into_json(self) -> Result<Value> {
+        let actions = self.actions?;
+        if actions.is_empty() {
+            return Err(ErrorKind::DataConversion.message("multisig request has no actions"));
+        }
+        Ok(json!({
+            "receiver_id": self.receiver_id,
+            "actions": actions,
+        }))
+    }
+}
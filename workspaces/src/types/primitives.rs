@@ -0,0 +1,77 @@
+//! Conversions between workspaces types and the types of nearcore's `near-primitives` and
+//! `near-crypto` crates, for reusing nearcore tooling alongside workspaces. Conversions that
+//! workspaces relies on itself are implemented alongside the types instead, and are
+//! available without the `interop_primitives` feature.
+
+use std::convert::TryFrom;
+
+use near_primitives::views::FinalExecutionOutcomeView;
+
+use crate::error::{Error, ErrorKind};
+use crate::result::ExecutionFinalResult;
+use crate::types::{AccessKey, AccountDetails, CryptoHash, InMemorySigner, PublicKey, SecretKey};
+
+impl From<near_crypto::PublicKey> for PublicKey {
+    fn from(pk: near_crypto::PublicKey) -> Self {
+        Self(pk)
+    }
+}
+
+impl From<near_crypto::SecretKey> for SecretKey {
+    fn from(sk: near_crypto::SecretKey) -> Self {
+        Self(sk)
+    }
+}
+
+impl From<SecretKey> for near_crypto::SecretKey {
+    fn from(sk: SecretKey) -> Self {
+        sk.0
+    }
+}
+
+impl From<near_crypto::InMemorySigner> for InMemorySigner {
+    fn from(signer: near_crypto::InMemorySigner) -> Self {
+        Self::from_secret_key(signer.account_id, SecretKey(signer.secret_key))
+    }
+}
+
+/// Fails for signers backed by an external [`Signer`](crate::types::Signer), which have no
+/// secret key in memory.
+impl TryFrom<InMemorySigner> for near_crypto::InMemorySigner {
+    type Error = Error;
+
+    fn try_from(signer: InMemorySigner) -> Result<Self, Self::Error> {
+        let secret_key = signer.secret_key().ok_or_else(|| {
+            ErrorKind::DataConversion
+                .message("signers backed by an external signer have no secret key")
+        })?;
+        Ok(Self::from_secret_key(
+            signer.account_id.clone(),
+            secret_key.0.clone(),
+        ))
+    }
+}
+
+impl From<CryptoHash> for near_primitives::hash::CryptoHash {
+    fn from(hash: CryptoHash) -> Self {
+        Self(hash.0)
+    }
+}
+
+impl From<near_primitives::account::AccessKey> for AccessKey {
+    fn from(access_key: near_primitives::account::AccessKey) -> Self {
+        near_primitives::views::AccessKeyView::from(access_key).into()
+    }
+}
+
+impl From<AccountDetails> for near_primitives::account::Account {
+    fn from(details: AccountDetails) -> Self {
+        details.into_near_account()
+    }
+}
+
+impl From<FinalExecutionOutcomeView> for ExecutionFinalResult {
+    fn from(view: FinalExecutionOutcomeView) -> Self {
+        Self::from_view(view)
+    }
+}
@@ -0,0 +1,186 @@
+use near_account_id::AccountId;
+use near_primitives::views::{ActionView, ReceiptEnumView, ReceiptView};
+
+use crate::result::ReceiptFunctionCall;
+use crate::types::{AccessKey, Gas, NearToken, PublicKey};
+use crate::CryptoHash;
+
+/// A receipt scheduled to execute on the receiver account, as returned by
+/// [`Worker::view_receipt`](crate::Worker::view_receipt). Receipts either carry actions
+/// to execute, or the data a promise resolved to.
+#[derive(Clone, Debug)]
+pub struct Receipt {
+    id: CryptoHash,
+    predecessor_id: AccountId,
+    receiver_id: AccountId,
+    signer_id: Option<AccountId>,
+    actions: Vec<ReceiptAction>,
+}
+
+impl Receipt {
+    /// The id of the receipt.
+    pub fn id(&self) -> &CryptoHash {
+        &self.id
+    }
+
+    /// The account that sent the receipt.
+    pub fn predecessor_id(&self) -> &AccountId {
+        &self.predecessor_id
+    }
+
+    /// The account the receipt executes on.
+    pub fn receiver_id(&self) -> &AccountId {
+        &self.receiver_id
+    }
+
+    /// The account that signed the transaction the receipt originates from. Only
+    /// receipts carrying actions have one.
+    pub fn signer_id(&self) -> Option<&AccountId> {
+        self.signer_id.as_ref()
+    }
+
+    /// The actions the receipt executes on the receiver account, in order. This is
+    /// empty for receipts carrying data instead of actions.
+    pub fn actions(&self) -> &[ReceiptAction] {
+        &self.actions
+    }
+
+    /// The function calls among the actions of the receipt.
+    pub fn function_calls(&self) -> impl Iterator<Item = &ReceiptFunctionCall> {
+        self.actions.iter().filter_map(|action| match action {
+            ReceiptAction::FunctionCall(call) => Some(call),
+            _ => None,
+        })
+    }
+
+    /// Whether the receipt carries the data a promise resolved to, rather than actions.
+    pub fn is_data(&self) -> bool {
+        self.signer_id.is_none()
+    }
+}
+
+/// An action executed by a [`Receipt`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum ReceiptAction {
+    /// Creates the receiver account.
+    CreateAccount,
+    /// Deploys a contract to the receiver account.
+    DeployContract {
+        /// Hash of the code getting deployed.
+        code_hash: CryptoHash,
+    },
+    /// Calls a function on the contract of the receiver account.
+    FunctionCall(ReceiptFunctionCall),
+    /// Transfers tokens to the receiver account.
+    Transfer { deposit: NearToken },
+    /// Stakes tokens of the receiver account with the validator key `public_key`.
+    Stake {
+        stake: NearToken,
+        public_key: PublicKey,
+    },
+    /// Adds an access key to the receiver account.
+    AddKey {
+        public_key: PublicKey,
+        access_key: AccessKey,
+    },
+    /// Deletes an access key of the receiver account.
+    DeleteKey { public_key: PublicKey },
+    /// Deletes the receiver account, sending its balance to `beneficiary_id`.
+    DeleteAccount { beneficiary_id: AccountId },
+    /// A meta transaction, with the actions delegated by `sender_id` executing on
+    /// `receiver_id` in a receipt of their own.
+    Delegate {
+        sender_id: AccountId,
+        receiver_id: AccountId,
+    },
+}
+
+impl From<ReceiptView> for Receipt {
+    fn from(view: ReceiptView) -> Self {
+        let ReceiptView {
+            predecessor_id,
+            receiver_id,
+            receipt_id,
+            receipt,
+            ..
+        } = view;
+        let id = CryptoHash::from(receipt_id);
+        let (signer_id, actions) = match receipt {
+            ReceiptEnumView::Action {
+                signer_id, actions, ..
+            } => {
+                let actions = actions
+                    .into_iter()
+                    .map(|action| {
+                        ReceiptAction::from_view(action, id, &predecessor_id, &receiver_id)
+                    })
+                    .collect();
+                (Some(signer_id), actions)
+            }
+            ReceiptEnumView::Data { .. } => (None, Vec::new()),
+        };
+
+        Self {
+            id,
+            predecessor_id,
+            receiver_id,
+            signer_id,
+            actions,
+        }
+    }
+}
+
+impl ReceiptAction {
+    fn from_view(
+        action: ActionView,
+        receipt_id: CryptoHash,
+        predecessor_id: &AccountId,
+        receiver_id: &AccountId,
+    ) -> Self {
+        match action {
+            ActionView::CreateAccount => Self::CreateAccount,
+            ActionView::DeployContract { code } => Self::DeployContract {
+                code_hash: CryptoHash::hash_bytes(&code),
+            },
+            ActionView::FunctionCall {
+                method_name,
+                args,
+                gas,
+                deposit,
+            } => Self::FunctionCall(ReceiptFunctionCall {
+                receipt_id,
+                predecessor_id: predecessor_id.clone(),
+                receiver_id: receiver_id.clone(),
+                method_name,
+                args: args.to_vec(),
+                gas: Gas::from_gas(gas),
+                deposit: NearToken::from_yoctonear(deposit),
+            }),
+            ActionView::Transfer { deposit } => Self::Transfer {
+                deposit: NearToken::from_yoctonear(deposit),
+            },
+            ActionView::Stake { stake, public_key } => Self::Stake {
+                stake: NearToken::from_yoctonear(stake),
+                public_key: PublicKey(public_key),
+            },
+            ActionView::AddKey {
+                public_key,
+                access_key,
+            } => Self::AddKey {
+                public_key: PublicKey(public_key),
+                access_key: access_key.into(),
+            },
+            ActionView::DeleteKey { public_key } => Self::DeleteKey {
+                public_key: PublicKey(public_key),
+            },
+            ActionView::DeleteAccount { beneficiary_id } => Self::DeleteAccount { beneficiary_id },
+            ActionView::Delegate {
+                delegate_action, ..
+            } => Self::Delegate {
+                sender_id: delegate_action.sender_id,
+                receiver_id: delegate_action.receiver_id,
+            },
+        }
+    }
+}
@@ -1,6 +1,10 @@
-use std::convert::TryFrom;
+use std::convert::{Infallible, TryFrom};
+use std::str::FromStr;
+
+use near_account_id::ParseAccountError;
 
 use crate::error::{Error, ErrorKind};
+use crate::types::{AccountId, Gas, NearToken};
 
 use super::PublicKey;
 
@@ -16,3 +20,62 @@ impl TryFrom<near_sdk::PublicKey> for PublicKey {
         })
     }
 }
+
+/// Conversions between workspaces types and their near-sdk equivalents, so that values can
+/// be passed between argument structs shared with a contract crate and workspaces without
+/// going through strings. This is a trait instead of `From`/`Into` impls since both sides
+/// of these conversions are types from other crates.
+pub trait SdkInterop: Sized {
+    /// The equivalent near-sdk type.
+    type Sdk;
+
+    /// Error converting from the near-sdk type, for types near-sdk validates less strictly.
+    type Error;
+
+    /// Convert into the equivalent near-sdk type.
+    fn into_sdk(self) -> Self::Sdk;
+
+    /// Convert from the equivalent near-sdk type.
+    fn from_sdk(value: Self::Sdk) -> Result<Self, Self::Error>;
+}
+
+impl SdkInterop for AccountId {
+    type Sdk = near_sdk::AccountId;
+    type Error = ParseAccountError;
+
+    fn into_sdk(self) -> Self::Sdk {
+        near_sdk::AccountId::new_unchecked(self.into())
+    }
+
+    fn from_sdk(value: Self::Sdk) -> Result<Self, Self::Error> {
+        // Both sides follow the same validation rules, so this only fails for ids created
+        // through `near_sdk::AccountId::new_unchecked`.
+        AccountId::from_str(value.as_str())
+    }
+}
+
+impl SdkInterop for NearToken {
+    type Sdk = near_sdk::Balance;
+    type Error = Infallible;
+
+    fn into_sdk(self) -> Self::Sdk {
+        self.as_yoctonear()
+    }
+
+    fn from_sdk(value: Self::Sdk) -> Result<Self, Self::Error> {
+        Ok(NearToken::from_yoctonear(value))
+    }
+}
+
+impl SdkInterop for Gas {
+    type Sdk = near_sdk::Gas;
+    type Error = Infallible;
+
+    fn into_sdk(self) -> Self::Sdk {
+        near_sdk::Gas(self.as_gas())
+    }
+
+    fn from_sdk(value: Self::Sdk) -> Result<Self, Self::Error> {
+        Ok(Gas::from_gas(value.0))
+    }
+}
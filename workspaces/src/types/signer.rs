@@ -0,0 +1,63 @@
+use std::fmt;
+
+use async_trait::async_trait;
+
+use crate::error::ErrorKind;
+use crate::result::Result;
+use crate::types::{KeyType, PublicKey};
+
+/// Signs the transactions of an account with a key that is held elsewhere than in memory,
+/// such as in a KMS, an HSM or a remote signing service. Accounts signing through one are
+/// created with [`Account::from_signer`] or [`Contract::from_signer`].
+///
+/// [`Account::from_signer`]: crate::Account::from_signer
+/// [`Contract::from_signer`]: crate::Contract::from_signer
+#[async_trait]
+pub trait Signer: Send + Sync {
+    /// Public key of the key this signer signs with, which the account has to hold an
+    /// access key for.
+    fn public_key(&self) -> PublicKey;
+
+    /// Sign `payload`, which is a borsh serialized transaction. As for every transaction
+    /// on NEAR, what has to be signed is the SHA-256 hash of the payload. The payload
+    /// itself is handed over for signers that show the transaction before signing it.
+    async fn sign(&self, payload: &[u8]) -> Result<Signature>;
+}
+
+/// Signature of a payload, as produced by a [`Signer`] or [`SecretKey::sign`].
+///
+/// [`SecretKey::sign`]: crate::types::SecretKey::sign
+#[derive(Clone, Eq, PartialEq)]
+pub struct Signature(pub(crate) near_crypto::Signature);
+
+impl Signature {
+    /// Construct a signature of `key_type` out of its raw bytes, which are 64 bytes for
+    /// [`KeyType::ED25519`] and 65 bytes for [`KeyType::SECP256K1`].
+    pub fn from_parts(key_type: KeyType, bytes: &[u8]) -> Result<Self> {
+        let signature = near_crypto::Signature::from_parts(key_type.into_near_keytype(), bytes)
+            .map_err(|e| ErrorKind::DataConversion.custom(e))?;
+        Ok(Self(signature))
+    }
+
+    /// Get the [`KeyType`] of the signature.
+    pub fn key_type(&self) -> KeyType {
+        KeyType::from_near_keytype(self.0.key_type())
+    }
+
+    /// Check whether this is a signature of `data` by the key of `public_key`.
+    pub fn verify(&self, data: &[u8], public_key: &PublicKey) -> bool {
+        self.0.verify(data, &public_key.0)
+    }
+}
+
+impl fmt::Debug for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
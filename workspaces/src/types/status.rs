@@ -0,0 +1,152 @@
+use chrono::{DateTime, Utc};
+use near_account_id::AccountId;
+use near_primitives::views::{StatusResponse, StatusSyncInfo};
+
+use crate::{BlockHeight, CryptoHash};
+
+/// Status of the node the worker is connected to, such as what chain it is on, what
+/// protocol version it runs and how far along the chain it has synced.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NodeStatus {
+    chain_id: String,
+    version: String,
+    build: String,
+    protocol_version: u32,
+    latest_protocol_version: u32,
+    validator_account_id: Option<AccountId>,
+    uptime_sec: i64,
+    sync_info: SyncInfo,
+}
+
+impl NodeStatus {
+    /// The id of the chain the node is on, such as `mainnet`, `testnet` or `sandbox`.
+    pub fn chain_id(&self) -> &str {
+        &self.chain_id
+    }
+
+    /// The version of `neard` the node runs.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// The build of `neard` the node runs, which is usually the git commit it was built from.
+    pub fn build(&self) -> &str {
+        &self.build
+    }
+
+    /// The protocol version currently active on the chain.
+    pub fn protocol_version(&self) -> u32 {
+        self.protocol_version
+    }
+
+    /// The latest protocol version the node supports.
+    pub fn latest_protocol_version(&self) -> u32 {
+        self.latest_protocol_version
+    }
+
+    /// The account id of the validator the node runs as, if it is a validator.
+    pub fn validator_account_id(&self) -> Option<&AccountId> {
+        self.validator_account_id.as_ref()
+    }
+
+    /// How long the node has been up for in seconds.
+    pub fn uptime_sec(&self) -> i64 {
+        self.uptime_sec
+    }
+
+    /// How far along the chain the node has synced.
+    pub fn sync_info(&self) -> &SyncInfo {
+        &self.sync_info
+    }
+
+    /// Whether the node is still catching up with the chain.
+    pub fn is_syncing(&self) -> bool {
+        self.sync_info.syncing
+    }
+
+    /// Height of the latest block the node knows of.
+    pub fn latest_block_height(&self) -> BlockHeight {
+        self.sync_info.latest_block_height
+    }
+
+    /// Hash of the latest block the node knows of.
+    pub fn latest_block_hash(&self) -> &CryptoHash {
+        &self.sync_info.latest_block_hash
+    }
+}
+
+/// How far along the chain a node has synced, as part of its [`NodeStatus`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SyncInfo {
+    latest_block_height: BlockHeight,
+    latest_block_hash: CryptoHash,
+    latest_block_time: u64,
+    latest_state_root: CryptoHash,
+    earliest_block_height: Option<BlockHeight>,
+    syncing: bool,
+}
+
+impl SyncInfo {
+    /// Height of the latest block the node knows of.
+    pub fn latest_block_height(&self) -> BlockHeight {
+        self.latest_block_height
+    }
+
+    /// Hash of the latest block the node knows of.
+    pub fn latest_block_hash(&self) -> &CryptoHash {
+        &self.latest_block_hash
+    }
+
+    /// Timestamp of the latest block the node knows of, in nanoseconds.
+    pub fn latest_block_time(&self) -> u64 {
+        self.latest_block_time
+    }
+
+    /// State root of the latest block the node knows of.
+    pub fn latest_state_root(&self) -> &CryptoHash {
+        &self.latest_state_root
+    }
+
+    /// Height of the earliest block the node still holds, if it reports one. Blocks
+    /// before this one have been garbage collected by the node.
+    pub fn earliest_block_height(&self) -> Option<BlockHeight> {
+        self.earliest_block_height
+    }
+
+    /// Whether the node is still catching up with the chain.
+    pub fn is_syncing(&self) -> bool {
+        self.syncing
+    }
+}
+
+fn timestamp_nanosec(time: DateTime<Utc>) -> u64 {
+    time.timestamp() as u64 * 1_000_000_000 + time.timestamp_subsec_nanos() as u64
+}
+
+impl From<StatusResponse> for NodeStatus {
+    fn from(status: StatusResponse) -> Self {
+        Self {
+            chain_id: status.chain_id,
+            version: status.version.version,
+            build: status.version.build,
+            protocol_version: status.protocol_version,
+            latest_protocol_version: status.latest_protocol_version,
+            validator_account_id: status.validator_account_id,
+            uptime_sec: status.uptime_sec,
+            sync_info: status.sync_info.into(),
+        }
+    }
+}
+
+impl From<StatusSyncInfo> for SyncInfo {
+    fn from(info: StatusSyncInfo) -> Self {
+        Self {
+            latest_block_height: info.latest_block_height,
+            latest_block_hash: info.latest_block_hash.into(),
+            latest_block_time: timestamp_nanosec(info.latest_block_time),
+            latest_state_root: info.latest_state_root.into(),
+            earliest_block_height: info.earliest_block_height,
+            syncing: info.syncing,
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use near_primitives::state_record::StateRecord;
+
+use crate::error::{ErrorKind, SandboxErrorCode};
+use crate::network::{NetworkClient, Sandbox, DEV_ACCOUNT_SEED};
+use crate::result::Result;
+use crate::rpc::client::Client;
+use crate::rpc::query::{Query, ViewAccount};
+use crate::types::{AccessKey, AccountId, BlockHeight, BlockId, KeyType, SecretKey};
+use crate::{CryptoHash, Network, Worker};
+
+/// Chain a sandbox is forked off of, out of which accounts get pulled into the sandbox as
+/// they are first used. Set up through [`sandbox_fork`](crate::sandbox_fork).
+pub(crate) struct Fork {
+    source: Worker<dyn Network>,
+    block_height: BlockHeight,
+    /// Accounts that were either pulled in already, or did not need to be. Only ever locked
+    /// in between network calls, so that pulling in one account does not hold up the others.
+    resolved: Mutex<HashSet<AccountId>>,
+}
+
+impl Fork {
+    pub(crate) fn new(source: Worker<dyn Network>, block_height: BlockHeight) -> Self {
+        Self {
+            source,
+            block_height,
+            resolved: Mutex::new(HashSet::new()),
+        }
+    }
+
+    fn is_resolved(&self, account_id: &AccountId) -> Result<bool> {
+        Ok(self.resolved.lock()?.contains(account_id))
+    }
+
+    /// Mark `account_id` as resolved, returning whether it was not already.
+    fn resolve(&self, account_id: &AccountId) -> Result<bool> {
+        Ok(self.resolved.lock()?.insert(account_id.clone()))
+    }
+
+    /// Pull `account_id` into the sandbox behind `into` along with its code and data, as it
+    /// was on the forked chain at the fork height. Accounts that exist in the sandbox
+    /// already, such as the ones created by the test itself, are left as they are.
+    pub(crate) async fn pull(&self, into: &Client, account_id: &AccountId) -> Result<()> {
+        if self.is_resolved(account_id)? {
+            return Ok(());
+        }
+        let in_sandbox = Query::new(
+            into,
+            ViewAccount {
+                account_id: account_id.clone(),
+            },
+        );
+        match in_sandbox.await {
+            Ok(_) => {
+                self.resolve(account_id)?;
+                return Ok(());
+            }
+            Err(err) if err.is_unknown_account() => {}
+            Err(err) => return Err(err),
+        }
+
+        let account = match self
+            .source
+            .view_account(account_id)
+            .block_reference(BlockId::Height(self.block_height).into())
+            .await
+        {
+            Ok(account) => account,
+            // Accounts that don't exist on the forked chain either are left to the sandbox.
+            Err(err) if err.is_unknown_account() => {
+                self.resolve(account_id)?;
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
+
+        let has_code = account.code_hash != CryptoHash::default();
+        let mut records = vec![StateRecord::Account {
+            account_id: account_id.clone(),
+            account: account.into_near_account(),
+        }];
+        if has_code {
+            let code = self
+                .source
+                .view_code(account_id)
+                .block_reference(BlockId::Height(self.block_height).into())
+                .await?;
+            records.push(StateRecord::Contract {
+                account_id: account_id.clone(),
+                code,
+            });
+        }
+        let state = self
+            .source
+            .view_state(account_id)
+            .block_reference(BlockId::Height(self.block_height).into())
+            .await
+            .map_err(|e| {
+                // Regular RPC nodes refuse to hand out the state of contracts holding more
+                // than 50kB of it, which takes an archival node to fork off of instead.
+                SandboxErrorCode::PatchStateFailure.full(
+                    format!(
+                        "failed to fork {account_id}: its state could not be viewed, which RPC \
+                         nodes refuse for contracts holding more than 50kB of it"
+                    ),
+                    e,
+                )
+            })?;
+        records.extend(state.into_iter().map(|(key, value)| StateRecord::Data {
+            account_id: account_id.clone(),
+            data_key: key.into(),
+            value: value.into(),
+        }));
+        // Let tests sign as the account with the same key dev accounts get.
+        let sk = SecretKey::from_seed(KeyType::ED25519, DEV_ACCOUNT_SEED);
+        records.push(StateRecord::AccessKey {
+            account_id: account_id.clone(),
+            public_key: sk.public_key().into(),
+            access_key: AccessKey::full_access().into(),
+        });
+
+        // Another pull of the same account may have finished in the meantime, in which case
+        // the account is left as it is, along with anything the test did with it since.
+        if !self.resolve(account_id)? {
+            return Ok(());
+        }
+        let patched = into.patch_state(records).await;
+        if let Err(err) = patched {
+            self.resolved.lock()?.remove(account_id);
+            return Err(SandboxErrorCode::PatchStateFailure
+                .full(format!("failed to fork {account_id} into sandbox"), err));
+        }
+        tracing::info!(target: "workspaces", "forked {} at height {}", account_id, self.block_height);
+
+        Ok(())
+    }
+}
+
+impl Worker<Sandbox> {
+    /// Pull account `id` into a forked sandbox ahead of time, along with its code and data
+    /// as of the fork height. Accounts are pulled in on their own when transactions are
+    /// sent to them or they are viewed, but not when they are only reached through
+    /// cross-contract calls, which is what this is for. Does nothing for accounts that exist
+    /// in the sandbox already. Errors out if the sandbox was not spun up with
+    /// [`sandbox_fork`](crate::sandbox_fork).
+    pub async fn fork_account(&self, id: &AccountId) -> Result<()> {
+        match &self.fork {
+            Some(fork) => fork.pull(self.client(), id).await,
+            None => Err(ErrorKind::Other.message("sandbox is not forked off of another chain")),
+        }
+    }
+}
+
+/// Pull `account_id` in from the chain `worker` is forked off of, if it is forked off of one.
+pub(crate) async fn fork_on_miss<T>(worker: &Worker<T>, account_id: &AccountId) -> Result<()>
+where
+    T: NetworkClient + ?Sized,
+{
+    match &worker.fork {
+        Some(fork) => fork.pull(worker.client(), account_id).await,
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,85 @@
+use near_primitives::transaction::Action;
+
+use crate::error::ErrorKind;
+use crate::result::{ExecutionFinalResult, Result};
+use crate::types::{Gas, GasWatchdogMode};
+use crate::Worker;
+
+/// Watchdog flagging transactions that burn close to all of the gas attached to their
+/// function calls, as configured through [`Worker::gas_watchdog`].
+pub(crate) struct GasWatchdog {
+    threshold: f64,
+    mode: GasWatchdogMode,
+}
+
+impl<T: ?Sized> Worker<T> {
+    /// Flag transactions burning more than `threshold` of the gas attached to their function
+    /// calls, where `threshold` is a fraction such as `0.9` for 90%. These calls are the ones
+    /// that start running out of gas once the state of the contract grows, so catching them
+    /// in tests surfaces them before they break in production. Depending on `mode`, flagged
+    /// transactions either get logged as a warning or fail with an error.
+    /// ```ignore
+    /// let mut worker = near_workspaces::sandbox().await?;
+    /// worker.gas_watchdog(0.9, GasWatchdogMode::Fail);
+    /// ```
+    pub fn gas_watchdog(&mut self, threshold: f64, mode: GasWatchdogMode) {
+        self.gas_watchdog = Some(std::sync::Arc::new(GasWatchdog { threshold, mode }));
+    }
+}
+
+/// Total amount of gas attached to the function calls within `actions`.
+pub(crate) fn attached_gas(actions: &[Action]) -> Gas {
+    Gas::from_gas(
+        actions
+            .iter()
+            .map(|action| match action {
+                Action::FunctionCall(call) => call.gas,
+                _ => 0,
+            })
+            .sum(),
+    )
+}
+
+/// Check how much of the `attached` gas the transaction behind `result` burnt, if `worker`
+/// has a watchdog set up. The gas burnt converting the transaction into a receipt is not
+/// paid for out of the attached gas, so only the gas burnt by the receipts counts.
+pub(crate) fn check_gas<T>(
+    worker: &Worker<T>,
+    attached: Gas,
+    result: &ExecutionFinalResult,
+) -> Result<()>
+where
+    T: ?Sized,
+{
+    let watchdog = match &worker.gas_watchdog {
+        Some(watchdog) if attached.as_gas() > 0 => watchdog,
+        _ => return Ok(()),
+    };
+
+    let burnt: u64 = result
+        .receipt_outcomes()
+        .iter()
+        .map(|outcome| outcome.gas_burnt.as_gas())
+        .sum();
+    let ratio = burnt as f64 / attached.as_gas() as f64;
+    if ratio <= watchdog.threshold {
+        return Ok(());
+    }
+
+    let msg = format!(
+        "transaction {} burnt {} out of the {} attached to it ({:.0}%), above the gas \
+         watchdog threshold of {:.0}%",
+        result.outcome().transaction_hash,
+        Gas::from_gas(burnt),
+        attached,
+        ratio * 100.0,
+        watchdog.threshold * 100.0
+    );
+    match watchdog.mode {
+        GasWatchdogMode::Warn => {
+            tracing::warn!(target: "workspaces", "{}", msg);
+            Ok(())
+        }
+        GasWatchdogMode::Fail => Err(ErrorKind::Execution.message(msg)),
+    }
+}
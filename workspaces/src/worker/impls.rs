@@ -1,16 +1,30 @@
+use std::any::Any;
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use crate::dump::StateDump;
+use crate::error::{ErrorKind, RpcErrorCode, SandboxErrorCode};
 use crate::network::{AllowDevAccountCreation, NetworkClient, NetworkInfo};
-use crate::network::{Info, Sandbox};
-use crate::operations::{CallTransaction, Function};
-use crate::result::{ExecutionFinalResult, Result};
-use crate::rpc::client::Client;
-use crate::rpc::patch::{ImportContractTransaction, PatchTransaction};
+use crate::network::{Info, NodeLog, Sandbox, SandboxNode, SandboxStats, SnapshotId};
+use crate::operations::{CallTransaction, Function, Transaction};
+use crate::result::{ExecutionFinalResult, Result, SubmitAllError, TransactionWithReceipts};
+use crate::rpc::client::{send_batch_txs_async, Client};
+use crate::rpc::patch::{
+    BloatStateTransaction, ImportContractTransaction, InjectReceiptsTransaction, PatchStateBuilder,
+    PatchTransaction,
+};
 use crate::rpc::query::{
     GasPrice, Query, QueryChunk, ViewAccessKey, ViewAccessKeyList, ViewAccount, ViewBlock,
     ViewCode, ViewFunction, ViewState,
 };
-use crate::types::{AccountId, InMemorySigner, NearToken, PublicKey};
-use crate::worker::Worker;
-use crate::{Account, Network};
+use crate::types::{
+    AccessKey, AccountDetailsPatch, AccountId, BalanceBreakdown, BlockHeight, BlockId, Finality,
+    Gas, InMemorySigner, NearToken, NodeStatus, PublicKey, Receipt, ShardId,
+};
+use crate::worker::{top_up_signer, Worker};
+use crate::{Account, CryptoHash, Network};
+
+use near_primitives::types::{EpochId, EpochReference};
 
 #[cfg(feature = "experimental")]
 use {
@@ -29,11 +43,47 @@ use {
     },
 };
 
+/// Number of times to fast forward at most while trying to reach a block timestamp.
+const FAST_FORWARD_TIMESTAMP_ATTEMPTS: usize = 10;
+
+/// Number of blocks to fast forward at most while waiting for an epoch switch to finalize.
+const FAST_FORWARD_EPOCH_ATTEMPTS: usize = 10;
+
+/// Number of epochs to fast forward at most while waiting for a protocol upgrade to kick in.
+/// Upgrades take two epochs, one for validators to vote and one for the vote to settle.
+const PROTOCOL_UPGRADE_EPOCHS: usize = 4;
+
+/// Balance of the account congesting the sandbox, which pays for the storage of its code.
+const CONGESTION_ACCOUNT_BALANCE: NearToken = NearToken::from_near(10);
+
+/// Contract burning all of the gas attached to calls into `burn`, to fill up chunks with:
+/// ```text
+/// (module
+///   (import "env" "memory" (memory 1))
+///   (func (export "burn") (loop (br 0))))
+/// ```
+const CONGESTION_WASM: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x04, 0x01, 0x60, 0x00, 0x00, 0x02, 0x0f,
+    0x01, 0x03, 0x65, 0x6e, 0x76, 0x06, 0x6d, 0x65, 0x6d, 0x6f, 0x72, 0x79, 0x02, 0x00, 0x01, 0x03,
+    0x02, 0x01, 0x00, 0x07, 0x08, 0x01, 0x04, 0x62, 0x75, 0x72, 0x6e, 0x00, 0x00, 0x0a, 0x09, 0x01,
+    0x07, 0x00, 0x03, 0x40, 0x0c, 0x00, 0x0b, 0x0b,
+];
+
+/// Time to wait on a new block at most, before considering the chain to have stalled.
+const STALLED_CHAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Seconds `wait_for_blocks` gives the network to produce each block on average.
+const MAX_SECS_PER_BLOCK: u64 = 10;
+
 impl<T: ?Sized> Clone for Worker<T> {
     fn clone(&self) -> Self {
         Self {
             workspace: self.workspace.clone(),
             tx_callbacks: self.tx_callbacks.clone(),
+            top_up: self.top_up.clone(),
+            gas_watchdog: self.gas_watchdog.clone(),
+            fork: self.fork.clone(),
+            subaccounts: self.subaccounts.clone(),
         }
     }
 }
@@ -76,6 +126,7 @@ where
                 function,
             },
         )
+        .with_fork(self.fork.clone())
     }
 
     /// View the WASM code bytes of a contract on the network.
@@ -86,13 +137,40 @@ where
                 account_id: contract_id.clone(),
             },
         )
+        .with_fork(self.fork.clone())
+    }
+
+    /// View the WASM code bytes of a contract as it was at the given block, by either its
+    /// height or hash. Only archival networks have the full history, so use a worker such
+    /// as [`mainnet_archival`] for blocks older than a few epochs.
+    ///
+    /// [`mainnet_archival`]: crate::mainnet_archival
+    pub fn view_code_at(
+        &self,
+        contract_id: &AccountId,
+        block: impl Into<BlockId>,
+    ) -> Query<'_, ViewCode> {
+        self.view_code(contract_id).block_id(block)
     }
 
     /// View the state of a account/contract on the network. This will return the internal
     /// state of the account in the form of a map of key-value pairs; where STATE contains
     /// info on a contract's internal data.
     pub fn view_state(&self, contract_id: &AccountId) -> Query<'_, ViewState> {
-        Query::view_state(self.client(), contract_id)
+        Query::view_state(self.client(), contract_id).with_fork(self.fork.clone())
+    }
+
+    /// View the state of a account/contract as it was at the given block, by either its
+    /// height or hash. Only archival networks have the full history, so use a worker such
+    /// as [`mainnet_archival`] for blocks older than a few epochs.
+    ///
+    /// [`mainnet_archival`]: crate::mainnet_archival
+    pub fn view_state_at(
+        &self,
+        contract_id: &AccountId,
+        block: impl Into<BlockId>,
+    ) -> Query<'_, ViewState> {
+        self.view_state(contract_id).block_id(block)
     }
 
     /// View the block from the network. Supply additional parameters such as [`block_height`]
@@ -129,6 +207,7 @@ where
                 public_key: pk.clone(),
             },
         )
+        .with_fork(self.fork.clone())
     }
 
     /// Views all the [`AccessKey`]s of the account specified by [`AccountId`]. This will
@@ -142,6 +221,7 @@ where
                 account_id: id.clone(),
             },
         )
+        .with_fork(self.fork.clone())
     }
 
     /// View account details of a specific account on the network.
@@ -152,11 +232,76 @@ where
                 account_id: account_id.clone(),
             },
         )
+        .with_fork(self.fork.clone())
     }
 
+    /// Breaks the balance of the account of `account_id` down into what pays for storage,
+    /// what can be spent and what is staked, at the storage cost currently set by the
+    /// network.
+    pub async fn balance_breakdown(&self, account_id: &AccountId) -> Result<BalanceBreakdown> {
+        let details = self.view_account(account_id).await?;
+        let cost = self.client().storage_amount_per_byte().await?;
+        Ok(details.balance_breakdown(cost))
+    }
+
+    /// View the price of a unit of gas at the latest block. There is no way to set the gas
+    /// price of a running sandbox, since it is part of every block header going back to
+    /// genesis. To test against a given gas price, pin it when spawning the sandbox with the
+    /// `gas_price` builder method of [`sandbox`](crate::sandbox) instead.
     pub fn gas_price(&self) -> Query<'_, GasPrice> {
         Query::new(self.client(), GasPrice)
     }
+
+    /// View the receipt with the id `receipt_id`, such as one of the receipts spawned by a
+    /// transaction, to inspect exactly which actions it scheduled to execute on its
+    /// receiver. Receipts are only available once they have been executed.
+    pub async fn view_receipt(&self, receipt_id: &CryptoHash) -> Result<Receipt> {
+        self.client()
+            .view_receipt(near_primitives::hash::CryptoHash(receipt_id.0))
+            .await
+            .map(Receipt::from)
+    }
+
+    /// View the outcome of the transaction with the hash `tx_hash` sent by `sender_id`,
+    /// along with the full bodies of the receipts it spawned. This allows verifying the
+    /// precise actions and deposits carried by each of the receipts, which the outcomes
+    /// of the receipts do not hold.
+    pub async fn view_tx_with_receipts(
+        &self,
+        sender_id: &AccountId,
+        tx_hash: &CryptoHash,
+    ) -> Result<TransactionWithReceipts> {
+        self.client()
+            .tx_status_with_receipts(sender_id, near_primitives::hash::CryptoHash(tx_hash.0))
+            .await
+            .map(TransactionWithReceipts::from_view)
+    }
+
+    /// Query the status of the node the worker is connected to, such as the chain it
+    /// is on, its protocol version and the latest block it knows of. Useful for gating
+    /// actions on the health of the node, or for logging the context of the chain.
+    pub async fn status(&self) -> Result<NodeStatus> {
+        self.client()
+            .status()
+            .await
+            .map(NodeStatus::from)
+            .map_err(|e| RpcErrorCode::QueryFailure.custom(e))
+    }
+
+    /// Query the shard that `account_id` lands on given how the network currently splits
+    /// accounts into shards, which tells whether receipts between two accounts cross
+    /// shards. The account does not have to exist.
+    pub async fn shard_id(&self, account_id: &AccountId) -> Result<ShardId> {
+        let layout = self.client().shard_layout().await?;
+        Ok(near_primitives::shard_layout::account_id_to_shard_id(
+            account_id, &layout,
+        ))
+    }
+
+    /// Query the number of shards the network currently splits accounts into.
+    pub async fn num_shards(&self) -> Result<u64> {
+        Ok(self.client().shard_layout().await?.num_shards())
+    }
 }
 
 impl<T> Worker<T>
@@ -171,11 +316,15 @@ where
         receiver_id: &AccountId,
         amount_yocto: NearToken,
     ) -> Result<ExecutionFinalResult> {
-        self.client()
+        let result = self
+            .client()
             .transfer_near(signer, receiver_id, amount_yocto)
             .await
             .map(ExecutionFinalResult::from_view)
-            .map_err(crate::error::Error::from)
+            .map_err(crate::error::Error::from)?;
+
+        top_up_signer(self, &signer.account_id).await;
+        Ok(result)
     }
 
     /// Deletes an account from the network. The beneficiary will receive the balance
@@ -186,11 +335,16 @@ where
         signer: &InMemorySigner,
         beneficiary_id: &AccountId,
     ) -> Result<ExecutionFinalResult> {
-        self.client()
+        let outcome = self
+            .client()
             .delete_account(signer, account_id, beneficiary_id)
             .await
             .map(ExecutionFinalResult::from_view)
-            .map_err(crate::error::Error::from)
+            .map_err(crate::error::Error::from)?;
+        if outcome.is_success() {
+            self.subaccounts.remove(account_id)?;
+        }
+        Ok(outcome)
     }
 }
 
@@ -268,6 +422,105 @@ where
             function,
         )
     }
+
+    /// Sign and submit all of `txs` back-to-back, and then wait on all of them to complete
+    /// concurrently. This is much faster than calling `transact` on each one in turn, which
+    /// waits for every transaction to complete before submitting the next one, and is
+    /// useful for speeding up the setup of tests. Calls can be submitted alongside batch
+    /// transactions by converting them with `into`:
+    /// ```ignore
+    /// let results = worker
+    ///     .submit_all([
+    ///         alice.call(contract.id(), "set_status").args_json(json!({"message": "hi"})).into(),
+    ///         alice.batch(bob.id()).transfer(NearToken::from_near(1)),
+    ///     ])
+    ///     .await?;
+    /// ```
+    /// Transactions signed with the same key are executed in the order they are given in.
+    /// The results are returned in that same order as well.
+    ///
+    /// If one of the transactions fails to be sent, the ones after it are not sent at all,
+    /// while the ones already sent are still waited on. Their outcomes are returned within
+    /// the [`SubmitAllError`] alongside the error, to tell which of them landed.
+    pub async fn submit_all(
+        &self,
+        txs: impl IntoIterator<Item = Transaction>,
+    ) -> Result<Vec<ExecutionFinalResult>, SubmitAllError> {
+        let txs = txs
+            .into_iter()
+            .map(Transaction::into_parts)
+            .collect::<Result<Vec<_>>>()
+            .map_err(|error| SubmitAllError {
+                results: Vec::new(),
+                error,
+            })?;
+        let (statuses, send_err) = send_batch_txs_async(&self.clone().coerce(), txs).await;
+
+        let handles = statuses
+            .into_iter()
+            .map(|status| tokio::spawn(status.wait()))
+            .collect::<Vec<_>>();
+        let mut results = Vec::with_capacity(handles.len());
+        for handle in handles {
+            let result = handle
+                .await
+                .map_err(|e| ErrorKind::Other.full("failed to wait on transaction", e))
+                .and_then(|result| result);
+            results.push(result);
+        }
+
+        let failed = results.iter().filter(|result| result.is_err()).count();
+        match send_err {
+            Some(error) => Err(SubmitAllError { results, error }),
+            None if failed > 0 => Err(SubmitAllError {
+                error: ErrorKind::Other.message(format!(
+                    "failed to wait on {failed} of the {} transactions",
+                    results.len()
+                )),
+                results,
+            }),
+            None => Ok(results
+                .into_iter()
+                .map(|result| result.expect("no transaction failed to be waited on"))
+                .collect()),
+        }
+    }
+
+    /// Wait until `n` more blocks have been produced by the network. On sandbox, this will
+    /// fast forward `n` blocks to produce them right away, while on other networks the
+    /// latest block is polled until the blocks have been produced. This allows the same
+    /// time dependent test code to be run on both sandbox and live networks. Errors out if
+    /// the blocks take longer than 10 seconds each on average, such as when the network
+    /// stalled. See [`Self::wait_for_blocks_within`] to pick the deadline.
+    pub async fn wait_for_blocks(&self, n: u64) -> Result<()> {
+        let timeout = Duration::from_secs(n.max(1).saturating_mul(MAX_SECS_PER_BLOCK));
+        self.wait_for_blocks_within(n, timeout).await
+    }
+
+    /// Same as [`Self::wait_for_blocks`], but erroring out once `timeout` passes without
+    /// the `n` blocks having been produced.
+    pub async fn wait_for_blocks_within(&self, n: u64, timeout: Duration) -> Result<()> {
+        tokio::time::timeout(timeout, self.produce_or_poll_blocks(n))
+            .await
+            .unwrap_or_else(|_| {
+                Err(ErrorKind::Other.message(format!(
+                    "network did not produce {n} blocks within {timeout:?}"
+                )))
+            })
+    }
+
+    async fn produce_or_poll_blocks(&self, n: u64) -> Result<()> {
+        if let Some(sandbox) = (self.workspace.as_ref() as &dyn Any).downcast_ref::<Sandbox>() {
+            return sandbox.fast_forward(n).await;
+        }
+
+        let target = self.view_block().await?.height() + n;
+        while self.view_block().await?.height() < target {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+
+        Ok(())
+    }
 }
 
 impl Worker<Sandbox> {
@@ -276,6 +529,14 @@ impl Worker<Sandbox> {
         Ok(Account::new(signer, self.clone().coerce()))
     }
 
+    /// Account `id` baked into the genesis of the sandbox with `with_genesis_account` or
+    /// `genesis_account` on [`sandbox`](crate::sandbox), signing with the key it was given
+    /// there. Errors out if `id` was not baked into genesis.
+    pub fn genesis_account(&self, id: &AccountId) -> Result<Account> {
+        let signer = self.workspace.genesis_signer(id)?;
+        Ok(Account::new(signer, self.clone().coerce()))
+    }
+
     /// Import a contract from the the given network, and return us a [`ImportContractTransaction`]
     /// which allows to specify further details, such as being able to import contract data and
     /// how far back in time we wanna grab the contract.
@@ -287,6 +548,41 @@ impl Worker<Sandbox> {
         ImportContractTransaction::new(id, worker.clone().coerce(), self.clone())
     }
 
+    /// Clone the account of `id` from the network `source` talks to, such as testnet or
+    /// mainnet, into this sandbox along with its code, state and access keys. This is
+    /// [`Worker::import_contract`] with [`ImportContractTransaction::with_data`] and
+    /// [`ImportContractTransaction::with_access_keys`] already specified, so the same
+    /// details can be specified on top, such as cloning into a different account:
+    /// ```ignore
+    /// let mainnet = near_workspaces::mainnet().await?;
+    /// let contract = worker
+    ///     .clone_account(&mainnet, &"wrap.near".parse()?)
+    ///     .dest_account_id(&"wrap.test.near".parse()?)
+    ///     .transact()
+    ///     .await?;
+    /// ```
+    pub fn clone_account<'a>(
+        &self,
+        source: &Worker<impl Network + 'static>,
+        id: &'a AccountId,
+    ) -> ImportContractTransaction<'a> {
+        self.import_contract(id, source)
+            .with_data()
+            .with_access_keys()
+    }
+
+    /// Import a contract out of a [`StateDump`] of a local node, rather than from another
+    /// network over RPC. Returns the same [`ImportContractTransaction`] as
+    /// [`Worker::import_contract`] does, for specifying further details such as importing
+    /// the contract data along with it.
+    pub fn import_contract_from_dump<'a>(
+        &self,
+        id: &'a AccountId,
+        dump: &'a StateDump,
+    ) -> ImportContractTransaction<'a> {
+        ImportContractTransaction::from_dump(id, dump, self.clone())
+    }
+
     /// Start patching the state of the account specified by the [`AccountId`]. This will create
     /// a [`PatchTransaction`] that will allow us to patch access keys, code, and contract state.
     /// This is similar to functions like [`Account::batch`] where we can perform multiple actions
@@ -295,6 +591,13 @@ impl Worker<Sandbox> {
         PatchTransaction::new(self, account_id.clone())
     }
 
+    /// Start patching a large number of records into the state of any number of accounts.
+    /// Unlike [`Worker::patch`], the records get patched in over as many batched requests
+    /// as needed, with the progress of it all reported through [`PatchStateBuilder::progress`].
+    pub fn bulk_patch(&self) -> PatchStateBuilder {
+        PatchStateBuilder::new(self)
+    }
+
     /// Patch state into the sandbox network, given a prefix key and value. This will allow us
     /// to set contract state that we have acquired in some manner, where we are able to test
     /// random cases that are hard to come up naturally as state evolves.
@@ -307,6 +610,122 @@ impl Worker<Sandbox> {
         self.workspace.patch_state(contract_id, key, value).await
     }
 
+    /// Set the balance of account `account_id` to `balance` by patching it in, leaving the
+    /// rest of the account as it is. Useful for minting arbitrary amounts of tokens, such as
+    /// to play a whale, without having to send them over from other accounts. Note that the
+    /// total supply of the chain does not change along with it. Errors out if the account
+    /// does not exist.
+    /// ```ignore
+    /// worker
+    ///     .patch_account_balance(whale.id(), NearToken::from_near(1_000_000))
+    ///     .await?;
+    /// ```
+    pub async fn patch_account_balance(
+        &self,
+        account_id: &AccountId,
+        balance: NearToken,
+    ) -> Result<()> {
+        self.patch(account_id)
+            .account_from_current(move |current| {
+                AccountDetailsPatch::from(current).balance(balance)
+            })
+            .transact()
+            .await
+    }
+
+    /// Swap the code deployed to account `account_id` for `wasm` by patching it in, without
+    /// sending a deploy transaction. This places contracts on accounts we hold no keys of,
+    /// such as ones imported from other networks, and hot-swaps code between phases of a
+    /// test. The state of the contract is left as it is. Errors out if the account does not
+    /// exist.
+    /// ```ignore
+    /// worker.patch_code(&"wrap.near".parse()?, &patched_wasm).await?;
+    /// ```
+    pub async fn patch_code(&self, account_id: &AccountId, wasm: &[u8]) -> Result<()> {
+        self.patch(account_id).code(wasm).transact().await
+    }
+
+    /// Add `access_key` for `public_key` to account `account_id` by patching it in, or
+    /// overwrite the access key already there for it. This lets a test act as any account
+    /// in the sandbox, including ones imported from other networks that we hold no keys of.
+    /// Errors out if the account does not exist.
+    /// ```ignore
+    /// let sk = SecretKey::from_random(KeyType::ED25519);
+    /// worker
+    ///     .patch_access_key(&owner_id, sk.public_key(), AccessKey::full_access())
+    ///     .await?;
+    /// let owner = Account::from_secret_key(owner_id, sk, &worker);
+    /// ```
+    pub async fn patch_access_key(
+        &self,
+        account_id: &AccountId,
+        public_key: PublicKey,
+        access_key: AccessKey,
+    ) -> Result<()> {
+        // Keys patched onto accounts that do not exist are left dangling in the state.
+        self.view_account(account_id).await?;
+        self.patch(account_id)
+            .access_key(public_key, access_key)
+            .transact()
+            .await
+    }
+
+    /// Remove `keys` from the state of the contract `account_id`, such as to simulate
+    /// corrupted or pruned storage and check how the contract recovers from it. Keys that
+    /// do not exist are skipped. Since sandbox can only patch state in and not out, the
+    /// keys get removed by a contract swapped onto the account for the occasion, after which
+    /// the original code is swapped back in. The calls into it are paid for by the root
+    /// account.
+    /// ```ignore
+    /// worker.delete_state(contract.id(), [b"STATE".as_slice()]).await?;
+    /// ```
+    pub async fn delete_state<I, K>(&self, account_id: &AccountId, keys: I) -> Result<()>
+    where
+        I: IntoIterator<Item = K>,
+        K: AsRef<[u8]>,
+    {
+        let keys = keys.into_iter().map(|key| key.as_ref().to_vec()).collect();
+        crate::rpc::patch::delete_state(self, account_id, keys).await
+    }
+
+    /// Remove all of the state of the contract `account_id`, leaving its code, keys and
+    /// balance as they are. See [`Worker::delete_state`] for more details.
+    pub async fn clear_state(&self, account_id: &AccountId) -> Result<()> {
+        let keys = self.view_state(account_id).await?.into_keys().collect();
+        crate::rpc::patch::delete_state(self, account_id, keys).await
+    }
+
+    /// Send crafted receipts to any account as if they came from account `predecessor_id`,
+    /// such as to call into callback handlers or methods only trusted accounts may call,
+    /// or to line up receipts in an order that is hard to get to otherwise. Returns an
+    /// [`InjectReceiptsTransaction`] to add the receipts to.
+    /// ```ignore
+    /// worker
+    ///     .inject_receipts(token.id())
+    ///     .call(vault.id(), Function::new("on_deposit").args_json(json!({ "amount": "10" })))
+    ///     .transact()
+    ///     .await?
+    ///     .into_result()?;
+    /// ```
+    pub fn inject_receipts(&self, predecessor_id: &AccountId) -> InjectReceiptsTransaction<'_> {
+        InjectReceiptsTransaction::new(self, predecessor_id.clone())
+    }
+
+    /// Write `entries` synthetic entries into the state of the contract `contract_id`, for
+    /// stress testing the contract over large collections. Returns a [`BloatStateTransaction`]
+    /// that allows us to specify the layout and size of the entries:
+    /// ```ignore
+    /// worker
+    ///     .bloat_state(contract.id(), 100_000)
+    ///     .key_prefix(b"m")
+    ///     .value_size(64)
+    ///     .transact()
+    ///     .await?;
+    /// ```
+    pub fn bloat_state(&self, contract_id: &AccountId, entries: u64) -> BloatStateTransaction {
+        BloatStateTransaction::new(self, contract_id.clone(), entries)
+    }
+
     /// Fast forward to a point in the future. The delta block height is supplied to tell the
     /// network to advanced a certain amount of blocks. This comes with the advantage only having
     /// to wait a fraction of the time it takes to produce the same number of blocks.
@@ -320,11 +739,391 @@ impl Worker<Sandbox> {
         self.workspace.fast_forward(delta_height).await
     }
 
+    /// Fast forward to the block height `height`, rather than by a delta of blocks. Since
+    /// sandbox keeps producing blocks on its own, the chain can end up a block or so past
+    /// `height` by the time this returns. Fails if the chain is already past `height`.
+    pub async fn fast_forward_to(&self, height: BlockHeight) -> Result<()> {
+        let current = self.view_block().await?.height();
+        if current > height {
+            return Err(SandboxErrorCode::FastForwardFailure.message(format!(
+                "cannot fast forward to block height {height}, chain is already at {current}"
+            )));
+        }
+        if current < height {
+            self.fast_forward(height - current).await?;
+        }
+        Ok(())
+    }
+
+    /// Fast forward `n` whole epochs, to right past the start of the `n`th epoch from now,
+    /// and wait until the switch into it is finalized. Epoch boundaries are what staking,
+    /// validator rewards and unlocking unstaked balances hinge on, and this saves having
+    /// to work out how many blocks they are away from the epoch length.
+    /// ```ignore
+    /// account.call(pool.id(), "unstake_all").transact().await?;
+    /// // Unstaked balances unlock after 4 epochs.
+    /// worker.fast_forward_epochs(4).await?;
+    /// account.call(pool.id(), "withdraw_all").transact().await?;
+    /// ```
+    pub async fn fast_forward_epochs(&self, n: u64) -> Result<()> {
+        if n == 0 {
+            return Ok(());
+        }
+        let epoch_length = self.client().epoch_length().await?;
+        let epoch = self
+            .client()
+            .epoch_validators(EpochReference::Latest)
+            .await?;
+        let target_epoch = epoch.epoch_height + n;
+
+        let target_height = epoch.epoch_start_height + n * epoch_length;
+        let height = self.view_block().await?.height();
+        if target_height > height {
+            self.fast_forward(target_height - height).await?;
+        }
+
+        // The epoch switch only takes once the last block of the previous epoch is final,
+        // which can take a few more blocks.
+        for _ in 0..FAST_FORWARD_EPOCH_ATTEMPTS {
+            let block = self.view_block().finality(Finality::Final).await?;
+            let epoch_id = EpochId(near_primitives::hash::CryptoHash(block.epoch_id().0));
+            let epoch = self
+                .client()
+                .epoch_validators(EpochReference::EpochId(epoch_id))
+                .await?;
+            if epoch.epoch_height >= target_epoch {
+                return Ok(());
+            }
+            self.fast_forward(1).await?;
+        }
+        Err(SandboxErrorCode::FastForwardFailure.message(format!(
+            "epoch switch was not finalized after fast forwarding {FAST_FORWARD_EPOCH_ATTEMPTS} more blocks"
+        )))
+    }
+
+    /// Upgrade the protocol of a sandbox started out at an older version through
+    /// `protocol_version`, returning the version it ends up at. This fast forwards epochs
+    /// until the upgrade the sandbox validator voted for kicks in, as it would on a live
+    /// network, and does nothing if the sandbox already runs the latest version.
+    /// ```ignore
+    /// let worker = near_workspaces::sandbox().protocol_version(63).await?;
+    /// // ... check behavior at the older protocol version
+    /// let version = worker.upgrade_protocol().await?;
+    /// // ... and again after the upgrade
+    /// ```
+    pub async fn upgrade_protocol(&self) -> Result<u32> {
+        let mut status = self.status().await?;
+        for _ in 0..PROTOCOL_UPGRADE_EPOCHS {
+            if status.protocol_version() >= status.latest_protocol_version() {
+                return Ok(status.protocol_version());
+            }
+            self.fast_forward_epochs(1).await?;
+            status = self.status().await?;
+        }
+        if status.protocol_version() >= status.latest_protocol_version() {
+            return Ok(status.protocol_version());
+        }
+        Err(SandboxErrorCode::FastForwardFailure.message(format!(
+            "protocol version only reached {} out of {} after {PROTOCOL_UPGRADE_EPOCHS} epochs",
+            status.protocol_version(),
+            status.latest_protocol_version()
+        )))
+    }
+
+    /// Produce a single block, for sandboxes only producing blocks on demand after being
+    /// spun up with `manual_block_production`. See [`Worker::produce_blocks`].
+    pub async fn produce_block(&self) -> Result<()> {
+        self.produce_blocks(1).await
+    }
+
+    /// Produce `count` blocks, one after the other, returning once the last of them is in.
+    /// Transactions sent in the meantime are included in these blocks, and the receipts
+    /// they spawn execute over the course of them. Sandboxes producing blocks on a timer
+    /// produce these on top of the ones they produce on their own.
+    /// ```ignore
+    /// let worker = near_workspaces::sandbox().manual_block_production().await?;
+    /// let status = account.call(contract.id(), "ping").transact_async().await?;
+    /// worker.produce_blocks(3).await?;
+    /// let outcome = status.await?;
+    /// ```
+    pub async fn produce_blocks(&self, count: u64) -> Result<()> {
+        self.fast_forward(count).await
+    }
+
+    /// Fast forward until the timestamp of the latest block reaches `timestamp`, given in
+    /// nanoseconds like [`Block::timestamp`]. Useful for tests that depend on wall-clock
+    /// time, such as contracts scheduling work on a cadence, without having to guess how
+    /// many blocks make up the time span. The chain ends up at or slightly past `timestamp`,
+    /// and is left as is if it is already past `timestamp`.
+    ///
+    /// [`Block::timestamp`]: crate::Block::timestamp
+    pub async fn fast_forward_to_timestamp(&self, timestamp: u64) -> Result<()> {
+        let mut block = self.view_block().await?;
+        // Start out assuming blocks are a second apart, and correct the estimate with how
+        // far each fast forward actually moved the timestamp along.
+        let mut nanos_per_block = 1_000_000_000;
+        for _ in 0..FAST_FORWARD_TIMESTAMP_ATTEMPTS {
+            if block.timestamp() >= timestamp {
+                return Ok(());
+            }
+
+            let remaining = timestamp - block.timestamp();
+            let delta_height =
+                std::cmp::max(1, (remaining + nanos_per_block - 1) / nanos_per_block);
+            self.fast_forward(delta_height).await?;
+
+            let next = self.view_block().await?;
+            let moved_blocks = next.height().saturating_sub(block.height());
+            let moved_time = next.timestamp().saturating_sub(block.timestamp());
+            if moved_blocks > 0 && moved_time > 0 {
+                nanos_per_block = std::cmp::max(1, moved_time / moved_blocks);
+            }
+            block = next;
+        }
+
+        if block.timestamp() >= timestamp {
+            return Ok(());
+        }
+        Err(SandboxErrorCode::FastForwardFailure.message(format!(
+            "block timestamp only reached {} out of {timestamp} after fast forwarding {} times",
+            block.timestamp(),
+            FAST_FORWARD_TIMESTAMP_ATTEMPTS
+        )))
+    }
+
+    /// Fast forward until the timestamp of the latest block is `duration` past what it is
+    /// now. See [`Worker::fast_forward_to_timestamp`] for more details.
+    /// ```ignore
+    /// // Let an hourly task become due.
+    /// worker.fast_forward_duration(Duration::from_secs(60 * 60)).await?;
+    /// ```
+    pub async fn fast_forward_duration(&self, duration: Duration) -> Result<()> {
+        let now = self.view_block().await?.timestamp();
+        let duration = u64::try_from(duration.as_nanos()).map_err(|e| {
+            SandboxErrorCode::FastForwardFailure.full("duration to fast forward is too long", e)
+        })?;
+        self.fast_forward_to_timestamp(now.saturating_add(duration))
+            .await
+    }
+
+    /// Snapshot the current state of the sandbox, so that it can be brought back to it later
+    /// on with [`Worker::restore`]. Useful for sharing an expensive setup between tests, which
+    /// each restore the snapshot taken right after it. The node gets briefly stopped while
+    /// the snapshot is taken, so transactions should not be in flight at the same time.
+    ///
+    /// Only sandboxes spawned by workspaces on this machine can be snapshotted.
+    /// ```ignore
+    /// let snapshot = worker.snapshot().await?;
+    /// contract.call("reset_everything").transact().await?;
+    /// worker.restore(snapshot).await?;
+    /// ```
+    pub async fn snapshot(&self) -> Result<SnapshotId> {
+        self.workspace.snapshot().await
+    }
+
+    /// Restore the state of the sandbox to a snapshot taken with [`Worker::snapshot`],
+    /// undoing everything that happened since, including blocks produced. Snapshots can be
+    /// restored any number of times.
+    pub async fn restore(&self, snapshot: SnapshotId) -> Result<()> {
+        self.workspace.restore(snapshot).await
+    }
+
+    /// Stop the node, giving it a chance to shut down gracefully. Requests sent to it fail
+    /// until it is brought back up with [`Worker::restart`]. Only sandboxes spawned by
+    /// workspaces on this machine can be stopped.
+    pub async fn stop(&self) -> Result<()> {
+        self.workspace.stop().await
+    }
+
+    /// Restart the node, stopping it first if it is still running, and wait for it to be
+    /// back up. The chain picks up where it left off with its state intact, which is useful
+    /// for checking that client code copes with the node going away, or that contracts
+    /// behave the same across a node reboot. The node listens on the same ports as before.
+    pub async fn restart(&self) -> Result<()> {
+        self.workspace.restart().await
+    }
+
+    /// Take the validator `node` of a [`sandbox_cluster`](crate::sandbox_cluster) offline
+    /// while the rest of the cluster produces `blocks` more blocks, and bring it back up
+    /// afterwards. The validator misses its turns producing blocks and chunks meanwhile,
+    /// which staking pool and reward distribution contracts can be tested against. Missing
+    /// enough of them within an epoch gets the validator kicked out of the validator set
+    /// of the epoch after next. The rest of the cluster has to hold more than two thirds of
+    /// the stake to keep producing blocks, and `node` cannot be the node this worker talks
+    /// to. A single node sandbox has no other validator to carry on without it, so it can
+    /// only be stopped along with the chain through [`Worker::stop`].
+    /// ```ignore
+    /// let (worker, nodes) = near_workspaces::sandbox_cluster(4).await?;
+    /// worker.pause_validator(&nodes[3], 20).await?;
+    /// ```
+    pub async fn pause_validator(&self, node: &SandboxNode, blocks: u64) -> Result<()> {
+        if node.rpc_addr() == self.rpc_addr() {
+            return Err(SandboxErrorCode::RunFailure.message(format!(
+                "cannot pause {}, since it is the node the worker talks to",
+                node.account_id()
+            )));
+        }
+
+        let target = self.view_block().await?.height() + blocks;
+        node.stop().await?;
+        let waited = self.wait_for_height(target).await.map_err(|err| {
+            SandboxErrorCode::RunFailure.full(
+                format!("chain stalled while {} was paused", node.account_id()),
+                err,
+            )
+        });
+
+        // Bring the node back up, even if the chain stalled without it.
+        node.start().await?;
+        waited
+    }
+
+    /// Poll the latest block until the chain reaches `height`, failing once no new block
+    /// came in for [`STALLED_CHAIN_TIMEOUT`].
+    async fn wait_for_height(&self, height: BlockHeight) -> Result<()> {
+        let mut latest = self.view_block().await?.height();
+        let mut last_progress = std::time::Instant::now();
+        while latest < height {
+            if last_progress.elapsed() > STALLED_CHAIN_TIMEOUT {
+                return Err(SandboxErrorCode::RunFailure.message(format!(
+                    "no new blocks for {STALLED_CHAIN_TIMEOUT:?} at height {latest}"
+                )));
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            let current = self.view_block().await?.height();
+            if current > latest {
+                latest = current;
+                last_progress = std::time::Instant::now();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Congest the sandbox by queueing up receipts burning `gas` in total, sent out right
+    /// away without waiting on them. Chunks can only fit so much gas, as set through
+    /// `gas_limit` on [`sandbox`](crate::sandbox), so the receipts take up the chunks of a
+    /// number of blocks to come. Receipts sent after them get delayed until the congestion
+    /// clears, which lets tests cover how clients and contracts cope with calls taking a
+    /// while to execute, such as callbacks running blocks later than usual or timeouts
+    /// kicking in. The receipts get executed by the `congestion` subaccount of the root
+    /// account, which lands on the shard of the root account on sandboxes running more
+    /// than one shard.
+    /// ```ignore
+    /// let worker = near_workspaces::sandbox().gas_limit(Gas::from_tgas(300)).await?;
+    /// // Takes up the chunks of the next ten blocks or so.
+    /// worker.congest(Gas::from_tgas(3000)).await?;
+    /// ```
+    pub async fn congest(&self, gas: Gas) -> Result<()> {
+        let root = self.root_account()?;
+        let burner = AccountId::try_from(format!("congestion.{}", root.id()))
+            .map_err(|e| ErrorKind::DataConversion.custom(e))?;
+        self.patch(&burner)
+            .account(AccountDetailsPatch::default().balance(CONGESTION_ACCOUNT_BALANCE))
+            .code(CONGESTION_WASM)
+            .transact()
+            .await?;
+
+        let max_gas = crate::operations::MAX_GAS.as_gas();
+        let calls = (gas.as_gas() + max_gas - 1) / max_gas;
+        for _ in 0..calls {
+            root.call(&burner, "burn")
+                .max_gas()
+                .transact_async()
+                .await?;
+        }
+        tracing::debug!(target: "workspaces", "congested sandbox with {calls} calls into {burner}");
+
+        Ok(())
+    }
+
+    /// Grab the resources the sandbox takes up at the moment: the latest block height, how
+    /// full the chunks of the latest block are, the size of the state on disk and the
+    /// memory of the node process. Polling this over a long running test, such as a fuzzing
+    /// run, catches the state of the contract under test blowing up. See [`SandboxStats`]
+    /// for which of these are available for which kind of sandbox.
+    pub async fn stats(&self) -> Result<SandboxStats> {
+        let block = self.view_block().await?;
+        let (used, limit) = block.chunks().iter().fold((0, 0), |(used, limit), chunk| {
+            (
+                used + chunk.gas_used.as_gas(),
+                limit + chunk.gas_limit.as_gas(),
+            )
+        });
+        let state_size = match self.home_dir() {
+            Some(home_dir) => Some(
+                crate::network::stats::dir_size(&home_dir.join("data"))
+                    .map_err(|e| ErrorKind::Io.full("failed to size the sandbox state", e))?,
+            ),
+            None => None,
+        };
+        let memory = self
+            .workspace
+            .server
+            .pid()
+            .and_then(crate::network::stats::resident_memory);
+
+        Ok(SandboxStats {
+            block_height: block.height(),
+            chunk_gas_used: Gas::from_gas(used),
+            chunk_gas_limit: Gas::from_gas(limit),
+            state_size,
+            memory,
+        })
+    }
+
+    /// Home directory of the node, for sandboxes spawned by workspaces or attached to
+    /// along with their home directory.
+    pub fn home_dir(&self) -> Option<&std::path::Path> {
+        self.workspace.home_dir()
+    }
+
+    /// Contents of the `config.json` the node runs with, out of its home directory.
+    pub fn config_json(&self) -> Result<serde_json::Value> {
+        self.workspace.read_home_file("config.json")
+    }
+
+    /// Contents of the `genesis.json` the chain was started from, out of the home
+    /// directory of the node.
+    pub fn genesis_json(&self) -> Result<serde_json::Value> {
+        self.workspace.read_home_file("genesis.json")
+    }
+
+    /// Handle to the log file of the node, for sandboxes spawned locally by workspaces.
+    /// ```ignore
+    /// if outcome.is_failure() {
+    ///     eprintln!("{}", worker.node_log()?.tail(50)?.join("\n"));
+    /// }
+    /// ```
+    pub fn node_log(&self) -> Result<NodeLog> {
+        self.workspace.node_log()
+    }
+
+    /// Take the lines the node logged since the last time they were taken, or since it
+    /// started. Useful for attaching what the node had to say to the failure of a test:
+    /// ```ignore
+    /// let outcome = contract.call("migrate").transact().await?;
+    /// assert!(outcome.is_success(), "{:#?}", worker.take_node_logs()?);
+    /// ```
+    /// Lines also get forwarded to `tracing` under the `neard` target as the node logs
+    /// them, so that tests capturing `tracing` events, such as the ones using `test-log`,
+    /// show them along with their own output. They can be filtered like any other target,
+    /// such as with `RUST_LOG=neard=warn`. Only sandboxes spawned locally have their logs
+    /// captured.
+    pub fn take_node_logs(&self) -> Result<Vec<String>> {
+        self.workspace.take_node_logs()
+    }
+
     /// The port being used by RPC
     pub fn rpc_port(&self) -> Option<u16> {
         self.workspace.server.rpc_port()
     }
 
+    /// The port the node listens on for other nodes, if it was spawned by workspaces.
+    pub fn net_port(&self) -> Option<u16> {
+        self.workspace.server.net_port()
+    }
+
     /// Get the address the client is using to connect to the RPC of the network.
     pub fn rpc_addr(&self) -> String {
         self.workspace.server.rpc_addr()
@@ -1,13 +1,26 @@
+mod fork;
+mod gas_watchdog;
 mod impls;
+mod subaccounts;
+mod top_up;
 
 use std::fmt;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use crate::network::builder::NetworkBuilder;
-use crate::network::{Betanet, Custom, Mainnet, Sandbox, Testnet};
-use crate::types::GasHook;
+use crate::network::config::HomeDir;
+use crate::network::{Betanet, Custom, Mainnet, Namespace, Sandbox, Testnet, ValidatorKey};
+use crate::types::{BlockHeight, GasHook};
 use crate::{Network, Result};
 
+pub(crate) use self::fork::{fork_on_miss, Fork};
+use self::gas_watchdog::GasWatchdog;
+pub(crate) use self::gas_watchdog::{attached_gas, check_gas};
+use self::subaccounts::Subaccounts;
+pub(crate) use self::top_up::top_up_signer;
+use self::top_up::TopUpPolicy;
+
 /// The `Worker` type allows us to interact with any NEAR related networks, such
 /// as mainnet and testnet. This controls where the environment the worker is
 /// running on top of it. Refer to this for all network related actions such as
@@ -15,6 +28,10 @@ use crate::{Network, Result};
 pub struct Worker<T: ?Sized> {
     pub(crate) workspace: Arc<T>,
     pub(crate) tx_callbacks: Vec<GasHook>,
+    pub(crate) top_up: Option<Arc<TopUpPolicy>>,
+    pub(crate) gas_watchdog: Option<Arc<GasWatchdog>>,
+    pub(crate) fork: Option<Arc<Fork>>,
+    pub(crate) subaccounts: Arc<Subaccounts>,
 }
 
 impl<T> Worker<T>
@@ -25,6 +42,10 @@ where
         Self {
             workspace: Arc::new(network),
             tx_callbacks: vec![],
+            top_up: None,
+            gas_watchdog: None,
+            fork: None,
+            subaccounts: Arc::default(),
         }
     }
 }
@@ -34,6 +55,10 @@ impl<T: Network + 'static> Worker<T> {
         Worker {
             workspace: self.workspace,
             tx_callbacks: self.tx_callbacks,
+            top_up: self.top_up,
+            gas_watchdog: self.gas_watchdog,
+            fork: self.fork,
+            subaccounts: self.subaccounts,
         }
     }
 }
@@ -51,6 +76,22 @@ pub fn sandbox<'a>() -> NetworkBuilder<'a, Sandbox> {
     NetworkBuilder::new("sandbox")
 }
 
+/// Spin up a new sandbox instance inside of a Docker container instead of running the
+/// sandbox binary on the host, and grab a [`Worker`] that interacts with it. Useful on
+/// machines without a prebuilt sandbox binary, such as ARM ones. Runs the
+/// `nearprotocol/sandbox:<version>` image by default, where `<version>` is the sandbox
+/// version this crate ships with, or the one picked with the `version` builder method. The
+/// image can be swapped out with the `docker_image` builder method, and images given
+/// without a tag get pinned to the sandbox version the same way.
+/// ```ignore
+/// let worker = near_workspaces::sandbox_docker()
+///     .docker_image("my-registry/neard-sandbox:1.38.0")
+///     .await?;
+/// ```
+pub fn sandbox_docker<'a>() -> NetworkBuilder<'a, Sandbox> {
+    sandbox().docker_image(crate::network::server::DEFAULT_DOCKER_IMAGE)
+}
+
 /// Spin up a new sandbox instance, and grab a [`Worker`] that interacts with it.
 pub async fn sandbox_with_version<'a>(version: &str) -> Result<Worker<Sandbox>> {
     let network_builder = NetworkBuilder::new("sandbox");
@@ -58,6 +99,94 @@ pub async fn sandbox_with_version<'a>(version: &str) -> Result<Worker<Sandbox>>
     Ok(Worker::new(network))
 }
 
+/// Spin up a local network of `validators` sandbox nodes, and grab a [`Worker`] that
+/// interacts with the first of them along with handles to each of the nodes. Handy for
+/// testing validator rotation or missed chunks by stopping nodes. The cluster runs for as
+/// long as the node handles are around. State patched through the worker, such as with
+/// `patch_state`, gets patched into every node. Other sandbox specific calls such as
+/// `fast_forward` only apply to the node the worker talks to, which makes the nodes
+/// disagree on the chain when used.
+/// ```ignore
+/// let (worker, nodes) = near_workspaces::sandbox_cluster(4).await?;
+/// nodes[3].stop().await?;
+/// // The rest of the validators keep the chain going.
+/// let block = worker.view_block().await?;
+/// ```
+pub async fn sandbox_cluster(
+    validators: usize,
+) -> Result<(Worker<Sandbox>, Vec<crate::network::SandboxNode>)> {
+    crate::network::cluster::spawn_cluster(
+        near_sandbox_utils::DEFAULT_NEAR_SANDBOX_VERSION,
+        validators,
+    )
+    .await
+}
+
+/// Spin up a local network of `validators` sandbox nodes running the `neard-sandbox`
+/// binary of version `version`. See [`sandbox_cluster`] for more details.
+pub async fn sandbox_cluster_with_version(
+    version: &str,
+    validators: usize,
+) -> Result<(Worker<Sandbox>, Vec<crate::network::SandboxNode>)> {
+    crate::network::cluster::spawn_cluster(version, validators).await
+}
+
+/// Spin up a sandbox forked off of the chain behind `rpc_url`, such as mainnet, as it was at
+/// `block_height`. Rather than importing the whole chain up front, accounts are pulled in
+/// along with their code and data the first time a transaction is sent to them or they are
+/// viewed, which makes testing against protocols with huge state feasible. Accounts only
+/// reached through cross-contract calls are not known ahead of the call, so they have to be
+/// pulled in up front with [`Worker::fork_account`]. Forked accounts can be signed for with
+/// the same key dev accounts get. Note that RPC nodes only hold the recent history of the
+/// chain and refuse to hand out more than 50kB of the state of a contract, so forking off of
+/// older heights or pulling in contracts with more state requires an archival node.
+/// ```ignore
+/// let height = near_workspaces::mainnet().await?.view_block().await?.height();
+/// let worker = near_workspaces::sandbox_fork(near_workspaces::network::mainnet::RPC_URL, height).await?;
+/// ```
+pub async fn sandbox_fork(rpc_url: &str, block_height: BlockHeight) -> Result<Worker<Sandbox>> {
+    let source = custom(rpc_url).await?.coerce();
+    let mut worker = sandbox().await?;
+    worker.fork = Some(Arc::new(Fork::new(source, block_height)));
+    Ok(worker)
+}
+
+/// Spin up a sandbox instance out of the home directory at `path`, such as one kept with
+/// `keep_home_dir`, picking the chain back up where it was left off. Settings that shape the
+/// chain, such as genesis overrides, have no effect since the chain already exists.
+pub fn sandbox_from_home<'a>(path: impl Into<PathBuf>) -> NetworkBuilder<'a, Sandbox> {
+    let mut builder = NetworkBuilder::new("sandbox");
+    builder.sandbox_config.home = Some(HomeDir::Reopen(path.into()));
+    builder
+}
+
+/// Attach to a sandbox node that is already running, such as one pre-started by CI or
+/// running in a container, and grab a [`Worker`] that interacts with it. `rpc_addr` is
+/// the address of its RPC service, where `http://` is assumed if no scheme is given, and
+/// `home_dir` is its home directory which the validator key is read out of. The node is
+/// left running once the worker is dropped.
+pub async fn sandbox_attach(
+    rpc_addr: &str,
+    home_dir: impl AsRef<std::path::Path>,
+) -> Result<Worker<Sandbox>> {
+    let rpc_addr = if rpc_addr.contains("://") {
+        rpc_addr.to_string()
+    } else {
+        format!("http://{rpc_addr}")
+    };
+    sandbox()
+        .rpc_addr(&rpc_addr)
+        .validator_key(ValidatorKey::HomeDir(home_dir.as_ref().to_path_buf()))
+        .await
+}
+
+/// Grab a fresh [`Namespace`] on a sandbox instance shared by the whole test binary,
+/// which is spun up on first use. This is much cheaper than spinning up a sandbox per
+/// test, at the cost of tests only being isolated by their accounts.
+pub async fn shared_sandbox() -> Result<Namespace> {
+    crate::network::shared::shared_namespace().await
+}
+
 /// Connect to the [testnet](https://explorer.testnet.near.org/) network, and grab
 /// a [`Worker`] that can interact with it.
 pub fn testnet<'a>() -> NetworkBuilder<'a, Testnet> {
@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::result::Result;
+use crate::types::{AccountId, InMemorySigner};
+
+/// Sub-accounts created through workspaces, along with the signers they were created
+/// with, which is what [`Account::delete_tree`](crate::Account::delete_tree) deletes. The
+/// signers, secret keys included, are held onto until the sub-account gets deleted or the
+/// last clone of the worker is dropped.
+#[derive(Default)]
+pub(crate) struct Subaccounts {
+    signers: Mutex<HashMap<AccountId, InMemorySigner>>,
+}
+
+impl Subaccounts {
+    /// Keep track of the sub-account `signer` signs for.
+    pub(crate) fn record(&self, signer: &InMemorySigner) -> Result<()> {
+        self.signers
+            .lock()?
+            .insert(signer.account_id.clone(), signer.clone());
+        Ok(())
+    }
+
+    /// Stop keeping track of `id`, such as once it got deleted.
+    pub(crate) fn remove(&self, id: &AccountId) -> Result<()> {
+        self.signers.lock()?.remove(id);
+        Ok(())
+    }
+
+    /// Signers of the recorded sub-accounts anywhere under `id`, with the deepest ones
+    /// first so that these can be deleted before their parents.
+    pub(crate) fn descendants(&self, id: &AccountId) -> Result<Vec<InMemorySigner>> {
+        let suffix = format!(".{id}");
+        let mut descendants = self
+            .signers
+            .lock()?
+            .values()
+            .filter(|signer| signer.account_id.as_str().ends_with(&suffix))
+            .cloned()
+            .collect::<Vec<_>>();
+        descendants.sort_by_key(|signer| std::cmp::Reverse(signer.account_id.as_str().len()));
+        Ok(descendants)
+    }
+}
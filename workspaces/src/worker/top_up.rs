@@ -0,0 +1,103 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use crate::network::NetworkClient;
+use crate::result::{ExecutionFinalResult, Result};
+use crate::types::{AccountId, InMemorySigner, NearToken};
+use crate::{Account, Worker};
+
+/// Policy topping up the balance of dev accounts out of a funder account, as configured
+/// through [`Worker::auto_top_up`].
+pub(crate) struct TopUpPolicy {
+    funder: InMemorySigner,
+    threshold: NearToken,
+    amount: NearToken,
+    accounts: Mutex<HashSet<AccountId>>,
+}
+
+impl TopUpPolicy {
+    /// Start keeping an eye on the balance of `id`.
+    pub(crate) fn track(&self, id: &AccountId) -> Result<()> {
+        self.accounts.lock()?.insert(id.clone());
+        Ok(())
+    }
+
+    /// Transfer to `id` out of the funder account if `id` is being tracked and its
+    /// balance dropped below the threshold.
+    async fn top_up<T>(&self, worker: &Worker<T>, id: &AccountId) -> Result<()>
+    where
+        T: NetworkClient + ?Sized,
+    {
+        if !self.accounts.lock()?.contains(id) {
+            return Ok(());
+        }
+
+        let balance = worker.view_account(id).await?.balance;
+        if balance >= self.threshold {
+            return Ok(());
+        }
+
+        tracing::debug!(
+            target: "workspaces",
+            "topping up {} with {} from {}, its balance dropped to {}",
+            id,
+            self.amount,
+            self.funder.account_id,
+            balance
+        );
+        // Transfer through the client directly, which skips the top up hook of the funder.
+        let outcome = worker
+            .client()
+            .transfer_near(&self.funder, id, self.amount)
+            .await?;
+        ExecutionFinalResult::from_view(outcome).into_result()?;
+        Ok(())
+    }
+}
+
+impl<T: ?Sized> Worker<T> {
+    /// Automatically top up the balance of dev accounts with `amount` out of `funder`,
+    /// whenever their balance drops below `threshold` after sending a transaction. This
+    /// prevents long running tests from failing midway with `NotEnoughBalance` errors.
+    /// Only accounts created through `dev_create_account` and `dev_deploy` after calling
+    /// this are kept topped up.
+    ///
+    /// The balance is checked after every transaction signed by one of these accounts,
+    /// whether sent through `transact`, `transact_async`, [`Worker::submit_all`],
+    /// `transfer_near`, `deploy` or `create_subaccount`. Deleting the account is the only
+    /// exception, since there is nothing left to top up. Each check costs an extra
+    /// `view_account` request, which is not made for the accounts that are not tracked.
+    /// On sandbox, the root account makes for a good funder:
+    /// ```ignore
+    /// let mut worker = near_workspaces::sandbox().await?;
+    /// let root = worker.root_account()?;
+    /// worker.auto_top_up(&root, NearToken::from_near(5), NearToken::from_near(50));
+    /// ```
+    pub fn auto_top_up(&mut self, funder: &Account, threshold: NearToken, amount: NearToken) {
+        self.top_up = Some(Arc::new(TopUpPolicy {
+            funder: funder.signer().clone(),
+            threshold,
+            amount,
+            accounts: Mutex::new(HashSet::new()),
+        }));
+    }
+}
+
+/// Top up the signer of a transaction that just went through, if the policy of `worker`
+/// says so. The transaction has been executed either way, so failing to top up is not
+/// treated as an error of the transaction.
+pub(crate) async fn top_up_signer<T>(worker: &Worker<T>, signer_id: &AccountId)
+where
+    T: NetworkClient + ?Sized,
+{
+    if let Some(policy) = &worker.top_up {
+        if let Err(err) = policy.top_up(worker, signer_id).await {
+            tracing::warn!(
+                target: "workspaces",
+                "failed to top up the balance of {}: {}",
+                signer_id,
+                err
+            );
+        }
+    }
+}
@@ -0,0 +1,74 @@
+#![cfg(all(feature = "unstable", feature = "abi"))]
+#![recursion_limit = "256"]
+use serde_json::json;
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_call_typed() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let compiled = near_workspaces::compile_project_with(
+        "./tests/test-contracts/status-message",
+        near_workspaces::CompileOptions::new().embed_abi(),
+    )
+    .await?;
+    let contract = worker.dev_deploy(&compiled.wasm).await?;
+
+    let abi = contract.abi().await?;
+    assert!(abi.body.functions.iter().any(|f| f.name == "set_status"));
+
+    // The build hands the ABI it embedded back as well.
+    assert!(compiled
+        .abi_path
+        .as_ref()
+        .map_or(false, |path| path.exists()));
+    let functions = compiled
+        .abi
+        .as_ref()
+        .and_then(|abi| abi["body"]["functions"].as_array())
+        .expect("ABI of the build has functions");
+    assert!(functions.iter().any(|f| f["name"] == "set_status"));
+
+    contract
+        .call_typed::<_, ()>("set_status", json!({ "message": "foo" }))
+        .await?;
+    let status: Option<String> = contract
+        .call_typed("get_status", json!({ "account_id": contract.id() }))
+        .await?;
+    assert_eq!(status.as_deref(), Some("foo"));
+
+    // Arguments of the wrong type are caught before calling into the contract:
+    let err = contract
+        .call_typed::<_, ()>("set_status", json!({ "message": 42 }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("failed to match the ABI"));
+
+    // So are missing arguments:
+    let err = contract
+        .call_typed::<_, Option<String>>("get_status", json!({}))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("account_id"));
+
+    // And return types that do not match:
+    assert!(contract
+        .call_typed::<_, u64>("get_status", json!({ "account_id": contract.id() }))
+        .await
+        .is_err());
+
+    assert!(contract
+        .call_typed::<_, ()>("no_such_function", ())
+        .await
+        .is_err());
+
+    // The ABI can be fetched once and reused across calls:
+    contract
+        .call_typed_with_abi::<_, ()>(&abi, "set_status", json!({ "message": "bar" }))
+        .await?;
+    let status: Option<String> = contract
+        .call_typed_with_abi(&abi, "get_status", json!({ "account_id": contract.id() }))
+        .await?;
+    assert_eq!(status.as_deref(), Some("bar"));
+
+    Ok(())
+}
@@ -1,10 +1,19 @@
 #![recursion_limit = "256"]
+use async_trait::async_trait;
 use near_token::NearToken;
+use near_workspaces::types::{
+    AccessKey, AccessKeyPermission, FunctionCallKey, KeyType, PublicKey, SecretKey, Signature,
+    Signer,
+};
+use near_workspaces::Account;
 use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
 use test_log::test;
 
 use std::fs::File;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 #[test(tokio::test)]
 async fn test_subaccount_creation() -> anyhow::Result<()> {
@@ -35,6 +44,120 @@ async fn test_subaccount_creation() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test(tokio::test)]
+async fn test_account_from_near_cli_file() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let account = worker.dev_create_account().await?;
+
+    // near-cli names the secret key `private_key` in the keyfiles it stores.
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join(format!("{}.json", account.id()));
+    let keyfile = serde_json::json!({
+        "account_id": account.id(),
+        "public_key": account.secret_key().unwrap().public_key(),
+        "private_key": account.secret_key().unwrap(),
+    });
+    std::fs::write(&path, serde_json::to_vec(&keyfile)?)?;
+
+    let loaded = Account::from_file(&path, &worker)?;
+    assert_eq!(loaded.id(), account.id());
+    assert_eq!(loaded.secret_key(), account.secret_key());
+    loaded
+        .transfer_near(worker.root_account()?.id(), NearToken::from_near(1))
+        .await?
+        .into_result()?;
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_named_tla_creation() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+
+    // Names only the registrar or the `near` account could create otherwise.
+    for id in ["alice", "alice.near"] {
+        let sk = SecretKey::from_random(KeyType::ED25519);
+        let account = worker.create_tla(id.parse()?, sk).await?.into_result()?;
+        assert_eq!(account.id().as_str(), id);
+        assert!(account.view_account().await?.balance > NearToken::from_near(0));
+        account
+            .transfer_near(worker.root_account()?.id(), NearToken::from_near(1))
+            .await?
+            .into_result()?;
+    }
+
+    let sk = SecretKey::from_random(KeyType::ED25519);
+    let contract = worker
+        .create_tla_and_deploy(
+            "status".parse()?,
+            sk.clone(),
+            include_bytes!("../../examples/res/status_message.wasm"),
+        )
+        .await?
+        .into_result()?;
+    assert_eq!(contract.id().as_str(), "status");
+    contract
+        .call("set_status")
+        .args_json(serde_json::json!({ "message": "hello" }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // Accounts that exist already are left alone.
+    assert!(worker.create_tla("alice".parse()?, sk).await.is_err());
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_lazy_subaccount() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let account = worker.dev_create_account().await?;
+
+    // Reference the whole tree up front, without creating anything.
+    let agent = account.subaccount("agent_1")?;
+    let nested = agent.subaccount("inner")?;
+    assert_eq!(agent.id().as_str(), format!("agent_1.{}", account.id()));
+    assert_eq!(nested.id().as_str(), format!("inner.{}", agent.id()));
+    assert!(!agent.exists().await?);
+    assert!(account.subaccount("Invalid Name").is_err());
+
+    // Nested subaccounts can only be created once their parent has been.
+    assert!(nested.create().transact().await.is_err());
+
+    let sk = SecretKey::from_seed(KeyType::ED25519, "agent key");
+    let agent = agent.with_key(sk.clone());
+    let created = agent
+        .create()
+        .initial_balance(NearToken::from_near(5))
+        .transact()
+        .await?
+        .into_result()?;
+    assert_eq!(created.id(), agent.id());
+    assert_eq!(created.secret_key(), Some(&sk));
+    assert!(agent.exists().await?);
+
+    let nested = agent.subaccount("inner")?;
+    nested
+        .create()
+        .initial_balance(NearToken::from_near(1))
+        .transact()
+        .await?
+        .into_result()?;
+    assert_eq!(
+        nested.view_account().await?.balance,
+        NearToken::from_near(1)
+    );
+
+    // Transactions can be signed with the key of the handle.
+    nested
+        .as_account()
+        .transfer_near(account.id(), NearToken::from_millinear(1))
+        .await?
+        .into_result()?;
+
+    Ok(())
+}
+
 #[test(tokio::test)]
 async fn test_transfer_near() -> anyhow::Result<()> {
     const INITIAL_BALANCE: NearToken = NearToken::from_near(100);
@@ -65,6 +188,49 @@ async fn test_transfer_near() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test(tokio::test)]
+async fn test_auto_top_up() -> anyhow::Result<()> {
+    let mut worker = near_workspaces::sandbox().await?;
+    let root = worker.root_account()?;
+    worker.auto_top_up(&root, NearToken::from_near(50), NearToken::from_near(100));
+
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+
+    // alice drops below the threshold, and gets topped up right after.
+    alice
+        .transfer_near(bob.id(), NearToken::from_near(60))
+        .await?
+        .into_result()?;
+    assert!(alice.view_account().await?.balance > NearToken::from_near(100));
+
+    // The same goes for the other ways of sending transactions.
+    alice
+        .create_subaccount("sub")
+        .initial_balance(NearToken::from_near(100))
+        .transact()
+        .await?
+        .into_result()?;
+    assert!(alice.view_account().await?.balance > NearToken::from_near(100));
+    alice
+        .batch(bob.id())
+        .transfer(NearToken::from_near(100))
+        .transact_async()
+        .await?
+        .await?
+        .into_result()?;
+    assert!(alice.view_account().await?.balance > NearToken::from_near(100));
+
+    // Staying above the threshold does not trigger a top up.
+    let balance = bob.view_account().await?.balance;
+    bob.transfer_near(alice.id(), NearToken::from_near(1))
+        .await?
+        .into_result()?;
+    assert!(bob.view_account().await?.balance < balance);
+
+    Ok(())
+}
+
 #[test(tokio::test)]
 async fn test_delete_account() -> anyhow::Result<()> {
     let worker = near_workspaces::sandbox().await?;
@@ -94,3 +260,508 @@ async fn test_delete_account() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test(tokio::test)]
+async fn test_delete_contract() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let beneficiary = worker.dev_create_account().await?;
+    let contract = worker
+        .dev_deploy(include_bytes!("../../examples/res/status_message.wasm"))
+        .await?;
+    let caller = worker.dev_create_account().await?;
+
+    let before = beneficiary.view_account().await?.balance;
+    let contract_id = contract.id().clone();
+    contract
+        .delete_contract(beneficiary.id())
+        .await?
+        .into_result()?;
+    assert!(beneficiary.view_account().await?.balance > before);
+
+    // Calls into the deleted contract fail, since its account does not exist anymore.
+    let err = caller
+        .call(&contract_id, "set_status")
+        .args_json(("hello",))
+        .transact()
+        .await?
+        .into_result()
+        .unwrap_err();
+    assert!(format!("{err:?}").contains("AccountDoesNotExist"));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_signer_key_selection() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let contract = worker
+        .dev_deploy(include_bytes!("../../examples/res/status_message.wasm"))
+        .await?;
+    let mut account = worker.dev_create_account().await?;
+
+    let fc_key = SecretKey::from_random(KeyType::ED25519);
+    account
+        .batch(account.id())
+        .add_key(
+            fc_key.public_key(),
+            AccessKey::function_call_access(contract.id(), &["set_status"], None),
+        )
+        .transact()
+        .await?
+        .into_result()?;
+    account.add_signer_key(fc_key.clone());
+    assert_eq!(account.signer_keys().len(), 2);
+
+    // The function call key is allowed to call `set_status`:
+    account
+        .call(contract.id(), "set_status")
+        .args_json(("hello",))
+        .signer_key(&fc_key.public_key())
+        .transact()
+        .await?
+        .into_result()?;
+
+    // But not allowed to attach a deposit:
+    let result = account
+        .call(contract.id(), "set_status")
+        .args_json(("hello",))
+        .deposit(NearToken::from_yoctonear(1))
+        .signer_key(&fc_key.public_key())
+        .transact()
+        .await;
+    assert!(result.is_err());
+
+    // Selecting a key the account does not hold fails:
+    let unknown = SecretKey::from_random(KeyType::ED25519).public_key();
+    let result = account
+        .call(contract.id(), "set_status")
+        .args_json(("hello",))
+        .signer_key(&unknown)
+        .transact()
+        .await;
+    assert!(result.is_err());
+
+    // Even when the args are only set afterwards:
+    let result = account
+        .call(contract.id(), "set_status")
+        .signer_key(&unknown)
+        .args_json(("hello",))
+        .transact()
+        .await;
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_secp256k1_accounts() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let contract = worker
+        .dev_deploy(include_bytes!("../../examples/res/status_message.wasm"))
+        .await?;
+
+    let mut account = worker
+        .dev_create_account_with_key_type(KeyType::SECP256K1)
+        .await?;
+    assert_eq!(account.secret_key().unwrap().key_type(), KeyType::SECP256K1);
+    account
+        .call(contract.id(), "set_status")
+        .args_json(("secp256k1",))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // Subaccounts and access keys can be secp256k1 keys as well:
+    let sk = SecretKey::from_seed(KeyType::SECP256K1, "sub key");
+    let sub = account
+        .create_subaccount("sub")
+        .keys(sk.clone())
+        .transact()
+        .await?
+        .into_result()?;
+    assert_eq!(sub.secret_key(), Some(&sk));
+    sub.transfer_near(account.id(), NearToken::from_near(1))
+        .await?
+        .into_result()?;
+
+    let fc_key = SecretKey::from_random(KeyType::SECP256K1);
+    account
+        .batch(account.id())
+        .add_key(
+            fc_key.public_key(),
+            AccessKey::function_call_access(contract.id(), &["set_status"], None),
+        )
+        .transact()
+        .await?
+        .into_result()?;
+    account.add_signer_key(fc_key.clone());
+    account
+        .call(contract.id(), "set_status")
+        .args_json(("function call key",))
+        .signer_key(&fc_key.public_key())
+        .transact()
+        .await?
+        .into_result()?;
+
+    Ok(())
+}
+
+/// Signs with a key it keeps to itself, as a remote signing service would.
+struct RemoteSigner {
+    key: SecretKey,
+    public_key: PublicKey,
+    signed: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+    fn public_key(&self) -> PublicKey {
+        self.public_key.clone()
+    }
+
+    async fn sign(&self, payload: &[u8]) -> near_workspaces::Result<Signature> {
+        self.signed.fetch_add(1, Ordering::SeqCst);
+        Ok(self.key.sign(&Sha256::digest(payload)))
+    }
+}
+
+#[test(tokio::test)]
+async fn test_external_signer() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let owner = worker.dev_create_account().await?;
+    let contract = worker
+        .dev_deploy(include_bytes!("../../examples/res/status_message.wasm"))
+        .await?;
+
+    let signed = Arc::new(AtomicUsize::new(0));
+    let account = Account::from_signer(
+        owner.id().clone(),
+        RemoteSigner {
+            key: owner.secret_key().unwrap().clone(),
+            public_key: owner.public_key(),
+            signed: signed.clone(),
+        },
+        &worker,
+    );
+    assert_eq!(account.public_key(), owner.public_key());
+    assert!(account.secret_key().is_none());
+    account
+        .call(contract.id(), "set_status")
+        .args_json(("signed remotely",))
+        .transact()
+        .await?
+        .into_result()?;
+    assert_eq!(signed.load(Ordering::SeqCst), 1);
+
+    // Signatures not made by the key the signer claims to sign with are caught before
+    // the transaction gets sent:
+    let account = Account::from_signer(
+        owner.id().clone(),
+        RemoteSigner {
+            key: SecretKey::from_random(KeyType::ED25519),
+            public_key: owner.public_key(),
+            signed,
+        },
+        &worker,
+    );
+    let result = account
+        .transfer_near(contract.id(), NearToken::from_near(1))
+        .await;
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+#[cfg(feature = "ledger")]
+#[test(tokio::test)]
+async fn test_ledger_signer_invalid_path() -> anyhow::Result<()> {
+    // Invalid paths are caught before reaching out to a device, so this runs without one.
+    let err = near_workspaces::types::LedgerSigner::new("not/a/path")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("invalid HD path"));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_access_key_lifecycle() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let contract = worker
+        .dev_deploy(include_bytes!("../../examples/res/status_message.wasm"))
+        .await?;
+    let mut account = worker.dev_create_account().await?;
+
+    let full_key = SecretKey::from_random(KeyType::ED25519).public_key();
+    account
+        .add_key(full_key.clone(), AccessKeyPermission::FullAccess)
+        .await?
+        .into_result()?;
+    assert!(account.view_access_key(&full_key).await.is_ok());
+    assert_eq!(account.signer_keys().len(), 1);
+
+    let fc_key = SecretKey::from_random(KeyType::ED25519);
+    let permission = AccessKey::function_call_access(contract.id(), &["set_status"], None);
+    account
+        .add_signing_key(fc_key.clone(), permission.permission)
+        .await?
+        .into_result()?;
+    assert_eq!(account.signer_keys().len(), 2);
+    account
+        .call(contract.id(), "set_status")
+        .args_json(("hello",))
+        .signer_key(&fc_key.public_key())
+        .transact()
+        .await?
+        .into_result()?;
+
+    for pk in [full_key, fc_key.public_key()] {
+        account.delete_key(&pk).await?.into_result()?;
+        assert!(account.view_access_key(&pk).await.is_err());
+    }
+    assert_eq!(account.signer_keys(), vec![account.public_key()]);
+
+    // The deleted key can no longer be selected to sign with:
+    let result = account
+        .call(contract.id(), "set_status")
+        .args_json(("hello",))
+        .signer_key(&fc_key.public_key())
+        .transact()
+        .await;
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_function_call_key_builder() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let contract = worker
+        .dev_deploy(include_bytes!("../../examples/res/status_message.wasm"))
+        .await?;
+    let account = worker.dev_create_account().await?;
+
+    // Keys can be attached when creating an account:
+    let sk = SecretKey::from_random(KeyType::ED25519);
+    let fc_key = SecretKey::from_random(KeyType::ED25519);
+    let mut sub = account
+        .create_subaccount("sub")
+        .keys(sk)
+        .add_key(
+            fc_key.public_key(),
+            FunctionCallKey::new(contract.id())
+                .methods(["set_status"])
+                .allowance(NearToken::from_near(1)),
+        )
+        .initial_balance(NearToken::from_near(5))
+        .transact()
+        .await?
+        .into_result()?;
+    sub.add_signer_key(fc_key.clone());
+    sub.call(contract.id(), "set_status")
+        .args_json(("hello",))
+        .signer_key(&fc_key.public_key())
+        .transact()
+        .await?
+        .into_result()?;
+
+    // Methods outside of the ones allowed cannot be called:
+    let result = sub
+        .call(contract.id(), "get_status")
+        .args_json((sub.id(),))
+        .signer_key(&fc_key.public_key())
+        .transact()
+        .await;
+    assert!(result.is_err());
+
+    // And neither can calls whose gas exceeds the allowance:
+    let poor_key = SecretKey::from_random(KeyType::ED25519);
+    sub.add_signing_key(
+        poor_key.clone(),
+        FunctionCallKey::new(contract.id()).allowance(NearToken::from_yoctonear(1)),
+    )
+    .await?
+    .into_result()?;
+    let result = sub
+        .call(contract.id(), "set_status")
+        .args_json(("hello",))
+        .signer_key(&poor_key.public_key())
+        .transact()
+        .await;
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_implicit_account() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let funder = worker.dev_create_account().await?;
+
+    let sk = SecretKey::from_random(KeyType::ED25519);
+    let implicit = funder
+        .create_implicit_account(sk.clone(), NearToken::from_near(5))
+        .await?
+        .into_result()?;
+    assert_eq!(implicit.id(), &sk.public_key().implicit_account_id()?);
+    assert_eq!(implicit.id().as_str().len(), 64);
+    assert_eq!(
+        implicit.view_account().await?.balance,
+        NearToken::from_near(5)
+    );
+
+    // The implicit account holds the key it got derived from:
+    implicit
+        .transfer_near(funder.id(), NearToken::from_near(1))
+        .await?
+        .into_result()?;
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_view_access_keys_of_any_account() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let contract = worker
+        .dev_deploy(include_bytes!("../../examples/res/status_message.wasm"))
+        .await?;
+    let mut account = worker.dev_create_account().await?;
+
+    let fc_key = SecretKey::from_random(KeyType::ED25519);
+    let permission = FunctionCallKey::new(contract.id()).methods(["set_status"]);
+    account
+        .add_signing_key(fc_key.clone(), permission.clone())
+        .await?
+        .into_result()?;
+    account
+        .call(contract.id(), "set_status")
+        .args_json(("hello",))
+        .signer_key(&fc_key.public_key())
+        .transact()
+        .await?
+        .into_result()?;
+
+    let mut keys = worker.view_access_keys(account.id()).await?;
+    keys.sort_by_key(|info| info.public_key != account.public_key());
+    assert_eq!(keys.len(), 2);
+    assert_eq!(keys[0].public_key, account.public_key());
+    assert_eq!(
+        keys[0].access_key.permission,
+        AccessKeyPermission::FullAccess
+    );
+    assert_eq!(keys[1].public_key, fc_key.public_key());
+    assert_eq!(keys[1].access_key.permission, permission.into());
+    assert!(keys[1].access_key.nonce > 0);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_rotate_key() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let mut account = worker.dev_create_account().await?;
+
+    let old_key = account.public_key();
+    account.rotate_key().await?.into_result()?;
+    assert_ne!(account.public_key(), old_key);
+    assert!(account.view_access_key(&old_key).await.is_err());
+    assert_eq!(worker.view_access_keys(account.id()).await?.len(), 1);
+
+    // The account keeps working with the new key:
+    let sk = SecretKey::from_seed(KeyType::ED25519, "rotated");
+    account.rotate_key_to(sk.clone()).await?.into_result()?;
+    assert_eq!(account.secret_key(), Some(&sk));
+    account
+        .transfer_near(worker.root_account()?.id(), NearToken::from_near(1))
+        .await?
+        .into_result()?;
+
+    // Rotating to the key already in use fails as a whole, leaving the account usable:
+    assert!(account.rotate_key_to(sk.clone()).await?.is_failure());
+    assert_eq!(account.secret_key(), Some(&sk));
+    assert!(account.view_access_key(&sk.public_key()).await.is_ok());
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_balance_breakdown() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let contract = worker
+        .dev_deploy(include_bytes!("../../examples/res/status_message.wasm"))
+        .await?;
+
+    let before = contract.balance_breakdown().await?;
+    let details = contract.view_account().await?;
+    assert_eq!(before.total, details.balance);
+    assert_eq!(before.staked, details.locked);
+    assert_eq!(before.storage_usage, details.storage_usage);
+    assert!(before.storage_locked > NearToken::from_yoctonear(0));
+    assert_eq!(
+        before.liquid.saturating_add(before.storage_locked),
+        before.total
+    );
+
+    // Storing more state locks more of the balance to pay for it:
+    contract
+        .call("set_status")
+        .args_json(("hello",))
+        .transact()
+        .await?
+        .into_result()?;
+    let after = contract.balance_breakdown().await?;
+    assert!(after.storage_usage > before.storage_usage);
+    assert!(after.storage_locked > before.storage_locked);
+    assert_eq!(
+        after.storage_locked,
+        after
+            .storage_cost_per_byte
+            .saturating_mul(after.storage_usage as u128)
+    );
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_delete_tree() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let beneficiary = worker.dev_create_account().await?;
+    let root = worker.dev_create_account().await?;
+
+    let a = root
+        .create_subaccount("a")
+        .initial_balance(NearToken::from_near(5))
+        .transact()
+        .await?
+        .into_result()?;
+    let b = a
+        .create_subaccount("b")
+        .initial_balance(NearToken::from_near(1))
+        .transact()
+        .await?
+        .into_result()?;
+    let c = root
+        .create_subaccount("c")
+        .initial_balance(NearToken::from_near(1))
+        .transact()
+        .await?
+        .into_result()?;
+    // Sub-accounts deleted by hand are skipped:
+    c.clone()
+        .delete_account(beneficiary.id())
+        .await?
+        .into_result()?;
+
+    let before = beneficiary.view_account().await?.balance;
+    let root_id = root.id().clone();
+    let outcomes = root.delete_tree(beneficiary.id()).await?;
+    assert_eq!(outcomes.len(), 3);
+    assert!(outcomes.iter().all(|outcome| outcome.is_success()));
+    assert!(beneficiary.view_account().await?.balance > before);
+
+    for id in [b.id(), a.id(), c.id(), &root_id] {
+        assert!(worker.view_account(id).await.is_err());
+    }
+
+    Ok(())
+}
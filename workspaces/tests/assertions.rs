@@ -0,0 +1,79 @@
+use near_workspaces::assertions::{format_gas, format_near, GasBudgets};
+use near_workspaces::types::{Gas, NearToken};
+use near_workspaces::{assert_gas_eq, assert_gas_under, assert_near_eq};
+
+#[test]
+fn test_format_near_and_gas() {
+    assert_eq!(format_near(NearToken::from_near(3)), "3 NEAR");
+    assert_eq!(
+        format_near(NearToken::from_yoctonear(3_859_999_999_999_999_999_999_000)),
+        "3 NEAR + 859999999999999999999000 yoctoNEAR"
+    );
+    assert_eq!(format_gas(Gas::from_tgas(30)), "30 Tgas");
+    assert_eq!(
+        format_gas(Gas::from_gas(12_000_000_345_678)),
+        "12 Tgas + 345678 gas"
+    );
+}
+
+#[test]
+fn test_assert_eq_passes() {
+    assert_near_eq!(NearToken::from_near(1), NearToken::from_millinear(1000));
+    assert_gas_eq!(Gas::from_tgas(1), Gas::from_gas(1_000_000_000_000), "gas");
+}
+
+#[test]
+#[should_panic(expected = "delta: +0 NEAR + 1000 yoctoNEAR")]
+fn test_assert_near_eq_fails() {
+    assert_near_eq!(
+        NearToken::from_yoctonear(3_860_000_000_000_000_000_000),
+        NearToken::from_yoctonear(3_859_999_999_999_999_999_000),
+    );
+}
+
+#[test]
+#[should_panic(expected = "delta: -5 Tgas")]
+fn test_assert_gas_eq_fails() {
+    assert_gas_eq!(Gas::from_tgas(25), Gas::from_tgas(30), "too little gas");
+}
+
+#[test]
+fn test_assert_gas_under() {
+    assert_gas_under!(Gas::from_tgas(10), Gas::from_tgas(10));
+    assert_gas_under!(&Gas::from_tgas(9), Gas::from_tgas(10), "within budget");
+}
+
+#[test]
+#[should_panic(expected = "over: +2 Tgas")]
+fn test_assert_gas_under_fails() {
+    assert_gas_under!(Gas::from_tgas(12), Gas::from_tgas(10));
+}
+
+#[tokio::test]
+async fn test_gas_budgets() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let contract = worker
+        .dev_deploy(include_bytes!("../../examples/res/status_message.wasm"))
+        .await?;
+
+    let outcome = contract
+        .call("set_status")
+        .args_json(("hello",))
+        .with_receipts()
+        .transact()
+        .await?;
+    assert_gas_under!(outcome, Gas::from_tgas(30));
+    GasBudgets::new()
+        .budget("set_status", Gas::from_tgas(10))
+        .budget("get_status", Gas::from_gas(1))
+        .assert_within(&outcome);
+
+    let result = std::panic::catch_unwind(|| {
+        GasBudgets::new()
+            .budget("set_status", Gas::from_gas(1))
+            .assert_within(&outcome)
+    });
+    assert!(result.is_err());
+
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use near_workspaces::types::{BalanceChecker, NearToken};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_balance_checker_transfer() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    worker.fast_forward(3).await?;
+
+    let checker = BalanceChecker::new(&worker, [alice.id(), bob.id()]).await?;
+    alice
+        .transfer_near(bob.id(), NearToken::from_near(1))
+        .await?
+        .into_result()?;
+
+    // Let the refunds settle and the transfer make it into a final block.
+    worker.fast_forward(3).await?;
+
+    let report = checker.check().await?;
+    assert!(report.burnt.as_yoctonear() > 0);
+    assert_eq!(
+        report.deltas[bob.id()],
+        NearToken::from_near(1).as_yoctonear() as i128
+    );
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_balance_checker_untracked_recipient() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    worker.fast_forward(3).await?;
+
+    let checker = BalanceChecker::new(&worker, [alice.id()]).await?;
+    alice
+        .transfer_near(bob.id(), NearToken::from_near(1))
+        .await?
+        .into_result()?;
+    worker.fast_forward(3).await?;
+
+    let report = checker.report().await?;
+    assert!(!report.is_conserved());
+    assert_eq!(
+        report.imbalance(),
+        NearToken::from_near(1).as_yoctonear() as i128
+    );
+    assert!(checker.check().await.is_err());
+
+    Ok(())
+}
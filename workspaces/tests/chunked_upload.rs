@@ -0,0 +1,95 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use near_workspaces::operations::Function;
+use serde_json::json;
+use test_log::test;
+
+/// Stage each chunk as the status of the contract, which only takes JSON arguments.
+fn status_args(_offset: usize, chunk: &[u8]) -> Vec<u8> {
+    serde_json::to_vec(&json!({ "message": String::from_utf8_lossy(chunk) })).unwrap()
+}
+
+#[test(tokio::test)]
+async fn test_upload_chunked() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let contract = worker
+        .dev_deploy(include_bytes!("../../examples/res/status_message.wasm"))
+        .await?;
+
+    let upload = contract
+        .upload_chunked("set_status", b"abcdefghij")
+        .chunk_size(4)
+        .chunk_args(status_args)
+        .apply(Function::new("set_status").args_json(json!({ "message": "applied" })));
+    let progress = upload.progress();
+    let outcomes = upload.transact().await?;
+
+    // Three chunks of at most 4 bytes, followed by the call applying them.
+    assert_eq!(outcomes.len(), 4);
+    assert!(outcomes.iter().all(|outcome| outcome.is_success()));
+    assert_eq!(progress.uploaded(), 10);
+    assert!(progress.is_complete());
+
+    let status: String = contract
+        .view("get_status")
+        .args_json(json!({ "account_id": contract.id() }))
+        .await?
+        .json()?;
+    assert_eq!(status, "applied");
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_upload_chunked_resume() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let contract = worker
+        .dev_deploy(include_bytes!("../../examples/res/status_message.wasm"))
+        .await?;
+    let payload = b"abcdefghij";
+
+    // Fail the second chunk once by handing the contract arguments it cannot parse.
+    let failed = Arc::new(AtomicBool::new(false));
+    let upload = contract
+        .upload_chunked("set_status", payload)
+        .chunk_size(4)
+        .chunk_args(move |offset, chunk| {
+            if offset == 4 && !failed.swap(true, Ordering::SeqCst) {
+                return b"not json".to_vec();
+            }
+            status_args(offset, chunk)
+        });
+    let progress = upload.progress();
+    assert!(upload.transact().await.is_err());
+    assert_eq!(progress.uploaded(), 4);
+    assert!(!progress.is_complete());
+
+    // Only the chunks left over get sent when resuming.
+    let outcomes = contract
+        .upload_chunked("set_status", payload)
+        .chunk_size(4)
+        .chunk_args(status_args)
+        .resume(&progress)
+        .transact()
+        .await?;
+    assert_eq!(outcomes.len(), 2);
+    assert!(progress.is_complete());
+
+    let status: String = contract
+        .view("get_status")
+        .args_json(json!({ "account_id": contract.id() }))
+        .await?
+        .json()?;
+    assert_eq!(status, "ij");
+
+    // Progress of an upload of a different payload cannot be resumed from.
+    assert!(contract
+        .upload_chunked("set_status", b"abc")
+        .resume(&progress)
+        .transact()
+        .await
+        .is_err());
+
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use std::time::Duration;
+
+use near_workspaces::types::NearToken;
+
+#[tokio::test]
+async fn test_sandbox_cluster() -> anyhow::Result<()> {
+    let (worker, nodes) = near_workspaces::sandbox_cluster(4).await?;
+    let validators: Vec<_> = nodes
+        .iter()
+        .map(|node| node.account_id().as_str())
+        .collect();
+    assert_eq!(validators, ["node0", "node1", "node2", "node3"]);
+    assert_eq!(worker.root_account()?.id(), nodes[0].account_id());
+
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+
+    // The chain keeps going with one out of four validators gone.
+    nodes[3].stop().await?;
+    let height = worker.view_block().await?.height();
+    alice
+        .transfer_near(bob.id(), NearToken::from_near(1))
+        .await?
+        .into_result()?;
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    assert!(worker.view_block().await?.height() > height);
+
+    // Once back up, the node catches up with what happened while it was gone.
+    nodes[3].start().await?;
+    let other = nodes[3].worker().await?;
+    let mut balance = NearToken::from_near(0);
+    for _ in 0..30 {
+        if let Ok(details) = other.view_account(bob.id()).await {
+            balance = details.balance;
+            if balance > NearToken::from_near(100) {
+                break;
+            }
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    assert!(balance > NearToken::from_near(100));
+
+    assert!(near_workspaces::sandbox_cluster(0).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cluster_patch_state() -> anyhow::Result<()> {
+    let (worker, nodes) = near_workspaces::sandbox_cluster(2).await?;
+    let account = worker.dev_create_account().await?;
+    worker
+        .patch_state(account.id(), b"greeting", b"hello")
+        .await?;
+
+    // The patch reaches every node, which keeps the cluster agreeing on its state.
+    let other = nodes[1].worker().await?;
+    let mut patched = None;
+    for _ in 0..30 {
+        if let Ok(state) = other.view_state(account.id()).await {
+            patched = state.get(b"greeting".as_slice()).cloned();
+            if patched.is_some() {
+                break;
+            }
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    assert_eq!(patched.as_deref(), Some(b"hello".as_slice()));
+
+    let height = worker.view_block().await?.height();
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    assert!(other.view_block().await?.height() > height);
+
+    assert!(nodes[1].home_dir()?.join("genesis.json").exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pause_validator() -> anyhow::Result<()> {
+    let (worker, nodes) = near_workspaces::sandbox_cluster(4).await?;
+    let height = worker.view_block().await?.height();
+
+    // The rest of the cluster produces the blocks while the validator is offline.
+    worker.pause_validator(&nodes[3], 10).await?;
+    assert!(worker.view_block().await?.height() >= height + 10);
+
+    // Once back, the validator follows the chain along with the rest of the cluster.
+    let other = nodes[3].worker().await?;
+    let mut synced = false;
+    for _ in 0..30 {
+        if other.view_block().await?.height() >= height + 10 {
+            synced = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    assert!(synced);
+
+    // The node the worker talks to cannot go offline under it.
+    assert!(worker.pause_validator(&nodes[0], 1).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cluster_version() -> anyhow::Result<()> {
+    let (worker, nodes) = near_workspaces::sandbox_cluster_with_version("1.35.0", 2).await?;
+    assert!(worker.status().await?.version().starts_with("1.35.0"));
+    for node in &nodes {
+        let status = node.worker().await?.status().await?;
+        assert!(status.version().starts_with("1.35.0"));
+    }
+    Ok(())
+}
@@ -0,0 +1,39 @@
+//! Credentials stored for near-cli are tested in their own test binary, since they get
+//! stored under the home directory, which is shared by all tests of a binary.
+use near_workspaces::types::{KeyType, SecretKey};
+use near_workspaces::Account;
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_store_near_credentials() -> anyhow::Result<()> {
+    // Spawn the sandbox before swapping out the home directory, so that the sandbox binary
+    // is looked up where it is installed.
+    let worker = near_workspaces::sandbox().await?;
+    let home = tempfile::tempdir()?;
+    std::env::set_var("HOME", home.path());
+
+    let alice = worker.dev_create_account().await?;
+    alice.store_near_credentials().await?;
+    let path = home
+        .path()
+        .join(".near-credentials/sandbox")
+        .join(format!("{}.json", alice.id()));
+    assert!(path.exists());
+    let stored = Account::from_near_credentials(alice.id(), &worker)?;
+    assert_eq!(stored.secret_key(), alice.secret_key());
+
+    // Storing credentials of the same account again does not touch the stored ones.
+    let mut replaced = alice.clone();
+    replaced.set_secret_key(SecretKey::from_random(KeyType::ED25519));
+    let err = replaced.store_near_credentials().await.unwrap_err();
+    assert!(err.to_string().contains("already stored"));
+    let stored = Account::from_near_credentials(alice.id(), &worker)?;
+    assert_eq!(stored.secret_key(), alice.secret_key());
+
+    // Unless asked to overwrite them.
+    replaced.overwrite_near_credentials().await?;
+    let stored = Account::from_near_credentials(alice.id(), &worker)?;
+    assert_eq!(stored.secret_key(), replaced.secret_key());
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use near_workspaces::result::ExecutionFinalResult;
+use near_workspaces::result::{ExecutionFinalResult, ReceiptStatus};
 use near_workspaces::types::NearToken;
 use near_workspaces::{AccountId, Contract};
 
@@ -80,3 +80,95 @@ async fn test_cross_contract_calls() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_cross_contract_assert_called() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let contract = worker.dev_deploy(FACTORY_CONTRACT).await?;
+    let status_amt = NearToken::from_near(35);
+
+    let status_id: AccountId = "status-top-level-account-long-name".parse().unwrap();
+    let outcome = contract
+        .call("deploy_status_message")
+        .args_json((status_id.clone(), status_amt))
+        .deposit(NearToken::from_near(50))
+        .max_gas()
+        .with_receipts()
+        .transact()
+        .await?;
+    outcome.assert_called(contract.id().as_str(), "deploy_status_message");
+
+    let outcome = contract
+        .call("complex_call")
+        .args_json((status_id.clone(), "hello world"))
+        .max_gas()
+        .with_receipts()
+        .transact()
+        .await?;
+    let call = outcome.assert_called_with(
+        status_id.as_str(),
+        "set_status",
+        &serde_json::json!({ "message": "hello world" }),
+    );
+    assert_eq!(call.predecessor_id, *contract.id());
+    outcome.assert_called(status_id.as_str(), "get_status");
+
+    let result = std::panic::catch_unwind(|| {
+        outcome.assert_called(status_id.as_str(), "nonexistent_method");
+    });
+    assert!(result.is_err());
+
+    // The receipts are only fetched when asked for:
+    let outcome = contract
+        .call("complex_call")
+        .args_json((status_id.clone(), "hello world"))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(outcome.function_calls().is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cross_contract_receipt_outcomes() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let contract = worker.dev_deploy(FACTORY_CONTRACT).await?;
+    let status_amt = NearToken::from_near(35);
+
+    let status_id: AccountId = "status-top-level-account-long-name".parse().unwrap();
+    cross_contract_create_contract(&status_id, &status_amt, &contract)
+        .await?
+        .into_result()?;
+
+    let outcome = contract
+        .call("complex_call")
+        .args_json((status_id.clone(), "hello world"))
+        .max_gas()
+        .with_receipts()
+        .transact()
+        .await?;
+
+    let receipts = outcome.typed_receipt_outcomes();
+    assert_eq!(receipts.len(), outcome.receipt_outcomes().len());
+    assert!(receipts
+        .iter()
+        .all(|receipt| receipt.predecessor_id.is_some()));
+
+    let from_factory = receipts
+        .iter()
+        .filter(|receipt| {
+            receipt.executor_id == status_id
+                && receipt.predecessor_id.as_ref() == Some(contract.id())
+        })
+        .collect::<Vec<_>>();
+    assert!(!from_factory.is_empty());
+    for receipt in from_factory {
+        match &receipt.status {
+            ReceiptStatus::SuccessValue(_) => assert!(receipt.gas_burnt.as_gas() > 0),
+            other => panic!("unexpected receipt status: {other:?}"),
+        }
+    }
+
+    Ok(())
+}
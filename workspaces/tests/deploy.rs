@@ -2,8 +2,10 @@
 use serde::{Deserialize, Serialize};
 use test_log::test;
 
-use near_workspaces::network::{Sandbox, ValidatorKey};
-use near_workspaces::{pick_unused_port, Worker};
+use near_workspaces::network::{GenesisAccount, Sandbox, ValidatorKey};
+use near_workspaces::operations::Function;
+use near_workspaces::types::{Gas, KeyType, NearToken, SecretKey};
+use near_workspaces::{pick_unused_port, AccountId, Worker};
 
 const NFT_WASM_FILEPATH: &str = "../examples/res/non_fungible_token.wasm";
 const EXPECTED_NFT_METADATA: &str = r#"{
@@ -57,6 +59,36 @@ async fn test_dev_deploy() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test(tokio::test)]
+async fn test_sandbox_startup_config() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox()
+        .startup_timeout(std::time::Duration::from_secs(60))
+        .health_check_interval(std::time::Duration::from_millis(100))
+        .startup_retries(2)
+        .await?;
+    deploy_and_assert(worker).await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_dev_deploy_and_init() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let wasm = std::fs::read(NFT_WASM_FILEPATH)?;
+    let owner = worker.dev_create_account().await?;
+    let contract = worker
+        .dev_deploy_and_init(
+            &wasm,
+            "new_default_meta",
+            serde_json::json!({ "owner_id": owner.id() }),
+        )
+        .await?
+        .into_result()?;
+
+    let actual: NftMetadata = contract.view("nft_metadata").await?.json()?;
+    assert_eq!(actual, expected());
+    Ok(())
+}
+
 #[test(tokio::test)]
 async fn test_manually_spawned_deploy() -> anyhow::Result<()> {
     let rpc_port = pick_unused_port().await?;
@@ -83,3 +115,466 @@ async fn test_manually_spawned_deploy() -> anyhow::Result<()> {
     child.kill().await?;
     Ok(())
 }
+
+#[test(tokio::test)]
+async fn test_sandbox_home_dir() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().chain_id("home-test").await?;
+    let home_dir = worker.home_dir().unwrap();
+    assert!(home_dir.join("data").is_dir());
+
+    let config = worker.config_json()?;
+    assert_eq!(
+        config["rpc"]["limits_config"]["json_payload_max_size"],
+        1024 * 1024 * 1024
+    );
+    assert_eq!(worker.genesis_json()?["chain_id"], "home-test");
+
+    let log = worker.node_log()?;
+    assert!(log.path().starts_with(home_dir));
+    assert!(log.path().is_file());
+    assert!(log.tail(10)?.len() <= 10);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_sandbox_stats() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let before = worker.stats().await?;
+    assert!(before.chunk_gas_limit.as_gas() > 0);
+    assert!(before.chunk_utilization() <= 1.0);
+    #[cfg(target_os = "linux")]
+    assert!(before.memory.unwrap() > 0);
+
+    worker
+        .dev_deploy(&std::fs::read(NFT_WASM_FILEPATH)?)
+        .await?;
+    let after = worker.stats().await?;
+    assert!(after.block_height > before.block_height);
+    assert!(after.state_size.unwrap() > 0);
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_sandbox_ports() -> anyhow::Result<()> {
+    let rpc_port = pick_unused_port().await?;
+    let base = pick_unused_port().await?.min(u16::MAX - 100);
+    let worker = near_workspaces::sandbox()
+        .rpc_port(rpc_port)
+        .port_range(base..base + 100)
+        .await?;
+    assert_eq!(worker.rpc_port(), Some(rpc_port));
+    let net_port = worker.net_port().unwrap();
+    assert!((base..base + 100).contains(&net_port));
+    assert_ne!(net_port, rpc_port);
+
+    // Pinned ports that are taken fail startup instead of being swapped out.
+    assert!(near_workspaces::sandbox().rpc_port(rpc_port).await.is_err());
+
+    deploy_and_assert(worker).await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_sandbox_docker_conflicts() -> anyhow::Result<()> {
+    // Nodes run in docker are spawned by workspaces, so they cannot be connected to instead.
+    let err = near_workspaces::sandbox_docker()
+        .rpc_addr("http://localhost:3030")
+        .validator_key(ValidatorKey::HomeDir("/tmp/near-sandbox".into()))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("docker_image"));
+    Ok(())
+}
+
+/// Needs a running Docker daemon, just like the `testcontainers` integration.
+#[cfg(feature = "testcontainers")]
+#[test(tokio::test)]
+async fn test_sandbox_docker() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox_docker().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+
+    let before = bob.view_account().await?.balance;
+    alice
+        .transfer_near(bob.id(), NearToken::from_near(1))
+        .await?
+        .into_result()?;
+    assert_eq!(
+        bob.view_account().await?.balance.as_yoctonear(),
+        before.as_yoctonear() + NearToken::from_near(1).as_yoctonear()
+    );
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_sandbox_attach() -> anyhow::Result<()> {
+    let rpc_port = pick_unused_port().await?;
+    let net_port = pick_unused_port().await?;
+    let home_dir = tempfile::tempdir()?;
+
+    near_sandbox_utils::init(&home_dir)?
+        .wait_with_output()
+        .await?;
+    let mut child = near_sandbox_utils::run(&home_dir, rpc_port, net_port)?;
+
+    let worker =
+        near_workspaces::sandbox_attach(&format!("127.0.0.1:{rpc_port}"), home_dir.path()).await?;
+    let contract = worker
+        .dev_deploy(&std::fs::read(NFT_WASM_FILEPATH)?)
+        .await?;
+
+    // Sandbox only APIs work against the attached node as well.
+    let height = worker.view_block().await?.height();
+    worker.fast_forward(10).await?;
+    assert!(worker.view_block().await?.height() >= height + 10);
+    worker.patch_state(contract.id(), b"STATE", b"").await?;
+    drop(worker);
+
+    // The node keeps running after the worker is gone.
+    near_workspaces::sandbox_attach(&format!("http://localhost:{rpc_port}"), home_dir.path())
+        .await?
+        .view_block()
+        .await?;
+
+    child.kill().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_sandbox_chain_id() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox()
+        .chain_id("workspaces-test")
+        .await?;
+    assert_eq!(worker.status().await?.chain_id(), "workspaces-test");
+
+    // Sandboxes running side by side keep their own chain ids.
+    let first = near_workspaces::sandbox().chain_id("first").await?;
+    let second = near_workspaces::sandbox().chain_id("second").await?;
+    assert_eq!(first.status().await?.chain_id(), "first");
+    assert_eq!(second.status().await?.chain_id(), "second");
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_multi_shard() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox()
+        .shard_layout(vec!["mm".parse()?])
+        .await?;
+    assert_eq!(worker.num_shards().await?, 2);
+
+    let root = worker.root_account()?;
+    let alice = root
+        .create_subaccount("alice")
+        .initial_balance(NearToken::from_near(10))
+        .transact()
+        .await?
+        .into_result()?;
+    let zed = root
+        .create_subaccount("zed")
+        .initial_balance(NearToken::from_near(10))
+        .transact()
+        .await?
+        .into_result()?;
+    assert_eq!(worker.shard_id(alice.id()).await?, 0);
+    assert_eq!(worker.shard_id(zed.id()).await?, 1);
+
+    // Transfers go through across shards.
+    alice
+        .transfer_near(zed.id(), NearToken::from_near(1))
+        .await?
+        .into_result()?;
+    assert!(zed.view_account().await?.balance > NearToken::from_near(10));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_sandbox_genesis() -> anyhow::Result<()> {
+    let min_gas_price = NearToken::from_yoctonear(500_000_000);
+    let worker = near_workspaces::sandbox()
+        .epoch_length(10)
+        .gas_limit(Gas::from_tgas(500))
+        .min_gas_price(min_gas_price)
+        .storage_cost_per_byte(NearToken::from_yoctonear(10u128.pow(19)))
+        .await?;
+
+    let block = worker.view_block().await?;
+    assert!(block.header().gas_price() >= min_gas_price);
+
+    // Moving two epochs along takes only a few fast forwarded blocks.
+    let epoch_id = *block.epoch_id();
+    worker.fast_forward(25).await?;
+    assert_ne!(*worker.view_block().await?.epoch_id(), epoch_id);
+
+    deploy_and_assert(worker).await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_sandbox_storage_cost() -> anyhow::Result<()> {
+    let cost = NearToken::from_yoctonear(2 * 10u128.pow(19));
+    let worker = near_workspaces::sandbox()
+        .storage_cost_per_byte(cost)
+        .await?;
+    let account = worker.dev_create_account().await?;
+
+    // The node charges the overridden cost, rather than the default one of the runtime.
+    let breakdown = worker.balance_breakdown(account.id()).await?;
+    assert_eq!(breakdown.storage_cost_per_byte, cost);
+    assert_eq!(
+        breakdown.storage_locked.as_yoctonear(),
+        cost.as_yoctonear() * breakdown.storage_usage as u128
+    );
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_sandbox_economics() -> anyhow::Result<()> {
+    // Without inflation, nothing gets minted to move the supply off of what it started at.
+    let total_supply = NearToken::from_near(5_000_000_000);
+    let worker = near_workspaces::sandbox()
+        .with_genesis_account("alice.test.near".parse()?, NearToken::from_near(1_000))
+        .total_supply(total_supply)
+        .max_inflation_rate(0, 1)
+        .protocol_reward_rate(0, 1)
+        .await?;
+    let block = worker.view_block().await?;
+    assert_eq!(block.header().total_supply(), total_supply);
+
+    let err = near_workspaces::sandbox()
+        .with_genesis_account("alice.test.near".parse()?, NearToken::from_near(1_000))
+        .total_supply(NearToken::from_near(1))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("total supply"));
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_sandbox_gas_price() -> anyhow::Result<()> {
+    let gas_price = NearToken::from_yoctonear(1_000_000_000);
+    let worker = near_workspaces::sandbox().gas_price(gas_price).await?;
+    assert_eq!(worker.view_block().await?.header().gas_price(), gas_price);
+
+    // Filled up blocks don't move the price away from where it was pinned.
+    deploy_and_assert(worker.clone()).await?;
+    worker.fast_forward(5).await?;
+    assert_eq!(worker.view_block().await?.header().gas_price(), gas_price);
+    assert_eq!(worker.gas_price().await?, gas_price);
+    Ok(())
+}
+
+#[cfg(feature = "experimental")]
+#[test(tokio::test)]
+async fn test_sandbox_genesis_config() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox()
+        .epoch_length(10)
+        .gas_limit(Gas::from_tgas(500))
+        .genesis("max_inflation_rate", serde_json::json!([1, 40]))
+        .await?;
+    let genesis = worker.genesis_config().await?;
+    assert_eq!(genesis.epoch_length, 10);
+    assert_eq!(genesis.gas_limit, Gas::from_tgas(500).as_gas());
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test(tokio::test)]
+async fn test_sandbox_tmpfs() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().tmpfs().await?;
+    deploy_and_assert(worker).await?;
+
+    let dir = tempfile::tempdir()?;
+    let worker = near_workspaces::sandbox().home_dir_in(dir.path()).await?;
+    deploy_and_assert(worker).await?;
+    assert!(std::fs::read_dir(dir.path())?.next().is_some());
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_sandbox_genesis_accounts() -> anyhow::Result<()> {
+    let alice: AccountId = "alice.test.near".parse()?;
+    let bob: AccountId = "bob.test.near".parse()?;
+    let nft: AccountId = "nft.test.near".parse()?;
+    let bob_sk = SecretKey::from_random(KeyType::ED25519);
+    let worker = near_workspaces::sandbox()
+        .with_genesis_account(alice.clone(), NearToken::from_near(10_000))
+        .genesis_account(
+            GenesisAccount::new(bob.clone(), NearToken::from_near(5)).secret_key(bob_sk),
+        )
+        .genesis_account(
+            GenesisAccount::new(nft.clone(), NearToken::from_near(50))
+                .code(std::fs::read(NFT_WASM_FILEPATH)?),
+        )
+        .await?;
+
+    let alice = worker.genesis_account(&alice)?;
+    assert_eq!(
+        alice.view_account().await?.balance,
+        NearToken::from_near(10_000)
+    );
+    alice
+        .transfer_near(&bob, NearToken::from_near(1))
+        .await?
+        .into_result()?;
+
+    // Bob signs with the key given to it, rather than the default one.
+    let bob = worker.genesis_account(&bob)?;
+    bob.transfer_near(alice.id(), NearToken::from_near(1))
+        .await?
+        .into_result()?;
+
+    let contract = worker.genesis_account(&nft)?;
+    contract
+        .call(contract.id(), "new_default_meta")
+        .args_json(serde_json::json!({ "owner_id": contract.id() }))
+        .transact()
+        .await?
+        .into_result()?;
+    let metadata: NftMetadata = contract.view(contract.id(), "nft_metadata").await?.json()?;
+    assert_eq!(metadata, expected());
+
+    assert!(worker.genesis_account(&"carol.test.near".parse()?).is_err());
+    Ok(())
+}
+
+async fn random_value_at(
+    worker: &Worker<Sandbox>,
+    height: u64,
+) -> anyhow::Result<near_workspaces::CryptoHash> {
+    while worker.view_block().await?.height() < height {
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    }
+    let block = worker.view_block().block_height(height).await?;
+    Ok(*block.header().random_value())
+}
+
+#[test(tokio::test)]
+async fn test_sandbox_random_seed() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().with_random_seed("seed").await?;
+    let same = near_workspaces::sandbox().with_random_seed("seed").await?;
+    let other = near_workspaces::sandbox().with_random_seed("other").await?;
+
+    let value = random_value_at(&worker, 3).await?;
+    assert_eq!(random_value_at(&same, 3).await?, value);
+    assert_ne!(random_value_at(&other, 3).await?, value);
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_sandbox_root_account() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox()
+        .root_account("myapp.near".parse()?)
+        .await?;
+
+    let root = worker.root_account()?;
+    assert_eq!(root.id().as_str(), "myapp.near");
+    let sub = root
+        .create_subaccount("sub")
+        .initial_balance(NearToken::from_near(10))
+        .transact()
+        .await?
+        .into_result()?;
+    assert_eq!(sub.id().as_str(), "sub.myapp.near");
+
+    // Dev accounts keep on working off of the new root account.
+    let dev = worker.dev_create_account().await?;
+    assert!(dev.view_account().await?.balance > NearToken::from_near(0));
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_sandbox_reopen_home_dir() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let home_dir = dir.path().join("home");
+
+    let worker = near_workspaces::sandbox().keep_home_dir(&home_dir).await?;
+    let wasm = std::fs::read(NFT_WASM_FILEPATH)?;
+    let contract = worker.dev_deploy(&wasm).await?;
+    contract
+        .call("new_default_meta")
+        .args_json(serde_json::json!({ "owner_id": contract.id() }))
+        .transact()
+        .await?
+        .into_result()?;
+    let height = worker.view_block().await?.height();
+    drop(worker);
+    assert!(home_dir.join("data").is_dir());
+
+    // Give the killed node a moment to release its lock on the data directory.
+    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+
+    let worker = near_workspaces::sandbox_from_home(&home_dir).await?;
+    assert!(worker.view_block().await?.height() >= height);
+    let actual: NftMetadata = worker.view(contract.id(), "nft_metadata").await?.json()?;
+    assert_eq!(actual, expected());
+
+    assert!(
+        near_workspaces::sandbox_from_home(dir.path().join("missing"))
+            .await
+            .is_err()
+    );
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_contract_upgrade() -> anyhow::Result<()> {
+    const STATUS_MSG_WASM: &[u8] = include_bytes!("../../examples/res/status_message.wasm");
+    const COUNTER_WASM: &[u8] = include_bytes!("../../examples/res/counter.wasm");
+
+    let worker = near_workspaces::sandbox().await?;
+    let contract = worker.dev_deploy(STATUS_MSG_WASM).await?;
+    contract
+        .call("set_status")
+        .args_json(("hello",))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // Redeploying compatible code keeps the state readable:
+    contract
+        .upgrade(STATUS_MSG_WASM)
+        .check_state(|before, after| {
+            if before != after {
+                return Err("state changed");
+            }
+            Ok(())
+        })
+        .transact()
+        .await?
+        .into_result()?;
+    let status: Option<String> = contract
+        .view("get_status")
+        .args_json((contract.id(),))
+        .await?
+        .json()?;
+    assert_eq!(status.as_deref(), Some("hello"));
+
+    // A failing migration reverts the deploy along with it:
+    let code_hash = contract.view_account().await?.code_hash;
+    let outcome = contract
+        .upgrade(COUNTER_WASM)
+        .migrate(Function::new("migrate"))
+        .transact()
+        .await?;
+    assert!(outcome.is_failure());
+    assert_eq!(contract.view_account().await?.code_hash, code_hash);
+
+    // A failing state check surfaces as an error:
+    let err = contract
+        .upgrade(STATUS_MSG_WASM)
+        .check_state(|_before, after| {
+            if after.is_empty() {
+                return Ok(());
+            }
+            Err("state is not empty")
+        })
+        .transact()
+        .await
+        .unwrap_err();
+    assert!(format!("{err:?}").contains("state is not empty"));
+
+    Ok(())
+}
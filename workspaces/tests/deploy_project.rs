@@ -25,3 +25,20 @@ async fn test_dev_deploy_project() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test(tokio::test)]
+async fn test_compile_project_with_code_hash() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let compiled = near_workspaces::compile_project_with(
+        "./tests/test-contracts/status-message",
+        near_workspaces::CompileOptions::new(),
+    )
+    .await?;
+    assert!(!compiled.reproducible);
+
+    let contract = worker.dev_deploy(&compiled.wasm).await?;
+    let details = contract.view_account().await?;
+    assert_eq!(details.code_hash, compiled.code_hash);
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+#![cfg(feature = "sqlite")]
+use near_workspaces::types::NearToken;
+use test_log::test;
+
+const STATUS_MSG_WASM_FILEPATH: &str = "../examples/res/status_message.wasm";
+
+#[test(tokio::test)]
+async fn test_export_sqlite() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let wasm = std::fs::read(STATUS_MSG_WASM_FILEPATH)?;
+    let contract = worker.dev_deploy(&wasm).await?;
+    let alice = worker.dev_create_account().await?;
+    alice
+        .call(contract.id(), "set_status")
+        .args_json(("hello",))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("state.db");
+    worker
+        .export_sqlite(&path, [contract.id(), alice.id()])
+        .await?;
+
+    let conn = rusqlite::Connection::open(&path)?;
+    let accounts: i64 = conn.query_row("SELECT COUNT(*) FROM accounts", [], |row| row.get(0))?;
+    assert_eq!(accounts, 2);
+
+    let balance: String = conn.query_row(
+        "SELECT balance FROM accounts WHERE account_id = ?1",
+        [alice.id().as_str()],
+        |row| row.get(0),
+    )?;
+    assert!(balance.parse::<u128>()? < NearToken::from_near(10).as_yoctonear());
+
+    let keys: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM access_keys WHERE permission = 'FullAccess'",
+        [],
+        |row| row.get(0),
+    )?;
+    assert_eq!(keys, 2);
+
+    let state_entries: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM state WHERE account_id = ?1",
+        [contract.id().as_str()],
+        |row| row.get(0),
+    )?;
+    assert!(state_entries > 0);
+
+    Ok(())
+}
@@ -315,3 +315,51 @@ async fn test_dropped_gas_meter() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test(tokio::test)]
+async fn test_gas_watchdog() -> anyhow::Result<()> {
+    use near_workspaces::types::{Gas, GasWatchdogMode};
+
+    let mut worker = near_workspaces::sandbox().await?;
+    worker.gas_watchdog(0.75, GasWatchdogMode::Fail);
+    let contract = worker
+        .dev_deploy(include_bytes!("../../examples/res/status_message.wasm"))
+        .await?;
+    let set_status = |gas: Gas| {
+        contract
+            .call("set_status")
+            .args_json(json!({ "message": "hello" }))
+            .gas(gas)
+            .transact()
+    };
+
+    // Plenty of gas attached, which the watchdog lets through.
+    let outcome = set_status(Gas::from_tgas(300)).await?;
+    let burnt: u64 = outcome
+        .receipt_outcomes()
+        .iter()
+        .map(|outcome| outcome.gas_burnt.as_gas())
+        .sum();
+
+    // Attaching barely more gas than the call burns trips the watchdog.
+    let err = set_status(Gas::from_gas(burnt * 6 / 5))
+        .await
+        .expect_err("watchdog should fail the transaction");
+    assert!(err.to_string().contains("gas watchdog"), "{err}");
+
+    // Warning instead of failing lets the transaction through.
+    worker.gas_watchdog(0.75, GasWatchdogMode::Warn);
+    let outcome = near_workspaces::Contract::from_secret_key(
+        contract.id().clone(),
+        contract.as_account().secret_key().unwrap().clone(),
+        &worker,
+    )
+    .call("set_status")
+    .args_json(json!({ "message": "hello" }))
+    .gas(Gas::from_gas(burnt * 6 / 5))
+    .transact()
+    .await?;
+    assert!(outcome.is_success());
+
+    Ok(())
+}
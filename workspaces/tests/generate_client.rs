@@ -0,0 +1,33 @@
+#![cfg(feature = "generate-client")]
+#![recursion_limit = "256"]
+use test_log::test;
+
+near_workspaces::generate_client!(
+    StatusMessage,
+    "tests/test-contracts/status-message/res/status_message_abi.json"
+);
+
+#[test(tokio::test)]
+async fn test_generated_client() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let contract = worker
+        .dev_deploy(include_bytes!("../../examples/res/status_message.wasm"))
+        .await?;
+    let client = StatusMessage::new(contract.clone());
+
+    assert_eq!(client.get_status(contract.id().clone()).await?, None);
+
+    // The prebuilt wasm deployed here does not mark `set_status` as payable, unlike the
+    // contract the ABI was generated from, so no deposit is attached.
+    client
+        .set_status("hello".to_string())
+        .transact()
+        .await?
+        .into_result()?;
+    assert_eq!(
+        client.get_status(contract.id().clone()).await?.as_deref(),
+        Some("hello")
+    );
+
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use near_workspaces::load::LoadTest;
+use serde_json::json;
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_load_test() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let contract = worker
+        .dev_deploy(include_bytes!("../../examples/res/status_message.wasm"))
+        .await?;
+
+    let set_status_id = contract.id().clone();
+    let missing_id = contract.id().clone();
+    let report = LoadTest::new(&worker)
+        .accounts(3)
+        .duration(Duration::from_secs(3))
+        .call("set_status", 3, move |account| {
+            account
+                .call(&set_status_id, "set_status")
+                .args_json(json!({ "message": "load" }))
+        })
+        .call("missing", 1, move |account| {
+            account.call(&missing_id, "missing_method")
+        })
+        .run()
+        .await?;
+
+    assert!(report.total() > 0);
+    assert_eq!(report.total(), report.calls().values().sum::<usize>());
+    assert_eq!(
+        report.succeeded(),
+        report
+            .calls()
+            .get("set_status")
+            .copied()
+            .unwrap_or_default()
+    );
+    assert!(report.tps() > 0.0);
+    assert!(report.latency(50.0) <= report.latency(99.0));
+
+    // Calls to the missing method all fail to resolve the method.
+    let failures = report.failures();
+    assert_eq!(report.failed(), failures.values().sum::<usize>());
+    for reason in failures.keys() {
+        assert!(reason.starts_with("ActionError::FunctionCallError::MethodResolveError"));
+    }
+    assert_eq!(
+        report.failed(),
+        report.calls().get("missing").copied().unwrap_or_default()
+    );
+
+    // The summary covers the totals, the latency percentiles and every call made.
+    let summary = report.to_string();
+    assert!(summary.starts_with(&format!("{} calls in ", report.total())));
+    assert!(summary.contains("latency: p50 "));
+    assert!(summary.contains("  set_status: "));
+    assert!(summary.contains("  missing: "));
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use near_workspaces::mock::MockContract;
+use near_workspaces::types::NearToken;
+use serde_json::json;
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_mock_contract_records_calls() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let wasm = MockContract::new()
+        .method("ft_on_transfer")
+        .method_returns_json("ft_transfer_call", &json!("10"))
+        .view_method_returns_json("ft_balance_of", &json!("100"))
+        .to_wasm();
+    let mock = worker.dev_deploy(&wasm).await?;
+    let alice = worker.dev_create_account().await?;
+
+    assert!(MockContract::calls(&mock).await?.is_empty());
+
+    let outcome = alice
+        .call(mock.id(), "ft_transfer_call")
+        .args_json(json!({ "receiver_id": "bob.near", "amount": "10" }))
+        .deposit(NearToken::from_yoctonear(1))
+        .transact()
+        .await?
+        .into_result()?;
+    assert_eq!(outcome.json::<String>()?, "10");
+
+    alice
+        .call(mock.id(), "ft_on_transfer")
+        .transact()
+        .await?
+        .into_result()?;
+
+    let balance: String = mock.view("ft_balance_of").await?.json()?;
+    assert_eq!(balance, "100");
+
+    let calls = MockContract::calls(&mock).await?;
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[0].method, "ft_transfer_call");
+    assert_eq!(calls[0].predecessor, *alice.id());
+    assert_eq!(calls[0].deposit, NearToken::from_yoctonear(1));
+    assert_eq!(
+        calls[0].args_json::<serde_json::Value>()?,
+        json!({ "receiver_id": "bob.near", "amount": "10" })
+    );
+    assert_eq!(calls[1].method, "ft_on_transfer");
+    assert!(calls[1].args.is_empty());
+    assert_eq!(calls[1].deposit, NearToken::from_yoctonear(0));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_mock_contract_beyond_a_page() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    // Both the response and the arguments of the call take up more than a page of memory.
+    let response = vec![7u8; 100 * 1024];
+    let wasm = MockContract::new()
+        .method_returns("large", response.clone())
+        .to_wasm();
+    let mock = worker.dev_deploy(&wasm).await?;
+
+    let args = vec![1u8; 100 * 1024];
+    let outcome = mock
+        .call("large")
+        .args(args.clone())
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?;
+    assert_eq!(outcome.raw_bytes()?, response);
+
+    let calls = MockContract::calls(&mock).await?;
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].args, args);
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use anyhow::Context;
+use near_workspaces::network::Sandbox;
+use near_workspaces::types::{
+    AccessKeyPermission, KeyType, Multisig, MultisigRequest, NearToken, SecretKey,
+};
+use near_workspaces::Worker;
+use test_log::test;
+
+/// The multisig contract of
+/// [near/core-contracts](https://github.com/near/core-contracts/tree/master/multisig).
+const MULTISIG_WASM: &str = "../examples/res/multisig.wasm";
+
+async fn deploy_multisig(worker: &Worker<Sandbox>) -> anyhow::Result<(Multisig, Vec<SecretKey>)> {
+    let wasm = std::fs::read(MULTISIG_WASM)
+        .with_context(|| format!("missing the core-contracts multisig at {MULTISIG_WASM}"))?;
+    let account = worker.dev_create_account().await?;
+    let members = vec![
+        SecretKey::from_random(KeyType::ED25519),
+        SecretKey::from_random(KeyType::ED25519),
+    ];
+    let multisig = Multisig::deploy(&account, &wasm, members.clone(), 2).await?;
+    Ok((multisig, members))
+}
+
+#[test(tokio::test)]
+async fn test_multisig_transfer() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let (multisig, members) = deploy_multisig(&worker).await?;
+    let bob = worker.dev_create_account().await?;
+    let before = bob.view_account().await?.balance;
+
+    let request = MultisigRequest::new(bob.id()).transfer(NearToken::from_near(1));
+    let id = multisig.propose(&members[0].public_key(), request).await?;
+    assert_eq!(multisig.request_ids().await?, vec![id]);
+    assert_eq!(
+        multisig.confirmations(id).await?,
+        vec![members[0].public_key()]
+    );
+
+    // A single confirmation out of the two required does not execute the request:
+    assert_eq!(bob.view_account().await?.balance, before);
+
+    multisig
+        .confirm(&members[1].public_key(), id)
+        .await?
+        .into_result()?;
+    assert!(multisig.request_ids().await?.is_empty());
+    assert_eq!(
+        bob.view_account().await?.balance.as_yoctonear(),
+        before.as_yoctonear() + NearToken::from_near(1).as_yoctonear()
+    );
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_multisig_add_key() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let (multisig, members) = deploy_multisig(&worker).await?;
+    let multisig_id = multisig.contract().id().clone();
+    let key = SecretKey::from_random(KeyType::ED25519);
+
+    let request = MultisigRequest::new(&multisig_id)
+        .add_key(key.public_key(), AccessKeyPermission::FullAccess);
+    let id = multisig.propose(&members[1].public_key(), request).await?;
+    assert!(worker
+        .view_access_key(&multisig_id, &key.public_key())
+        .await
+        .is_err());
+
+    multisig
+        .confirm(&members[0].public_key(), id)
+        .await?
+        .into_result()?;
+    let access_key = worker
+        .view_access_key(&multisig_id, &key.public_key())
+        .await?;
+    assert!(matches!(
+        access_key.permission,
+        AccessKeyPermission::FullAccess
+    ));
+
+    Ok(())
+}
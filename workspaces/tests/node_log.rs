@@ -0,0 +1,28 @@
+//! Node logs are tested in their own test binary, since the log filter of the node is
+//! picked through the environment, which is shared by all tests of a binary.
+use test_log::test;
+
+const STATUS_MSG_WASM_FILEPATH: &str = "../examples/res/status_message.wasm";
+
+#[test(tokio::test)]
+async fn test_take_node_logs() -> anyhow::Result<()> {
+    // The node logs at the info level on startup, so there is something to take for sure.
+    std::env::set_var("NEAR_ENABLE_SANDBOX_LOG", "1");
+    std::env::set_var("NEAR_SANDBOX_LOG", "info");
+    let worker = near_workspaces::sandbox().await?;
+    worker
+        .dev_deploy(&std::fs::read(STATUS_MSG_WASM_FILEPATH)?)
+        .await?;
+
+    // Lines are taken only once, in the order the node logged them.
+    let mut taken = worker.take_node_logs()?;
+    assert!(!taken.is_empty());
+    taken.extend(worker.take_node_logs()?);
+    let log = worker.node_log()?.read()?;
+    assert!(log
+        .lines()
+        .take(taken.len())
+        .eq(taken.iter().map(String::as_str)));
+
+    Ok(())
+}
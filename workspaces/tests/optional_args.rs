@@ -66,3 +66,30 @@ async fn test_optional_args_present() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test(tokio::test)]
+async fn test_wait_until() -> anyhow::Result<()> {
+    use near_workspaces::types::TxExecutionStatus;
+
+    let worker = near_workspaces::sandbox()
+        .wait_until(TxExecutionStatus::Final)
+        .await?;
+    let contract = init(&worker).await?;
+
+    let res = contract
+        .call("ft_total_supply")
+        .wait_until(TxExecutionStatus::ExecutedOptimistic)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // Only waiting for inclusion leaves the outcome to be polled for afterwards.
+    let status = contract
+        .call("ft_total_supply")
+        .wait_until(TxExecutionStatus::Included)
+        .transact_async()
+        .await?;
+    assert!(status.await?.is_success());
+
+    Ok(())
+}
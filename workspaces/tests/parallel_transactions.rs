@@ -50,7 +50,7 @@ async fn test_parallel_async() -> anyhow::Result<()> {
 
     // nonce of access key before any transactions occured.
     let nonce_start = worker
-        .view_access_key(account.id(), &account.secret_key().public_key())
+        .view_access_key(account.id(), &account.secret_key().unwrap().public_key())
         .await?
         .nonce;
 
@@ -87,7 +87,7 @@ async fn test_parallel_async() -> anyhow::Result<()> {
     assert_eq!(final_set_msg, "j");
 
     let nonce_end = worker
-        .view_access_key(account.id(), &account.secret_key().public_key())
+        .view_access_key(account.id(), &account.secret_key().unwrap().public_key())
         .await?
         .nonce;
 
@@ -96,3 +96,130 @@ async fn test_parallel_async() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_submit_all() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let contract = worker.dev_deploy(STATUS_MSG_CONTRACT).await?;
+    let account = worker.dev_create_account().await?;
+
+    let msgs = ["a", "b", "c", "d", "e"];
+    let results = worker
+        .submit_all(msgs.iter().map(|msg| {
+            account
+                .call(contract.id(), "set_status")
+                .args_json(json!({ "message": msg }))
+                .into()
+        }))
+        .await?;
+    assert_eq!(results.len(), msgs.len());
+    for result in results {
+        assert!(result.is_success());
+    }
+
+    // Transactions signed with the same key are executed in submission order.
+    let final_set_msg = account
+        .call(contract.id(), "get_status")
+        .args_json(json!({ "account_id": account.id() }))
+        .view()
+        .await?
+        .json::<String>()?;
+    assert_eq!(final_set_msg, "e");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_submit_all_partial_failure() -> anyhow::Result<()> {
+    use near_workspaces::types::{KeyType, SecretKey};
+    use near_workspaces::Account;
+
+    let worker = near_workspaces::sandbox().await?;
+    let contract = worker.dev_deploy(STATUS_MSG_CONTRACT).await?;
+    let account = worker.dev_create_account().await?;
+
+    // Signing with a key the account does not have fails to be sent, which stops the
+    // transactions after it from being sent as well.
+    let unknown_key = Account::from_secret_key(
+        account.id().clone(),
+        SecretKey::from_random(KeyType::ED25519),
+        &worker,
+    );
+    let err = worker
+        .submit_all(
+            ["a", "b", "c"]
+                .iter()
+                .zip([&account, &unknown_key, &account])
+                .map(|(msg, signer)| {
+                    signer
+                        .call(contract.id(), "set_status")
+                        .args_json(json!({ "message": msg }))
+                        .into()
+                }),
+        )
+        .await
+        .unwrap_err();
+
+    // The transaction sent before the failure is still waited on and reported.
+    assert_eq!(err.results.len(), 1);
+    assert!(err.results[0].as_ref().unwrap().is_success());
+    let status = account
+        .call(contract.id(), "get_status")
+        .args_json(json!({ "account_id": account.id() }))
+        .view()
+        .await?
+        .json::<String>()?;
+    assert_eq!(status, "a");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stale_nonce_retry() -> anyhow::Result<()> {
+    use near_workspaces::network::ValidatorKey;
+    use near_workspaces::types::NearToken;
+
+    let worker = near_workspaces::sandbox().await?;
+    let root = worker.root_account()?;
+    let validator_key = ValidatorKey::Known(root.id().clone(), root.secret_key().unwrap().clone());
+    let account = worker.dev_create_account().await?;
+
+    // A second connection keeps a nonce cache of its own, which goes stale once the first
+    // connection sends transactions with the same key.
+    let other = near_workspaces::sandbox()
+        .rpc_addr(&worker.rpc_addr())
+        .validator_key(validator_key.clone())
+        .await?;
+    let opted_out = near_workspaces::sandbox()
+        .rpc_addr(&worker.rpc_addr())
+        .validator_key(validator_key)
+        .stale_tx_retries(0)
+        .await?;
+    for worker in [&other, &opted_out] {
+        worker
+            .transfer_near(account.signer(), root.id(), NearToken::from_yoctonear(1))
+            .await?
+            .into_result()?;
+    }
+
+    for _ in 0..3 {
+        account
+            .transfer_near(root.id(), NearToken::from_yoctonear(1))
+            .await?
+            .into_result()?;
+    }
+
+    let result = other
+        .call(account.signer(), root.id(), "noop")
+        .transact()
+        .await?;
+    assert_eq!(result.retries(), 1);
+
+    assert!(opted_out
+        .call(account.signer(), root.id(), "noop")
+        .transact()
+        .await
+        .is_err());
+
+    Ok(())
+}
@@ -6,6 +6,7 @@ use near_token::NearToken;
 use serde_json::json;
 use test_log::test;
 
+use near_workspaces::operations::Function;
 use near_workspaces::types::{KeyType, SecretKey};
 use near_workspaces::{AccessKey, AccountDetailsPatch, AccountId, Contract, DevNetwork, Worker};
 
@@ -208,3 +209,466 @@ async fn test_patch_account_from_current() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Write a state dump holding the status message contract under `contract_id`, along
+/// with the plain account `other_id`, into `dir`.
+fn write_status_dump(
+    dir: &std::path::Path,
+    contract_id: &AccountId,
+    other_id: &AccountId,
+) -> anyhow::Result<()> {
+    use near_primitives::account::Account;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::state_record::StateRecord;
+
+    let wasm = std::fs::read(STATUS_MSG_WASM_FILEPATH)?;
+    let status_msg = StatusMessage {
+        records: vec![Record {
+            k: "alice.near".to_string(),
+            v: "hello from the dump".to_string(),
+        }],
+    };
+    let account = |code_hash| Account::new(10_u128.pow(25), 0, code_hash, 100_000);
+    let records = vec![
+        StateRecord::Account {
+            account_id: contract_id.clone(),
+            account: account(near_primitives::hash::hash(&wasm)),
+        },
+        StateRecord::Account {
+            account_id: other_id.clone(),
+            account: account(CryptoHash::default()),
+        },
+        StateRecord::Contract {
+            account_id: contract_id.clone(),
+            code: wasm,
+        },
+        StateRecord::Data {
+            account_id: contract_id.clone(),
+            data_key: b"STATE".to_vec().into(),
+            value: status_msg.try_to_vec()?.into(),
+        },
+    ];
+    std::fs::write(dir.join("records.json"), serde_json::to_vec(&records)?)?;
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_import_contract_from_dump() -> anyhow::Result<()> {
+    use near_workspaces::dump::StateDump;
+
+    let worker = near_workspaces::sandbox().await?;
+    let contract_id: AccountId = "dumped-status.near".parse()?;
+    let other_id: AccountId = "other.near".parse()?;
+    let dir = tempfile::tempdir()?;
+    write_status_dump(dir.path(), &contract_id, &other_id)?;
+
+    let dump = StateDump::load_accounts(dir.path(), [&contract_id]).await?;
+    assert!(dump.contains(&contract_id));
+    assert!(!dump.contains(&other_id));
+
+    let contract = worker
+        .import_contract_from_dump(&contract_id, &dump)
+        .initial_balance(NearToken::from_near(50))
+        .with_data()
+        .transact()
+        .await?;
+    assert_eq!(
+        contract.view_account().await?.balance,
+        NearToken::from_near(50)
+    );
+
+    let status: String = contract
+        .view("get_status")
+        .args_json(json!({ "account_id": "alice.near" }))
+        .await?
+        .json()?;
+    assert_eq!(status, "hello from the dump");
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_seed_from_dump() -> anyhow::Result<()> {
+    let contract_id: AccountId = "dumped-status.near".parse()?;
+    let other_id: AccountId = "other.near".parse()?;
+    let dir = tempfile::tempdir()?;
+    write_status_dump(dir.path(), &contract_id, &other_id)?;
+
+    let worker = near_workspaces::sandbox()
+        .seed_from_dump(dir.path())
+        .await?;
+    assert_eq!(
+        worker.view_account(&other_id).await?.balance,
+        NearToken::from_near(10)
+    );
+
+    let status: String = worker
+        .view(&contract_id, "get_status")
+        .args_json(json!({ "account_id": "alice.near" }))
+        .await?
+        .json()?;
+    assert_eq!(status, "hello from the dump");
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_bloat_state() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let (contract_id, status_msg) = view_status_state(&worker).await?;
+    let storage_usage = worker.view_account(&contract_id).await?.storage_usage;
+
+    // Spans more than a single patch request worth of entries.
+    const ENTRIES: u64 = 1500;
+    worker
+        .bloat_state(&contract_id, ENTRIES)
+        .key_prefix(b"x")
+        .value_size(8)
+        .transact()
+        .await?;
+
+    let state = worker.view_state(&contract_id).await?;
+    assert_eq!(state.len() as u64, ENTRIES + 1);
+    assert_eq!(
+        state[[b"x".as_slice(), &42u64.to_be_bytes()].concat().as_slice()],
+        vec![42; 8]
+    );
+    assert_eq!(
+        StatusMessage::try_from_slice(&state[b"STATE".as_slice()])?,
+        status_msg
+    );
+
+    // Each entry takes up its key, its value and the storage overhead of a record.
+    let bloated_usage = worker.view_account(&contract_id).await?.storage_usage;
+    assert_eq!(bloated_usage - storage_usage, ENTRIES * (9 + 8 + 40));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_sandbox_fork() -> anyhow::Result<()> {
+    let wrap_id: AccountId = "wrap.near".parse()?;
+    let mainnet = near_workspaces::mainnet().await?;
+    let height = mainnet.view_block().await?.height();
+    let mainnet_wrap = mainnet.view_account(&wrap_id).await?;
+
+    let worker =
+        near_workspaces::sandbox_fork(near_workspaces::network::mainnet::RPC_URL, height).await?;
+    let alice = worker.dev_create_account().await?;
+
+    // Viewing the forked contract pulls it in along with its code and data, as long as the
+    // RPC node hands out its state, which regular nodes refuse to above 50kB of it.
+    let mainnet_state = mainnet.view_state(&wrap_id).block_height(height).await;
+    let metadata = worker.view(&wrap_id, "ft_metadata").await;
+    match mainnet_state {
+        Ok(_) => {
+            let metadata: serde_json::Value = metadata?.json()?;
+            assert_eq!(metadata["symbol"], "wNEAR");
+            let wrap = worker.view_account(&wrap_id).await?;
+            assert_eq!(wrap.code_hash, mainnet_wrap.code_hash);
+        }
+        Err(_) => assert!(metadata.unwrap_err().to_string().contains("50kB")),
+    }
+
+    // Accounts created within the sandbox are left as they are.
+    worker.fork_account(alice.id()).await?;
+    assert_eq!(
+        worker.view_account(alice.id()).await?.code_hash,
+        Default::default()
+    );
+
+    // Sandboxes not forked off of another chain have nothing to pull accounts in from.
+    let worker = near_workspaces::sandbox().await?;
+    assert!(worker.fork_account(&wrap_id).await.is_err());
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_patch_account_balance() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let (contract_id, status_msg) = view_status_state(&worker).await?;
+    let before = worker.view_account(&contract_id).await?;
+
+    let whale = NearToken::from_near(1_000_000_000);
+    worker.patch_account_balance(&contract_id, whale).await?;
+
+    // Only the balance changes, while the code and state stay put.
+    let after = worker.view_account(&contract_id).await?;
+    assert_eq!(after.balance, whale);
+    assert_eq!(after.code_hash, before.code_hash);
+    assert_eq!(after.storage_usage, before.storage_usage);
+    let status: String = worker
+        .view(&contract_id, "get_status")
+        .args_json(json!({ "account_id": contract_id }))
+        .await?
+        .json()?;
+    assert_eq!(status, status_msg.records[0].v);
+
+    // The minted balance can be spent like any other.
+    let account = worker.dev_create_account().await?;
+    worker.patch_account_balance(account.id(), whale).await?;
+    account
+        .transfer_near(&contract_id, NearToken::from_near(500_000_000))
+        .await?
+        .into_result()?;
+    assert!(worker.view_account(&contract_id).await?.balance > NearToken::from_near(1_400_000_000));
+
+    let missing: AccountId = "missing.test.near".parse()?;
+    assert!(worker.patch_account_balance(&missing, whale).await.is_err());
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_patch_code() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let status_msg_wasm = std::fs::read(STATUS_MSG_WASM_FILEPATH)?;
+    let account = worker.dev_create_account().await?;
+
+    // Code lands on the account without it signing a deploy.
+    worker.patch_code(account.id(), &status_msg_wasm).await?;
+    assert_eq!(worker.view_code(account.id()).await?, status_msg_wasm);
+    let caller = worker.dev_create_account().await?;
+    caller
+        .call(account.id(), "set_status")
+        .args_json(json!({ "message": "patched" }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // Swapping the code keeps the state the previous code left behind.
+    let counter_wasm = std::fs::read("../examples/res/counter.wasm")?;
+    worker.patch_code(account.id(), &counter_wasm).await?;
+    assert_eq!(worker.view_code(account.id()).await?, counter_wasm);
+    assert!(worker
+        .view_state(account.id())
+        .await?
+        .contains_key(b"STATE".as_slice()));
+
+    let missing: AccountId = "missing.test.near".parse()?;
+    assert!(worker.patch_code(&missing, &counter_wasm).await.is_err());
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_patch_access_key() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let wasm = std::fs::read(STATUS_MSG_WASM_FILEPATH)?;
+    let contract = worker.dev_deploy(&wasm).await?;
+
+    // Act as the contract with a key of our own.
+    let sk = SecretKey::from_random(KeyType::ED25519);
+    worker
+        .patch_access_key(contract.id(), sk.public_key(), AccessKey::full_access())
+        .await?;
+    let key = worker
+        .view_access_key(contract.id(), &sk.public_key())
+        .await?;
+    assert!(matches!(
+        key.permission,
+        near_workspaces::types::AccessKeyPermission::FullAccess
+    ));
+
+    let contract = Contract::from_secret_key(contract.id().clone(), sk, &worker);
+    contract
+        .call("set_status")
+        .args_json(json!({ "message": "acting as" }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let missing: AccountId = "missing.test.near".parse()?;
+    let sk = SecretKey::from_random(KeyType::ED25519);
+    assert!(worker
+        .patch_access_key(&missing, sk.public_key(), AccessKey::full_access())
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_delete_state() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let (contract_id, _) = view_status_state(&worker).await?;
+    let code = worker.view_code(&contract_id).await?;
+    worker
+        .bloat_state(&contract_id, 300)
+        .key_prefix(b"x")
+        .transact()
+        .await?;
+    let storage_usage = worker.view_account(&contract_id).await?.storage_usage;
+
+    // Spans more than a single call worth of keys, along with one that does not exist.
+    let keys: Vec<_> = (0..250u64)
+        .map(|i| [b"x".as_slice(), &i.to_be_bytes()].concat())
+        .chain(std::iter::once(b"missing".to_vec()))
+        .collect();
+    worker.delete_state(&contract_id, &keys).await?;
+    let state = worker.view_state(&contract_id).await?;
+    assert_eq!(state.len(), 51);
+    assert!(!state.contains_key(&keys[0]));
+    assert!(state.contains_key(b"STATE".as_slice()));
+
+    // The storage usage shrinks along with the state, while the code stays put.
+    let account = worker.view_account(&contract_id).await?;
+    assert_eq!(storage_usage - account.storage_usage, 250 * (9 + 32 + 40));
+    assert_eq!(worker.view_code(&contract_id).await?, code);
+
+    // A contract coming up without its state falls back to its defaults.
+    worker.clear_state(&contract_id).await?;
+    assert!(worker.view_state(&contract_id).await?.is_empty());
+    let status: Option<String> = worker
+        .view(&contract_id, "get_status")
+        .args_json(json!({ "account_id": contract_id }))
+        .await?
+        .json()?;
+    assert_eq!(status, None);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_bulk_patch() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let wasm = std::fs::read(STATUS_MSG_WASM_FILEPATH)?;
+    let contract_id: AccountId = "bulk.test.near".parse()?;
+    let sk = SecretKey::from_seed(KeyType::ED25519, "bulk");
+
+    // A small batch size to spread the records over a number of requests.
+    let entries: Vec<(Vec<u8>, Vec<u8>)> = (0..500u64)
+        .map(|i| (i.to_be_bytes().to_vec(), vec![7; 16]))
+        .collect();
+    let patch = worker
+        .bulk_patch()
+        .account(
+            &contract_id,
+            AccountDetailsPatch::default().balance(NearToken::from_near(100)),
+        )
+        .code(&contract_id, &wasm)
+        .access_key(&contract_id, sk.public_key(), AccessKey::full_access())
+        .states(
+            &contract_id,
+            entries.iter().map(|(k, v)| (k.as_slice(), v.as_slice())),
+        )
+        .batch_size(8 * 1024);
+    let progress = patch.progress();
+    assert!(!progress.is_complete());
+    patch.transact().await?;
+    assert!(progress.is_complete());
+    assert_eq!(progress.patched(), 503);
+    assert_eq!(progress.total(), 503);
+
+    let account = worker.view_account(&contract_id).await?;
+    assert_eq!(account.balance, NearToken::from_near(100));
+    assert_eq!(worker.view_code(&contract_id).await?, wasm);
+    assert_eq!(worker.view_state(&contract_id).await?.len(), 500);
+
+    // The patched in access key is usable to sign for the account.
+    let contract = Contract::from_secret_key(contract_id, sk, &worker);
+    contract
+        .call("set_status")
+        .args_json(json!({ "message": "bulk" }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_inject_receipts() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let (contract_id, _) = view_status_state(&worker).await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let bob_balance = bob.view_account().await?.balance;
+
+    // The contract sees alice as the one calling it, without alice signing anything.
+    worker
+        .inject_receipts(alice.id())
+        .call(
+            &contract_id,
+            Function::new("set_status").args_json(json!({ "message": "injected" })),
+        )
+        .transfer(bob.id(), NearToken::from_near(1))
+        .transact()
+        .await?
+        .into_result()?;
+    let status: Option<String> = worker
+        .view(&contract_id, "get_status")
+        .args_json(json!({ "account_id": alice.id() }))
+        .await?
+        .json()?;
+    assert_eq!(status.as_deref(), Some("injected"));
+    assert_eq!(
+        bob.view_account().await?.balance,
+        bob_balance.saturating_add(NearToken::from_near(1))
+    );
+
+    // Alice is left without a contract, like before the receipts got sent out.
+    assert_eq!(
+        alice.view_account().await?.code_hash,
+        near_workspaces::CryptoHash::default()
+    );
+    assert!(worker
+        .inject_receipts(alice.id())
+        .transfer(alice.id(), NearToken::from_near(1))
+        .transact()
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_clone_account() -> anyhow::Result<()> {
+    let source = near_workspaces::sandbox().await?;
+    let worker = near_workspaces::sandbox().await?;
+
+    let wasm = std::fs::read(STATUS_MSG_WASM_FILEPATH)?;
+    let original = source.dev_deploy(&wasm).await?;
+    original
+        .call("set_status")
+        .args_json(json!({ "message": "hello from the source" }))
+        .transact()
+        .await?
+        .into_result()?;
+    let extra_key = SecretKey::from_random(KeyType::ED25519);
+    original
+        .as_account()
+        .add_key(extra_key.public_key(), AccessKey::full_access().permission)
+        .await?
+        .into_result()?;
+
+    let dest_id: AccountId = "cloned.test.near".parse()?;
+    let contract = worker
+        .clone_account(&source, original.id())
+        .dest_account_id(&dest_id)
+        .transact()
+        .await?;
+    assert_eq!(contract.id(), &dest_id);
+    assert_eq!(contract.view_code().await?, wasm);
+
+    let status: String = contract
+        .view("get_status")
+        .args_json(json!({ "account_id": original.id() }))
+        .await?
+        .json()?;
+    assert_eq!(status, "hello from the source");
+
+    // The keys of the source account came along, next to the key workspaces signs with:
+    assert!(contract
+        .view_access_key(&extra_key.public_key())
+        .await
+        .is_ok());
+    assert!(contract
+        .view_access_key(&contract.as_account().public_key())
+        .await
+        .is_ok());
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use near_workspaces::network::{NetworkInfo, SandboxPool};
+use test_log::test;
+
+const STATUS_MSG_WASM_FILEPATH: &str = "../examples/res/status_message.wasm";
+
+#[test(tokio::test)]
+async fn test_sandbox_pool_leases_fresh_nodes() -> anyhow::Result<()> {
+    let pool = SandboxPool::new(2);
+    let first = pool.lease().await?;
+    let second = pool.lease().await?;
+    assert_ne!(first.info().rpc_url, second.info().rpc_url);
+
+    let wasm = std::fs::read(STATUS_MSG_WASM_FILEPATH)?;
+    let contract = first.dev_deploy(&wasm).await?;
+    assert!(first.view_account(contract.id()).await.is_ok());
+    assert!(second.view_account(contract.id()).await.is_err());
+
+    // Leasing more nodes than the pool holds waits on the replacements booting up.
+    let third = pool.lease().await?;
+    assert!(third.view_account(contract.id()).await.is_err());
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_sandbox_pool_recycles_nodes() -> anyhow::Result<()> {
+    let pool = SandboxPool::recycling(1, near_workspaces::sandbox);
+    let worker = pool.checkout().await?;
+    let rpc_url = worker.info().rpc_url.clone();
+
+    let wasm = std::fs::read(STATUS_MSG_WASM_FILEPATH)?;
+    let contract = worker.dev_deploy(&wasm).await?;
+    assert!(worker.view_account(contract.id()).await.is_ok());
+    drop(worker);
+
+    // The same node comes back, with the state it booted with.
+    let worker = pool.checkout().await?;
+    assert_eq!(worker.info().rpc_url, rpc_url);
+    assert!(worker.view_account(contract.id()).await.is_err());
+    // And keeps working for transactions of the next test.
+    worker.dev_deploy(&wasm).await?;
+
+    Ok(())
+}
@@ -1,4 +1,6 @@
-use near_workspaces::types::NearToken;
+use std::time::Duration;
+
+use near_workspaces::types::{Gas, NearToken};
 use near_workspaces::{network::Sandbox, Contract, Worker};
 
 async fn init() -> anyhow::Result<(Worker<Sandbox>, Contract)> {
@@ -45,3 +47,432 @@ async fn test_invalid_query() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_execution_failure_details() -> anyhow::Result<()> {
+    let (_worker, contract) = init().await?;
+
+    let failure = contract
+        .call("set_status")
+        .args_json(("some message",))
+        .deposit(NearToken::from_yoctonear(1))
+        .transact()
+        .await?
+        .into_result()
+        .expect_err("expected error while passing deposit to `status_msg.set_status`");
+
+    let details = failure.failure_details();
+    assert_eq!(
+        details.error_type,
+        "ActionError::FunctionCallError::ExecutionError"
+    );
+    assert_eq!(
+        details.panic_message.as_deref(),
+        Some("Smart contract panicked: Method doesn't accept deposit")
+    );
+    assert!(details.receipt_id.is_some());
+    assert_eq!(details.total_gas_burnt, failure.total_gas_burnt);
+
+    // The same details should be reachable from a workspaces error:
+    let err = near_workspaces::error::Error::from(failure);
+    assert_eq!(
+        err.execution_failure().map(|f| f.error_type()),
+        Some(details.error_type),
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_storage_report() -> anyhow::Result<()> {
+    let (_worker, contract) = init().await?;
+    let before = contract.storage_report().await?;
+    assert_eq!(before.state_keys, 0);
+    assert_eq!(
+        before.code_size,
+        include_bytes!("../../examples/res/status_message.wasm").len()
+    );
+
+    contract
+        .call("set_status")
+        .args_json(("some message",))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let after = contract.storage_report().await?;
+    assert!(after.state_keys > 0);
+    assert!(after.state_bytes > 0);
+    assert!(after.storage_usage > before.storage_usage);
+    assert_eq!(after.code_size, before.code_size);
+
+    // The report is read at a single block, which the account can be viewed at as well.
+    assert!(after.block_height > before.block_height);
+    let details = contract
+        .view_account()
+        .block_height(after.block_height)
+        .await?;
+    assert_eq!(details.storage_usage, after.storage_usage);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_view_state_and_code_at() -> anyhow::Result<()> {
+    let (worker, contract) = init().await?;
+    contract
+        .call("set_status")
+        .args_json(("first message",))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // Make sure the first status is part of a final block before recording the height.
+    worker.fast_forward(3).await?;
+    let height = worker.view_block().await?.height();
+
+    contract
+        .call("set_status")
+        .args_json(("second message",))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let past_state = worker.view_state_at(contract.id(), height).await?;
+    let state = worker.view_state(contract.id()).await?;
+    assert_ne!(past_state, state);
+
+    let code = worker.view_code_at(contract.id(), height).await?;
+    assert_eq!(code, contract.view_code().await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_wait_for_blocks() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let before = worker.view_block().await?.height();
+
+    // Sandbox fast forwards, so this should not take anywhere near 10000 blocks of time.
+    worker.wait_for_blocks(10000).await?;
+
+    let after = worker.view_block().await?.height();
+    assert!(after - before >= 10000);
+
+    // Blocks not produced in time fail the wait instead of hanging the test.
+    let err = worker
+        .wait_for_blocks_within(10000, Duration::from_millis(1))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("did not produce"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_view_result_block() -> anyhow::Result<()> {
+    let (worker, contract) = init().await?;
+    contract
+        .call("set_status")
+        .args_json(("some message",))
+        .transact()
+        .await?
+        .into_result()?;
+    worker.fast_forward(3).await?;
+
+    let block = worker.view_block().await?;
+    let result = contract
+        .view("get_status")
+        .args_json((contract.id(),))
+        .block_height(block.height())
+        .await?;
+    assert_eq!(result.block_height, block.height());
+    assert_eq!(&result.block_hash, block.hash());
+
+    // Results of nearcore's own view calls still convert over, without their block.
+    let raw = near_primitives::views::CallResult {
+        result: result.result.clone(),
+        logs: result.logs.clone(),
+    };
+    let converted = near_workspaces::result::ViewResultDetails::from(raw);
+    assert_eq!(converted.result, result.result);
+    assert_eq!(converted.block_height, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_status() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().chain_id("status-test").await?;
+    worker.fast_forward(3).await?;
+
+    let status = worker.status().await?;
+    assert_eq!(status.chain_id(), "status-test");
+    assert!(!status.is_syncing());
+    assert!(status.protocol_version() <= status.latest_protocol_version());
+    // Sandbox runs a single validator, which is its root account.
+    assert_eq!(
+        status.validator_account_id(),
+        Some(worker.root_account()?.id())
+    );
+
+    let block = worker.view_block().await?;
+    assert!(status.latest_block_height() <= block.height());
+    assert!(status.sync_info().latest_block_time() <= block.timestamp());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_view_receipt() -> anyhow::Result<()> {
+    use near_workspaces::types::ReceiptAction;
+
+    let (worker, contract) = init().await?;
+    let outcome = contract
+        .call("set_status")
+        .args_json(("some message",))
+        .transact()
+        .await?;
+    assert!(outcome.is_success());
+
+    // The transaction converts into a single receipt calling into the contract.
+    let receipt_id = outcome.outcome().receipt_ids[0];
+    let receipt = worker.view_receipt(&receipt_id).await?;
+    assert_eq!(receipt.id(), &receipt_id);
+    assert_eq!(receipt.receiver_id(), contract.id());
+    assert_eq!(receipt.signer_id(), Some(contract.id()));
+    assert!(!receipt.is_data());
+
+    match receipt.actions() {
+        [ReceiptAction::FunctionCall(call)] => {
+            assert_eq!(call.method_name, "set_status");
+            assert_eq!(call.args_json::<(String,)>()?.0, "some message");
+            assert_eq!(call.deposit, NearToken::from_yoctonear(0));
+        }
+        actions => panic!("expected a single function call, got {actions:?}"),
+    }
+
+    assert!(worker
+        .view_receipt(&near_workspaces::CryptoHash::default())
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_view_tx_with_receipts() -> anyhow::Result<()> {
+    use near_workspaces::types::ReceiptAction;
+
+    let worker = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+
+    let outcome = alice
+        .transfer_near(bob.id(), NearToken::from_millinear(42))
+        .await?;
+    assert!(outcome.is_success());
+
+    let tx = worker
+        .view_tx_with_receipts(alice.id(), &outcome.outcome().transaction_hash)
+        .await?;
+    assert!(tx.result().is_success());
+
+    // The receipt the transaction converted into carries the transfer to bob.
+    let receipt_id = tx.result().outcome().receipt_ids[0];
+    let receipt = tx.receipt(&receipt_id).expect("receipt of the transaction");
+    assert_eq!(receipt.receiver_id(), bob.id());
+    assert_eq!(receipt.predecessor_id(), alice.id());
+    assert!(matches!(
+        receipt.actions(),
+        [ReceiptAction::Transfer { deposit }] if *deposit == NearToken::from_millinear(42)
+    ));
+    assert_eq!(tx.receipts_to(bob.id()).count(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fast_forward_to_timestamp() -> anyhow::Result<()> {
+    const HOUR: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+    let worker = near_workspaces::sandbox().await?;
+    let before = worker.view_block().await?;
+
+    worker.fast_forward_duration(HOUR).await?;
+    let after = worker.view_block().await?;
+    assert!(after.timestamp() - before.timestamp() >= HOUR.as_nanos() as u64);
+
+    // Timestamps already in the past leave the chain where it is.
+    worker.fast_forward_to_timestamp(before.timestamp()).await?;
+    assert!(worker.view_block().await?.height() - after.height() < 10);
+
+    let target = after.timestamp() + 2 * HOUR.as_nanos() as u64;
+    worker.fast_forward_to_timestamp(target).await?;
+    assert!(worker.view_block().await?.timestamp() >= target);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fast_forward_to() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let target = worker.view_block().await?.height() + 1000;
+
+    worker.fast_forward_to(target).await?;
+    let height = worker.view_block().await?.height();
+    assert!(height >= target && height - target < 10);
+
+    // Heights in the past cannot be fast forwarded to.
+    assert!(worker.fast_forward_to(target - 500).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sandbox_version() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().version("1.35.0").await?;
+    let status = worker.status().await?;
+    assert!(status.version().starts_with("1.35.0"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_manual_block_production() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().manual_block_production().await?;
+    let height = worker.view_block().await?.height();
+
+    // No blocks get produced on their own.
+    tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+    assert_eq!(worker.view_block().await?.height(), height);
+
+    worker.produce_block().await?;
+    assert!(worker.view_block().await?.height() > height);
+
+    // Transactions go through once blocks are produced for them.
+    let root = worker.root_account()?;
+    let status = root
+        .call(root.id(), "missing_method")
+        .transact_async()
+        .await?;
+    worker.produce_blocks(5).await?;
+    assert!(status.await?.is_failure());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_block_time() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox()
+        .block_time(std::time::Duration::from_millis(100))
+        .await?;
+    let start = worker.view_block().await?;
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    let end = worker.view_block().await?;
+
+    // Blocks come in a lot quicker than the default of one every 600ms or so.
+    let blocks = end.height() - start.height();
+    assert!(blocks >= 5, "only {blocks} blocks got produced");
+    let avg_nanos = (end.timestamp() - start.timestamp()) / blocks;
+    assert!(avg_nanos < 400_000_000);
+
+    let err = near_workspaces::sandbox()
+        .block_time(std::time::Duration::from_millis(100))
+        .manual_block_production()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("block_time"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_congest() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox()
+        .gas_limit(Gas::from_tgas(300))
+        .await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+
+    // The transfer has to wait on the receipts congesting the chunks ahead of it.
+    worker.congest(Gas::from_tgas(3000)).await?;
+    let outcome = alice
+        .transfer_near(bob.id(), NearToken::from_near(1))
+        .await?
+        .into_result()?;
+    let sent = worker
+        .view_block()
+        .block_hash(outcome.outcome().block_hash)
+        .await?
+        .height();
+    let received = worker
+        .view_block()
+        .block_hash(outcome.receipt_outcomes()[0].block_hash)
+        .await?
+        .height();
+    assert!(
+        received - sent >= 3,
+        "transfer went through in {} blocks",
+        received - sent
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fast_forward_epochs() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().epoch_length(10).await?;
+    let block = worker.view_block().await?;
+
+    worker.fast_forward_epochs(1).await?;
+    let next = worker.view_block().await?;
+    assert_ne!(next.epoch_id(), block.epoch_id());
+    // The epoch coming up after the current one is the one it got to.
+    assert_eq!(next.epoch_id(), block.header().next_epoch_id());
+
+    worker.fast_forward_epochs(2).await?;
+    let last = worker.view_block().await?;
+    assert_ne!(last.epoch_id(), next.epoch_id());
+    assert_ne!(last.epoch_id(), next.header().next_epoch_id());
+    assert!(last.height() >= next.height() + 10);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upgrade_protocol() -> anyhow::Result<()> {
+    let latest = near_workspaces::sandbox()
+        .await?
+        .status()
+        .await?
+        .latest_protocol_version();
+
+    let worker = near_workspaces::sandbox()
+        .protocol_version(latest - 1)
+        .epoch_length(10)
+        .await?;
+    assert_eq!(worker.status().await?.protocol_version(), latest - 1);
+    let contract = worker
+        .dev_deploy(include_bytes!("../../examples/res/status_message.wasm"))
+        .await?;
+    contract
+        .call("set_status")
+        .args_json(("before",))
+        .transact()
+        .await?
+        .into_result()?;
+
+    assert_eq!(worker.upgrade_protocol().await?, latest);
+    assert_eq!(worker.status().await?.protocol_version(), latest);
+    // State carries over the upgrade.
+    let status: String = contract
+        .view("get_status")
+        .args_json((contract.id(),))
+        .await?
+        .json()?;
+    assert_eq!(status, "before");
+
+    // Upgrading a sandbox at the latest version already leaves it as is.
+    assert_eq!(worker.upgrade_protocol().await?, latest);
+
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use test_log::test;
+
+const STATUS_MSG_WASM_FILEPATH: &str = "../examples/res/status_message.wasm";
+
+#[test(tokio::test)]
+async fn test_shared_sandbox_namespace() -> anyhow::Result<()> {
+    let ns = near_workspaces::shared_sandbox().await?;
+    let alice = ns.create_account("alice").await?;
+    assert_eq!(alice.id().as_str(), format!("alice.{}", ns.id()));
+
+    let wasm = std::fs::read(STATUS_MSG_WASM_FILEPATH)?;
+    let contract = ns.dev_deploy(&wasm).await?;
+    assert!(contract.id().as_str().ends_with(ns.id().as_str()));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_shared_sandbox_isolated_namespaces() -> anyhow::Result<()> {
+    let first = near_workspaces::shared_sandbox().await?;
+    let second = near_workspaces::shared_sandbox().await?;
+    assert_ne!(first.id(), second.id());
+
+    // The same name can be used by several tests without colliding.
+    let a = first.create_account("alice").await?;
+    let b = second.create_account("alice").await?;
+    assert_ne!(a.id(), b.id());
+    assert_eq!(first.worker().rpc_addr(), second.worker().rpc_addr());
+
+    Ok(())
+}
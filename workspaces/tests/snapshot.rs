@@ -0,0 +1,106 @@
+use serde_json::json;
+
+const STATUS_MSG_WASM_FILEPATH: &str = "../examples/res/status_message.wasm";
+
+#[tokio::test]
+async fn test_snapshot_restore() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let wasm = std::fs::read(STATUS_MSG_WASM_FILEPATH)?;
+    let contract = worker.dev_deploy(&wasm).await?;
+
+    contract
+        .call("set_status")
+        .args_json(json!({ "message": "before" }))
+        .transact()
+        .await?
+        .into_result()?;
+    let snapshot = worker.snapshot().await?;
+
+    contract
+        .call("set_status")
+        .args_json(json!({ "message": "after" }))
+        .transact()
+        .await?
+        .into_result()?;
+    let account = worker.dev_create_account().await?;
+
+    // Restoring can be done more than once, and undoes everything done in between.
+    for _ in 0..2 {
+        worker.restore(snapshot).await?;
+        let status: String = contract
+            .view("get_status")
+            .args_json(json!({ "account_id": contract.id() }))
+            .await?
+            .json()?;
+        assert_eq!(status, "before");
+        assert!(worker.view_account(account.id()).await.is_err());
+
+        // Transactions from accounts that existed at the time of the snapshot still go
+        // through, as their nonces are queried again.
+        contract
+            .call("set_status")
+            .args_json(json!({ "message": "again" }))
+            .transact()
+            .await?
+            .into_result()?;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_restore_unknown_snapshot() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let other = near_workspaces::sandbox().await?;
+    let _ = other.snapshot().await?;
+    let snapshot = other.snapshot().await?;
+
+    // Snapshots are tied to the sandbox they were taken from.
+    assert!(worker.restore(snapshot).await.is_err());
+
+    // Even when that sandbox took as many snapshots as the other one.
+    let _ = worker.snapshot().await?;
+    let ours = worker.snapshot().await?;
+    assert_ne!(ours, snapshot);
+    let err = worker.restore(snapshot).await.unwrap_err();
+    assert!(err.to_string().contains("no snapshot"));
+    worker.restore(ours).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stop_restart() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let wasm = std::fs::read(STATUS_MSG_WASM_FILEPATH)?;
+    let contract = worker.dev_deploy(&wasm).await?;
+    contract
+        .call("set_status")
+        .args_json(json!({ "message": "before" }))
+        .transact()
+        .await?
+        .into_result()?;
+    let height = worker.view_block().await?.height();
+
+    worker.stop().await?;
+    assert!(worker.view_block().await.is_err());
+
+    worker.restart().await?;
+    assert!(worker.view_block().await?.height() >= height);
+    let status: String = contract
+        .view("get_status")
+        .args_json(json!({ "account_id": contract.id() }))
+        .await?
+        .json()?;
+    assert_eq!(status, "before");
+
+    // Cached nonces still line up with the chain, and restarting a running node works too.
+    worker.restart().await?;
+    contract
+        .call("set_status")
+        .args_json(json!({ "message": "after" }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use std::time::Duration;
+
+use near_workspaces::rpc::transport::TransportConfig;
+use sha2::Digest;
+use test_log::test;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+const STATUS_MSG_WASM_FILEPATH: &str = "../examples/res/status_message.wasm";
+
+#[test(tokio::test)]
+async fn test_custom_transport() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox()
+        .transport(
+            TransportConfig::new()
+                .pool_max_idle_per_host(4)
+                .pool_idle_timeout(Duration::from_secs(30))
+                .tcp_keepalive(Duration::from_secs(10))
+                .timeout(Duration::from_secs(60)),
+        )
+        .await?;
+
+    let account = worker.dev_create_account().await?;
+    assert!(worker.view_account(account.id()).await.is_ok());
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_transport_headers() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox()
+        .transport(TransportConfig::new().header("x-workspaces-test", "1"))
+        .await?;
+
+    // RPC calls keep going through with additional headers configured.
+    let account = worker.dev_create_account().await?;
+    let outcome = account
+        .transfer_near(
+            account.id(),
+            near_workspaces::types::NearToken::from_near(1),
+        )
+        .await?;
+    assert!(outcome.is_success());
+    assert!(worker.view_account(account.id()).await.is_ok());
+
+    // Invalid headers are caught when building the transport.
+    let err = near_workspaces::sandbox()
+        .transport(TransportConfig::new().header("invalid header", "1"))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("invalid header name"));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_transport_downloads() -> anyhow::Result<()> {
+    // Start from an empty cache, so that the wasm has to be downloaded.
+    let cache_dir = std::env::temp_dir().join(format!(
+        "near-workspaces-transport-{}",
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_nanos()
+    ));
+    std::env::set_var("NEAR_WORKSPACES_CACHE_DIR", &cache_dir);
+
+    // Serves the wasm only to requests carrying the header configured on the transport.
+    let wasm = std::fs::read(STATUS_MSG_WASM_FILEPATH)?;
+    let sha256 = format!("{:x}", sha2::Sha256::digest(&wasm));
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{}/status_message.wasm", listener.local_addr()?);
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                match stream.read(&mut buf).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => request.extend_from_slice(&buf[..n]),
+                }
+            }
+            let request = String::from_utf8_lossy(&request).to_lowercase();
+            let response = if request.contains("x-workspaces-test: 1") {
+                let mut response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                    wasm.len()
+                )
+                .into_bytes();
+                response.extend_from_slice(&wasm);
+                response
+            } else {
+                b"HTTP/1.1 403 Forbidden\r\ncontent-length: 0\r\nconnection: close\r\n\r\n".to_vec()
+            };
+            let _ = stream.write_all(&response).await;
+        }
+    });
+
+    let worker = near_workspaces::sandbox().await?;
+    assert!(worker.dev_deploy_url(&url, &sha256).await.is_err());
+
+    let worker = near_workspaces::sandbox()
+        .transport(TransportConfig::new().header("x-workspaces-test", "1"))
+        .await?;
+    let contract = worker.dev_deploy_url(&url, &sha256).await?;
+    assert_eq!(
+        contract.view_code().await?,
+        std::fs::read(STATUS_MSG_WASM_FILEPATH)?
+    );
+
+    let _ = std::fs::remove_dir_all(cache_dir);
+    Ok(())
+}
+
+#[test]
+fn test_faucet_transport() -> anyhow::Result<()> {
+    use near_workspaces::network::HelperFaucet;
+
+    let url: url::Url = "https://helper.testnet.near.org".parse()?;
+    HelperFaucet::with_transport(
+        url.clone(),
+        &TransportConfig::new().proxy("http://127.0.0.1:3128"),
+    )?;
+
+    // The faucet is held to the same transport as the RPC client, invalid proxies included.
+    let err = HelperFaucet::with_transport(url, &TransportConfig::new().proxy("not a proxy"))
+        .unwrap_err();
+    assert!(err.to_string().contains("invalid proxy"));
+    Ok(())
+}
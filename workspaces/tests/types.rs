@@ -40,6 +40,52 @@ fn test_keypair_secp256k1() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_implicit_account_id() -> anyhow::Result<()> {
+    let pk = SecretKey::from_seed(KeyType::ED25519, "test").public_key();
+    assert_eq!(
+        pk.implicit_account_id()?.as_str(),
+        "bb4dc639b212e075a751685b26bdcea5920a504181ff2910e8549742127092a0"
+    );
+
+    let pk = SecretKey::from_seed(KeyType::SECP256K1, "test").public_key();
+    assert!(pk.implicit_account_id().is_err());
+
+    Ok(())
+}
+
+#[cfg(feature = "seed-phrase")]
+#[test]
+fn test_keypair_from_seed_phrase() -> anyhow::Result<()> {
+    use near_workspaces::types::DEFAULT_SEED_PHRASE_HD_PATH;
+
+    let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    let sk = SecretKey::from_seed_phrase(phrase, DEFAULT_SEED_PHRASE_HD_PATH)?;
+    assert!(matches!(sk.key_type(), KeyType::ED25519));
+    assert_eq!(
+        sk.to_string(),
+        "ed25519:F1kPR175szkGxEL52A9H6Z5ocS2BtaipprK2Hiob9DjGzHTkScrBb1yt44baXPZ3LxyHcsTPdBjHmn6zx147txH"
+    );
+    assert_eq!(
+        sk.public_key().to_string(),
+        "ed25519:6j4b6zUaty6fD1awqcGCCU9JYGCWYUgdJhQrzfZhqE25"
+    );
+
+    let sk = SecretKey::from_seed_phrase(phrase, "m/44'/397'/1'")?;
+    assert_eq!(
+        sk.public_key().to_string(),
+        "ed25519:51ZftJ8qPN8mJ3oqgQb3fqPbotQVZfTC4mxLgwz34FTK"
+    );
+
+    // Phrases with a bad checksum and malformed paths are rejected.
+    let bad_phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon";
+    assert!(SecretKey::from_seed_phrase(bad_phrase, DEFAULT_SEED_PHRASE_HD_PATH).is_err());
+    assert!(SecretKey::from_seed_phrase(phrase, "m/44'/abc'").is_err());
+
+    Ok(())
+}
+
 #[test]
 fn test_pubkey_serialization() -> anyhow::Result<()> {
     for key_type in [KeyType::ED25519, KeyType::SECP256K1] {
@@ -106,3 +152,79 @@ fn test_valid_account_id() {
         "Something changed underneath for testnet to not be a valid Account ID"
     );
 }
+
+#[cfg(feature = "interop_primitives")]
+#[test]
+fn test_near_primitives_interop() -> anyhow::Result<()> {
+    use std::convert::TryFrom;
+
+    let sk = SecretKey::from_seed(KeyType::ED25519, "test");
+    let near_sk: near_crypto::SecretKey = sk.clone().into();
+    assert_eq!(PublicKey::from(near_sk.public_key()), sk.public_key());
+    assert_eq!(SecretKey::from(near_sk), sk);
+
+    let hash = near_workspaces::CryptoHash([7; 32]);
+    let near_hash: near_primitives::hash::CryptoHash = hash.into();
+    assert_eq!(near_workspaces::CryptoHash::from(near_hash), hash);
+
+    let access_key = near_workspaces::AccessKey::full_access();
+    let near_access_key: near_primitives::account::AccessKey = access_key.clone().into();
+    assert_eq!(
+        near_workspaces::AccessKey::from(near_access_key),
+        access_key
+    );
+
+    let signer = near_workspaces::InMemorySigner::from_secret_key("alice.near".parse()?, sk);
+    let near_signer = near_crypto::InMemorySigner::try_from(signer)?;
+    assert_eq!(near_signer.account_id.as_str(), "alice.near");
+
+    Ok(())
+}
+
+#[cfg(feature = "interop_primitives")]
+#[tokio::test]
+async fn test_raw_action() -> anyhow::Result<()> {
+    use near_primitives::transaction::{Action, TransferAction};
+
+    let worker = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let before = bob.view_account().await?.balance;
+
+    alice
+        .batch(bob.id())
+        .action(Action::Transfer(TransferAction { deposit: 1 }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let after = bob.view_account().await?.balance;
+    assert_eq!(after.as_yoctonear(), before.as_yoctonear() + 1);
+
+    Ok(())
+}
+
+#[cfg(feature = "interop_sdk")]
+#[test]
+fn test_sdk_interop() -> anyhow::Result<()> {
+    use near_workspaces::types::{Gas, NearToken, SdkInterop};
+
+    let id: AccountId = "alice.near".parse()?;
+    let sdk_id = id.clone().into_sdk();
+    assert_eq!(sdk_id.as_str(), "alice.near");
+    assert_eq!(AccountId::from_sdk(sdk_id)?, id);
+
+    // near-sdk lets ids skip validation, which workspaces does not.
+    let invalid = near_sdk::AccountId::new_unchecked("Not A Valid Id".to_string());
+    assert!(AccountId::from_sdk(invalid).is_err());
+
+    let amount = NearToken::from_near(5);
+    assert_eq!(amount.into_sdk(), 5 * near_sdk::ONE_NEAR);
+    assert_eq!(NearToken::from_sdk(amount.into_sdk())?, amount);
+
+    let gas = Gas::from_tgas(30);
+    assert_eq!(gas.into_sdk(), near_sdk::Gas::ONE_TERA * 30);
+    assert_eq!(Gas::from_sdk(gas.into_sdk())?, gas);
+
+    Ok(())
+}